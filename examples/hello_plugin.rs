@@ -0,0 +1,57 @@
+//! Native plugin drawing `HELLO <settings>` line, see
+//! `src/providers/plugin.rs` for its ABI. Build it with
+//! `cargo build --example hello_plugin` and copy
+//! `target/debug/examples/libhello_plugin.so` (`.dylib`, `.dll`) into
+//! `[plugins]` path, it shows up as `hello_plugin` provider.
+
+use std::ffi::{c_char, c_void, CStr, CString};
+
+/// same layout as `elora_hid::providers::plugin::PluginV1`, plugins define
+/// it themselves as they'd do in C
+#[repr(C)]
+pub struct PluginV1 {
+    abi_version: u32,
+    refresh_secs: u64,
+    create: unsafe extern "C" fn(settings: *const c_char) -> *mut c_void,
+    fetch: unsafe extern "C" fn(state: *mut c_void, out: *mut *mut c_char) -> i32,
+    free_string: unsafe extern "C" fn(text: *mut c_char),
+    destroy: unsafe extern "C" fn(state: *mut c_void),
+}
+
+unsafe extern "C" fn create(settings: *const c_char) -> *mut c_void {
+    let settings = CStr::from_ptr(settings).to_string_lossy().into_owned();
+    Box::into_raw(Box::new(settings)).cast()
+}
+
+unsafe extern "C" fn fetch(state: *mut c_void, out: *mut *mut c_char) -> i32 {
+    let settings = &*(state as *const String);
+    match CString::new(format!("HELLO {}", settings)) {
+        Ok(text) => {
+            *out = text.into_raw();
+            0
+        }
+        Err(_) => 1,
+    }
+}
+
+unsafe extern "C" fn free_string(text: *mut c_char) {
+    drop(CString::from_raw(text));
+}
+
+unsafe extern "C" fn destroy(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut String));
+}
+
+static TABLE: PluginV1 = PluginV1 {
+    abi_version: 1,
+    refresh_secs: 60,
+    create,
+    fetch,
+    free_string,
+    destroy,
+};
+
+#[no_mangle]
+pub extern "C" fn elora_plugin_v1() -> *const PluginV1 {
+    &TABLE
+}
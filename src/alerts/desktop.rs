@@ -0,0 +1,62 @@
+//! Desktop notifications on host
+//!
+//! Shown with notify-rust: freedesktop notifications over D-Bus on linux,
+//! Notification Center on macos and toast notifications on windows. Urgent
+//! alerts are critical, so they stay until dismissed where host supports it.
+
+use notify_rust::Notification;
+
+use crate::BoxError;
+
+/// shows notification with `summary` title and `body` text
+pub async fn notify(summary: &str, body: &str, urgent: bool) -> Result<(), BoxError> {
+    let mut notification = Notification::new();
+    notification
+        .appname("elora_hid")
+        .summary(summary)
+        .body(body);
+    platform::show(notification, urgent).await
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use notify_rust::{Notification, Urgency};
+
+    use crate::BoxError;
+
+    pub async fn show(mut notification: Notification, urgent: bool) -> Result<(), BoxError> {
+        if urgent {
+            notification.urgency(Urgency::Critical);
+        }
+        notification.show_async().await?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use notify_rust::Notification;
+
+    use crate::BoxError;
+
+    /// Notification Center has no urgency, showing blocks until it's shown
+    pub async fn show(notification: Notification, _urgent: bool) -> Result<(), BoxError> {
+        tokio::task::spawn_blocking(move || notification.show().map(drop)).await??;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use notify_rust::{Notification, Urgency};
+
+    use crate::BoxError;
+
+    pub async fn show(mut notification: Notification, urgent: bool) -> Result<(), BoxError> {
+        if urgent {
+            notification.urgency(Urgency::Critical);
+        }
+        tokio::task::spawn_blocking(move || notification.show()).await??;
+        Ok(())
+    }
+}
@@ -0,0 +1,737 @@
+//! Price alerts
+//!
+//! Rules like `TSLA > 300` or `BTC < -5% in 1h` are checked against metrics
+//! of fetched lines. Each rule is armed until it starts to hold, then it
+//! fires once and stays firing until value clears threshold by hysteresis
+//! margin, so value wobbling around threshold doesn't flap. Rules compare
+//! metrics within single provider, rules made by provider see its own lines
+//! only and user rules can be limited to one with `provider`, otherwise they
+//! track every provider showing symbol on its own. Fired alert goes
+//! to sinks: keyboard (firmware can flash rgb underglow or status led on
+//! it), desktop notification and webhook. Desktop notification can be turned
+//! off per rule, ex. `{ rule = "BTC < 40000", notify = false }`, and for
+//! rules made by providers with `quiet = ["ci"]`.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::{
+    protocol::{Message, ALERT_ABOVE, ALERT_BELOW, ALERT_URGENT},
+    providers::Line,
+    retry::RetryConfig,
+    EloraError,
+};
+
+pub mod desktop;
+pub mod webhook;
+
+/// `[alerts]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    /// ex. `["TSLA > 300", "BTC < -5% in 1h"]`, symbol as shown on display,
+    /// or table with `provider` its symbol is looked up in
+    pub rules: Vec<RuleEntry>,
+    /// percent of threshold (percentage points for change rules) value has
+    /// to go back past it before rule can fire again
+    pub hysteresis: f64,
+    /// send alert command to keyboard
+    pub keyboard: bool,
+    /// show desktop notification
+    pub notify: bool,
+    /// providers whose alerts don't show desktop notification, ex. `["ping"]`
+    pub quiet: Vec<String>,
+    /// url alerts are posted to as json, ex. Slack or Discord webhook
+    pub webhook: Option<String>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        AlertConfig {
+            rules: Vec::new(),
+            hysteresis: 0.0,
+            keyboard: true,
+            notify: true,
+            quiet: Vec::new(),
+            webhook: None,
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !(0.0..100.0).contains(&self.hysteresis) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "alerts.hysteresis {} must be between 0 and 100",
+                self.hysteresis
+            )));
+        }
+        if let Some(url) = &self.webhook {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "alerts.webhook {:?} is not http(s) url",
+                    url
+                )));
+            }
+        }
+        if let Some(provider) = self.quiet.iter().find(|p| !PROVIDERS.contains(&p.as_str())) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "alerts.quiet {:?} is not one of providers making alerts: {}",
+                provider,
+                PROVIDERS.join(", ")
+            )));
+        }
+        self.parse_rules().map(|_| ())
+    }
+
+    pub fn parse_rules(&self) -> Result<Vec<Rule>, EloraError> {
+        self.rules.iter().map(RuleEntry::parse).collect()
+    }
+
+    /// `rules` made by `provider`, without desktop notification when it's
+    /// in `quiet`
+    pub fn provider_rules(&self, provider: &str, rules: Vec<Rule>) -> Vec<Rule> {
+        let notify = !self.quiet.iter().any(|quiet| quiet == provider);
+        rules
+            .into_iter()
+            .map(|rule| Rule {
+                notify: rule.notify && notify,
+                ..rule
+            })
+            .collect()
+    }
+}
+
+/// Providers which make alert rules of their own, same as ones
+/// `scheduler::provider_rules` gives rules of
+pub const PROVIDERS: &[&str] = &[
+    "calendar", "ci", "gitlab", "jenkins", "oncall", "ping", "pomodoro", "printer", "sensors",
+    "sports",
+];
+
+/// Rule of `[alerts]`, either `"TSLA > 300"` or table with options of it,
+/// ex. `{ rule = "TSLA > 300", provider = "stocks", notify = false }`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum RuleEntry {
+    Rule(String),
+    Table(RuleTable),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleTable {
+    pub rule: String,
+    /// provider whose metrics rule is checked against, all of them if unset
+    pub provider: Option<String>,
+    /// show desktop notification when `[alerts] notify` is on
+    pub notify: bool,
+}
+
+impl Default for RuleTable {
+    fn default() -> Self {
+        RuleTable {
+            rule: String::new(),
+            provider: None,
+            notify: true,
+        }
+    }
+}
+
+impl RuleEntry {
+    fn parse(&self) -> Result<Rule, EloraError> {
+        match self {
+            RuleEntry::Rule(rule) => rule.parse(),
+            RuleEntry::Table(table) => Ok(Rule {
+                provider: table.provider.clone(),
+                notify: table.notify,
+                ..table.rule.parse()?
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+impl Direction {
+    fn op(self) -> char {
+        match self {
+            Direction::Above => '>',
+            Direction::Below => '<',
+        }
+    }
+}
+
+/// Single threshold, ex. `TSLA > 300`, or threshold of change within
+/// window, ex. `BTC < -5% in 1h`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub symbol: String,
+    /// provider whose metrics rule is checked against, `None` checks every
+    /// provider showing symbol separately
+    pub provider: Option<String>,
+    pub direction: Direction,
+    /// value, or percent change when rule has window
+    pub threshold: f64,
+    /// percent change of value within this long window is compared instead
+    /// of value itself
+    pub window: Option<Duration>,
+    /// alert text instead of `SYMBOL value > threshold`, for rules made by
+    /// providers, ex. `elora_hid CI failed`
+    pub text: Option<String>,
+    /// sent to keyboard as urgent alert, for rules made by providers which
+    /// need attention right away, ex. triggered on-call incident
+    pub urgent: bool,
+    /// show desktop notification when it's enabled in `[alerts]`
+    pub notify: bool,
+}
+
+impl Rule {
+    fn holds(&self, value: f64) -> bool {
+        match self.direction {
+            Direction::Above => value > self.threshold,
+            Direction::Below => value < self.threshold,
+        }
+    }
+
+    /// whether value went back past threshold by `hysteresis` percent of
+    /// it, or percentage points for change rules
+    fn clears(&self, value: f64, hysteresis: f64) -> bool {
+        let margin = match self.window {
+            Some(_) => hysteresis,
+            None => self.threshold.abs() * hysteresis / 100.0,
+        };
+        match self.direction {
+            Direction::Above => value <= self.threshold - margin,
+            Direction::Below => value >= self.threshold + margin,
+        }
+    }
+}
+
+/// Parses window like `90s`, `30m`, `1h` or `1d`
+fn parse_window(window: &str) -> Option<Duration> {
+    let unit = window.find(|c: char| !c.is_ascii_digit())?;
+    let count: u64 = window[..unit].parse().ok()?;
+    let secs = match &window[unit..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count * secs)).filter(|window| !window.is_zero())
+}
+
+/// Window in largest unit it's whole in, ex. `1h`
+fn window_text(window: Duration) -> String {
+    let secs = window.as_secs();
+    [(24 * 60 * 60, 'd'), (60 * 60, 'h'), (60, 'm')]
+        .into_iter()
+        .find(|(unit, _)| secs.is_multiple_of(*unit))
+        .map_or(format!("{}s", secs), |(unit, name)| {
+            format!("{}{}", secs / unit, name)
+        })
+}
+
+impl FromStr for Rule {
+    type Err = EloraError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            EloraError::ConfigInvalid(format!(
+                "alert {:?} is not in `SYMBOL > value`, `SYMBOL < value` or \
+                 `SYMBOL > percent% in window` form",
+                rule
+            ))
+        };
+        let (at, direction) = match (rule.find('>'), rule.find('<')) {
+            (Some(at), None) => (at, Direction::Above),
+            (None, Some(at)) => (at, Direction::Below),
+            _ => return Err(invalid()),
+        };
+        let symbol = rule[..at].trim();
+        if symbol.is_empty() {
+            return Err(invalid());
+        }
+        let value = rule[at + 1..].trim();
+        let (threshold, window) = match value.split_once(" in ") {
+            Some((percent, window)) => {
+                let percent = percent.trim().strip_suffix('%').ok_or_else(invalid)?;
+                let window = parse_window(window.trim()).ok_or_else(invalid)?;
+                (percent.trim().parse().map_err(|_| invalid())?, Some(window))
+            }
+            None => (value.parse().map_err(|_| invalid())?, None),
+        };
+        Ok(Rule {
+            symbol: symbol.to_string(),
+            provider: None,
+            direction,
+            threshold,
+            window,
+            text: None,
+            urgent: false,
+            notify: true,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.symbol,
+            self.direction.op(),
+            self.threshold
+        )?;
+        if let Some(window) = self.window {
+            write!(f, "% in {}", window_text(window))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rule which started to hold, with value (or percent change) which
+/// crossed it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub rule: Rule,
+    /// provider whose metric crossed rule
+    pub provider: String,
+    pub value: f64,
+}
+
+impl Alert {
+    /// ex. `TSLA 301 > 300` or `BTC -5.3% in 1h < -5%`
+    pub fn text(&self) -> String {
+        if let Some(text) = &self.rule.text {
+            return text.clone();
+        }
+        match self.rule.window {
+            Some(window) => format!(
+                "{} {:+.1}% in {} {} {}%",
+                self.rule.symbol,
+                self.value,
+                window_text(window),
+                self.rule.direction.op(),
+                self.rule.threshold
+            ),
+            None => format!(
+                "{} {:.0} {} {}",
+                self.rule.symbol,
+                self.value,
+                self.rule.direction.op(),
+                self.rule.threshold
+            ),
+        }
+    }
+
+    pub fn message(&self) -> Message {
+        let direction = match self.rule.direction {
+            _ if self.rule.urgent => ALERT_URGENT,
+            Direction::Above => ALERT_ABOVE,
+            Direction::Below => ALERT_BELOW,
+        };
+        Message::alert(direction, self.text().as_bytes())
+    }
+}
+
+/// State of single rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// waiting for rule to hold
+    Armed,
+    /// alert was sent, waiting for value to clear threshold
+    Firing,
+}
+
+/// Rule with its state and values seen within its window, against metrics
+/// of single provider
+struct Tracked {
+    /// index of rule in `Alerts::rules`
+    rule: usize,
+    provider: String,
+    state: State,
+    /// oldest first, first one is from window start or before it
+    samples: VecDeque<(Instant, f64)>,
+}
+
+impl Tracked {
+    /// Value rule is compared with, percent change since window start for
+    /// change rules. `None` until change rule has two samples
+    fn observe(&mut self, window: Option<Duration>, value: f64, now: Instant) -> Option<f64> {
+        let Some(window) = window else {
+            return Some(value);
+        };
+        self.samples.push_back((now, value));
+        // keep one sample from before window as its baseline
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(at, _)| now.duration_since(*at) >= window)
+        {
+            self.samples.pop_front();
+        }
+        let (at, base) = *self.samples.front()?;
+        if at == now || base == 0.0 {
+            return None;
+        }
+        Some((value - base) / base * 100.0)
+    }
+}
+
+/// Rules with their state machines, so alert fires once when threshold is
+/// crossed and again only after value went back past it
+pub struct Alerts {
+    rules: Vec<Rule>,
+    /// state of rule per provider, added once provider shows its symbol
+    tracked: Vec<Tracked>,
+    hysteresis: f64,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Alerts {
+            rules,
+            tracked: Vec::new(),
+            hysteresis: 0.0,
+        }
+    }
+
+    /// value has to go back past threshold by `percent` of it before rule
+    /// is armed again, see [`AlertConfig::hysteresis`]
+    pub fn with_hysteresis(mut self, percent: f64) -> Self {
+        self.hysteresis = percent;
+        self
+    }
+
+    /// state of rule `rule` (index in rules given) against metrics of
+    /// `provider`, `None` until provider showed its symbol
+    pub fn state(&self, rule: usize, provider: &str) -> Option<State> {
+        self.tracked
+            .iter()
+            .find(|tracked| tracked.rule == rule && tracked.provider == provider)
+            .map(|tracked| tracked.state)
+    }
+
+    /// Checks rules against metrics of lines, given with name of provider
+    /// they're from. Stale lines are skipped, their value isn't current, and
+    /// symbol on several lines of provider is observed once, from first of
+    /// them
+    pub fn check<'a>(
+        &mut self,
+        lines: impl IntoIterator<Item = (&'a str, &'a Line)>,
+    ) -> Vec<Alert> {
+        self.check_at(lines, Instant::now())
+    }
+
+    fn check_at<'a>(
+        &mut self,
+        lines: impl IntoIterator<Item = (&'a str, &'a Line)>,
+        now: Instant,
+    ) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        let mut observed = HashSet::new();
+        for (provider, line) in lines.into_iter().filter(|(_, line)| !line.stale) {
+            let Some(metric) = &line.metric else {
+                continue;
+            };
+            if !observed.insert((provider, metric.symbol.as_str())) {
+                continue;
+            }
+            for (index, rule) in self.rules.iter().enumerate() {
+                if rule.symbol != metric.symbol
+                    || rule.provider.as_deref().is_some_and(|p| p != provider)
+                {
+                    continue;
+                }
+                let at = match self
+                    .tracked
+                    .iter()
+                    .position(|tracked| tracked.rule == index && tracked.provider == provider)
+                {
+                    Some(at) => at,
+                    None => {
+                        self.tracked.push(Tracked {
+                            rule: index,
+                            provider: provider.to_string(),
+                            state: State::Armed,
+                            samples: VecDeque::new(),
+                        });
+                        self.tracked.len() - 1
+                    }
+                };
+                let tracked = &mut self.tracked[at];
+                let Some(value) = tracked.observe(rule.window, metric.value, now) else {
+                    continue;
+                };
+                match tracked.state {
+                    State::Armed if rule.holds(value) => {
+                        tracked.state = State::Firing;
+                        alerts.push(Alert {
+                            rule: rule.clone(),
+                            provider: provider.to_string(),
+                            value,
+                        });
+                    }
+                    State::Firing if rule.clears(value, self.hysteresis) => {
+                        log::debug!("Alert {} of {} cleared", rule, provider);
+                        tracked.state = State::Armed;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        alerts
+    }
+}
+
+/// Where alerts go, from `[alerts]` config. Keyboard alone without it, for
+/// rules made by providers
+#[derive(Debug, Clone)]
+pub struct Sinks {
+    pub keyboard: bool,
+    pub notify: bool,
+    pub webhook: Option<String>,
+    /// backoff of failed webhook posts
+    retry: RetryConfig,
+    client: Client,
+}
+
+impl Sinks {
+    pub fn new(config: Option<&AlertConfig>, retry: RetryConfig) -> Self {
+        Sinks {
+            keyboard: config.is_none_or(|config| config.keyboard),
+            notify: config.is_some_and(|config| config.notify),
+            webhook: config.and_then(|config| config.webhook.clone()),
+            retry,
+            client: Client::new(),
+        }
+    }
+
+    /// Shows desktop notification and posts to webhook in background, as
+    /// they're enabled for sinks and rule. Keyboard is alerted by caller
+    /// which holds connections
+    pub fn send(&self, alert: &Alert) {
+        if self.notify && alert.rule.notify {
+            let body = alert.text();
+            let urgent = alert.rule.urgent;
+            tokio::spawn(async move {
+                if let Err(e) = desktop::notify("elora_hid alert", &body, urgent).await {
+                    log::warn!("Unable to show desktop notification: {}", e);
+                }
+            });
+        }
+        if let Some(url) = self.webhook.clone() {
+            let client = self.client.clone();
+            let alert = alert.clone();
+            let retry = self.retry.clone();
+            tokio::spawn(async move {
+                if let Err(e) = webhook::post(&client, &url, &alert, &retry).await {
+                    log::warn!("Unable to post alert to webhook: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[test]
+fn testing_rule_parsing() {
+    let rule: Rule = "TSLA > 300".parse().unwrap();
+    assert_eq!(rule.symbol, "TSLA");
+    assert_eq!(rule.direction, Direction::Above);
+    assert_eq!(rule.threshold, 300.0);
+    assert_eq!(rule.to_string(), "TSLA > 300");
+
+    assert_eq!(
+        "BTC<40000.5".parse::<Rule>().unwrap().direction,
+        Direction::Below
+    );
+    assert!("TSLA >= 300".parse::<Rule>().is_err());
+    assert!("TSLA 300".parse::<Rule>().is_err());
+    assert!("> 300".parse::<Rule>().is_err());
+
+    let rule: Rule = "BTC < -5% in 1h".parse().unwrap();
+    assert_eq!(rule.threshold, -5.0);
+    assert_eq!(rule.window, Some(Duration::from_secs(3600)));
+    assert_eq!(rule.to_string(), "BTC < -5% in 1h");
+    assert_eq!(
+        "ETH > 3% in 90m".parse::<Rule>().unwrap().to_string(),
+        "ETH > 3% in 90m"
+    );
+    assert!("BTC < -5 in 1h".parse::<Rule>().is_err());
+    assert!("BTC < -5% in 1w".parse::<Rule>().is_err());
+    assert!("BTC < -5% in 0m".parse::<Rule>().is_err());
+
+    let config: AlertConfig = toml::from_str(
+        "rules = [\"TSLA > 300\", { rule = \"BTC < 40000\", provider = \"crypto\", notify = false }]\n\
+         quiet = [\"ping\"]",
+    )
+    .unwrap();
+    config.validate().unwrap();
+    let rules = config.parse_rules().unwrap();
+    assert!(rules[0].notify);
+    assert!(!rules[1].notify);
+    assert_eq!(rules[1].to_string(), "BTC < 40000");
+    assert_eq!(rules[0].provider, None);
+    assert_eq!(rules[1].provider.as_deref(), Some("crypto"));
+    assert!(!config.provider_rules("ping", rules.clone())[0].notify);
+    assert!(config.provider_rules("ci", rules)[0].notify);
+    assert!(toml::from_str::<AlertConfig>("quiet = [\"stocks\"]")
+        .unwrap()
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn testing_alert_crossing() {
+    let mut alerts = Alerts::new(vec!["TSLA > 300".parse().unwrap()]);
+    let line = |price| Line::new("").with_metric("TSLA", price);
+
+    assert!(alerts.check([("stocks", &line(290.0))]).is_empty());
+    let fired = alerts.check([("stocks", &line(301.0))]);
+    assert_eq!(fired[0].text(), "TSLA 301 > 300");
+    assert_eq!(fired[0].provider, "stocks");
+    assert_eq!(fired[0].message().payload[0], ALERT_ABOVE);
+    // still above, already alerted
+    assert!(alerts.check([("stocks", &line(310.0))]).is_empty());
+
+    let mut stale = line(290.0);
+    stale.stale = true;
+    assert!(alerts.check([("stocks", &stale)]).is_empty());
+    assert!(alerts.check([("stocks", &line(290.0))]).is_empty());
+    assert_eq!(alerts.check([("stocks", &line(305.0))]).len(), 1);
+}
+
+#[test]
+fn testing_alert_hysteresis_and_window() {
+    let mut alerts = Alerts::new(vec![
+        "TSLA > 300".parse().unwrap(),
+        "BTC < -5% in 1h".parse().unwrap(),
+    ])
+    .with_hysteresis(1.0);
+    let start = Instant::now();
+    let at = |mins: u64| start + Duration::from_secs(mins * 60);
+    let tsla = |price| Line::new("").with_metric("TSLA", price);
+    let btc = |price| Line::new("").with_metric("BTC", price);
+
+    assert_eq!(alerts.check_at([("stocks", &tsla(301.0))], at(0)).len(), 1);
+    // dips under threshold but not by 1%, so it doesn't fire again
+    assert!(alerts
+        .check_at([("stocks", &tsla(299.0))], at(1))
+        .is_empty());
+    assert!(alerts
+        .check_at([("stocks", &tsla(302.0))], at(2))
+        .is_empty());
+    assert_eq!(alerts.state(0, "stocks"), Some(State::Firing));
+    assert!(alerts
+        .check_at([("stocks", &tsla(296.0))], at(3))
+        .is_empty());
+    assert_eq!(alerts.state(0, "stocks"), Some(State::Armed));
+    assert_eq!(alerts.check_at([("stocks", &tsla(301.0))], at(4)).len(), 1);
+
+    assert!(alerts
+        .check_at([("crypto", &btc(60000.0))], at(0))
+        .is_empty());
+    assert!(alerts
+        .check_at([("crypto", &btc(58000.0))], at(30))
+        .is_empty());
+    let fired = alerts.check_at([("crypto", &btc(56700.0))], at(50));
+    assert_eq!(fired[0].text(), "BTC -5.5% in 1h < -5%");
+    assert_eq!(fired[0].message().payload[0], ALERT_BELOW);
+    // window moved past 60000, change against 58000 is only -2.2%
+    assert!(alerts
+        .check_at([("crypto", &btc(56700.0))], at(95))
+        .is_empty());
+    assert_eq!(alerts.state(1, "crypto"), Some(State::Armed));
+
+    // symbol on two lines of provider is single sample, second one would
+    // become baseline once first leaves window
+    let mut alerts = Alerts::new(vec!["BTC > 2% in 1h".parse().unwrap()]);
+    assert!(alerts
+        .check_at([("crypto", &btc(60000.0))], at(0))
+        .is_empty());
+    assert!(alerts
+        .check_at(
+            [("crypto", &btc(60500.0)), ("crypto", &btc(60600.0))],
+            at(10)
+        )
+        .is_empty());
+    let fired = alerts.check_at([("crypto", &btc(61750.0))], at(70));
+    assert_eq!(fired[0].text(), "BTC +2.1% in 1h > 2%");
+}
+
+#[test]
+fn testing_alert_provider_scope() {
+    let ci = Rule {
+        provider: Some("ci".into()),
+        .."elora < 1".parse().unwrap()
+    };
+    let tsla = RuleEntry::Table(RuleTable {
+        rule: "TSLA > 300".into(),
+        provider: Some("stocks".into()),
+        ..RuleTable::default()
+    });
+    let mut alerts = Alerts::new(vec![
+        ci,
+        tsla.parse().unwrap(),
+        "BTC > 2% in 1h".parse().unwrap(),
+    ]);
+    let start = Instant::now();
+    let at = |mins: u64| start + Duration::from_secs(mins * 60);
+    let line = |symbol: &str, value| Line::new("").with_metric(symbol, value);
+
+    // jenkins job named like ci repo doesn't fire ci rule
+    assert!(alerts
+        .check_at([("jenkins", &line("elora", 0.0))], at(0))
+        .is_empty());
+    assert_eq!(alerts.state(0, "jenkins"), None);
+    let fired = alerts.check_at([("ci", &line("elora", 0.0))], at(0));
+    assert_eq!(fired[0].provider, "ci");
+
+    // wallet balance isn't stock price
+    assert!(alerts
+        .check_at([("wallets", &line("TSLA", 1000.0))], at(0))
+        .is_empty());
+    assert_eq!(
+        alerts
+            .check_at([("stocks", &line("TSLA", 301.0))], at(0))
+            .len(),
+        1
+    );
+
+    // rule without provider keeps baseline of every provider on its own
+    let wallet = line("BTC", 1000.0);
+    assert!(alerts
+        .check_at(
+            [("wallets", &wallet), ("crypto", &line("BTC", 60000.0))],
+            at(0)
+        )
+        .is_empty());
+    let fired = alerts.check_at(
+        [("wallets", &wallet), ("crypto", &line("BTC", 61500.0))],
+        at(10),
+    );
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].provider, "crypto");
+    assert_eq!(alerts.state(2, "wallets"), Some(State::Armed));
+}
+
+#[test]
+fn testing_sinks_without_config() {
+    let sinks = Sinks::new(None, RetryConfig::default());
+    assert!(sinks.keyboard);
+    assert!(!sinks.notify);
+    assert!(Sinks::new(Some(&AlertConfig::default()), RetryConfig::default()).notify);
+}
@@ -0,0 +1,87 @@
+//! Alerts posted as json to webhook
+//!
+//! Body has alert text both as `text` (Slack) and `content` (Discord), so
+//! incoming webhooks of either show it as is, with rule details and time it
+//! fired for other receivers. Failed posts are retried with `[retry]`
+//! backoff, so alert isn't lost to one bad request.
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::json;
+
+use super::Alert;
+use crate::{
+    retry::{self, RetryConfig},
+    BoxError,
+};
+
+/// posts `alert` to `url`, error of last attempt is returned
+pub async fn post(
+    client: &Client,
+    url: &str,
+    alert: &Alert,
+    config: &RetryConfig,
+) -> Result<(), BoxError> {
+    let body = body(alert, Utc::now());
+    let mut retry = 0;
+    loop {
+        let posted = async {
+            client
+                .post(url)
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok::<_, BoxError>(())
+        }
+        .await;
+        match posted {
+            Err(e) if retry + 1 < config.attempts => {
+                let delay = config.delay(retry, retry::jitter());
+                log::warn!("Webhook post failed: {}, retrying in {:?}", e, delay);
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            posted => return posted,
+        }
+    }
+}
+
+fn body(alert: &Alert, at: DateTime<Utc>) -> serde_json::Value {
+    let text = alert.text();
+    json!({
+        "text": text,
+        "content": text,
+        "symbol": alert.rule.symbol,
+        "provider": alert.provider,
+        "value": alert.value,
+        "rule": alert.rule.to_string(),
+        "urgent": alert.rule.urgent,
+        "at": at.to_rfc3339(),
+    })
+}
+
+#[test]
+fn testing_webhook_body() {
+    let alert = Alert {
+        rule: "TSLA > 300".parse().unwrap(),
+        provider: "stocks".into(),
+        value: 301.2,
+    };
+    let at = DateTime::parse_from_rfc3339("2024-10-16T14:30:00Z")
+        .unwrap()
+        .to_utc();
+    assert_eq!(
+        body(&alert, at),
+        json!({
+            "text": "TSLA 301 > 300",
+            "content": "TSLA 301 > 300",
+            "symbol": "TSLA",
+            "provider": "stocks",
+            "value": 301.2,
+            "rule": "TSLA > 300",
+            "urgent": false,
+            "at": "2024-10-16T14:30:00+00:00",
+        })
+    );
+}
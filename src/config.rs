@@ -0,0 +1,1070 @@
+//! TOML configuration file
+//!
+//! Loaded from `~/.config/elora_hid/config.toml` unless other path is given
+//! with `--config`. Every field is optional and falls back to defaults which
+//! match Elora keyboard and previously hardcoded tickers.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    alerts::AlertConfig,
+    hid,
+    history::HistoryConfig,
+    market::MarketConfig,
+    metrics::MetricsConfig,
+    providers::{
+        air::AirConfig, alphavantage::AlphaVantageConfig, astro::AstroConfig,
+        battery::BatteryConfig, bluetooth::BluetoothConfig, calendar::CalendarConfig, ci::CiConfig,
+        clock::ClockConfig, countdown::CountdownConfig, crypto::CryptoConfig, disk::DiskConfig,
+        dividends::DividendsConfig, docker::DockerConfig, downloads::DownloadsConfig,
+        earnings::EarningsConfig, electricity::ElectricityConfig, exchange::ExchangeConfig,
+        exec::ExecConfig, f1::F1Config, feargreed::FearGreedConfig, finnhub::FinnhubConfig,
+        fx::FxConfig, github::GitHubConfig, gitlab::GitLabConfig, gpu::GpuConfig,
+        headlines::HeadlinesConfig, homeassistant::HomeAssistantConfig, imap::ImapConfig,
+        jenkins::JenkinsConfig, jira::JiraConfig, kubernetes::KubernetesConfig, live::LiveConfig,
+        media::MediaConfig, mqtt::MqttConfig, network::NetworkConfig, oncall::OnCallConfig,
+        parcels::ParcelsConfig, pihole::PiHoleConfig, ping::PingConfig, plugin::PluginsConfig,
+        pomodoro::PomodoroConfig, portfolio::PortfolioConfig, printer::PrinterConfig,
+        prometheus::PrometheusConfig, push::PushConfig, sensors::SensorsConfig,
+        sentry::SentryConfig, speedtest::SpeedtestConfig, sports::SportsConfig, stocks,
+        stocks::QuotesConfig, system::SystemConfig, transit::TransitConfig, wallets::WalletsConfig,
+        weather::WeatherConfig,
+    },
+    render::{self, ScrollConfig, Template},
+    retry::RetryConfig,
+    scheduler, EloraError,
+};
+
+const LOG_LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// yahoo tickers to fetch, ex. `["TSLA", "VWRL.AS"]`, indices, futures and
+    /// fx pairs too, ex. `["^GSPC", "GC=F", "EURUSD=X"]`. Empty list disables
+    /// stocks
+    pub tickers: Vec<String>,
+    /// How often to refetch new data from dependency services in seconds,
+    /// used for providers without own interval
+    pub refresh_secs: u64,
+    /// refresh interval per provider name in seconds, ex. `weather = 900`
+    pub intervals: BTreeMap<String, u64>,
+    /// default log level, `RUST_LOG` env variable takes precedence
+    pub log_level: String,
+    pub device: DeviceConfig,
+    /// keyboards data is shown on, keyed by serial number. Without them
+    /// single keyboard matching `[device]` is used
+    pub keyboards: Vec<KeyboardConfig>,
+    /// how failed fetches are retried
+    pub retry: RetryConfig,
+    /// quote sources tried when Yahoo fails
+    pub quotes: Option<QuotesConfig>,
+    /// trading hours of exchanges, closed tickers aren't refetched when
+    /// section is present
+    pub market: Option<MarketConfig>,
+    /// value and profit of held stocks, enabled when section is present
+    pub portfolio: Option<PortfolioConfig>,
+    /// exchange rates and conversion of stock prices, enabled when section
+    /// is present
+    pub fx: Option<FxConfig>,
+    /// CoinGecko crypto prices, enabled when section is present
+    pub crypto: Option<CryptoConfig>,
+    /// stock and crypto fear & greed indices, enabled when section is present
+    pub feargreed: Option<FearGreedConfig>,
+    /// balances of public crypto addresses, enabled when section is present
+    pub wallets: Option<WalletsConfig>,
+    /// Binance or Kraken account balances, enabled when section is present
+    pub exchange: Option<ExchangeConfig>,
+    /// OpenWeatherMap current weather, enabled when section is present
+    pub weather: Option<WeatherConfig>,
+    /// Open-Meteo air quality and pollen, enabled when section is present
+    pub air: Option<AirConfig>,
+    /// sunrise, sunset and moon phase, enabled when section is present
+    pub astro: Option<AstroConfig>,
+    /// Alpha Vantage quotes, enabled when section is present
+    pub alphavantage: Option<AlphaVantageConfig>,
+    /// Finnhub quotes and company news, enabled when section is present
+    pub finnhub: Option<FinnhubConfig>,
+    /// upcoming earnings reports of tickers, enabled when section is present
+    pub earnings: Option<EarningsConfig>,
+    /// upcoming ex-dividend dates of tickers, enabled when section is present
+    pub dividends: Option<DividendsConfig>,
+    /// unread GitHub notifications, enabled when section is present
+    pub github: Option<GitHubConfig>,
+    /// GitHub Actions status of repos, enabled when section is present
+    pub ci: Option<CiConfig>,
+    /// latest gitlab pipelines, enabled when section is present
+    pub gitlab: Option<GitLabConfig>,
+    /// last builds of jenkins jobs, enabled when section is present
+    pub jenkins: Option<JenkinsConfig>,
+    /// my jira issues in filter, enabled when section is present
+    pub jira: Option<JiraConfig>,
+    /// next meetings from ics feed, enabled when section is present
+    pub calendar: Option<CalendarConfig>,
+    /// countdowns to configured dates, enabled when section is present
+    pub countdown: Option<CountdownConfig>,
+    /// day-ahead electricity spot price, enabled when section is present
+    pub electricity: Option<ElectricityConfig>,
+    /// next departures from transit stop, enabled when section is present
+    pub transit: Option<TransitConfig>,
+    /// rss, atom or Hacker News headlines, enabled when section is present
+    pub headlines: Option<HeadlinesConfig>,
+    /// pod health of kubernetes namespaces, enabled when section is present
+    pub kubernetes: Option<KubernetesConfig>,
+    /// local docker container states, enabled when section is present
+    pub docker: Option<DockerConfig>,
+    /// results of promql queries, enabled when section is present
+    pub prometheus: Option<PrometheusConfig>,
+    /// pagerduty or opsgenie on-call state, enabled when section is present
+    pub oncall: Option<OnCallConfig>,
+    /// sentry issues and error rate, enabled when section is present
+    pub sentry: Option<SentryConfig>,
+    /// daily crates.io and npm downloads, enabled when section is present
+    pub downloads: Option<DownloadsConfig>,
+    /// twitch and youtube live status, enabled when section is present
+    pub live: Option<LiveConfig>,
+    /// today's football matches, enabled when section is present
+    pub sports: Option<SportsConfig>,
+    /// next formula 1 session, enabled when section is present
+    pub f1: Option<F1Config>,
+    /// unread mail per imap folder, enabled when section is present
+    pub imap: Option<ImapConfig>,
+    /// Home Assistant entity states, enabled when section is present
+    pub homeassistant: Option<HomeAssistantConfig>,
+    /// octoprint or moonraker print progress, enabled when section is present
+    pub printer: Option<PrinterConfig>,
+    /// pi-hole queries blocked today, enabled when section is present
+    pub pihole: Option<PiHoleConfig>,
+    /// status and eta of tracked parcels, enabled when section is present
+    pub parcels: Option<ParcelsConfig>,
+    /// cpu, memory and load of this machine, enabled when section is present
+    pub system: Option<SystemConfig>,
+    /// utilization, vram and temperature of gpus, enabled when section is present
+    pub gpu: Option<GpuConfig>,
+    /// cpu temperature and fan speed, enabled when section is present
+    pub sensors: Option<SensorsConfig>,
+    /// free space of mounted disks, enabled when section is present
+    pub disk: Option<DiskConfig>,
+    /// charge of laptop battery, enabled when section is present
+    pub battery: Option<BatteryConfig>,
+    /// battery levels of bluetooth devices, enabled when section is present
+    pub bluetooth: Option<BluetoothConfig>,
+    /// throughput of network interfaces, enabled when section is present
+    pub network: Option<NetworkConfig>,
+    /// latency of watched hosts, enabled when section is present
+    pub ping: Option<PingConfig>,
+    /// periodic internet speed test, enabled when section is present
+    pub speedtest: Option<SpeedtestConfig>,
+    /// lines printed by user's command, enabled when section is present
+    pub exec: Option<ExecConfig>,
+    /// currently playing track, enabled when section is present
+    pub media: Option<MediaConfig>,
+    /// values of mqtt topics, enabled when section is present
+    pub mqtt: Option<MqttConfig>,
+    /// lines pushed over http, enabled when section is present
+    pub push: Option<PushConfig>,
+    /// pomodoro timer controlled from cli and keyboard, enabled when section
+    /// is present
+    pub pomodoro: Option<PomodoroConfig>,
+    /// local time and extra timezones, enabled when section is present
+    pub clock: Option<ClockConfig>,
+    /// plugin providers, loaded from native libraries and wasm modules in
+    /// its path when section is present
+    pub plugins: Option<PluginsConfig>,
+    /// price thresholds, alerting keyboard and desktop when crossed
+    pub alerts: Option<AlertConfig>,
+    /// rolling history of metrics sent as sparklines, enabled when section
+    /// is present
+    pub history: Option<HistoryConfig>,
+    /// prometheus `/metrics` endpoint, enabled when section is present
+    pub metrics: Option<MetricsConfig>,
+    /// line layout per provider name, ex. `stocks = "{symbol:<5}{price:>6.1}"`
+    pub templates: BTreeMap<String, String>,
+    /// scrolling of lines longer than display, enabled when section is present
+    pub scroll: Option<ScrollConfig>,
+    /// extra unicode to font byte mappings, for boards with custom font
+    pub encoding: BTreeMap<char, u8>,
+    /// How long every page stays on display in seconds
+    pub page_secs: u64,
+    /// screens rotated on display, without pages everything is on one screen
+    pub pages: Vec<PageConfig>,
+    /// page name to names of keyboards it is sent to, ex.
+    /// `system = ["macropad"]`. Pages without route go to every keyboard
+    /// which doesn't list its own `pages`
+    pub routes: BTreeMap<String, Vec<String>>,
+    /// whole page is sent every this many updates, only changed rows in
+    /// between. Set with `--full-refresh-every`, not read from file
+    #[serde(skip)]
+    pub full_refresh_every: u32,
+}
+
+/// `[[pages]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageConfig {
+    pub name: String,
+    /// names of providers drawn on page, ex. `["stocks", "crypto"]`
+    pub providers: Vec<String>,
+}
+
+/// `[[keyboards]]` entry. Ids which aren't given are taken from `[device]`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyboardConfig {
+    /// shown in logs and `elora_hid status`
+    pub name: String,
+    pub serial: Option<String>,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// names of `[[pages]]` rotated on this keyboard, all when empty
+    #[serde(default)]
+    pub pages: Vec<String>,
+}
+
+/// Keyboard with its ids and pages resolved, see [`Config::keyboards`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyboard {
+    pub name: String,
+    pub device: DeviceConfig,
+    /// page names, all pages when empty
+    pub pages: Vec<String>,
+}
+
+/// ids used to find keyboard between connected usb devices. Defaults match
+/// Elora, any other QMK board with raw hid enabled works by setting its own
+/// vendor and product id (see `elora_hid list-devices`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// raw hid interface usage, same for every QMK board
+    pub usage: u16,
+    pub usage_page: u16,
+    /// picks one of several connected boards with same ids
+    pub serial: Option<String>,
+    /// how often to check if keyboard got disconnected or reconnected in seconds
+    pub poll_secs: u64,
+    /// ask firmware for its protocol version before sending data, turn off
+    /// for firmware which predates handshake
+    pub handshake: bool,
+    /// layout pages are sent in, binary needs protocol version 4 firmware
+    /// and falls back to text for older one
+    pub payload: Payload,
+}
+
+/// Layout of page payload, see `docs/PROTOCOL.md`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    /// ascii text firmware draws as is
+    #[default]
+    Text,
+    /// typed records of [`protocol::binary`](crate::protocol::binary)
+    Binary,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            tickers: stocks::TICKERS.iter().map(|t| t.to_string()).collect(),
+            refresh_secs: scheduler::REFRESH_RATE_SECS.into(),
+            intervals: BTreeMap::new(),
+            log_level: "info".into(),
+            device: DeviceConfig::default(),
+            keyboards: Vec::new(),
+            retry: RetryConfig::default(),
+            quotes: None,
+            market: None,
+            portfolio: None,
+            fx: None,
+            crypto: None,
+            feargreed: None,
+            wallets: None,
+            exchange: None,
+            weather: None,
+            air: None,
+            astro: None,
+            alphavantage: None,
+            finnhub: None,
+            earnings: None,
+            dividends: None,
+            github: None,
+            ci: None,
+            gitlab: None,
+            jenkins: None,
+            jira: None,
+            calendar: None,
+            countdown: None,
+            electricity: None,
+            transit: None,
+            headlines: None,
+            kubernetes: None,
+            docker: None,
+            prometheus: None,
+            oncall: None,
+            sentry: None,
+            downloads: None,
+            live: None,
+            sports: None,
+            f1: None,
+            imap: None,
+            homeassistant: None,
+            printer: None,
+            pihole: None,
+            parcels: None,
+            system: None,
+            gpu: None,
+            sensors: None,
+            disk: None,
+            battery: None,
+            bluetooth: None,
+            network: None,
+            ping: None,
+            speedtest: None,
+            exec: None,
+            media: None,
+            mqtt: None,
+            push: None,
+            pomodoro: None,
+            clock: None,
+            plugins: None,
+            alerts: None,
+            history: None,
+            metrics: None,
+            templates: BTreeMap::new(),
+            scroll: None,
+            encoding: BTreeMap::new(),
+            page_secs: 10,
+            pages: Vec::new(),
+            routes: BTreeMap::new(),
+            full_refresh_every: render::delta::FULL_REFRESH_EVERY,
+        }
+    }
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            vendor_id: hid::VENDOR_ID,
+            product_id: hid::PRODUCT_ID,
+            usage: hid::USAGE_ID,
+            usage_page: hid::USAGE_PAGE,
+            serial: None,
+            poll_secs: 2,
+            handshake: true,
+            payload: Payload::Text,
+        }
+    }
+}
+
+impl DeviceConfig {
+    /// checks if usb interface with given ids is the configured keyboard
+    pub fn matches(
+        &self,
+        vendor_id: u16,
+        product_id: u16,
+        usage_page: u16,
+        usage: u16,
+        serial: Option<&str>,
+    ) -> bool {
+        vendor_id == self.vendor_id
+            && product_id == self.product_id
+            && usage_page == self.usage_page
+            && usage == self.usage
+            && match &self.serial {
+                Some(expected) => serial == Some(expected.as_str()),
+                None => true,
+            }
+    }
+}
+
+/// `~/.config/elora_hid/config.toml`, respecting `XDG_CONFIG_HOME`
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("elora_hid").join("config.toml"))
+}
+
+/// File config is loaded from: given path, or default location when there
+/// is file at it
+pub fn resolve_path(path: Option<&Path>) -> Option<PathBuf> {
+    match path {
+        Some(path) => Some(path.to_path_buf()),
+        None => default_path().filter(|path| path.exists()),
+    }
+}
+
+impl Config {
+    /// Loads config from given path. Without path default location is tried
+    /// and if there is no file defaults are used
+    pub fn load(path: Option<&Path>) -> Result<Config, EloraError> {
+        let Some(path) = resolve_path(path) else {
+            return Ok(Config::default());
+        };
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            EloraError::ConfigInvalid(format!("unable to read {}: {}", path.display(), e))
+        })?;
+        Config::from_toml(&content).map_err(|e| match e {
+            EloraError::ConfigInvalid(message) => {
+                EloraError::ConfigInvalid(format!("{}: {}", path.display(), message))
+            }
+            other => other,
+        })
+    }
+
+    /// Parses and validates config from toml string
+    pub fn from_toml(content: &str) -> Result<Config, EloraError> {
+        let config: Config =
+            toml::from_str(content).map_err(|e| EloraError::ConfigInvalid(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parsed `[templates]` entry of provider
+    pub fn template(&self, provider: &str) -> Option<Template> {
+        Template::parse(self.templates.get(provider)?).ok()
+    }
+
+    /// Stock tickers and portfolio holdings, market hours apply to them
+    pub fn stock_tickers(&self) -> impl Iterator<Item = &String> {
+        self.tickers
+            .iter()
+            .chain(self.portfolio.iter().flat_map(|p| p.holdings.keys()))
+    }
+
+    /// Names of providers enabled in config, same as `DataProvider::name`.
+    /// Plugins come last, named after their libraries
+    pub fn provider_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if !self.tickers.is_empty() {
+            names.push("stocks");
+        }
+        if self.portfolio.is_some() {
+            names.push("portfolio");
+        }
+        if self.fx.is_some() {
+            names.push("fx");
+        }
+        if self.crypto.is_some() {
+            names.push("crypto");
+        }
+        if self.feargreed.is_some() {
+            names.push("feargreed");
+        }
+        if self.wallets.is_some() {
+            names.push("wallets");
+        }
+        if self.exchange.is_some() {
+            names.push("exchange");
+        }
+        if self.weather.is_some() {
+            names.push("weather");
+        }
+        if self.air.is_some() {
+            names.push("air");
+        }
+        if self.astro.is_some() {
+            names.push("astro");
+        }
+        if self.alphavantage.is_some() {
+            names.push("alphavantage");
+        }
+        if self.finnhub.is_some() {
+            names.push("finnhub");
+        }
+        if self.earnings.is_some() {
+            names.push("earnings");
+        }
+        if self.dividends.is_some() {
+            names.push("dividends");
+        }
+        if self.github.is_some() {
+            names.push("github");
+        }
+        if self.ci.is_some() {
+            names.push("ci");
+        }
+        if self.gitlab.is_some() {
+            names.push("gitlab");
+        }
+        if self.jenkins.is_some() {
+            names.push("jenkins");
+        }
+        if self.jira.is_some() {
+            names.push("jira");
+        }
+        if self.calendar.is_some() {
+            names.push("calendar");
+        }
+        if self.countdown.is_some() {
+            names.push("countdown");
+        }
+        if self.electricity.is_some() {
+            names.push("electricity");
+        }
+        if self.transit.is_some() {
+            names.push("transit");
+        }
+        if self.headlines.is_some() {
+            names.push("headlines");
+        }
+        if self.kubernetes.is_some() {
+            names.push("kubernetes");
+        }
+        if self.docker.is_some() {
+            names.push("docker");
+        }
+        if self.prometheus.is_some() {
+            names.push("prometheus");
+        }
+        if self.oncall.is_some() {
+            names.push("oncall");
+        }
+        if self.sentry.is_some() {
+            names.push("sentry");
+        }
+        if self.downloads.is_some() {
+            names.push("downloads");
+        }
+        if self.live.is_some() {
+            names.push("live");
+        }
+        if self.sports.is_some() {
+            names.push("sports");
+        }
+        if self.f1.is_some() {
+            names.push("f1");
+        }
+        if self.imap.is_some() {
+            names.push("imap");
+        }
+        if self.homeassistant.is_some() {
+            names.push("homeassistant");
+        }
+        if self.printer.is_some() {
+            names.push("printer");
+        }
+        if self.pihole.is_some() {
+            names.push("pihole");
+        }
+        if self.parcels.is_some() {
+            names.push("parcels");
+        }
+        if self.system.is_some() {
+            names.push("system");
+        }
+        if self.gpu.is_some() {
+            names.push("gpu");
+        }
+        if self.sensors.is_some() {
+            names.push("sensors");
+        }
+        if self.disk.is_some() {
+            names.push("disk");
+        }
+        if self.battery.is_some() {
+            names.push("battery");
+        }
+        if self.bluetooth.is_some() {
+            names.push("bluetooth");
+        }
+        if self.network.is_some() {
+            names.push("network");
+        }
+        if self.ping.is_some() {
+            names.push("ping");
+        }
+        if self.speedtest.is_some() {
+            names.push("speedtest");
+        }
+        if self.exec.is_some() {
+            names.push("exec");
+        }
+        if self.media.is_some() {
+            names.push("media");
+        }
+        if self.mqtt.is_some() {
+            names.push("mqtt");
+        }
+        if self.push.is_some() {
+            names.push("push");
+        }
+        if self.pomodoro.is_some() {
+            names.push("pomodoro");
+        }
+        if self.clock.is_some() {
+            names.push("clock");
+        }
+        let mut names: Vec<String> = names.into_iter().map(String::from).collect();
+        if let Some(plugins) = &self.plugins {
+            names.extend(plugins.discover().into_iter().map(|(name, _)| name));
+        }
+        names
+    }
+
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let provider_names = self.provider_names();
+        if provider_names.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "at least one ticker or provider section is required".into(),
+            ));
+        }
+        if let Some(ticker) = self.tickers.iter().find(|t| t.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "ticker {:?} can't be empty",
+                ticker
+            )));
+        }
+        if self.refresh_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "refresh_secs must be greater than 0".into(),
+            ));
+        }
+        for (name, &secs) in &self.intervals {
+            if !provider_names.contains(name) {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "intervals.{} is not enabled provider",
+                    name
+                )));
+            }
+            if secs == 0 {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "intervals.{} must be greater than 0",
+                    name
+                )));
+            }
+        }
+        if self.device.poll_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "device.poll_secs must be greater than 0".into(),
+            ));
+        }
+        if !LOG_LEVELS.contains(&self.log_level.to_lowercase().as_str()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "log_level {:?} is not one of {}",
+                self.log_level,
+                LOG_LEVELS.join(", ")
+            )));
+        }
+        for (name, template) in &self.templates {
+            if !provider_names.contains(name) {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "templates.{} is not enabled provider",
+                    name
+                )));
+            }
+            Template::parse(template)
+                .map_err(|e| EloraError::ConfigInvalid(format!("templates.{}: {}", name, e)))?;
+        }
+        self.retry.validate()?;
+        if let Some(scroll) = &self.scroll {
+            scroll.validate()?;
+        }
+        if let Some(market) = &self.market {
+            market.validate()?;
+            if let Some(page) = &market.closed_page {
+                if !self.pages.iter().any(|p| &p.name == page) {
+                    return Err(EloraError::ConfigInvalid(format!(
+                        "market.closed_page {:?} is not one of pages",
+                        page
+                    )));
+                }
+            }
+        }
+        if let Some(alerts) = &self.alerts {
+            alerts.validate()?;
+        }
+        if let Some(history) = &self.history {
+            history.validate()?;
+        }
+        if let Some(portfolio) = &self.portfolio {
+            portfolio.validate()?;
+        }
+        if let Some(fx) = &self.fx {
+            fx.validate()?;
+        }
+        if let Some(crypto) = &self.crypto {
+            crypto.validate()?;
+        }
+        if let Some(feargreed) = &self.feargreed {
+            feargreed.validate()?;
+        }
+        if let Some(wallets) = &self.wallets {
+            wallets.validate()?;
+        }
+        if let Some(exchange) = &self.exchange {
+            exchange.validate()?;
+        }
+        if let Some(weather) = &self.weather {
+            weather.validate()?;
+        }
+        if let Some(air) = &self.air {
+            air.validate()?;
+        }
+        if let Some(astro) = &self.astro {
+            astro.validate()?;
+        }
+        if let Some(alphavantage) = &self.alphavantage {
+            alphavantage.validate()?;
+        }
+        if let Some(finnhub) = &self.finnhub {
+            finnhub.validate()?;
+        }
+        if let Some(earnings) = &self.earnings {
+            earnings.validate()?;
+        }
+        if let Some(dividends) = &self.dividends {
+            dividends.validate()?;
+        }
+        if let Some(github) = &self.github {
+            github.validate()?;
+        }
+        if let Some(ci) = &self.ci {
+            ci.validate()?;
+        }
+        if let Some(gitlab) = &self.gitlab {
+            gitlab.validate()?;
+        }
+        if let Some(jenkins) = &self.jenkins {
+            jenkins.validate()?;
+        }
+        if let Some(jira) = &self.jira {
+            jira.validate()?;
+        }
+        if let Some(calendar) = &self.calendar {
+            calendar.validate()?;
+        }
+        if let Some(countdown) = &self.countdown {
+            countdown.validate()?;
+        }
+        if let Some(electricity) = &self.electricity {
+            electricity.validate()?;
+        }
+        if let Some(transit) = &self.transit {
+            transit.validate()?;
+        }
+        if let Some(headlines) = &self.headlines {
+            headlines.validate()?;
+        }
+        if let Some(kubernetes) = &self.kubernetes {
+            kubernetes.validate()?;
+        }
+        if let Some(docker) = &self.docker {
+            docker.validate()?;
+        }
+        if let Some(prometheus) = &self.prometheus {
+            prometheus.validate()?;
+        }
+        if let Some(oncall) = &self.oncall {
+            oncall.validate()?;
+        }
+        if let Some(sentry) = &self.sentry {
+            sentry.validate()?;
+        }
+        if let Some(downloads) = &self.downloads {
+            downloads.validate()?;
+        }
+        if let Some(live) = &self.live {
+            live.validate()?;
+        }
+        if let Some(sports) = &self.sports {
+            sports.validate()?;
+        }
+        if let Some(f1) = &self.f1 {
+            f1.validate()?;
+        }
+        if let Some(imap) = &self.imap {
+            imap.validate()?;
+        }
+        if let Some(homeassistant) = &self.homeassistant {
+            homeassistant.validate()?;
+        }
+        if let Some(printer) = &self.printer {
+            printer.validate()?;
+        }
+        if let Some(pihole) = &self.pihole {
+            pihole.validate()?;
+        }
+        if let Some(parcels) = &self.parcels {
+            parcels.validate()?;
+        }
+        if let Some(system) = &self.system {
+            system.validate()?;
+        }
+        if let Some(gpu) = &self.gpu {
+            gpu.validate()?;
+        }
+        if let Some(sensors) = &self.sensors {
+            sensors.validate()?;
+        }
+        if let Some(disk) = &self.disk {
+            disk.validate()?;
+        }
+        if let Some(battery) = &self.battery {
+            battery.validate()?;
+        }
+        if let Some(bluetooth) = &self.bluetooth {
+            bluetooth.validate()?;
+        }
+        if let Some(network) = &self.network {
+            network.validate()?;
+        }
+        if let Some(ping) = &self.ping {
+            ping.validate()?;
+        }
+        if let Some(speedtest) = &self.speedtest {
+            speedtest.validate()?;
+        }
+        if let Some(exec) = &self.exec {
+            exec.validate()?;
+        }
+        if let Some(plugins) = &self.plugins {
+            plugins.validate()?;
+            if let Some(name) = provider_names
+                .iter()
+                .enumerate()
+                .find(|(i, name)| provider_names[..*i].contains(name))
+                .map(|(_, name)| name)
+            {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "plugin {:?} has same name as built in provider",
+                    name
+                )));
+            }
+        }
+        if let Some(media) = &self.media {
+            media.validate()?;
+        }
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.validate()?;
+        }
+        if let Some(push) = &self.push {
+            push.validate()?;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.validate()?;
+        }
+        if let Some(pomodoro) = &self.pomodoro {
+            pomodoro.validate()?;
+        }
+        if let Some(clock) = &self.clock {
+            clock.validate()?;
+        }
+        if let Some(quotes) = &self.quotes {
+            quotes.validate()?;
+        }
+        if self.page_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "page_secs must be greater than 0".into(),
+            ));
+        }
+        self.validate_keyboards()?;
+        if self.pages.len() > u8::MAX as usize {
+            return Err(EloraError::ConfigInvalid(format!(
+                "at most {} pages are supported",
+                u8::MAX
+            )));
+        }
+        for page in &self.pages {
+            if page.providers.is_empty() {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "page {:?} needs at least one provider",
+                    page.name
+                )));
+            }
+            if let Some(name) = page
+                .providers
+                .iter()
+                .find(|name| !provider_names.contains(name))
+            {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "page {:?} uses provider {:?} which is not enabled",
+                    page.name, name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Keyboards data is sent to: `[[keyboards]]` merged over `[device]`, or
+    /// `[device]` alone named `default`
+    pub fn keyboards(&self) -> Vec<Keyboard> {
+        if self.keyboards.is_empty() {
+            return vec![Keyboard {
+                name: "default".into(),
+                device: self.device.clone(),
+                pages: Vec::new(),
+            }];
+        }
+        self.keyboards
+            .iter()
+            .map(|keyboard| Keyboard {
+                name: keyboard.name.clone(),
+                device: DeviceConfig {
+                    vendor_id: keyboard.vendor_id.unwrap_or(self.device.vendor_id),
+                    product_id: keyboard.product_id.unwrap_or(self.device.product_id),
+                    serial: keyboard.serial.clone().or(self.device.serial.clone()),
+                    ..self.device.clone()
+                },
+                pages: keyboard.pages.clone(),
+            })
+            .collect()
+    }
+
+    fn validate_keyboards(&self) -> Result<(), EloraError> {
+        let keyboards = self.keyboards();
+        for (i, keyboard) in keyboards.iter().enumerate() {
+            let invalid = |message: &str| {
+                Err(EloraError::ConfigInvalid(format!(
+                    "keyboard {:?} {}",
+                    keyboard.name, message
+                )))
+            };
+            if let Some(name) = keyboard
+                .pages
+                .iter()
+                .find(|name| !self.pages.iter().any(|page| &page.name == *name))
+            {
+                return invalid(&format!("shows page {:?} which is not one of pages", name));
+            }
+            for other in &keyboards[..i] {
+                if other.name == keyboard.name {
+                    return invalid("is listed twice");
+                }
+                let device = &keyboard.device;
+                if (other.device.vendor_id, other.device.product_id)
+                    == (device.vendor_id, device.product_id)
+                    && (other.device.serial.is_none()
+                        || device.serial.is_none()
+                        || other.device.serial == device.serial)
+                {
+                    return invalid(&format!(
+                        "has same ids as {:?}, set serial of both (see `elora_hid list-devices`)",
+                        other.name
+                    ));
+                }
+            }
+        }
+        for (page, names) in &self.routes {
+            if !self.pages.iter().any(|p| &p.name == page) {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "routes.{} is not one of pages",
+                    page
+                )));
+            }
+            if let Some(name) = names
+                .iter()
+                .find(|name| !keyboards.iter().any(|keyboard| &keyboard.name == *name))
+            {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "routes.{} sends to unknown keyboard {:?}",
+                    page, name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn testing_config_parsing() {
+    let config = Config::from_toml(
+        r#"
+        tickers = ["AAPL"]
+        refresh_secs = 30
+
+        [device]
+        product_id = 0x1234
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.tickers, vec!["AAPL"]);
+    assert_eq!(config.refresh_secs, 30);
+    assert_eq!(config.log_level, "info");
+    assert_eq!(config.device.vendor_id, hid::VENDOR_ID);
+    assert_eq!(config.device.product_id, 0x1234);
+
+    let config = Config::from_toml("[encoding]\n\"°\" = 0xF8").unwrap();
+    assert_eq!(config.encoding.get(&'°'), Some(&0xF8));
+}
+
+#[test]
+fn testing_example_config() {
+    let config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+    assert_eq!(config, Config::default());
+}
+
+#[test]
+fn testing_device_matching() {
+    let mut ids = DeviceConfig::default();
+    assert!(ids.matches(0x8d1d, 0x9d9d, 0xFF60, 0x61, None));
+    assert!(!ids.matches(0x8d1d, 0x9d9d, 0x0001, 0x06, None));
+
+    ids.serial = Some("elora-home".into());
+    assert!(ids.matches(0x8d1d, 0x9d9d, 0xFF60, 0x61, Some("elora-home")));
+    assert!(!ids.matches(0x8d1d, 0x9d9d, 0xFF60, 0x61, Some("kyria-work")));
+    assert!(!ids.matches(0x8d1d, 0x9d9d, 0xFF60, 0x61, None));
+}
+
+#[test]
+fn testing_keyboards() {
+    let config = Config::from_toml(
+        r#"
+        [[pages]]
+        name = "stocks"
+        providers = ["stocks"]
+
+        [[keyboards]]
+        name = "home"
+        serial = "elora-home"
+
+        [[keyboards]]
+        name = "work"
+        product_id = 0x1234
+        pages = ["stocks"]
+        "#,
+    )
+    .unwrap();
+    let keyboards = config.keyboards();
+    assert_eq!(keyboards[0].device.serial.as_deref(), Some("elora-home"));
+    assert_eq!(keyboards[0].device.product_id, hid::PRODUCT_ID);
+    assert_eq!(keyboards[1].device.product_id, 0x1234);
+    assert_eq!(keyboards[1].pages, vec!["stocks"]);
+    assert_eq!(Config::default().keyboards()[0].name, "default");
+
+    let same_ids =
+        "[[keyboards]]\nname = \"home\"\n[[keyboards]]\nname = \"work\"\nserial = \"kyria\"";
+    assert!(Config::from_toml(same_ids).is_err());
+    assert!(Config::from_toml("[[keyboards]]\nname = \"home\"\npages = [\"crypto\"]").is_err());
+}
+
+#[test]
+fn testing_config_validation() {
+    assert!(Config::from_toml("tickers = []").is_err());
+    assert!(Config::from_toml("refresh_secs = 0").is_err());
+    assert!(Config::from_toml("log_level = \"loud\"").is_err());
+    assert!(Config::from_toml("ticker = [\"TSLA\"]").is_err());
+    assert!(Config::from_toml("[crypto]\ncoins = []").is_err());
+    assert!(Config::from_toml("[retry]\nattempts = 0").is_err());
+    assert!(Config::from_toml("[market]\nclosed_page = \"crypto\"").is_err());
+    assert!(Config::from_toml("[market.holidays]\nNYSE = [\"2024-12-25\"]").is_ok());
+    assert!(Config::from_toml("[templates]\nstocks = \"{price:x}\"").is_err());
+    assert!(Config::from_toml("[templates]\ncrypto = \"{price}\"").is_err());
+    assert!(Config::from_toml("[alerts]\nrules = [\"TSLA = 300\"]").is_err());
+    assert!(Config::from_toml("tickers = []\n[crypto]").is_ok());
+    assert!(
+        Config::from_toml("[portfolio.holdings]\nTSLA = { shares = 10, cost_basis = 180 }").is_ok()
+    );
+    assert_eq!(Config::from_toml("").unwrap(), Config::default());
+}
@@ -0,0 +1,53 @@
+//! Errors returned by this crate
+//!
+//! [`EloraError`] is what library functions fail with, so consumers can match
+//! on what went wrong. Providers fail with any boxed error, it gets wrapped
+//! into [`EloraError::FetchFailed`] together with provider name.
+
+use std::error::Error;
+
+use thiserror::Error;
+
+use crate::protocol::framing::FrameError;
+
+/// any error, Send + Sync so it can cross await points and tasks
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum EloraError {
+    /// no connected usb device matches configured ids
+    #[error("keyboard not found")]
+    DeviceNotFound,
+    /// keyboard was found, but writing report to it failed
+    #[error("unable to write to keyboard: {0}")]
+    WriteFailed(#[source] hidapi::HidError),
+    /// hidapi failed outside of writing, ex. opening or reading device
+    #[error("hid error: {0}")]
+    Hid(#[from] hidapi::HidError),
+    /// firmware didn't answer handshake or speaks too old protocol, `0` when
+    /// there was no answer
+    #[error("keyboard firmware protocol version {0} is not supported, flash firmware speaking version {min} or newer", min = crate::protocol::MIN_PROTOCOL_VERSION)]
+    UnsupportedFirmware(u8),
+    /// message doesn't fit into frames or keyboard sent malformed frame
+    #[error("protocol error: {0}")]
+    Protocol(#[from] FrameError),
+    #[error("provider {provider} failed to fetch: {source}")]
+    FetchFailed {
+        provider: String,
+        #[source]
+        source: BoxError,
+    },
+    /// none of providers returned any lines
+    #[error("no data fetched from providers")]
+    NoData,
+    /// keyboard or file can't be opened by current user, message says how
+    /// to grant access
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("invalid config: {0}")]
+    ConfigInvalid(String),
+    /// reading or writing local files failed
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
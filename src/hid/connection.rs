@@ -0,0 +1,196 @@
+//! Opened raw hid interface of keyboard, used both for sending and receiving
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use hidapi::HidApi;
+use tokio::sync::mpsc;
+
+use super::{find_elora_device, udev, HidTransport};
+use crate::{
+    config::DeviceConfig,
+    metrics::METRICS,
+    protocol::{
+        framing::{self, Decoder},
+        Command, Message, REPORT_SIZE,
+    },
+    EloraError,
+};
+
+/// how long single read holds device lock, so writes in between aren't delayed
+const READ_SLICE: Duration = Duration::from_millis(50);
+
+/// Connection to keyboard. Methods take `&self`, so connection can be shared
+/// in `Arc` between reader thread and senders
+pub struct KeyboardConnection {
+    device: Mutex<Box<dyn HidTransport>>,
+    decoder: Mutex<Decoder>,
+}
+
+impl KeyboardConnection {
+    /// Opens first connected device matching ids. Failure caused by missing
+    /// hidraw permissions is reported as [`EloraError::PermissionDenied`]
+    pub fn open(api: &HidApi, ids: &DeviceConfig) -> Result<Self, EloraError> {
+        let info = find_elora_device(api, ids).ok_or(EloraError::DeviceNotFound)?;
+        let device = info
+            .open_device(api)
+            .map_err(|e| udev::check_access(info).err().unwrap_or(e.into()))?;
+        Ok(KeyboardConnection::with_transport(device))
+    }
+
+    /// connection over any transport, ex. [`MockTransport`](super::MockTransport) in tests
+    pub fn with_transport(transport: impl HidTransport + 'static) -> Self {
+        KeyboardConnection {
+            device: Mutex::new(Box::new(transport)),
+            decoder: Mutex::new(Decoder::new()),
+        }
+    }
+
+    /// sends message split into 32 byte raw hid frames
+    pub fn send(&self, message: &Message) -> Result<(), EloraError> {
+        let frames = framing::encode(message)?;
+        {
+            let device = self.device.lock().unwrap();
+            for frame in &frames {
+                device.write(frame).map_err(|e| {
+                    METRICS.write_failed();
+                    EloraError::WriteFailed(e)
+                })?;
+                METRICS.sent(frame.len());
+            }
+        }
+
+        log::debug!(
+            "{:?} in {} frames: {}",
+            message.command,
+            frames.len(),
+            String::from_utf8_lossy(&message.payload)
+        );
+        Ok(())
+    }
+
+    /// Sends hello and waits up to `timeout` for version firmware answers
+    /// with. Other messages arriving meanwhile are skipped, `None` when
+    /// keyboard didn't answer
+    pub fn handshake(&self, timeout: Duration) -> Result<Option<u8>, EloraError> {
+        self.send(&Message::hello())?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.recv(remaining)? {
+                Some(message) if message.command == Command::Version => {
+                    return Ok(message.payload.first().copied());
+                }
+                Some(message) => log::debug!("Skipping {:?} during handshake", message.command),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for whole message from keyboard. Malformed
+    /// frames are logged and skipped
+    pub fn recv(&self, timeout: Duration) -> Result<Option<Message>, EloraError> {
+        let deadline = Instant::now() + timeout;
+        let mut report = [0u8; REPORT_SIZE];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+
+            let read = {
+                let device = self.device.lock().unwrap();
+                device.read_timeout(&mut report, remaining.min(READ_SLICE).as_millis() as i32)?
+            };
+            if read == 0 {
+                continue;
+            }
+
+            match self.decoder.lock().unwrap().push(&report[..read]) {
+                Ok(Some(message)) => return Ok(Some(message)),
+                Ok(None) => {}
+                Err(e) => log::warn!("Dropping frame from keyboard: {}", e),
+            }
+        }
+    }
+}
+
+/// Reads messages from keyboard on background thread until read fails (ex.
+/// keyboard unplugged) or receiver is dropped
+pub fn spawn_reader(connection: Arc<KeyboardConnection>) -> mpsc::Receiver<Message> {
+    let (tx, rx) = mpsc::channel(16);
+
+    thread::spawn(move || loop {
+        match connection.recv(Duration::from_millis(500)) {
+            Ok(Some(message)) => {
+                if tx.blocking_send(message).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {
+                if tx.is_closed() {
+                    return;
+                }
+            }
+            Err(e) => {
+                log::warn!("Stopped reading from keyboard: {}", e);
+                return;
+            }
+        }
+    });
+
+    rx
+}
+
+#[test]
+fn testing_connection_over_mock() {
+    use super::MockTransport;
+
+    let mock = MockTransport::new();
+    let connection = KeyboardConnection::with_transport(mock.clone());
+
+    let refresh = Message::new(Command::Refresh, Vec::new());
+    for frame in framing::encode(&refresh).unwrap() {
+        mock.push_incoming(&frame[1..]);
+    }
+    let received = connection.recv(Duration::from_millis(10)).unwrap();
+    assert_eq!(received, Some(refresh));
+    assert_eq!(connection.recv(Duration::from_millis(10)).unwrap(), None);
+
+    mock.fail_writes(true);
+    assert!(matches!(
+        connection.send(&Message::new(Command::Clear, Vec::new())),
+        Err(EloraError::WriteFailed(_))
+    ));
+    assert!(mock.written().is_empty());
+}
+
+#[test]
+fn testing_handshake() {
+    use super::MockTransport;
+    use crate::protocol::PROTOCOL_VERSION;
+
+    let mock = MockTransport::new();
+    let connection = KeyboardConnection::with_transport(mock.clone());
+    assert_eq!(
+        connection.handshake(Duration::from_millis(10)).unwrap(),
+        None
+    );
+    assert_eq!(mock.messages().unwrap(), vec![Message::hello()]);
+
+    for message in [
+        Message::new(Command::Ack, vec![Command::Hello as u8]),
+        Message::new(Command::Version, vec![1]),
+    ] {
+        for frame in framing::encode(&message).unwrap() {
+            mock.push_incoming(&frame[1..]);
+        }
+    }
+    let version = connection.handshake(Duration::from_millis(10)).unwrap();
+    assert_eq!(version, Some(1));
+    assert_eq!(mock.messages().unwrap()[1].payload, vec![PROTOCOL_VERSION]);
+}
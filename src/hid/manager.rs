@@ -0,0 +1,171 @@
+//! Long-lived keyboard connection shared by everything talking to keyboard
+//!
+//! Opening device is slow and can race with other hid users, so connection
+//! is opened once and reused until writing to it fails. Every opened
+//! connection starts with version handshake, messages firmware's protocol
+//! version doesn't know are dropped. In dry-run mode frames are printed to
+//! stdout instead, so no keyboard is needed.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hidapi::HidApi;
+
+use super::KeyboardConnection;
+use crate::{
+    config::DeviceConfig,
+    protocol::{self, framing, Message, PROTOCOL_VERSION},
+    EloraError,
+};
+
+/// how long firmware has to answer hello
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Opened connection with result of its handshake, firmware version which
+/// isn't supported is kept so keyboard isn't reopened on every send
+struct Opened {
+    connection: Arc<KeyboardConnection>,
+    version: Result<u8, u8>,
+}
+
+pub struct ConnectionManager {
+    ids: DeviceConfig,
+    connection: Mutex<Option<Opened>>,
+    dry_run: bool,
+}
+
+impl ConnectionManager {
+    pub fn new(ids: DeviceConfig) -> Self {
+        ConnectionManager {
+            ids,
+            connection: Mutex::new(None),
+            dry_run: false,
+        }
+    }
+
+    /// manager which prints frames of sent messages instead of writing them
+    pub fn dry_run(ids: DeviceConfig) -> Self {
+        ConnectionManager {
+            dry_run: true,
+            ..ConnectionManager::new(ids)
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Opened connection, opening it and doing handshake first if there is
+    /// none. Fails with [`EloraError::UnsupportedFirmware`] until reset when
+    /// firmware is too old
+    pub fn connection(&self) -> Result<Arc<KeyboardConnection>, EloraError> {
+        if self.dry_run {
+            return Err(EloraError::DeviceNotFound);
+        }
+        let mut opened = self.connection.lock().unwrap();
+        if opened.is_none() {
+            let api = HidApi::new()?;
+            let connection = Arc::new(KeyboardConnection::open(&api, &self.ids)?);
+            log::debug!("Opened keyboard connection");
+            let version = self.handshake(&connection)?;
+            *opened = Some(Opened {
+                connection,
+                version,
+            });
+        }
+        let opened = opened.as_ref().unwrap();
+        match opened.version {
+            Ok(_) => Ok(opened.connection.clone()),
+            Err(firmware) => Err(EloraError::UnsupportedFirmware(firmware)),
+        }
+    }
+
+    fn handshake(&self, connection: &KeyboardConnection) -> Result<Result<u8, u8>, EloraError> {
+        if !self.ids.handshake {
+            log::debug!(
+                "Handshake disabled, assuming protocol version {}",
+                PROTOCOL_VERSION
+            );
+            return Ok(Ok(PROTOCOL_VERSION));
+        }
+        let firmware = connection.handshake(HANDSHAKE_TIMEOUT)?.unwrap_or(0);
+        Ok(match protocol::negotiate(firmware) {
+            Some(version) => {
+                log::info!(
+                    "Keyboard speaks protocol version {}, using version {}",
+                    firmware,
+                    version
+                );
+                Ok(version)
+            }
+            None => {
+                log::error!("{}", EloraError::UnsupportedFirmware(firmware));
+                Err(firmware)
+            }
+        })
+    }
+
+    /// Protocol version agreed with keyboard, `None` before keyboard is
+    /// opened or when its firmware isn't supported
+    pub fn version(&self) -> Option<u8> {
+        if self.dry_run {
+            return Some(PROTOCOL_VERSION);
+        }
+        let opened = self.connection.lock().unwrap();
+        opened.as_ref().and_then(|opened| opened.version.ok())
+    }
+
+    /// Sends message on current connection. When write fails (ex. keyboard
+    /// was replugged and old handle is dead) connection is reopened and
+    /// message sent once more
+    pub fn send(&self, message: &Message) -> Result<(), EloraError> {
+        if self.dry_run {
+            return print_frames(message);
+        }
+        let connection = self.connection()?;
+        if let Some(version) = self.version() {
+            if message.command.since_version() > version {
+                log::debug!(
+                    "Dropping {:?}, keyboard speaks protocol version {}",
+                    message.command,
+                    version
+                );
+                return Ok(());
+            }
+            if let Some(downgraded) = message.downgrade(version) {
+                return self.send(&downgraded);
+            }
+        }
+        match connection.send(message) {
+            Err(EloraError::WriteFailed(e)) => {
+                log::warn!("Write failed ({}), reopening keyboard connection", e);
+                self.reset();
+                self.connection()?.send(message)
+            }
+            res => res,
+        }
+    }
+
+    /// drops current connection, next use opens new one
+    pub fn reset(&self) {
+        self.connection.lock().unwrap().take();
+    }
+}
+
+/// Prints message and its frames as hex, exactly as they'd be written
+fn print_frames(message: &Message) -> Result<(), EloraError> {
+    let frames = framing::encode(message)?;
+    println!(
+        "{:?} in {} frames: {}",
+        message.command,
+        frames.len(),
+        message.payload.escape_ascii()
+    );
+    for frame in &frames {
+        let hex: Vec<String> = frame.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("  {}", hex.join(" "));
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! USB raw hid transport to Elora keyboard
+
+use hidapi::{DeviceInfo, HidApi};
+
+use crate::{
+    config::DeviceConfig,
+    protocol::{Command, Message},
+    EloraError,
+};
+
+pub mod connection;
+pub mod manager;
+pub mod transport;
+pub mod udev;
+pub mod watcher;
+
+pub use connection::KeyboardConnection;
+pub use manager::ConnectionManager;
+pub use transport::{HidTransport, MockTransport};
+
+/// splitkb.com vendor id
+pub const VENDOR_ID: u16 = 0x8d1d;
+/// Elora product id
+pub const PRODUCT_ID: u16 = 0x9d9d;
+
+pub const USAGE_ID: u16 = 0x61;
+pub const USAGE_PAGE: u16 = 0xFF60;
+
+/// checks if device is raw hid interface of configured keyboard
+pub fn is_elora_device(dev: &DeviceInfo, ids: &DeviceConfig) -> bool {
+    ids.matches(
+        dev.vendor_id(),
+        dev.product_id(),
+        dev.usage_page(),
+        dev.usage(),
+        dev.serial_number(),
+    )
+}
+
+/// searches for connected elora keyboard
+pub fn find_elora_device<'a>(api: &'a HidApi, ids: &DeviceConfig) -> Option<&'a DeviceInfo> {
+    let device = api.device_list().find(|&dev| is_elora_device(dev, ids));
+    device
+}
+
+/// sends text buffer to keyboard as single page
+pub async fn send_to_keyboard(buf: Vec<u8>, ids: &DeviceConfig) -> Result<(), EloraError> {
+    log::info!("Sending to usb keyboard");
+
+    let connection = ConnectionManager::new(ids.clone()).connection()?;
+    send_text(&connection, &buf)
+}
+
+/// sends text buffer as single page on already opened connection
+pub fn send_text(connection: &KeyboardConnection, buf: &[u8]) -> Result<(), EloraError> {
+    connection.send(&Message::text(0, 1, buf))
+}
+
+/// clears keyboard display, so no stale data is left after exit
+pub async fn clear_display(ids: &DeviceConfig) -> Result<(), EloraError> {
+    send_message(&Message::new(Command::Clear, Vec::new()), ids).await
+}
+
+/// Sends message to keyboard split into 32 byte raw hid frames, on freshly
+/// opened and handshaken connection. Use [`ConnectionManager`] for repeated
+/// sends
+pub async fn send_message(message: &Message, ids: &DeviceConfig) -> Result<(), EloraError> {
+    log::info!("Sending to usb keyboard");
+
+    ConnectionManager::new(ids.clone()).send(message)
+}
+
+#[test]
+fn testing_send_text_frames() {
+    let mock = MockTransport::new();
+    let connection = KeyboardConnection::with_transport(mock.clone());
+    let lines = vec![crate::providers::Line::new("AAPL 190.5$ ▲1.2%"); 3];
+    let text = crate::render::convert_to_buffer(&lines);
+    send_text(&connection, &text).unwrap();
+
+    let written = mock.written();
+    assert!(written.len() > 1);
+    assert!(written
+        .iter()
+        .all(|r| r.len() == crate::protocol::REPORT_SIZE + 1 && r[0] == 0));
+    assert_eq!(mock.messages().unwrap(), vec![Message::text(0, 1, &text)]);
+}
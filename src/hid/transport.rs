@@ -0,0 +1,111 @@
+//! Raw report io under [`KeyboardConnection`](super::KeyboardConnection)
+//!
+//! Real keyboards are reached through `hidapi`, tests use [`MockTransport`]
+//! which records written frames, so sending and framing can be checked
+//! without hardware.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use hidapi::{HidDevice, HidError};
+
+use crate::protocol::{
+    framing::{Decoder, FrameError},
+    Message,
+};
+
+/// Writes and reads raw hid reports. Written reports start with report id
+/// byte like `hidapi` expects, read ones don't have it
+pub trait HidTransport: Send {
+    fn write(&self, report: &[u8]) -> Result<usize, HidError>;
+
+    /// reads single report into `buf`, returns 0 when nothing came in `timeout_ms`
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize, HidError>;
+}
+
+impl HidTransport for HidDevice {
+    fn write(&self, report: &[u8]) -> Result<usize, HidError> {
+        HidDevice::write(self, report)
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize, HidError> {
+        HidDevice::read_timeout(self, buf, timeout_ms)
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    written: Vec<Vec<u8>>,
+    incoming: VecDeque<Vec<u8>>,
+    fail_writes: bool,
+}
+
+/// In-memory transport. Clones share state, so one clone can be given to
+/// connection and other kept to inspect what was written
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// all reports written so far, with report id byte
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().written.clone()
+    }
+
+    /// written reports reassembled back into messages
+    pub fn messages(&self) -> Result<Vec<Message>, FrameError> {
+        let mut decoder = Decoder::new();
+        let mut messages = Vec::new();
+        for report in self.written() {
+            if let Some(message) = decoder.push(&report[1..])? {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
+
+    /// queues report to be returned by next read, as if sent by keyboard
+    pub fn push_incoming(&self, report: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .incoming
+            .push_back(report.to_vec());
+    }
+
+    /// makes following writes fail, like on unplugged keyboard
+    pub fn fail_writes(&self, fail: bool) {
+        self.state.lock().unwrap().fail_writes = fail;
+    }
+}
+
+impl HidTransport for MockTransport {
+    fn write(&self, report: &[u8]) -> Result<usize, HidError> {
+        let mut state = self.state.lock().unwrap();
+        if state.fail_writes {
+            return Err(HidError::HidApiError {
+                message: "mock write failed".to_string(),
+            });
+        }
+        state.written.push(report.to_vec());
+        Ok(report.len())
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], _timeout_ms: i32) -> Result<usize, HidError> {
+        match self.state.lock().unwrap().incoming.pop_front() {
+            Some(report) => {
+                let len = report.len().min(buf.len());
+                buf[..len].copy_from_slice(&report[..len]);
+                Ok(len)
+            }
+            None => Ok(0),
+        }
+    }
+}
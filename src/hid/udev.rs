@@ -0,0 +1,94 @@
+//! udev rule letting logged in user open keyboard's hidraw node on linux
+//!
+//! Without rule hidraw nodes belong to root and opening them fails with
+//! EACCES, which hidapi reports only as generic open failure. Device node is
+//! checked directly, so such failure can be told apart and explained.
+
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStrExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use hidapi::DeviceInfo;
+
+use crate::{config::DeviceConfig, EloraError};
+
+/// rule file `setup-udev` writes
+pub const RULE_PATH: &str = "/etc/udev/rules.d/70-elora_hid.rules";
+
+/// Rules granting access to hidraw nodes of keyboards with configured ids to
+/// user logged in on seat (`uaccess`), one per vendor and product id
+pub fn rule(devices: &[DeviceConfig]) -> String {
+    let mut ids: Vec<(u16, u16)> = devices
+        .iter()
+        .map(|device| (device.vendor_id, device.product_id))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    let mut rule = String::from(
+        "# raw hid of keyboards used by elora_hid, written by `elora_hid setup-udev`\n",
+    );
+    for (vendor_id, product_id) in ids {
+        rule.push_str(&format!(
+            "KERNEL==\"hidraw*\", SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"\n",
+            vendor_id, product_id
+        ));
+    }
+    rule
+}
+
+/// Writes rules for configured keyboards to `path`, returns `path`
+pub fn install(devices: &[DeviceConfig], path: &Path) -> Result<PathBuf, EloraError> {
+    fs::write(path, rule(devices)).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => EloraError::PermissionDenied(format!(
+            "can't write {}, run `sudo elora_hid setup-udev`",
+            path.display()
+        )),
+        _ => e.into(),
+    })?;
+    Ok(path.to_path_buf())
+}
+
+/// Checks that hidraw node of device can be opened for read and write,
+/// failing with guidance when permissions are missing. Does nothing on
+/// other platforms
+pub fn check_access(device: &DeviceInfo) -> Result<(), EloraError> {
+    #[cfg(target_os = "linux")]
+    {
+        let path = std::ffi::OsStr::from_bytes(device.path().to_bytes());
+        if let Err(e) = fs::OpenOptions::new().read(true).write(true).open(path) {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                return Err(EloraError::PermissionDenied(guidance(Path::new(path))));
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = device;
+    Ok(())
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn guidance(node: &Path) -> String {
+    format!(
+        "no permission to open {}. Install udev rule with `sudo elora_hid setup-udev`, \
+         then reload rules with `sudo udevadm control --reload-rules && sudo udevadm trigger` \
+         and replug keyboard",
+        node.display()
+    )
+}
+
+#[test]
+fn testing_udev_rule() {
+    let home = DeviceConfig::default();
+    let work = DeviceConfig {
+        product_id: 0x1234,
+        ..DeviceConfig::default()
+    };
+    let rule = rule(&[home.clone(), work, home]);
+    assert_eq!(rule.lines().count(), 3);
+    assert!(rule.contains("ATTRS{idVendor}==\"8d1d\", ATTRS{idProduct}==\"9d9d\""));
+    assert!(rule.contains("TAG+=\"uaccess\""));
+    assert!(guidance(Path::new("/dev/hidraw3")).starts_with("no permission to open /dev/hidraw3."));
+}
@@ -0,0 +1,57 @@
+//! Hot-plug detection of keyboard
+//!
+//! Usb device list is polled on background thread, so there are no platform
+//! specific notification apis involved and it works same on linux, macos and
+//! windows.
+
+use std::{thread, time::Duration};
+
+use hidapi::HidApi;
+use tokio::sync::watch;
+
+use super::find_elora_device;
+use crate::config::DeviceConfig;
+
+/// Starts polling for keyboard every `poll_interval`. Receiver holds `true`
+/// while keyboard is connected and gets notified on every change. Polling
+/// stops once all receivers are dropped
+pub fn spawn(ids: DeviceConfig, poll_interval: Duration) -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+
+    thread::spawn(move || {
+        let mut api = match HidApi::new() {
+            Ok(api) => api,
+            Err(e) => {
+                log::error!("Unable to start device watcher: {}", e);
+                return;
+            }
+        };
+
+        while !tx.is_closed() {
+            let connected = match api.refresh_devices() {
+                Ok(()) => find_elora_device(&api, &ids).is_some(),
+                Err(e) => {
+                    log::warn!("Unable to refresh device list: {}", e);
+                    false
+                }
+            };
+
+            tx.send_if_modified(|state| {
+                if *state == connected {
+                    return false;
+                }
+                if connected {
+                    log::info!("Keyboard connected");
+                } else {
+                    log::warn!("Keyboard disconnected, waiting for it to reappear");
+                }
+                *state = connected;
+                true
+            });
+
+            thread::sleep(poll_interval);
+        }
+    });
+
+    rx
+}
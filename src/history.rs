@@ -0,0 +1,187 @@
+//! Rolling history of metric values, drawn as sparklines
+//!
+//! Every fetched metric (ticker price, portfolio value, ...) is kept in ring
+//! buffer of its symbol. Firmware gets history squeezed into
+//! [`SPARKLINE_LEN`] levels per symbol, enough for tiny chart next to price.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::Deserialize;
+
+use crate::{providers::Line, EloraError};
+
+/// buckets of sparkline, one byte each
+pub const SPARKLINE_LEN: usize = 8;
+/// highest level of bucket, sparkline fits into 8px high row
+pub const SPARKLINE_MAX: u8 = 7;
+/// level of bucket without any values yet
+pub const SPARKLINE_EMPTY: u8 = 0xFF;
+
+/// `[history]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// values kept per symbol, at 60s refresh 120 is last two hours
+    pub capacity: usize,
+    /// symbols to keep history of, every metric when empty
+    pub symbols: Vec<String>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            capacity: 120,
+            symbols: Vec::new(),
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.capacity < SPARKLINE_LEN {
+            return Err(EloraError::ConfigInvalid(format!(
+                "history.capacity must be at least {}",
+                SPARKLINE_LEN
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Fixed size buffer dropping oldest item once full
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        RingBuffer {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// items from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+/// History of every recorded symbol
+#[derive(Debug, Clone)]
+pub struct History {
+    config: HistoryConfig,
+    series: BTreeMap<String, RingBuffer<f64>>,
+}
+
+impl History {
+    pub fn new(config: HistoryConfig) -> Self {
+        History {
+            config,
+            series: BTreeMap::new(),
+        }
+    }
+
+    /// Records metrics of freshly fetched lines. Stale lines repeat old
+    /// value, so they're skipped
+    pub fn record<'a>(&mut self, lines: impl IntoIterator<Item = &'a Line>) {
+        for line in lines.into_iter().filter(|line| !line.stale) {
+            let Some(metric) = &line.metric else {
+                continue;
+            };
+            if !self.config.symbols.is_empty() && !self.config.symbols.contains(&metric.symbol) {
+                continue;
+            }
+            self.series
+                .entry(metric.symbol.clone())
+                .or_insert_with(|| RingBuffer::new(self.config.capacity))
+                .push(metric.value);
+        }
+    }
+
+    pub fn values(&self, symbol: &str) -> Option<&RingBuffer<f64>> {
+        self.series.get(symbol)
+    }
+
+    pub fn sparkline(&self, symbol: &str) -> Option<[u8; SPARKLINE_LEN]> {
+        let values: Vec<f64> = self.values(symbol)?.iter().copied().collect();
+        Some(sparkline(&values))
+    }
+}
+
+/// Squeezes values (oldest first) into [`SPARKLINE_LEN`] bucket averages
+/// scaled to `0..=SPARKLINE_MAX` between lowest and highest bucket. Fewer
+/// values than buckets leave leading buckets [`SPARKLINE_EMPTY`], so chart
+/// grows from right. Flat history is drawn in the middle
+pub fn sparkline(values: &[f64]) -> [u8; SPARKLINE_LEN] {
+    let mut buckets = [None; SPARKLINE_LEN];
+    if values.len() < SPARKLINE_LEN {
+        let start = SPARKLINE_LEN - values.len();
+        for (bucket, &value) in buckets[start..].iter_mut().zip(values) {
+            *bucket = Some(value);
+        }
+    } else {
+        for (i, bucket) in buckets.iter_mut().enumerate() {
+            let chunk =
+                &values[i * values.len() / SPARKLINE_LEN..(i + 1) * values.len() / SPARKLINE_LEN];
+            *bucket = Some(chunk.iter().sum::<f64>() / chunk.len() as f64);
+        }
+    }
+
+    let known = buckets.iter().flatten();
+    let min = known.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = known.copied().fold(f64::NEG_INFINITY, f64::max);
+    buckets.map(|bucket| match bucket {
+        None => SPARKLINE_EMPTY,
+        Some(_) if (max - min).abs() < f64::EPSILON => SPARKLINE_MAX / 2,
+        Some(value) => ((value - min) / (max - min) * SPARKLINE_MAX as f64).round() as u8,
+    })
+}
+
+#[test]
+fn testing_sparkline() {
+    let mut ring = RingBuffer::new(3);
+    for i in 0..5 {
+        ring.push(i);
+    }
+    assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+    let rising: Vec<f64> = (0..16).map(f64::from).collect();
+    assert_eq!(sparkline(&rising), [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(
+        sparkline(&[5.0, 10.0]),
+        [255, 255, 255, 255, 255, 255, 0, 7]
+    );
+    assert_eq!(sparkline(&[3.0; 10]), [3; 8]);
+
+    let mut history = History::new(HistoryConfig {
+        capacity: 8,
+        symbols: vec!["TSLA".into()],
+    });
+    let mut stale = Line::new("").with_metric("TSLA", 1.0);
+    stale.stale = true;
+    history.record(&[
+        Line::new("").with_metric("TSLA", 240.0),
+        Line::new("").with_metric("BTC", 43000.0),
+        stale,
+    ]);
+    assert_eq!(history.values("TSLA").unwrap().len(), 1);
+    assert!(history.values("BTC").is_none());
+}
@@ -0,0 +1,297 @@
+//! Local socket running daemon answers status queries on
+//!
+//! Unix domain socket in `$XDG_RUNTIME_DIR` (temp dir without it), named
+//! pipe on Windows. Client writes `status` line and gets single line of
+//! [`Status`] json back, which is what `elora_hid status` shows.
+//! `pomodoro [start|stop|toggle]` controls pomodoro timer and is answered
+//! with [`PomodoroStatus`] json.
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{
+    config::DeviceConfig,
+    metrics::{FetchCounts, METRICS},
+    providers::pomodoro::{self, PomodoroStatus},
+    EloraError,
+};
+
+/// state of every keyboard by name, updated by scheduler as it runs
+static KEYBOARDS: Mutex<BTreeMap<String, KeyboardStatus>> = Mutex::new(BTreeMap::new());
+
+/// longest request line accepted
+const MAX_REQUEST: usize = 256;
+
+/// What running daemon reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    /// empty before worker starts
+    pub keyboards: Vec<KeyboardStatus>,
+    pub providers: BTreeMap<String, FetchCounts>,
+    /// unix time data was last written to keyboard
+    pub last_write: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardStatus {
+    pub name: String,
+    pub device: DeviceStatus,
+    /// page currently shown
+    pub page: PageStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceStatus {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub serial: Option<String>,
+    pub connected: bool,
+    /// version agreed with firmware, `None` until keyboard is opened
+    pub protocol_version: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageStatus {
+    pub index: usize,
+    pub name: String,
+    pub count: usize,
+}
+
+/// stores what keyboard shows now, to be reported in [`Status`]
+pub fn update(keyboard: KeyboardStatus) {
+    KEYBOARDS
+        .lock()
+        .unwrap()
+        .insert(keyboard.name.clone(), keyboard);
+}
+
+/// forgets keyboards, ex. when config with other keyboards is loaded
+pub fn reset() {
+    KEYBOARDS.lock().unwrap().clear();
+}
+
+impl DeviceStatus {
+    pub fn new(ids: &DeviceConfig, connected: bool, protocol_version: Option<u8>) -> Self {
+        DeviceStatus {
+            vendor_id: ids.vendor_id,
+            product_id: ids.product_id,
+            serial: ids.serial.clone(),
+            connected,
+            protocol_version,
+        }
+    }
+}
+
+/// Current status of this process
+pub fn status() -> Status {
+    Status {
+        pid: std::process::id(),
+        keyboards: KEYBOARDS.lock().unwrap().values().cloned().collect(),
+        providers: METRICS.fetches(),
+        last_write: METRICS.last_refresh(),
+    }
+}
+
+/// Answers `pomodoro [start|stop|toggle]`, bare `pomodoro` only reports
+/// timer
+fn pomodoro_response(request: &str) -> String {
+    let status = match request["pomodoro".len()..].trim() {
+        "" => pomodoro::status().ok_or_else(|| "pomodoro is not enabled".to_string()),
+        name => match pomodoro::Action::parse(name) {
+            Some(action) => pomodoro::control(action).map_err(|e| e.to_string()),
+            None => Err(format!("unknown pomodoro action {:?}", name)),
+        },
+    };
+    match status {
+        Ok(status) => serde_json::to_string(&status).unwrap(),
+        Err(e) => serde_json::json!({ "error": e }).to_string(),
+    }
+}
+
+/// Answers single request on `stream`
+async fn handle(stream: impl AsyncRead + AsyncWrite + Unpin) {
+    let mut stream = BufReader::new(stream);
+    let mut request = String::new();
+    let read = (&mut stream)
+        .take(MAX_REQUEST as u64)
+        .read_line(&mut request)
+        .await;
+    let response = match read.map(|_| request.trim()) {
+        Ok("status") => serde_json::to_string(&status()).unwrap(),
+        Ok(request) if request.split(' ').next() == Some("pomodoro") => pomodoro_response(request),
+        Ok(other) => format!("{{\"error\":\"unknown command {:?}\"}}", other),
+        Err(e) => {
+            log::debug!("Unable to read status request: {}", e);
+            return;
+        }
+    };
+    let stream = stream.get_mut();
+    if let Err(e) = stream.write_all(format!("{}\n", response).as_bytes()).await {
+        log::debug!("Unable to answer status request: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+/// Sends `command` to daemon, returns its answer. Fails with io error when
+/// no daemon listens
+async fn request(command: &str) -> Result<String, EloraError> {
+    let mut stream = BufReader::new(connect().await?);
+    stream
+        .get_mut()
+        .write_all(format!("{}\n", command).as_bytes())
+        .await?;
+    let mut response = String::new();
+    stream.read_line(&mut response).await?;
+    Ok(response)
+}
+
+/// error of connecting to socket no daemon listens on
+fn not_running(error: &EloraError) -> bool {
+    matches!(error, EloraError::Io(e) if matches!(
+        e.kind(),
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+    ))
+}
+
+/// Status of running daemon, `None` when there is none
+pub async fn query() -> Result<Option<Status>, EloraError> {
+    let response = match request("status").await {
+        Ok(response) => response,
+        Err(e) if not_running(&e) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&response)
+        .map(Some)
+        .map_err(|e| EloraError::Io(std::io::Error::other(e)))
+}
+
+/// Sends pomodoro `action` to daemon, or only asks where timer is at
+/// without one
+pub async fn pomodoro(action: Option<pomodoro::Action>) -> Result<PomodoroStatus, EloraError> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Answer {
+        Status(PomodoroStatus),
+        Error { error: String },
+    }
+
+    let command = match action {
+        Some(action) => format!("pomodoro {}", action.name()),
+        None => "pomodoro".into(),
+    };
+    let response = request(&command).await.map_err(|e| match not_running(&e) {
+        true => EloraError::Io(std::io::Error::other(
+            "daemon is not running, pomodoro timer runs in it",
+        )),
+        false => e,
+    })?;
+    match serde_json::from_str(&response) {
+        Ok(Answer::Status(status)) => Ok(status),
+        Ok(Answer::Error { error }) => Err(EloraError::ConfigInvalid(error)),
+        Err(e) => Err(EloraError::Io(std::io::Error::other(e))),
+    }
+}
+
+/// `elora_hid.sock` in `$XDG_RUNTIME_DIR`, or temp dir without it
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => std::env::temp_dir(),
+    };
+    dir.join("elora_hid.sock")
+}
+
+/// named pipe daemon listens on
+#[cfg(windows)]
+pub fn socket_path() -> PathBuf {
+    PathBuf::from(r"\\.\pipe\elora_hid")
+}
+
+#[cfg(unix)]
+async fn connect() -> Result<tokio::net::UnixStream, EloraError> {
+    Ok(tokio::net::UnixStream::connect(socket_path()).await?)
+}
+
+#[cfg(windows)]
+async fn connect() -> Result<tokio::net::windows::named_pipe::NamedPipeClient, EloraError> {
+    Ok(tokio::net::windows::named_pipe::ClientOptions::new().open(socket_path())?)
+}
+
+#[cfg(unix)]
+async fn serve() -> Result<(), EloraError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path();
+    if connect().await.is_ok() {
+        return Err(EloraError::Io(std::io::Error::other(format!(
+            "other daemon already listens on {}",
+            path.display()
+        ))));
+    }
+    // left behind by daemon which didn't exit cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    log::debug!("Answering status on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        handle(stream).await;
+    }
+}
+
+#[cfg(windows)]
+async fn serve() -> Result<(), EloraError> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let path = socket_path();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)?;
+    log::debug!("Answering status on {}", path.display());
+    loop {
+        server.connect().await?;
+        // next client connects to fresh instance while this one is answered
+        let client = std::mem::replace(&mut server, ServerOptions::new().create(&path)?);
+        handle(client).await;
+    }
+}
+
+/// Answers status queries in background for as long as runtime lives
+pub fn spawn() {
+    tokio::spawn(async {
+        if let Err(e) = serve().await {
+            log::warn!("Status socket stopped: {}", e);
+        }
+    });
+}
+
+#[tokio::test]
+async fn testing_status_request() {
+    let (client, server) = tokio::io::duplex(1024);
+    update(KeyboardStatus {
+        name: "home".into(),
+        device: DeviceStatus::new(&DeviceConfig::default(), true, Some(6)),
+        page: PageStatus {
+            index: 0,
+            name: "stocks".into(),
+            count: 2,
+        },
+    });
+    let server = tokio::spawn(handle(server));
+
+    let mut client = BufReader::new(client);
+    client.get_mut().write_all(b"status\n").await.unwrap();
+    let mut response = String::new();
+    client.read_line(&mut response).await.unwrap();
+    server.await.unwrap();
+
+    let status: Status = serde_json::from_str(&response).unwrap();
+    assert_eq!(status.pid, std::process::id());
+    let keyboard = &status.keyboards[0];
+    assert_eq!(keyboard.device.vendor_id, crate::hid::VENDOR_ID);
+    assert_eq!(keyboard.page.name, "stocks");
+}
@@ -0,0 +1,28 @@
+//! Secrets from keyring of host, so api secrets don't have to be written
+//! into config
+//!
+//! Entries are looked up by [`SERVICE`] and account name through `keyring`
+//! crate. Secret Service (GNOME Keyring, KWallet) over D-Bus on linux,
+//! stored with
+//! `secret-tool store --label=... service elora_hid username <name>`. Login
+//! keychain on macos, stored with
+//! `security add-generic-password -s elora_hid -a <name> -w`. Credential
+//! Manager on windows, stored with
+//! `cmdkey /generic:<name>.elora_hid /user:<name> /pass`.
+
+use ::keyring::Entry;
+
+use crate::BoxError;
+
+/// service all entries of elora_hid are stored under
+pub const SERVICE: &str = "elora_hid";
+
+/// secret stored under `account`
+pub async fn secret(account: &str) -> Result<String, BoxError> {
+    let name = account.to_string();
+    // keyring blocks on D-Bus and keychain calls, so it doesn't run on
+    // runtime threads
+    let secret =
+        tokio::task::spawn_blocking(move || Entry::new(SERVICE, &name)?.get_password()).await?;
+    secret.map_err(|e| format!("keyring entry {}/{}: {}", SERVICE, account, e).into())
+}
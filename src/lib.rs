@@ -0,0 +1,25 @@
+//! Elora HID interface
+//!
+//! Collects data from remote services and pushes it through USB raw hid to
+//! Elora split keyboard. Binary `elora_hid` is a thin wrapper around this crate,
+//! so transport and fetching logic can be reused from other daemons.
+
+pub mod alerts;
+pub mod config;
+pub mod error;
+pub mod hid;
+pub mod history;
+pub mod ipc;
+pub mod keyring;
+pub mod market;
+pub mod metrics;
+pub mod protocol;
+pub mod providers;
+pub mod ratelimit;
+pub mod reload;
+pub mod render;
+pub mod retry;
+pub mod scheduler;
+pub mod service;
+
+pub use error::{BoxError, EloraError};
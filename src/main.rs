@@ -1,168 +1,578 @@
-use std::{collections::BTreeMap, error::Error, time::Duration};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use elora_hid::{
+    config::{self, Config},
+    hid, ipc, metrics,
+    protocol::{self, Message},
+    providers::{self, pomodoro, Line},
+    render, scheduler, service, EloraError,
+};
+use hidapi::HidApi;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Pushes data through USB raw hid to Elora split keyboard"
+)]
+struct Cli {
+    /// path to config file, defaults to ~/.config/elora_hid/config.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// run under systemd: no banner, readiness and watchdog through sd_notify
+    #[arg(long, global = true)]
+    daemon: bool,
+
+    /// print frames to stdout instead of sending them, no keyboard needed
+    #[arg(long, global = true, alias = "no-hid")]
+    dry_run: bool,
+
+    /// send whole page every N updates, changed rows only in between
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    full_refresh_every: Option<u32>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-use hidapi::{DeviceInfo, HidApi};
-use regex::Regex;
-use reqwest::Client;
+#[derive(Subcommand)]
+enum Command {
+    /// fetch data and send it to keyboard every refresh interval (default)
+    Run,
+    /// list connected hid devices and mark the ones matching config
+    ListDevices,
+    /// send arbitrary text to keyboard once
+    Send {
+        #[arg(long)]
+        text: String,
+    },
+    /// fetch data once and print it without sending to keyboard
+    TestFetch,
+    /// show status of running daemon, or connected keyboard and protocol
+    /// version agreed with its firmware when no daemon runs
+    Status {
+        /// print daemon status as json
+        #[arg(long)]
+        json: bool,
+    },
+    /// start or stop pomodoro timer of running daemon, shows where it's at
+    /// without action
+    Pomodoro {
+        #[command(subcommand)]
+        action: Option<PomodoroAction>,
+    },
+    /// run as system service: systemd unit on linux, windows service on windows
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+    /// write udev rule letting current user open keyboard on linux, needs root
+    SetupUdev {
+        /// print rule to stdout instead of writing it
+        #[arg(long)]
+        print: bool,
+    },
+    /// write and load macOS LaunchAgent running this binary in daemon mode
+    InstallLaunchd,
+    /// unload LaunchAgent and remove its plist
+    UninstallLaunchd,
+    /// same as `service install`
+    #[command(hide = true)]
+    InstallService {
+        #[arg(long)]
+        system: bool,
+    },
+}
 
-/// splitkb.com vendor id
-const VENDOR_ID: u16 = 0x8d1d;
-/// Elora product id
-const PRODUCT_ID: u16 = 0x9d9d;
+#[derive(Subcommand)]
+enum ServiceAction {
+    /// write systemd unit, LaunchAgent on macOS or register windows service
+    /// starting at boot
+    Install {
+        /// system unit in /etc/systemd/system instead of user unit, windows
+        /// services always are
+        #[arg(long)]
+        system: bool,
+    },
+    /// remove what `install` wrote, stopping windows service first
+    Uninstall {
+        #[arg(long)]
+        system: bool,
+    },
+    /// run in daemon mode, started by service manager
+    Run,
+}
 
-const USAGE_ID: u16 = 0x61;
-const USAGE_PAGE: u16 = 0xFF60;
+#[derive(Subcommand)]
+enum PomodoroAction {
+    /// start next work interval or break
+    Start,
+    /// stop timer, next start begins work interval
+    Stop,
+    /// start when stopped, stop when running
+    Toggle,
+}
 
-/// How often to refetch new data from dependency services in seconds
-const REFRESH_RATE_SECS: u16 = 60;
+/// Fetches and sends data to keyboard until `shutdown` resolves. Config file,
+/// when there is one, is watched and applied on change
+async fn run(
+    config: &Config,
+    path: Option<&Path>,
+    daemon: bool,
+    dry_run: bool,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EloraError> {
+    let path = config::resolve_path(path);
+    if let Some(metrics) = &config.metrics {
+        metrics::spawn(metrics);
+    }
+    ipc::spawn();
+    if dry_run {
+        return match &path {
+            Some(path) => scheduler::start_reloading(path, config.clone(), true).await,
+            None => scheduler::start_dry_run(config).await,
+        };
+    }
+    if !daemon {
+        print_banner();
+    }
 
-// type alias for stock tickers
-type StockTickerType = BTreeMap<&'static str, f64>;
-// interested tickers
-const TICKERS: [(&str, f64); 3] = [("TSLA", 0.0), ("VWRL.AS", 0.0), ("NVDA", 0.0)];
+    let api = HidApi::new()?;
+    for keyboard in config.keyboards() {
+        match hid::find_elora_device(&api, &keyboard.device) {
+            None => log::warn!(
+                "Keyboard {} not found connected, waiting for it",
+                keyboard.name
+            ),
+            Some(device) => {
+                if let Err(e) = hid::udev::check_access(device) {
+                    log::error!("{}", e);
+                }
+            }
+        }
+    }
 
-// custom app error
-type AppError = Box<dyn Error>;
+    if daemon {
+        service::notify_ready();
+        service::spawn_watchdog();
+    }
 
-async fn fetch_stock_tickers() -> Result<StockTickerType, AppError> {
-    log::info!("Fetching stock tickers from remote");
+    let worker = async {
+        match &path {
+            Some(path) => scheduler::start_reloading(path, config.clone(), false).await,
+            None => scheduler::start(config).await,
+        }
+    };
+    tokio::select! {
+        res = worker => res,
+        _ = shutdown => {
+            log::info!("Shutting down, clearing keyboard display");
+            if daemon {
+                service::notify_stopping();
+            }
+            for keyboard in config.keyboards() {
+                if let Err(e) = hid::clear_display(&keyboard.device).await {
+                    log::warn!("Unable to clear display of {}: {}", keyboard.name, e);
+                }
+            }
+            Ok(())
+        }
+    }
+}
 
-    let mut stocks = BTreeMap::from(TICKERS);
+fn print_banner() {
+    println!(
+        r"
+  _____ _                   _   _ ___ ____  
+ | ____| | ___  _ __ __ _  | | | |_ _|  _ \ 
+ |  _| | |/ _ \| '__/ _` | | |_| || || | | |
+ | |___| | (_) | | | (_| | |  _  || || |_| |
+ |_____|_|\___/|_|  \__,_| |_| |_|___|____/
+"
+    );
+}
 
-    for stock in stocks.clone().into_iter() {
-        let regex_str = format!(
-            "data-symbol=\"{}.*?regularMarketPrice.*?value=\"(?<price>.*?)\"",
-            stock.0
-        );
+/// resolves on ctrl+c, or SIGTERM on unix (ex. `systemctl stop`)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = match signal(SignalKind::terminate()) {
+            Ok(sigterm) => sigterm,
+            Err(e) => {
+                log::warn!("Unable to listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
 
-        let chrome_user_agent = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.114 Safari/537.36";
-        let client = Client::builder().user_agent(chrome_user_agent).build().unwrap();
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
 
-        let price = Regex::new(&regex_str)?;
-        let url = format!("https://finance.yahoo.com/quote/{}/", stock.0);
-        let req = client.get(url).send().await?;
-        let body = req.text().await?;
+fn list_devices(config: &Config) -> Result<(), EloraError> {
+    let api = HidApi::new()?;
+    for dev in api.device_list() {
+        let marker = if config
+            .keyboards()
+            .iter()
+            .any(|keyboard| hid::is_elora_device(dev, &keyboard.device))
+        {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} {:04x}:{:04x} usage_page={:#06x} usage={:#04x} serial={} {} {} {}",
+            marker,
+            dev.vendor_id(),
+            dev.product_id(),
+            dev.usage_page(),
+            dev.usage(),
+            dev.serial_number().unwrap_or("-"),
+            dev.manufacturer_string().unwrap_or(""),
+            dev.product_string().unwrap_or(""),
+            dev.path().to_string_lossy(),
+        );
+    }
+    Ok(())
+}
 
-        if let Some(caps) = price.captures(&body) {
-            let b = caps.name("price").map_or("0", |m| m.as_str());
-            if let Some(v) = stocks.get_mut(stock.0) {
-                *v = b.parse().unwrap_or(0.0);
-            }
+async fn status(config: &Config, json: bool) -> Result<(), EloraError> {
+    match ipc::query().await? {
+        Some(status) if json => println!("{}", serde_json::to_string_pretty(&status).unwrap()),
+        Some(status) => print_status(&status),
+        None => {
+            println!("daemon: not running");
+            keyboard_status(config)?;
         }
     }
+    Ok(())
+}
 
-    log::debug!("Fetching complete");
-
-    Ok(stocks)
+/// local time of unix timestamp with how long ago it was
+fn ago(time: Option<i64>) -> String {
+    let Some(time) = time.and_then(|time| chrono::DateTime::from_timestamp(time, 0)) else {
+        return "never".into();
+    };
+    let secs = (chrono::Utc::now() - time).num_seconds().max(0);
+    format!(
+        "{} ({}s ago)",
+        time.with_timezone(&chrono::Local).format("%H:%M:%S"),
+        secs
+    )
 }
 
-/// Converts StockTickerType into string which is sent through usb to keyboard
-fn convert_to_buffer(stocks: StockTickerType) -> Vec<u8> {
-    let mut buf = Vec::new();
-    for (ticker, v) in stocks {
-        // we use max 4 chars for ticker so it fits. example:
-        // TSLA: 500$
-        // VWRL: 200$
-        let st_string = format!("{:.4}: {:.0}$", ticker, v);
-        for ch in st_string.chars() {
-            buf.push(ch as u8);
+fn print_status(status: &ipc::Status) {
+    println!("daemon: running (pid {})", status.pid);
+    for keyboard in &status.keyboards {
+        let device = &keyboard.device;
+        print!(
+            "{}: {:04x}:{:04x} {}",
+            keyboard.name,
+            device.vendor_id,
+            device.product_id,
+            if device.connected {
+                "connected"
+            } else {
+                "disconnected"
+            }
+        );
+        match device.protocol_version {
+            Some(version) => println!(", protocol version {}", version),
+            None => println!(),
         }
+        let page = &keyboard.page;
+        println!("  page: {} ({}/{})", page.name, page.index + 1, page.count);
+    }
+    println!("last write: {}", ago(status.last_write));
+    for (provider, counts) in &status.providers {
+        println!(
+            "{}: last fetch {}, {} ok, {} failed",
+            provider,
+            ago(counts.last_success),
+            counts.successes,
+            counts.failures
+        );
     }
-    buf
 }
 
-/// searches for connected elora keyboard
-fn find_elora_device(api: &HidApi) -> Option<&DeviceInfo> {
-    let device = api.device_list().find(|&dev| {
-        dev.vendor_id() == VENDOR_ID
-            && dev.product_id() == PRODUCT_ID
-            && dev.usage() == USAGE_ID
-            && dev.usage_page() == USAGE_PAGE
+async fn pomodoro(action: Option<PomodoroAction>) -> Result<(), EloraError> {
+    let action = action.map(|action| match action {
+        PomodoroAction::Start => pomodoro::Action::Start,
+        PomodoroAction::Stop => pomodoro::Action::Stop,
+        PomodoroAction::Toggle => pomodoro::Action::Toggle,
     });
-    device
+    let status = ipc::pomodoro(action).await?;
+    println!(
+        "pomodoro: {} {:02}:{:02} {}, {} done",
+        status.phase.label(),
+        status.left_secs / 60,
+        status.left_secs % 60,
+        if status.running { "running" } else { "ready" },
+        status.done
+    );
+    Ok(())
 }
 
-/// sends stock ticker to keyboard
-async fn send_to_keyboard(stocks: StockTickerType) -> Result<(), AppError> {
-    log::info!("Sending to usb keyboard");
-
+/// Status of every configured keyboard, fails only when none is connected
+fn keyboard_status(config: &Config) -> Result<(), EloraError> {
     let api = HidApi::new()?;
-    let device = find_elora_device(&api);
-
-    if device.is_none() {
-        return Err("Device disconnected".into());
+    println!("host protocol version: {}", protocol::PROTOCOL_VERSION);
+    let mut found = false;
+    for keyboard in config.keyboards() {
+        let Some(device) = hid::find_elora_device(&api, &keyboard.device) else {
+            println!("keyboard {}: not found", keyboard.name);
+            continue;
+        };
+        found = true;
+        println!(
+            "keyboard {}: {} {} ({:04x}:{:04x})",
+            keyboard.name,
+            device.manufacturer_string().unwrap_or(""),
+            device.product_string().unwrap_or(""),
+            device.vendor_id(),
+            device.product_id()
+        );
+        let manager = hid::ConnectionManager::new(keyboard.device);
+        match manager.connection() {
+            Ok(_) => {
+                if let Some(version) = manager.version() {
+                    println!("  negotiated protocol version: {}", version);
+                }
+            }
+            Err(e) => println!("  unable to connect: {}", e),
+        }
+    }
+    if !found {
+        return Err(EloraError::DeviceNotFound);
     }
+    Ok(())
+}
 
-    let device = device.unwrap().open_device(&api);
-    let buf = convert_to_buffer(stocks);
-    device?.write(&buf)?;
+/// Sends text to every configured keyboard, fails only when it reached none
+async fn send_text(config: &Config, text: String, dry_run: bool) -> Result<(), EloraError> {
+    let buf = render::convert_with(&[Line::new(text)], &render::Encoding::new(&config.encoding));
+    let mut last_error = None;
+    let mut sent = false;
+    for keyboard in config.keyboards() {
+        let result = if dry_run {
+            hid::ConnectionManager::dry_run(keyboard.device).send(&Message::text(0, 1, &buf))
+        } else {
+            hid::send_to_keyboard(buf.clone(), &keyboard.device).await
+        };
+        match result {
+            Ok(()) => sent = true,
+            Err(e) => {
+                log::warn!("Unable to send text to {}: {}", keyboard.name, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if !sent => Err(e),
+        _ => Ok(()),
+    }
+}
 
-    log::debug!("{}", String::from_utf8(buf).unwrap());
+#[cfg(not(windows))]
+fn install_service(config: Option<&Path>, system: bool) -> Result<(), EloraError> {
+    if cfg!(target_os = "macos") {
+        return install_launchd(config);
+    }
+    let path = service::install(config, system)?;
+    let systemctl = if system {
+        "systemctl"
+    } else {
+        "systemctl --user"
+    };
+    println!("Wrote {}", path.display());
+    println!(
+        "Enable with: {} daemon-reload && {} enable --now {}",
+        systemctl,
+        systemctl,
+        service::UNIT_NAME
+    );
+    Ok(())
+}
 
+#[cfg(windows)]
+fn install_service(config: Option<&Path>, _system: bool) -> Result<(), EloraError> {
+    service::windows::install(config)?;
+    println!(
+        "Registered {} service, start with: sc start {}",
+        service::windows::SERVICE_NAME,
+        service::windows::SERVICE_NAME
+    );
     Ok(())
 }
 
-/// Main worker which fetches stuff and sends it to keyboard
-async fn run() -> Result<(), AppError> {
-    let stocks = fetch_stock_tickers().await?;
-    let res = send_to_keyboard(stocks).await;
-    if res.is_err() {
-        log::error!("Error occured while sending data to keyboard");
+#[cfg(not(windows))]
+fn uninstall_service(system: bool) -> Result<(), EloraError> {
+    if cfg!(target_os = "macos") {
+        return uninstall_launchd();
     }
+    let path = service::uninstall(system)?;
+    println!("Removed {}", path.display());
     Ok(())
 }
 
-#[tokio::main]
-async fn main() {
-    env_logger::init();
+#[cfg(windows)]
+fn uninstall_service(_system: bool) -> Result<(), EloraError> {
+    service::windows::uninstall()?;
+    println!("Removed {} service", service::windows::SERVICE_NAME);
+    Ok(())
+}
 
+fn setup_udev(config: &Config, print: bool) -> Result<(), EloraError> {
+    let devices: Vec<_> = config
+        .keyboards()
+        .into_iter()
+        .map(|keyboard| keyboard.device)
+        .collect();
+    if print {
+        print!("{}", hid::udev::rule(&devices));
+        return Ok(());
+    }
+    let path = hid::udev::install(&devices, Path::new(hid::udev::RULE_PATH))?;
+    println!("Wrote {}", path.display());
     println!(
-        r"
-  _____ _                   _   _ ___ ____  
- | ____| | ___  _ __ __ _  | | | |_ _|  _ \ 
- |  _| | |/ _ \| '__/ _` | | |_| || || | | |
- | |___| | (_) | | | (_| | |  _  || || |_| |
- |_____|_|\___/|_|  \__,_| |_| |_|___|____/
-"
+        "Apply with: sudo udevadm control --reload-rules && sudo udevadm trigger, then replug keyboard"
     );
+    Ok(())
+}
 
-    let api = HidApi::new().unwrap();
-    let device = find_elora_device(&api);
+fn install_launchd(config: Option<&Path>) -> Result<(), EloraError> {
+    let path = service::launchd::install(config)?;
+    println!("Wrote and loaded {}", path.display());
+    Ok(())
+}
 
-    if device.is_none() {
-        log::error!("Error: Elora keyboard not found connected");
-        return;
-    }
+fn uninstall_launchd() -> Result<(), EloraError> {
+    let path = service::launchd::uninstall()?;
+    println!("Unloaded and removed {}", path.display());
+    Ok(())
+}
+
+/// Daemon started by systemd or launchd, same as `--daemon run`
+#[cfg(not(windows))]
+async fn run_service(config: Config, path: Option<PathBuf>) -> Result<(), EloraError> {
+    run(&config, path.as_deref(), true, false, shutdown_signal()).await
+}
+
+/// Daemon started by service control manager. Dispatcher blocks and calls
+/// back on its own thread, which drives daemon on this runtime until stop
+/// control arrives
+#[cfg(windows)]
+async fn run_service(config: Config, path: Option<PathBuf>) -> Result<(), EloraError> {
+    let runtime = tokio::runtime::Handle::current();
+    let worker = Box::new(move |stop: tokio::sync::oneshot::Receiver<()>| {
+        runtime.block_on(run(&config, path.as_deref(), true, false, async {
+            let _ = stop.await;
+        }))
+    });
+    tokio::task::spawn_blocking(move || service::windows::run(worker))
+        .await
+        .map_err(|e| EloraError::Io(std::io::Error::other(e)))?
+}
 
-    let mut interval = tokio::time::interval(Duration::from_secs(REFRESH_RATE_SECS.into()));
-    loop {
-        interval.tick().await;
-        let _ = run().await;
+async fn test_fetch(config: &Config) -> Result<(), EloraError> {
+    let mut lines = Vec::new();
+    for provider in providers::from_config(config)? {
+        println!("{}:", provider.name());
+        match provider.fetch().await {
+            Ok(mut provider_lines) => {
+                if let Some(template) = config.template(provider.name()) {
+                    render::apply_template(&mut provider_lines, &template);
+                }
+                for line in &provider_lines {
+                    println!("  {}", line.text);
+                }
+                lines.append(&mut provider_lines);
+            }
+            Err(e) => println!("  error: {}", e),
+        }
     }
+    let buf = render::convert_with(&lines, &render::Encoding::new(&config.encoding));
+    println!("payload: {}", String::from_utf8_lossy(&buf));
+    Ok(())
 }
 
-#[tokio::test]
-async fn testing_fetch_of_stock() -> Result<(), AppError> {
-    let st = fetch_stock_tickers().await?;
+/// Exit code for error, following sysexits.h so service managers and scripts
+/// can tell bad config from missing keyboard
+fn exit_code(error: &EloraError) -> i32 {
+    match error {
+        EloraError::ConfigInvalid(_) => 78,
+        EloraError::DeviceNotFound => 69,
+        EloraError::PermissionDenied(_) => 77,
+        EloraError::UnsupportedFirmware(_) => 76,
+        EloraError::WriteFailed(_)
+        | EloraError::Hid(_)
+        | EloraError::Protocol(_)
+        | EloraError::Io(_) => 74,
+        EloraError::FetchFailed { .. } | EloraError::NoData => 75,
+        _ => 1,
+    }
+}
 
-    // Example output:
-    //
-    // [src/main.rs:120] &st = {
-    // "VWRL.AS": 107.2,
-    // "TSLA": 237.03,
-    // "AAPL": 180.51,
-    // }
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
 
-    assert_eq!(st.contains_key("TSLA"), true);
-    assert_eq!(st.get("TSLA").unwrap() > &0.0, true);
+    let mut config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exit_code(&e));
+        }
+    };
 
-    assert_eq!(st.contains_key("VWRL.AS"), true);
-    assert_eq!(st.get("VWRL.AS").unwrap() > &0.0, true);
-    Ok(())
-}
+    if let Some(every) = cli.full_refresh_every {
+        config.full_refresh_every = every;
+    }
 
-#[test]
-fn testing_conversion_to_buffer() {
-    let stocks: StockTickerType = BTreeMap::from([("TSLA", 500.0), ("VWRL.AS", 200.1)]);
-    let buf = convert_to_buffer(stocks);
-    assert_eq!(String::from_utf8(buf).unwrap(), "TSLA: 500$VWRL: 200$");
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&config.log_level))
+        .init();
+
+    let res = match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            run(
+                &config,
+                cli.config.as_deref(),
+                cli.daemon,
+                cli.dry_run,
+                shutdown_signal(),
+            )
+            .await
+        }
+        Command::ListDevices => list_devices(&config),
+        Command::Send { text } => send_text(&config, text, cli.dry_run).await,
+        Command::TestFetch => test_fetch(&config).await,
+        Command::Status { json } => status(&config, json).await,
+        Command::Pomodoro { action } => pomodoro(action).await,
+        Command::Service { action } => match action {
+            ServiceAction::Install { system } => install_service(cli.config.as_deref(), system),
+            ServiceAction::Uninstall { system } => uninstall_service(system),
+            ServiceAction::Run => run_service(config, cli.config).await,
+        },
+        Command::SetupUdev { print } => setup_udev(&config, print),
+        Command::InstallLaunchd => install_launchd(cli.config.as_deref()),
+        Command::UninstallLaunchd => uninstall_launchd(),
+        Command::InstallService { system } => install_service(cli.config.as_deref(), system),
+    };
+
+    if let Err(e) = res {
+        eprintln!("Error: {}", e);
+        std::process::exit(exit_code(&e));
+    }
 }
@@ -0,0 +1,284 @@
+//! Trading hours of stock exchanges
+//!
+//! Ticker is matched to exchange by its yahoo suffix (`VWRL.AS` trades in
+//! Amsterdam, no suffix is US, futures and fx pairs trade around the clock)
+//! unless `[market]` config says otherwise.
+//! Exchange is closed outside its hours, on weekends and on configured
+//! holidays, stock providers don't refetch closed tickers and mark them
+//! `closed` instead.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::{
+    providers::{stocks::AssetClass, Line},
+    EloraError,
+};
+
+/// Exchange with its local trading hours
+#[derive(Debug, PartialEq)]
+pub struct Exchange {
+    /// code used in `[market]` config, ex. `NYSE`
+    pub code: &'static str,
+    /// yahoo ticker suffixes, ex. `AS` of `VWRL.AS`
+    pub suffixes: &'static [&'static str],
+    pub timezone: Tz,
+    /// opening and closing local time in minutes from midnight, `None` when
+    /// exchange never closes
+    pub hours: Option<(u32, u32)>,
+    /// pre-market opening and after-hours closing, same form as `hours`
+    pub extended: Option<(u32, u32)>,
+}
+
+/// Known exchanges, first one is used for tickers without suffix
+pub const EXCHANGES: &[Exchange] = &[
+    Exchange {
+        code: "NYSE",
+        suffixes: &[],
+        timezone: Tz::America__New_York,
+        hours: Some((9 * 60 + 30, 16 * 60)),
+        extended: Some((4 * 60, 20 * 60)),
+    },
+    Exchange {
+        code: "TSX",
+        suffixes: &["TO", "V"],
+        timezone: Tz::America__Toronto,
+        hours: Some((9 * 60 + 30, 16 * 60)),
+        extended: None,
+    },
+    Exchange {
+        code: "LSE",
+        suffixes: &["L"],
+        timezone: Tz::Europe__London,
+        hours: Some((8 * 60, 16 * 60 + 30)),
+        extended: None,
+    },
+    Exchange {
+        code: "EURONEXT",
+        suffixes: &["AS", "PA", "BR", "MI"],
+        timezone: Tz::Europe__Amsterdam,
+        hours: Some((9 * 60, 17 * 60 + 30)),
+        extended: None,
+    },
+    Exchange {
+        code: "XETRA",
+        suffixes: &["DE", "F"],
+        timezone: Tz::Europe__Berlin,
+        hours: Some((9 * 60, 17 * 60 + 30)),
+        extended: None,
+    },
+    Exchange {
+        code: "SIX",
+        suffixes: &["SW"],
+        timezone: Tz::Europe__Zurich,
+        hours: Some((9 * 60, 17 * 60 + 30)),
+        extended: None,
+    },
+    Exchange {
+        code: "TSE",
+        suffixes: &["T"],
+        timezone: Tz::Asia__Tokyo,
+        hours: Some((9 * 60, 15 * 60)),
+        extended: None,
+    },
+    Exchange {
+        code: "HKEX",
+        suffixes: &["HK"],
+        timezone: Tz::Asia__Hong_Kong,
+        hours: Some((9 * 60 + 30, 16 * 60)),
+        extended: None,
+    },
+    // crypto, futures, fx and other tickers traded all the time, ex. `BTC-USD`
+    Exchange {
+        code: "24H",
+        suffixes: &[],
+        timezone: Tz::UTC,
+        hours: None,
+        extended: None,
+    },
+];
+
+/// exchange with given `[market]` config code
+pub fn exchange(code: &str) -> Option<&'static Exchange> {
+    EXCHANGES
+        .iter()
+        .find(|exchange| exchange.code.eq_ignore_ascii_case(code))
+}
+
+impl Exchange {
+    /// checks if exchange trades at `now`, `holidays` are local dates
+    pub fn is_open<T: TimeZone>(&self, now: &DateTime<T>, holidays: &[NaiveDate]) -> bool {
+        let Some(hours) = self.hours else {
+            return true;
+        };
+        self.is_within(hours, now, holidays)
+    }
+
+    /// checks if exchange trades only pre-market or after-hours at `now`
+    pub fn is_extended<T: TimeZone>(&self, now: &DateTime<T>, holidays: &[NaiveDate]) -> bool {
+        self.extended
+            .is_some_and(|hours| self.is_within(hours, now, holidays))
+            && !self.is_open(now, holidays)
+    }
+
+    fn is_within<T: TimeZone>(
+        &self,
+        (open, close): (u32, u32),
+        now: &DateTime<T>,
+        holidays: &[NaiveDate],
+    ) -> bool {
+        let local = now.with_timezone(&self.timezone);
+        if matches!(local.weekday(), Weekday::Sat | Weekday::Sun)
+            || holidays.contains(&local.date_naive())
+        {
+            return false;
+        }
+        let minute = local.hour() * 60 + local.minute();
+        open <= minute && minute < close
+    }
+}
+
+/// `[market]` config section
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketConfig {
+    /// exchange code per ticker whose suffix doesn't tell it, ex. `"BTC-USD" = "24H"`
+    pub exchanges: BTreeMap<String, String>,
+    /// closed days per exchange code, ex. `NYSE = ["2024-12-25"]`
+    pub holidays: BTreeMap<String, Vec<NaiveDate>>,
+    /// page shown instead of rotating pages while every exchange is closed
+    pub closed_page: Option<String>,
+}
+
+impl MarketConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let codes = self
+            .exchanges
+            .iter()
+            .map(|(ticker, code)| (format!("market.exchanges.{}", ticker), code))
+            .chain(
+                self.holidays
+                    .keys()
+                    .map(|code| ("market.holidays".to_string(), code)),
+            );
+        for (key, code) in codes {
+            if exchange(code).is_none() {
+                let known: Vec<&str> = EXCHANGES.iter().map(|e| e.code).collect();
+                return Err(EloraError::ConfigInvalid(format!(
+                    "{}: unknown exchange {:?}, known are {}",
+                    key,
+                    code,
+                    known.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// exchange ticker trades on
+    pub fn exchange_of(&self, ticker: &str) -> &'static Exchange {
+        if let Some(exchange) = self.exchanges.get(ticker).and_then(|code| exchange(code)) {
+            return exchange;
+        }
+        // they trade nearly all week, pausing only for an hour a day
+        let around_the_clock =
+            matches!(AssetClass::of(ticker), AssetClass::Future | AssetClass::Fx);
+        if let Some(exchange) = around_the_clock.then(|| exchange("24H")).flatten() {
+            return exchange;
+        }
+        ticker
+            .rsplit_once('.')
+            .and_then(|(_, suffix)| {
+                EXCHANGES.iter().find(|exchange| {
+                    exchange
+                        .suffixes
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(suffix))
+                })
+            })
+            .unwrap_or(&EXCHANGES[0])
+    }
+
+    pub fn is_open(&self, ticker: &str, now: &DateTime<Utc>) -> bool {
+        let exchange = self.exchange_of(ticker);
+        exchange.is_open(now, self.holidays_of(exchange))
+    }
+
+    /// checks if ticker's exchange is in pre-market or after-hours at `now`
+    pub fn is_extended(&self, ticker: &str, now: &DateTime<Utc>) -> bool {
+        let exchange = self.exchange_of(ticker);
+        exchange.is_extended(now, self.holidays_of(exchange))
+    }
+
+    fn holidays_of(&self, exchange: &Exchange) -> &[NaiveDate] {
+        self.holidays
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(exchange.code))
+            .map_or(&[][..], |(_, days)| days.as_slice())
+    }
+
+    /// checks if exchanges of all tickers are closed at `now`
+    pub fn all_closed<'a>(
+        &self,
+        mut tickers: impl Iterator<Item = &'a String>,
+        now: &DateTime<Utc>,
+    ) -> bool {
+        tickers.all(|ticker| !self.is_open(ticker, now))
+    }
+}
+
+/// adds `closed` marker to line of ticker which isn't trading, also given to
+/// templates as `closed` field
+pub fn mark_closed(mut line: Line) -> Line {
+    line.text.push_str(" closed");
+    line.with_field("closed", "closed")
+}
+
+#[test]
+fn testing_market_hours() {
+    let config = MarketConfig {
+        exchanges: BTreeMap::from([("BTC-USD".to_string(), "24H".to_string())]),
+        holidays: BTreeMap::from([(
+            "NYSE".to_string(),
+            vec![NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()],
+        )]),
+        closed_page: None,
+    };
+    assert_eq!(config.exchange_of("TSLA").code, "NYSE");
+    assert_eq!(config.exchange_of("VWRL.AS").code, "EURONEXT");
+    assert_eq!(config.exchange_of("BTC-USD").code, "24H");
+    assert_eq!(config.exchange_of("GC=F").code, "24H");
+    assert_eq!(config.exchange_of("^GSPC").code, "NYSE");
+
+    let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+    // tuesday 15:00 UTC is 10:00 in New York and 16:00 in Amsterdam
+    let tuesday = at("2024-01-09T15:00:00Z");
+    assert!(config.is_open("TSLA", &tuesday));
+    assert!(config.is_open("VWRL.AS", &tuesday));
+    // 17:00 UTC is past 17:30 in Amsterdam
+    assert!(!config.is_open("VWRL.AS", &at("2024-01-09T17:00:00Z")));
+    // new york is on summer time already, opens 13:30 UTC
+    assert!(config.is_open("TSLA", &at("2024-03-12T13:45:00Z")));
+    assert!(!config.is_open("TSLA", &at("2024-01-09T13:45:00Z")));
+    // 8:45 and 21:00 in New York
+    assert!(config.is_extended("TSLA", &at("2024-01-09T13:45:00Z")));
+    assert!(!config.is_extended("TSLA", &at("2024-01-10T02:00:00Z")));
+    assert!(!config.is_extended("TSLA", &tuesday));
+    assert!(!config.is_extended("VWRL.AS", &at("2024-01-09T17:00:00Z")));
+
+    let saturday = at("2024-01-13T15:00:00Z");
+    assert!(!config.is_open("TSLA", &saturday));
+    assert!(!config.is_open("TSLA", &at("2024-12-25T15:00:00Z")));
+    assert!(config.is_open("BTC-USD", &saturday));
+    let tickers = ["TSLA".to_string(), "VWRL.AS".to_string()];
+    assert!(config.all_closed(tickers.iter(), &saturday));
+    assert!(!config.all_closed(tickers.iter(), &tuesday));
+
+    assert!(config.validate().is_ok());
+    let mut invalid = config.clone();
+    invalid.exchanges.insert("X".into(), "MOON".into());
+    assert!(invalid.validate().is_err());
+}
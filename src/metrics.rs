@@ -0,0 +1,262 @@
+//! Prometheus metrics of running daemon
+//!
+//! Counters are kept in process wide [`METRICS`] and updated where fetches
+//! and hid writes happen. With `[metrics]` section they're served in text
+//! exposition format on `GET /metrics`, so alert can fire when keyboard
+//! stops getting data.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::Utc;
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{BoxError, EloraError};
+
+/// counters of whole process
+pub static METRICS: Metrics = Metrics::new();
+
+/// `[metrics]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// address `/metrics` is served on
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            listen: "127.0.0.1:9187".into(),
+        }
+    }
+}
+
+impl MetricsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.listen.parse::<SocketAddr>().is_err() {
+            return Err(EloraError::ConfigInvalid(format!(
+                "metrics.listen {:?} is not ip:port address",
+                self.listen
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// fetch counters of single provider
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchCounts {
+    pub successes: u64,
+    pub failures: u64,
+    /// unix time of last successful fetch
+    pub last_success: Option<i64>,
+}
+
+pub struct Metrics {
+    fetches: Mutex<BTreeMap<String, FetchCounts>>,
+    write_errors: AtomicU64,
+    bytes_sent: AtomicU64,
+    /// unix time data was last sent to keyboard, 0 before first send
+    last_refresh: AtomicI64,
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Metrics {
+            fetches: Mutex::new(BTreeMap::new()),
+            write_errors: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            last_refresh: AtomicI64::new(0),
+        }
+    }
+
+    /// counts single fetch attempt of provider, retries included
+    pub fn fetched(&self, provider: &str, success: bool) {
+        let mut fetches = self.fetches.lock().unwrap();
+        let counts = fetches.entry(provider.to_string()).or_default();
+        if success {
+            counts.successes += 1;
+            counts.last_success = Some(Utc::now().timestamp());
+        } else {
+            counts.failures += 1;
+        }
+    }
+
+    pub fn write_failed(&self) {
+        self.write_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// data got to keyboard
+    pub fn refreshed(&self) {
+        self.last_refresh
+            .store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    /// fetch counters by provider name
+    pub fn fetches(&self) -> BTreeMap<String, FetchCounts> {
+        self.fetches.lock().unwrap().clone()
+    }
+
+    /// unix time data was last sent to keyboard, `None` before first send
+    pub fn last_refresh(&self) -> Option<i64> {
+        Some(self.last_refresh.load(Ordering::Relaxed)).filter(|&time| time != 0)
+    }
+
+    /// Metrics in prometheus text exposition format
+    pub fn render(&self) -> String {
+        let fetches = self.fetches.lock().unwrap();
+        let mut out = String::new();
+        let mut per_provider =
+            |name: &str, kind: &str, help: &str, value: &dyn Fn(&FetchCounts) -> Option<String>| {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+                for (provider, counts) in fetches.iter() {
+                    if let Some(value) = value(counts) {
+                        let _ = writeln!(
+                            out,
+                            "{}{{provider=\"{}\"}} {}",
+                            name,
+                            escape(provider),
+                            value
+                        );
+                    }
+                }
+            };
+        per_provider(
+            "elora_fetch_success_total",
+            "counter",
+            "Successful fetches per provider",
+            &|counts| Some(counts.successes.to_string()),
+        );
+        per_provider(
+            "elora_fetch_failure_total",
+            "counter",
+            "Failed fetch attempts per provider, retries included",
+            &|counts| Some(counts.failures.to_string()),
+        );
+        per_provider(
+            "elora_last_fetch_success_timestamp_seconds",
+            "gauge",
+            "Unix time of last successful fetch per provider",
+            &|counts| counts.last_success.map(|time| time.to_string()),
+        );
+        let mut single = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(
+                out,
+                "# HELP {} {}\n# TYPE {} {}\n{} {}",
+                name, help, name, kind, name, value
+            );
+        };
+        single(
+            "elora_hid_write_errors_total",
+            "counter",
+            "Failed writes to keyboard",
+            self.write_errors.load(Ordering::Relaxed).to_string(),
+        );
+        single(
+            "elora_hid_bytes_sent_total",
+            "counter",
+            "Bytes of hid reports written to keyboard",
+            self.bytes_sent.load(Ordering::Relaxed).to_string(),
+        );
+        single(
+            "elora_last_refresh_timestamp_seconds",
+            "gauge",
+            "Unix time data was last sent to keyboard, 0 before first send",
+            self.last_refresh.load(Ordering::Relaxed).to_string(),
+        );
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+/// escapes label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn response(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() != "/metrics" {
+        return Ok(response(StatusCode::NOT_FOUND, "not found\n"));
+    }
+    if req.method() != Method::GET {
+        return Ok(response(StatusCode::METHOD_NOT_ALLOWED, "use GET\n"));
+    }
+    let mut response = response(StatusCode::OK, &METRICS.render());
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    Ok(response)
+}
+
+async fn serve(addr: SocketAddr) -> Result<(), BoxError> {
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    log::info!("Serving metrics on http://{}/metrics", addr);
+    server.await?;
+    Ok(())
+}
+
+/// Serves [`METRICS`] in background for as long as runtime lives
+pub fn spawn(config: &MetricsConfig) {
+    let Ok(addr) = config.listen.parse() else {
+        log::error!("Invalid metrics.listen {:?}", config.listen);
+        return;
+    };
+    tokio::spawn(async move {
+        if let Err(e) = serve(addr).await {
+            log::error!("Metrics server on {} stopped: {}", addr, e);
+        }
+    });
+}
+
+#[test]
+fn testing_metrics_render() {
+    let metrics = Metrics::new();
+    metrics.fetched("stocks", true);
+    metrics.fetched("stocks", false);
+    metrics.fetched("crypto", false);
+    metrics.sent(64);
+    metrics.write_failed();
+
+    let out = metrics.render();
+    assert!(out.contains("# TYPE elora_fetch_success_total counter\n"));
+    assert!(out.contains("elora_fetch_success_total{provider=\"stocks\"} 1\n"));
+    assert!(out.contains("elora_fetch_failure_total{provider=\"crypto\"} 1\n"));
+    assert!(!out.contains("elora_last_fetch_success_timestamp_seconds{provider=\"crypto\"}"));
+    assert!(out.contains("\nelora_hid_bytes_sent_total 64\n"));
+    assert!(out.contains("\nelora_hid_write_errors_total 1\n"));
+    assert!(out.contains("\nelora_last_refresh_timestamp_seconds 0\n"));
+}
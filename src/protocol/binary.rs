@@ -0,0 +1,266 @@
+//! Compact binary layout of page, sent as [`Command::Binary`] instead of text
+//!
+//! Quotes (lines with `price` field, ex. stocks and crypto) are sent as
+//! fixed size records, so firmware reads price and change as numbers instead
+//! of parsing text and decides itself how to draw them. Symbols get one byte
+//! id, name of symbol is sent only until keyboard received it once. Other
+//! lines are sent as text records. All numbers are little endian.
+//!
+//! | record | layout                                                   |
+//! |--------|----------------------------------------------------------|
+//! | symbol | `0x01`, id, length, ascii symbol                         |
+//! | quote  | `0x02`, id, price i32, change i16, flags                 |
+//! | text   | `0x03`, flags, length, text in display encoding          |
+//! | range  | `0x04`, id, day low, day high, 52-week low, high i32     |
+//!
+//! Range record follows quote whose line has range fields (`[quotes]`
+//! `ranges`), only for firmware speaking [`RANGE_VERSION`].
+
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+use super::PROTOCOL_VERSION;
+use super::{Command, Message};
+use crate::{
+    providers::Line,
+    render::{template::Value, Encoding},
+};
+
+pub const RECORD_SYMBOL: u8 = 0x01;
+pub const RECORD_QUOTE: u8 = 0x02;
+pub const RECORD_TEXT: u8 = 0x03;
+pub const RECORD_RANGE: u8 = 0x04;
+
+/// first protocol version which has [`RECORD_RANGE`], older firmware stops
+/// decoding page at unknown record
+pub const RANGE_VERSION: u8 = 9;
+
+/// quote price is sent in cents, ex. `24150` is `241.50`
+pub const PRICE_SCALE: f64 = 100.0;
+/// quote change is sent in hundredths of percent, ex. `-120` is `-1.2%`
+pub const CHANGE_SCALE: f64 = 100.0;
+
+/// record is previously fetched value, its provider failed to fetch
+pub const FLAG_STALE: u8 = 0x01;
+/// price rose since previous close
+pub const FLAG_UP: u8 = 0x02;
+/// price fell since previous close
+pub const FLAG_DOWN: u8 = 0x04;
+/// exchange of ticker is closed
+pub const FLAG_CLOSED: u8 = 0x08;
+/// value crossed provider's warning threshold
+pub const FLAG_HIGHLIGHT: u8 = 0x10;
+
+/// Encodes pages into binary messages, remembering symbol ids keyboard knows
+#[derive(Debug, Default)]
+pub struct BinaryEncoder {
+    ids: BTreeMap<String, u8>,
+    /// ids keyboard received symbol record of
+    known: Vec<u8>,
+    /// ids sent in last message, known once it's confirmed
+    pending: Vec<u8>,
+}
+
+impl BinaryEncoder {
+    pub fn new() -> Self {
+        BinaryEncoder::default()
+    }
+
+    /// Page `page` out of `page_count`, prefixed like [`Message::text`], for
+    /// firmware speaking protocol `version`
+    pub fn page(
+        &mut self,
+        page: u8,
+        page_count: u8,
+        lines: &[Line],
+        encoding: &Encoding,
+        version: u8,
+    ) -> Message {
+        let mut payload = vec![page, page_count];
+        self.pending.clear();
+        for line in lines {
+            match self.quote(line) {
+                Some((id, price)) => {
+                    if !self.known.contains(&id) && !self.pending.contains(&id) {
+                        let symbol = &line.metric.as_ref().unwrap().symbol;
+                        let symbol = &symbol.as_bytes()[..symbol.len().min(u8::MAX as usize)];
+                        payload.extend([RECORD_SYMBOL, id, symbol.len() as u8]);
+                        payload.extend_from_slice(symbol);
+                        self.pending.push(id);
+                    }
+                    let change = signed_change(line).unwrap_or_default();
+                    let change = (change * CHANGE_SCALE)
+                        .round()
+                        .clamp(i16::MIN as f64, i16::MAX as f64)
+                        as i16;
+                    let mut flags = flags(line);
+                    if change > 0 {
+                        flags |= FLAG_UP;
+                    } else if change < 0 {
+                        flags |= FLAG_DOWN;
+                    }
+                    payload.extend([RECORD_QUOTE, id]);
+                    payload.extend_from_slice(&price.to_le_bytes());
+                    payload.extend_from_slice(&change.to_le_bytes());
+                    payload.push(flags);
+                    if let Some(range) = range(line).filter(|_| version >= RANGE_VERSION) {
+                        payload.extend([RECORD_RANGE, id]);
+                        for price in range {
+                            payload.extend_from_slice(&fixed_price(price).to_le_bytes());
+                        }
+                    }
+                }
+                None => {
+                    let mut text = Vec::new();
+                    encoding.encode(&line.text, &mut text);
+                    text.truncate(u8::MAX as usize);
+                    payload.extend([RECORD_TEXT, flags(line), text.len() as u8]);
+                    payload.extend(text);
+                }
+            }
+        }
+        Message::new(Command::Binary, payload)
+    }
+
+    /// Id and fixed point price of quote line, `None` when line isn't quote
+    /// or all 256 ids are taken
+    fn quote(&mut self, line: &Line) -> Option<(u8, i32)> {
+        let metric = line
+            .metric
+            .as_ref()
+            .filter(|_| line.field("price").is_some())?;
+        let id = match self.ids.get(&metric.symbol) {
+            Some(&id) => id,
+            None => {
+                let id = u8::try_from(self.ids.len()).ok()?;
+                self.ids.insert(metric.symbol.clone(), id);
+                id
+            }
+        };
+        Some((id, fixed_price(metric.value)))
+    }
+
+    /// last page was delivered, its symbols won't be sent again
+    pub fn confirm(&mut self) {
+        self.known.append(&mut self.pending);
+    }
+
+    /// keyboard was reconnected and forgot symbols, ids stay same
+    pub fn reset(&mut self) {
+        self.known.clear();
+        self.pending.clear();
+    }
+}
+
+fn fixed_price(price: f64) -> i32 {
+    (price * PRICE_SCALE)
+        .round()
+        .clamp(i32::MIN as f64, i32::MAX as f64) as i32
+}
+
+/// Day low and high, 52-week low and high of quote line, 0 where unknown.
+/// `None` when line has neither range
+fn range(line: &Line) -> Option<[f64; 4]> {
+    let number = |name| match line.field(name) {
+        Some(&Value::Number(value)) => Some(value),
+        _ => None,
+    };
+    let range = ["day_low", "day_high", "year_low", "year_high"].map(number);
+    range
+        .iter()
+        .any(Option::is_some)
+        .then(|| range.map(Option::unwrap_or_default))
+}
+
+fn flags(line: &Line) -> u8 {
+    let mut flags = 0;
+    if line.stale {
+        flags |= FLAG_STALE;
+    }
+    if line.field("closed").is_some() {
+        flags |= FLAG_CLOSED;
+    }
+    if line.highlight {
+        flags |= FLAG_HIGHLIGHT;
+    }
+    flags
+}
+
+/// change field of stock line is absolute, arrow tells its direction
+fn signed_change(line: &Line) -> Option<f64> {
+    let &Value::Number(change) = line.field("change")? else {
+        return None;
+    };
+    match line.field("arrow") {
+        Some(Value::Text(arrow)) if arrow == "▼" => Some(-change),
+        _ => Some(change),
+    }
+}
+
+#[test]
+fn testing_binary_page() {
+    let encoding = Encoding::default();
+    let quote = Line::new("TSLA 241 ▼1.2%")
+        .with_field("arrow", "▼")
+        .with_field("change", 1.2)
+        .with_field("symbol", "TSLA")
+        .with_field("price", 241.5)
+        .with_metric("TSLA", 241.5);
+    let lines = [quote, Line::new("CPU 12%")];
+
+    let mut encoder = BinaryEncoder::new();
+    let message = encoder.page(0, 2, &lines, &encoding, PROTOCOL_VERSION);
+    assert_eq!(message.command, Command::Binary);
+    let mut expected = vec![0, 2, RECORD_SYMBOL, 0, 4];
+    expected.extend(b"TSLA");
+    expected.extend([
+        RECORD_QUOTE,
+        0,
+        0x56,
+        0x5E,
+        0x00,
+        0x00,
+        0x88,
+        0xFF,
+        FLAG_DOWN,
+    ]);
+    expected.extend([RECORD_TEXT, 0, 7]);
+    expected.extend(b"CPU 12%");
+    assert_eq!(message.payload, expected);
+
+    // symbol is resent until page with it is confirmed
+    assert_eq!(
+        encoder
+            .page(0, 2, &lines, &encoding, PROTOCOL_VERSION)
+            .payload,
+        expected
+    );
+    encoder.confirm();
+    let message = encoder.page(0, 2, &lines, &encoding, PROTOCOL_VERSION);
+    assert_eq!(message.payload[2], RECORD_QUOTE);
+    encoder.reset();
+    assert_eq!(
+        encoder
+            .page(0, 2, &lines, &encoding, PROTOCOL_VERSION)
+            .payload,
+        expected
+    );
+
+    // range follows quote, older firmware doesn't get it
+    let ranged = [lines[0]
+        .clone()
+        .with_field("day_low", 238.0)
+        .with_field("day_high", 245.25)];
+    encoder.confirm();
+    let payload = encoder
+        .page(0, 1, &ranged, &encoding, PROTOCOL_VERSION)
+        .payload;
+    assert_eq!(payload.len(), 2 + 9 + 2 + 16);
+    assert_eq!(&payload[11..17], &[RECORD_RANGE, 0, 0xF8, 0x5C, 0x00, 0x00]);
+    assert_eq!(&payload[17..21], &24525i32.to_le_bytes());
+    assert_eq!(&payload[21..29], &[0; 8]);
+    let payload = encoder
+        .page(0, 1, &ranged, &encoding, RANGE_VERSION - 1)
+        .payload;
+    assert_eq!(payload.len(), 2 + 9);
+}
@@ -0,0 +1,169 @@
+//! Splitting messages into 32 byte raw hid reports and joining them back
+//!
+//! Frame layout:
+//!
+//! | byte  | meaning                                         |
+//! |-------|-------------------------------------------------|
+//! | 0     | command, see [`Command`]                        |
+//! | 1     | sequence number of frame inside message, from 0 |
+//! | 2     | flags, bit 0 set on last frame of message       |
+//! | 3     | count of payload bytes in this frame            |
+//! | 4..32 | payload, zero padded                            |
+
+use thiserror::Error;
+
+use super::{Command, Message, REPORT_SIZE};
+
+pub const HEADER_SIZE: usize = 4;
+/// payload bytes which fit into single frame
+pub const CHUNK_SIZE: usize = REPORT_SIZE - HEADER_SIZE;
+/// sequence number is single byte, so message can't have more frames
+pub const MAX_FRAMES: usize = 256;
+pub const MAX_PAYLOAD: usize = CHUNK_SIZE * MAX_FRAMES;
+
+/// flag set on last frame of message
+pub const FLAG_END: u8 = 0x01;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FrameError {
+    #[error("payload of {0} bytes doesn't fit into {max} frames", max = MAX_FRAMES)]
+    PayloadTooLong(usize),
+    #[error("report of {0} bytes is too short")]
+    ShortReport(usize),
+    #[error("unknown command {0:#04x}")]
+    UnknownCommand(u8),
+    #[error("invalid chunk length {0}")]
+    InvalidLength(usize),
+    /// frame out of order, ex. because previous one was lost
+    #[error("unexpected frame {seq}, expected {expected}")]
+    UnexpectedFrame { seq: usize, expected: usize },
+}
+
+/// One hid write: report id (always 0, QMK doesn't use report ids) followed
+/// by 32 byte report
+pub type Frame = [u8; REPORT_SIZE + 1];
+
+/// Splits message into frames ready to be written to hid device. Empty
+/// payload still produces single end frame
+pub fn encode(message: &Message) -> Result<Vec<Frame>, FrameError> {
+    if message.payload.len() > MAX_PAYLOAD {
+        return Err(FrameError::PayloadTooLong(message.payload.len()));
+    }
+
+    let chunks: Vec<&[u8]> = if message.payload.is_empty() {
+        vec![&[]]
+    } else {
+        message.payload.chunks(CHUNK_SIZE).collect()
+    };
+    let last = chunks.len() - 1;
+
+    let frames = chunks
+        .into_iter()
+        .enumerate()
+        .map(|(seq, chunk)| {
+            let mut frame: Frame = [0; REPORT_SIZE + 1];
+            frame[1] = message.command as u8;
+            frame[2] = seq as u8;
+            frame[3] = if seq == last { FLAG_END } else { 0 };
+            frame[4] = chunk.len() as u8;
+            frame[1 + HEADER_SIZE..1 + HEADER_SIZE + chunk.len()].copy_from_slice(chunk);
+            frame
+        })
+        .collect();
+    Ok(frames)
+}
+
+/// Reassembles messages from 32 byte reports, same as firmware does
+#[derive(Debug, Default)]
+pub struct Decoder {
+    command: Option<Command>,
+    next_seq: usize,
+    payload: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    /// Feeds single report (without report id). Returns message once its end
+    /// frame arrives. Frame with sequence 0 always starts new message, so
+    /// decoder recovers after dropped frames
+    pub fn push(&mut self, report: &[u8]) -> Result<Option<Message>, FrameError> {
+        if report.len() < HEADER_SIZE {
+            return Err(FrameError::ShortReport(report.len()));
+        }
+        let command = Command::try_from(report[0]).map_err(FrameError::UnknownCommand)?;
+        let seq = report[1] as usize;
+        let flags = report[2];
+        let len = report[3] as usize;
+        if len > CHUNK_SIZE || HEADER_SIZE + len > report.len() {
+            return Err(FrameError::InvalidLength(len));
+        }
+
+        if seq == 0 {
+            self.command = Some(command);
+            self.next_seq = 0;
+            self.payload.clear();
+        }
+        if self.command != Some(command) || seq != self.next_seq {
+            let expected = self.next_seq;
+            self.reset();
+            return Err(FrameError::UnexpectedFrame { seq, expected });
+        }
+
+        self.payload
+            .extend_from_slice(&report[HEADER_SIZE..HEADER_SIZE + len]);
+        self.next_seq += 1;
+
+        if flags & FLAG_END != 0 {
+            let payload = std::mem::take(&mut self.payload);
+            self.reset();
+            return Ok(Some(Message::new(command, payload)));
+        }
+        Ok(None)
+    }
+
+    fn reset(&mut self) {
+        self.command = None;
+        self.next_seq = 0;
+        self.payload.clear();
+    }
+}
+
+#[test]
+fn testing_framing_roundtrip() {
+    let payload: Vec<u8> = (0..70).collect();
+    let frames = encode(&Message::new(Command::Text, payload.clone())).unwrap();
+    assert_eq!(frames.len(), 3);
+    assert!(frames.iter().all(|f| f[0] == 0));
+    assert_eq!(&frames[0][1..5], &[Command::Text as u8, 0, 0, 28]);
+    assert_eq!(&frames[2][1..5], &[Command::Text as u8, 2, FLAG_END, 14]);
+
+    let mut decoder = Decoder::new();
+    assert_eq!(decoder.push(&frames[0][1..]).unwrap(), None);
+    assert_eq!(decoder.push(&frames[1][1..]).unwrap(), None);
+    let message = decoder.push(&frames[2][1..]).unwrap().unwrap();
+    assert_eq!(message, Message::new(Command::Text, payload));
+}
+
+#[test]
+fn testing_framing_errors() {
+    let frames = encode(&Message::new(Command::Text, vec![b'a'; 60])).unwrap();
+    let mut decoder = Decoder::new();
+    decoder.push(&frames[0][1..]).unwrap();
+    // frame 1 was lost
+    assert_eq!(
+        decoder.push(&frames[2][1..]),
+        Err(FrameError::UnexpectedFrame {
+            seq: 2,
+            expected: 1
+        })
+    );
+
+    assert!(encode(&Message::new(Command::Text, vec![0; MAX_PAYLOAD + 1])).is_err());
+    assert_eq!(
+        encode(&Message::new(Command::Text, vec![])).unwrap().len(),
+        1
+    );
+}
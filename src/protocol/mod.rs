@@ -0,0 +1,266 @@
+//! Raw hid protocol spoken with keyboard firmware
+//!
+//! QMK raw hid reports are fixed 32 byte packets, so every message is split
+//! into frames by [`framing`]. See `docs/PROTOCOL.md` for layout and matching
+//! decoder on QMK side.
+
+pub mod binary;
+pub mod framing;
+
+/// QMK raw hid report size
+pub const REPORT_SIZE: usize = 32;
+
+/// Protocol version host speaks, sent in [`Command::Hello`]
+pub const PROTOCOL_VERSION: u8 = 9;
+/// Oldest firmware protocol version host can downgrade to. Firmware which
+/// doesn't answer hello at all draws raw bytes and would misrender frames
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+
+/// Byte drawn in front of stale lines in text payload, cp437 `‼` in QMK
+/// glcdfont. Line is stale when its provider failed to fetch and last known
+/// value is resent
+pub const STALE_MARKER: u8 = 0x13;
+/// Byte drawn in front of highlighted lines in text payload, cp437 `►` in
+/// QMK glcdfont, see [`Line::highlight`](crate::providers::Line::highlight)
+pub const HIGHLIGHT_MARKER: u8 = 0x10;
+
+/// [`Command::Alert`] direction byte of value which rose above threshold
+pub const ALERT_ABOVE: u8 = 0x01;
+/// [`Command::Alert`] direction byte of value which fell below threshold
+pub const ALERT_BELOW: u8 = 0x02;
+/// [`Command::Alert`] direction byte of urgent alert, ex. triggered on-call
+/// incident. Firmware older than version 8 gets it as [`ALERT_ABOVE`]
+pub const ALERT_URGENT: u8 = 0x03;
+/// first protocol version which has [`ALERT_URGENT`]
+const URGENT_ALERT_VERSION: u8 = 8;
+
+/// [`Command::Page`] action byte switching to next page
+pub const PAGE_NEXT: u8 = 0x01;
+/// [`Command::Page`] action byte switching to previous page
+pub const PAGE_PREVIOUS: u8 = 0x02;
+/// [`Command::Page`] action byte switching to page index in next byte
+pub const PAGE_SHOW: u8 = 0x03;
+
+/// [`Command::Pomodoro`] action byte starting timer
+pub const POMODORO_START: u8 = 0x01;
+/// [`Command::Pomodoro`] action byte stopping timer
+pub const POMODORO_STOP: u8 = 0x02;
+/// [`Command::Pomodoro`] action byte starting stopped or stopping running timer
+pub const POMODORO_TOGGLE: u8 = 0x03;
+
+/// Type of message, first byte of every frame. Commands from `0x80` up are
+/// sent by keyboard to host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Command {
+    /// text to draw on display, prefixed with page index and page count
+    Text = 0x01,
+    /// clear display, sent when host shuts down so stale data isn't left on it
+    Clear = 0x02,
+    /// alert threshold was crossed, prefixed with direction, see [`Message::alert`]
+    Alert = 0x03,
+    /// host protocol version, keyboard answers with [`Command::Version`]
+    Hello = 0x04,
+    /// price history of symbols on current page, see [`Message::sparklines`]
+    Sparkline = 0x05,
+    /// page as compact binary records, see [`binary`]
+    Binary = 0x06,
+    /// changed rows of page keyboard already shows, see [`Message::rows`]
+    Rows = 0x07,
+    /// keyboard received message, payload is command of received message
+    Ack = 0x80,
+    /// keyboard reports protocol version it speaks, single byte payload
+    Version = 0x81,
+    /// keyboard asks host to refetch and resend data
+    Refresh = 0x82,
+    /// keyboard asks host to switch page, ex. on encoder turn, see
+    /// [`PageRequest`]
+    Page = 0x83,
+    /// keyboard starts or stops pomodoro timer, ex. on key tap, see
+    /// [`providers::pomodoro`](crate::providers::pomodoro)
+    Pomodoro = 0x84,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Command::Text),
+            0x02 => Ok(Command::Clear),
+            0x03 => Ok(Command::Alert),
+            0x04 => Ok(Command::Hello),
+            0x05 => Ok(Command::Sparkline),
+            0x06 => Ok(Command::Binary),
+            0x07 => Ok(Command::Rows),
+            0x80 => Ok(Command::Ack),
+            0x81 => Ok(Command::Version),
+            0x82 => Ok(Command::Refresh),
+            0x83 => Ok(Command::Page),
+            0x84 => Ok(Command::Pomodoro),
+            other => Err(other),
+        }
+    }
+}
+
+impl Command {
+    /// first protocol version which has command
+    pub fn since_version(self) -> u8 {
+        match self {
+            Command::Alert => 2,
+            Command::Sparkline => 3,
+            Command::Binary => 4,
+            Command::Rows => 5,
+            Command::Page => 6,
+            Command::Pomodoro => 7,
+            _ => 1,
+        }
+    }
+}
+
+/// Version both sides speak, `None` when firmware is too old for host
+pub fn negotiate(firmware: u8) -> Option<u8> {
+    if firmware < MIN_PROTOCOL_VERSION {
+        None
+    } else {
+        Some(firmware.min(PROTOCOL_VERSION))
+    }
+}
+
+/// Page switch asked for by keyboard in [`Command::Page`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageRequest {
+    Next,
+    Previous,
+    Show(u8),
+}
+
+impl PageRequest {
+    /// Request from [`Command::Page`] payload, `None` when action is unknown
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        match payload {
+            [PAGE_NEXT, ..] => Some(PageRequest::Next),
+            [PAGE_PREVIOUS, ..] => Some(PageRequest::Previous),
+            [PAGE_SHOW, index, ..] => Some(PageRequest::Show(*index)),
+            _ => None,
+        }
+    }
+
+    /// Page shown after request out of `page_count` pages, wrapping around.
+    /// Index past last page keeps `current` one
+    pub fn apply(self, current: usize, page_count: usize) -> usize {
+        match self {
+            PageRequest::Next => (current + 1) % page_count,
+            PageRequest::Previous => (current + page_count - 1) % page_count,
+            PageRequest::Show(index) if (index as usize) < page_count => index as usize,
+            PageRequest::Show(_) => current,
+        }
+    }
+}
+
+/// Whole message reassembled from frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub command: Command,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(command: Command, payload: Vec<u8>) -> Self {
+        Message { command, payload }
+    }
+
+    /// Text drawn as page `page` out of `page_count` rotated pages
+    pub fn text(page: u8, page_count: u8, text: &[u8]) -> Self {
+        let mut payload = Vec::with_capacity(text.len() + 2);
+        payload.push(page);
+        payload.push(page_count);
+        payload.extend_from_slice(text);
+        Message::new(Command::Text, payload)
+    }
+
+    /// Rows of page `page` which changed since it was last sent, each as
+    /// row index, length and text. `row_count` is rows page has now, so
+    /// firmware can clear ones past it
+    pub fn rows(page: u8, page_count: u8, row_count: u8, rows: &[(u8, &[u8])]) -> Self {
+        let mut payload = vec![page, page_count, row_count];
+        for (index, text) in rows {
+            let text = &text[..text.len().min(u8::MAX as usize)];
+            payload.push(*index);
+            payload.push(text.len() as u8);
+            payload.extend_from_slice(text);
+        }
+        Message::new(Command::Rows, payload)
+    }
+
+    /// Handshake sent right after opening keyboard
+    pub fn hello() -> Self {
+        Message::new(Command::Hello, vec![PROTOCOL_VERSION])
+    }
+
+    /// Sparklines of symbols, each as symbol length, ascii symbol and
+    /// [`SPARKLINE_LEN`](crate::history::SPARKLINE_LEN) levels
+    pub fn sparklines(sparklines: &[(&str, [u8; crate::history::SPARKLINE_LEN])]) -> Self {
+        let mut payload = Vec::new();
+        for (symbol, levels) in sparklines {
+            let symbol = &symbol.as_bytes()[..symbol.len().min(u8::MAX as usize)];
+            payload.push(symbol.len() as u8);
+            payload.extend_from_slice(symbol);
+            payload.extend_from_slice(levels);
+        }
+        Message::new(Command::Sparkline, payload)
+    }
+
+    /// Alert with direction ([`ALERT_ABOVE`], [`ALERT_BELOW`] or
+    /// [`ALERT_URGENT`]) and text describing it, firmware flashes rgb or led
+    /// on it
+    pub fn alert(direction: u8, text: &[u8]) -> Self {
+        let mut payload = Vec::with_capacity(text.len() + 1);
+        payload.push(direction);
+        payload.extend_from_slice(text);
+        Message::new(Command::Alert, payload)
+    }
+
+    /// Same message as firmware speaking older `version` understands it,
+    /// `None` when it needs no change
+    pub fn downgrade(&self, version: u8) -> Option<Message> {
+        match (self.command, self.payload.first()) {
+            (Command::Alert, Some(&ALERT_URGENT)) if version < URGENT_ALERT_VERSION => {
+                let mut payload = self.payload.clone();
+                payload[0] = ALERT_ABOVE;
+                Some(Message::new(Command::Alert, payload))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn testing_version_negotiation() {
+    assert_eq!(negotiate(0), None);
+    assert_eq!(negotiate(1), Some(1));
+    assert_eq!(negotiate(PROTOCOL_VERSION), Some(PROTOCOL_VERSION));
+    assert_eq!(negotiate(PROTOCOL_VERSION + 1), Some(PROTOCOL_VERSION));
+    assert!(Command::Alert.since_version() > MIN_PROTOCOL_VERSION);
+
+    let urgent = Message::alert(ALERT_URGENT, b"DB down");
+    assert_eq!(urgent.downgrade(7).unwrap().payload, b"\x01DB down");
+    assert_eq!(urgent.downgrade(PROTOCOL_VERSION), None);
+    assert_eq!(Message::alert(ALERT_BELOW, b"x").downgrade(2), None);
+}
+
+#[test]
+fn testing_page_request() {
+    assert_eq!(PageRequest::parse(&[PAGE_NEXT]), Some(PageRequest::Next));
+    assert_eq!(
+        PageRequest::parse(&[PAGE_SHOW, 2]),
+        Some(PageRequest::Show(2))
+    );
+    assert_eq!(PageRequest::parse(&[PAGE_SHOW]), None);
+    assert_eq!(PageRequest::parse(&[]), None);
+
+    assert_eq!(PageRequest::Next.apply(2, 3), 0);
+    assert_eq!(PageRequest::Previous.apply(0, 3), 2);
+    assert_eq!(PageRequest::Show(1).apply(0, 3), 1);
+    assert_eq!(PageRequest::Show(7).apply(0, 3), 0);
+}
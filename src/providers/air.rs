@@ -0,0 +1,228 @@
+//! Air quality and pollen from Open-Meteo, ex. `AIR 23 PM2.5 5 grass 12`
+//!
+//! Pollen, in grains per m³, is of strongest type and shown only where
+//! Open-Meteo has it (Europe). Index carries metric under label, so
+//! `[alerts]` rule like `AIR > 50` tells to keep window closed.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// pollen types Open-Meteo forecasts, as named in its api
+const POLLEN: [&str; 6] = ["alder", "birch", "grass", "mugwort", "olive", "ragweed"];
+
+/// air quality index scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    /// 0 to 100+, good below 20
+    #[default]
+    European,
+    /// 0 to 500, good below 50
+    Us,
+}
+
+impl Scale {
+    fn variable(self) -> &'static str {
+        match self {
+            Scale::European => "european_aqi",
+            Scale::Us => "us_aqi",
+        }
+    }
+}
+
+/// `[air]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AirConfig {
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub scale: Scale,
+    pub label: String,
+}
+
+impl Default for AirConfig {
+    fn default() -> Self {
+        AirConfig {
+            lat: None,
+            lon: None,
+            scale: Scale::default(),
+            label: "AIR".into(),
+        }
+    }
+}
+
+impl AirConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        match (self.lat, self.lon) {
+            (Some(lat), Some(lon))
+                if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) =>
+            {
+                Ok(())
+            }
+            _ => Err(EloraError::ConfigInvalid(
+                "air needs lat and lon of location".into(),
+            )),
+        }
+    }
+}
+
+/// Response of `/v1/air-quality` with `current` variables
+#[derive(Debug, Deserialize)]
+struct AirResponse {
+    current: Current,
+}
+
+#[derive(Debug, Deserialize)]
+struct Current {
+    /// index of either scale, only requested one is present
+    #[serde(alias = "european_aqi", alias = "us_aqi")]
+    aqi: Option<f64>,
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    alder_pollen: Option<f64>,
+    birch_pollen: Option<f64>,
+    grass_pollen: Option<f64>,
+    mugwort_pollen: Option<f64>,
+    olive_pollen: Option<f64>,
+    ragweed_pollen: Option<f64>,
+}
+
+impl Current {
+    /// strongest pollen type with its count, `None` outside of Europe or
+    /// when no pollen is in air
+    fn pollen(&self) -> Option<(&'static str, f64)> {
+        let counts = [
+            self.alder_pollen,
+            self.birch_pollen,
+            self.grass_pollen,
+            self.mugwort_pollen,
+            self.olive_pollen,
+            self.ragweed_pollen,
+        ];
+        POLLEN
+            .into_iter()
+            .zip(counts)
+            .filter_map(|(name, count)| Some((name, count?)))
+            .filter(|(_, count)| *count >= 1.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// Formats air quality into line, ex. `AIR 23 PM2.5 5 grass 12`
+fn to_line(config: &AirConfig, current: &Current) -> Result<Line, BoxError> {
+    let aqi = current.aqi.ok_or("no air quality index for location")?;
+    let mut text = format!("{} {:.0}", config.label, aqi);
+    let mut line = Line::new("")
+        .with_field("label", config.label.as_str())
+        .with_field("aqi", aqi);
+    if let Some(pm2_5) = current.pm2_5 {
+        text.push_str(&format!(" PM2.5 {:.0}", pm2_5));
+        line = line.with_field("pm2_5", pm2_5);
+    }
+    if let Some(pm10) = current.pm10 {
+        line = line.with_field("pm10", pm10);
+    }
+    if let Some((pollen, count)) = current.pollen() {
+        text.push_str(&format!(" {} {:.0}", pollen, count));
+        line = line
+            .with_field("pollen", pollen)
+            .with_field("pollen_count", count);
+    }
+    line.text = text;
+    Ok(line.with_metric(&config.label, aqi))
+}
+
+/// Open-Meteo air quality at configured location
+pub struct AirProvider {
+    config: AirConfig,
+    client: Client,
+}
+
+impl AirProvider {
+    pub fn new(config: AirConfig) -> Self {
+        AirProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for AirProvider {
+    fn name(&self) -> &str {
+        "air"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        // model updates hourly
+        Some(Duration::from_secs(900))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching air quality from open-meteo");
+
+        let (lat, lon) = match (self.config.lat, self.config.lon) {
+            (Some(lat), Some(lon)) => (lat, lon),
+            _ => return Err("air.lat and air.lon are required".into()),
+        };
+        let variables = [self.config.scale.variable(), "pm2_5", "pm10"]
+            .into_iter()
+            .map(String::from)
+            .chain(POLLEN.iter().map(|pollen| format!("{}_pollen", pollen)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let response: AirResponse = self
+            .client
+            .get("https://air-quality-api.open-meteo.com/v1/air-quality")
+            .query(&[
+                ("latitude", lat.to_string()),
+                ("longitude", lon.to_string()),
+                ("current", variables),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(vec![to_line(&self.config, &response.current)?])
+    }
+}
+
+#[test]
+fn testing_air_line() {
+    let config = AirConfig {
+        lat: Some(52.37),
+        lon: Some(4.89),
+        ..AirConfig::default()
+    };
+    config.validate().unwrap();
+    assert!(AirConfig::default().validate().is_err());
+
+    let response: AirResponse = serde_json::from_str(
+        r#"{"latitude":52.4,"longitude":4.9,"current_units":{"european_aqi":"EAQI"},
+            "current":{"time":"2026-10-14T15:00","interval":3600,"european_aqi":23,
+            "pm2_5":5.2,"pm10":9.8,"alder_pollen":0.0,"birch_pollen":0.3,"grass_pollen":12.4,
+            "mugwort_pollen":1.5,"olive_pollen":0.0,"ragweed_pollen":0.0}}"#,
+    )
+    .unwrap();
+    let line = to_line(&config, &response.current).unwrap();
+    assert_eq!(line.text, "AIR 23 PM2.5 5 grass 12");
+    assert_eq!(line.metric.unwrap().value, 23.0);
+
+    let us: AirResponse = serde_json::from_str(
+        r#"{"current":{"time":"2026-10-14T15:00","us_aqi":61,"pm2_5":17.9,"pm10":null,
+            "alder_pollen":null,"birch_pollen":null,"grass_pollen":null,
+            "mugwort_pollen":null,"olive_pollen":null,"ragweed_pollen":null}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        to_line(&config, &us.current).unwrap().text,
+        "AIR 61 PM2.5 18"
+    );
+}
@@ -0,0 +1,213 @@
+//! Stock quotes from Alpha Vantage `GLOBAL_QUOTE` api
+//!
+//! Free key allows only 5 requests a minute, so requests are queued through
+//! [`RateLimiter`] and refresh interval grows with count of tickers instead of
+//! having to be tuned by hand. Ticker which can't be fetched (ex. daily limit
+//! was hit) keeps its last quote.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    stocks::{self, Quote, Quotes},
+    DataProvider, Line,
+};
+use crate::{ratelimit::RateLimiter, BoxError, EloraError};
+
+/// env variable used when `api_key` is not in config
+pub const API_KEY_ENV: &str = "ALPHAVANTAGE_API_KEY";
+
+/// `[alphavantage]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlphaVantageConfig {
+    pub api_key: Option<String>,
+    /// stock symbols, ex. `["IBM", "TSCO.LON"]`
+    pub tickers: Vec<String>,
+    /// requests allowed per minute, 5 on free key
+    pub requests_per_minute: usize,
+}
+
+impl Default for AlphaVantageConfig {
+    fn default() -> Self {
+        AlphaVantageConfig {
+            api_key: None,
+            tickers: Vec::new(),
+            requests_per_minute: 5,
+        }
+    }
+}
+
+impl AlphaVantageConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.tickers.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "alphavantage.tickers needs at least one ticker".into(),
+            ));
+        }
+        if self.requests_per_minute == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "alphavantage.requests_per_minute must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn api_key(&self) -> Result<String, BoxError> {
+        match &self.api_key {
+            Some(key) => Ok(key.clone()),
+            None => std::env::var(API_KEY_ENV).map_err(|_| {
+                format!("alphavantage.api_key or {} env is required", API_KEY_ENV).into()
+            }),
+        }
+    }
+}
+
+/// Response of `GLOBAL_QUOTE`, numbers come as strings. Over limit requests
+/// get `Note` or `Information` message instead of quote
+#[derive(Debug, Deserialize)]
+struct QuoteResponse {
+    #[serde(rename = "Global Quote")]
+    quote: Option<GlobalQuote>,
+    #[serde(rename = "Note")]
+    note: Option<String>,
+    #[serde(rename = "Information")]
+    information: Option<String>,
+    #[serde(rename = "Error Message")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlobalQuote {
+    #[serde(rename = "05. price")]
+    price: Option<String>,
+    #[serde(rename = "08. previous close")]
+    previous_close: Option<String>,
+}
+
+fn parse_quote(body: &str) -> Result<Quote, BoxError> {
+    let response: QuoteResponse = serde_json::from_str(body)?;
+    if let Some(message) = response.error.or(response.note).or(response.information) {
+        return Err(message.into());
+    }
+    let quote = response.quote.ok_or("no Global Quote in response")?;
+    let price = quote
+        .price
+        .ok_or("no price in response, symbol may be unknown")?
+        .parse()?;
+    Ok(Quote {
+        price,
+        previous_close: quote.previous_close.and_then(|close| close.parse().ok()),
+        currency: None,
+        extended: None,
+        day_range: None,
+        year_range: None,
+    })
+}
+
+/// Alpha Vantage stock prices, fetched one ticker at a time within limit
+pub struct AlphaVantageProvider {
+    config: AlphaVantageConfig,
+    client: Client,
+    limiter: RateLimiter,
+    last_quotes: Mutex<Quotes>,
+}
+
+impl AlphaVantageProvider {
+    pub fn new(config: AlphaVantageConfig) -> Self {
+        AlphaVantageProvider {
+            limiter: RateLimiter::per_minute(config.requests_per_minute),
+            config,
+            client: Client::new(),
+            last_quotes: Mutex::new(Quotes::new()),
+        }
+    }
+
+    async fn fetch_quote(&self, api_key: &str, ticker: &str) -> Result<Quote, BoxError> {
+        self.limiter.acquire().await;
+        let body = self
+            .client
+            .get("https://www.alphavantage.co/query")
+            .query(&[
+                ("function", "GLOBAL_QUOTE"),
+                ("symbol", ticker),
+                ("apikey", api_key),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        parse_quote(&body)
+    }
+}
+
+#[async_trait]
+impl DataProvider for AlphaVantageProvider {
+    fn name(&self) -> &str {
+        "alphavantage"
+    }
+
+    /// as often as all tickers fit into limit, ex. 12 tickers every 3 minutes
+    /// on free key
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(self.limiter.period_of(self.config.tickers.len()))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching alphavantage quotes from remote");
+
+        let api_key = self.config.api_key()?;
+        let mut fetched = 0;
+        for ticker in &self.config.tickers {
+            match self.fetch_quote(&api_key, ticker).await {
+                Ok(quote) => {
+                    self.last_quotes
+                        .lock()
+                        .unwrap()
+                        .insert(ticker.clone(), quote);
+                    fetched += 1;
+                }
+                Err(e) => log::error!("Unable to fetch {} from alphavantage: {}", ticker, e),
+            }
+        }
+        if fetched == 0 {
+            return Err("no ticker could be fetched".into());
+        }
+
+        let last_quotes = self.last_quotes.lock().unwrap();
+        Ok(self
+            .config
+            .tickers
+            .iter()
+            .filter_map(|ticker| {
+                let quote = last_quotes.get(ticker)?;
+                Some(stocks::quote_line(ticker, quote))
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn testing_alphavantage_quote() {
+    let body = r#"{"Global Quote":{"01. symbol":"IBM","02. open":"162.1600","05. price":"161.6300","07. latest trading day":"2024-01-12","08. previous close":"162.1600","09. change":"-0.5300","10. change percent":"-0.3268%"}}"#;
+    let quote = parse_quote(body).unwrap();
+    assert_eq!(quote.price, 161.63);
+    assert_eq!(quote.previous_close, Some(162.16));
+
+    let body = r#"{"Note":"Thank you for using Alpha Vantage! Our standard API call frequency is 5 calls per minute"}"#;
+    assert!(parse_quote(body).is_err());
+    assert!(parse_quote(r#"{"Global Quote":{}}"#).is_err());
+
+    let provider = AlphaVantageProvider::new(AlphaVantageConfig {
+        tickers: (0..12).map(|i| format!("T{}", i)).collect(),
+        ..AlphaVantageConfig::default()
+    });
+    assert_eq!(
+        provider.refresh_interval(),
+        Some(Duration::from_secs(3 * 60))
+    );
+}
@@ -0,0 +1,202 @@
+//! Sunrise, sunset and moon phase computed from coordinates, ex.
+//! `▲07:12 ▼18:45 🌒`
+//!
+//! Nothing is fetched: sun uses NOAA sunrise equation, good to a minute or
+//! two, and moon phase is counted in mean synodic months from known new
+//! moon. Moon glyphs are transliterated unless `[encoding]` maps them.
+
+use std::{f64::consts::PI, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// mean length of lunar month in days
+const SYNODIC_MONTH: f64 = 29.530_588_853;
+/// new moon of 2000-01-06 18:14 UTC, phase is counted from it
+const NEW_MOON: i64 = 947_182_440;
+/// julian day of 2000-01-01 12:00 UTC
+const J2000: f64 = 2_451_545.0;
+/// julian day of unix epoch
+const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+
+/// moon phases from new moon, with glyph shown and name for templates
+const PHASES: [(&str, &str); 8] = [
+    ("🌑", "new"),
+    ("🌒", "waxing crescent"),
+    ("🌓", "first quarter"),
+    ("🌔", "waxing gibbous"),
+    ("🌕", "full"),
+    ("🌖", "waning gibbous"),
+    ("🌗", "last quarter"),
+    ("🌘", "waning crescent"),
+];
+
+/// `[astro]` config section
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AstroConfig {
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+}
+
+impl AstroConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        match (self.lat, self.lon) {
+            (Some(lat), Some(lon))
+                if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) =>
+            {
+                Ok(())
+            }
+            _ => Err(EloraError::ConfigInvalid(
+                "astro needs lat and lon of location".into(),
+            )),
+        }
+    }
+}
+
+/// Sun on given day
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sun {
+    Rises(DateTime<Utc>, DateTime<Utc>),
+    /// midnight sun
+    AlwaysUp,
+    /// polar night
+    AlwaysDown,
+}
+
+fn from_julian(day: f64) -> DateTime<Utc> {
+    let secs = ((day - UNIX_EPOCH_JD) * 86400.0).round() as i64;
+    Utc.timestamp_opt(secs, 0).unwrap()
+}
+
+/// Sunrise and sunset on `date` at coordinates by sunrise equation
+fn sun(date: NaiveDate, lat: f64, lon: f64) -> Sun {
+    let (sin, cos) = (
+        |deg: f64| deg.to_radians().sin(),
+        |deg: f64| deg.to_radians().cos(),
+    );
+    let noon = date.and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp() as f64;
+    let n = (noon / 86400.0 + UNIX_EPOCH_JD - J2000 + 0.0008).round();
+    let mean_noon = n - lon / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon).rem_euclid(360.0);
+    let center = 1.9148 * sin(anomaly) + 0.02 * sin(2.0 * anomaly) + 0.0003 * sin(3.0 * anomaly);
+    let longitude = (anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+    let transit = J2000 + mean_noon + 0.0053 * sin(anomaly) - 0.0069 * sin(2.0 * longitude);
+    let declination = (sin(longitude) * sin(23.4397)).asin().to_degrees();
+    // -0.833° is refraction plus sun's radius, rim shows while center is
+    // below horizon
+    let hour_angle = (sin(-0.833) - sin(lat) * sin(declination)) / (cos(lat) * cos(declination));
+    if hour_angle < -1.0 {
+        return Sun::AlwaysUp;
+    }
+    if hour_angle > 1.0 {
+        return Sun::AlwaysDown;
+    }
+    let half_day = hour_angle.acos().to_degrees() / 360.0;
+    Sun::Rises(
+        from_julian(transit - half_day),
+        from_julian(transit + half_day),
+    )
+}
+
+/// Age of moon in days since new moon, and index into [`PHASES`]
+fn moon(now: DateTime<Utc>) -> (f64, usize) {
+    let age = ((now.timestamp() - NEW_MOON) as f64 / 86400.0).rem_euclid(SYNODIC_MONTH);
+    let phase = (age / SYNODIC_MONTH * 8.0 + 0.5) as usize % 8;
+    (age, phase)
+}
+
+/// Formats sun and moon into line, ex. `▲07:12 ▼18:45 🌒`, sun times in
+/// zone `tz`
+fn to_line<Tz: TimeZone>(sun: Sun, now: DateTime<Utc>, tz: &Tz) -> Line
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let (age, phase) = moon(now);
+    let (glyph, name) = PHASES[phase];
+    let illumination = (1.0 - (2.0 * PI * age / SYNODIC_MONTH).cos()) / 2.0 * 100.0;
+    let time = |at: DateTime<Utc>| at.with_timezone(tz).format("%H:%M").to_string();
+    let (text, times, daylight) = match sun {
+        Sun::Rises(rise, set) => {
+            let daylight = (set - rise).num_minutes() as f64 / 60.0;
+            let (rise, set) = (time(rise), time(set));
+            (
+                format!("▲{} ▼{} {}", rise, set, glyph),
+                Some((rise, set)),
+                daylight,
+            )
+        }
+        Sun::AlwaysUp => (format!("▲all day {}", glyph), None, 24.0),
+        Sun::AlwaysDown => (format!("▼all day {}", glyph), None, 0.0),
+    };
+    let mut line = Line::new(text)
+        .with_field("moon", glyph)
+        .with_field("phase", name)
+        .with_field("illumination", illumination.round())
+        .with_field("daylight", (daylight * 10.0).round() / 10.0);
+    if let Some((rise, set)) = times {
+        line = line.with_field("sunrise", rise).with_field("sunset", set);
+    }
+    line
+}
+
+/// Sun and moon at configured coordinates, computed locally
+pub struct AstroProvider {
+    config: AstroConfig,
+}
+
+impl AstroProvider {
+    pub fn new(config: AstroConfig) -> Self {
+        AstroProvider { config }
+    }
+}
+
+#[async_trait]
+impl DataProvider for AstroProvider {
+    fn name(&self) -> &str {
+        "astro"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(600))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let (lat, lon) = match (self.config.lat, self.config.lon) {
+            (Some(lat), Some(lon)) => (lat, lon),
+            _ => return Err("astro.lat and astro.lon are required".into()),
+        };
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).date_naive();
+        Ok(vec![to_line(sun(today, lat, lon), now, &Local)])
+    }
+}
+
+#[test]
+fn testing_astro_line() {
+    let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+    let near = |a: DateTime<Utc>, b: &str| (a - at(b)).num_minutes().abs() <= 2;
+
+    // Amsterdam on midsummer, 05:18 and 22:06 CEST
+    let midsummer = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+    let Sun::Rises(rise, set) = sun(midsummer, 52.37, 4.89) else {
+        panic!("sun rises in Amsterdam");
+    };
+    assert!(near(rise, "2026-06-21T03:18:00Z"), "{}", rise);
+    assert!(near(set, "2026-06-21T20:06:00Z"), "{}", set);
+    assert_eq!(sun(midsummer, 78.22, 15.65), Sun::AlwaysUp);
+    assert_eq!(sun(midsummer, -78.22, 15.65), Sun::AlwaysDown);
+
+    // full moon of 2024-01-25 17:54 UTC
+    assert_eq!(PHASES[moon(at("2024-01-25T17:54:00Z")).1].1, "full");
+    let line = to_line(
+        Sun::Rises(at("2026-10-14T05:12:00Z"), at("2026-10-14T16:45:00Z")),
+        at("2026-10-14T12:00:00Z"),
+        &Utc,
+    );
+    assert_eq!(line.text, "▲05:12 ▼16:45 🌒");
+}
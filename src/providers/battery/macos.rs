@@ -0,0 +1,57 @@
+//! Internal battery as reported by `pmset -g batt`
+
+use tokio::process::Command;
+
+use super::{State, Status};
+use crate::BoxError;
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    pub async fn status(&self) -> Result<Option<Status>, BoxError> {
+        let output = Command::new("pmset").args(["-g", "batt"]).output().await?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr)
+                .trim()
+                .to_string()
+                .into());
+        }
+        Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Battery line of `pmset` output, ex. ` -InternalBattery-0 (id=4653155)`
+/// followed by tab and `54%; discharging; 2:41 remaining present: true`.
+/// Macs without battery have no such line
+fn parse(output: &str) -> Option<Status> {
+    let line = output
+        .lines()
+        .find(|line| line.contains("InternalBattery"))?;
+    let (_, details) = line.split_once('\t')?;
+    let mut parts = details.split(';').map(str::trim);
+    let percent = parts.next()?.strip_suffix('%')?.parse().ok()?;
+    let state = match parts.next()? {
+        "charging" => State::Charging,
+        "discharging" => State::Discharging,
+        // `charged`, `finishing charge` or `AC attached` at charge limit
+        _ => State::Full,
+    };
+    // `(no estimate)` while it's being computed
+    let minutes_left = parts
+        .next()
+        .and_then(|left| left.split_whitespace().next())
+        .and_then(|left| left.split_once(':'))
+        .and_then(|(hours, minutes)| {
+            Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+        })
+        .filter(|_| state == State::Discharging);
+    Some(Status {
+        percent,
+        state,
+        minutes_left,
+    })
+}
@@ -0,0 +1,181 @@
+//! Battery charge of host machine, ex. `BAT 54% 2h41m` or `BAT 76% ▲`
+//!
+//! Backends: sysfs power supply class on linux, `pmset` on macos and
+//! `GetSystemPowerStatus` on windows. Line is highlighted while discharging
+//! below `warn_percent`, and percent carries metric under label.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod powerstatus;
+#[cfg(target_os = "linux")]
+mod sysfs;
+
+#[cfg(target_os = "macos")]
+use macos::Backend;
+#[cfg(windows)]
+use powerstatus::Backend;
+#[cfg(target_os = "linux")]
+use sysfs::Backend;
+
+/// `[battery]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// discharging battery below this percent is highlighted
+    pub warn_percent: f64,
+    pub label: String,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig {
+            warn_percent: 20.0,
+            label: "BAT".into(),
+        }
+    }
+}
+
+impl BatteryConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !(0.0..=100.0).contains(&self.warn_percent) {
+            return Err(EloraError::ConfigInvalid(
+                "battery.warn_percent must be between 0 and 100".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Charging,
+    Discharging,
+    /// plugged in and not charging, ex. full or held at charge limit
+    Full,
+}
+
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            State::Charging => "charging",
+            State::Discharging => "discharging",
+            State::Full => "full",
+        }
+    }
+}
+
+/// Charge of battery as backend reports it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Status {
+    pub percent: f64,
+    pub state: State,
+    /// minutes until empty while discharging, when OS estimates it
+    pub minutes_left: Option<u32>,
+}
+
+/// Formats battery status into line, ex. `BAT 54% 2h41m` while discharging,
+/// `BAT 76% ▲` while charging or `BAT 100%` when full
+fn to_line(config: &BatteryConfig, status: Status) -> Line {
+    let mut text = format!("{} {:.0}%", config.label, status.percent);
+    let mut line = Line::new("")
+        .with_field("label", config.label.as_str())
+        .with_field("percent", status.percent)
+        .with_field("state", status.state.name());
+    match (status.state, status.minutes_left) {
+        (State::Charging, _) => text.push_str(" ▲"),
+        (State::Discharging, Some(minutes)) => {
+            let left = format!("{}h{:02}m", minutes / 60, minutes % 60);
+            text.push_str(&format!(" {}", left));
+            line = line.with_field("left", left);
+        }
+        (State::Discharging, None) => text.push_str(" ▼"),
+        (State::Full, _) => {}
+    }
+    line.text = text;
+    let low = status.state == State::Discharging && status.percent < config.warn_percent;
+    line.with_highlight(low)
+        .with_metric(&config.label, status.percent)
+}
+
+/// Fallback for platforms without battery support
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+struct Backend;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl Backend {
+    fn new() -> Self {
+        Backend
+    }
+
+    async fn status(&self) -> Result<Option<Status>, BoxError> {
+        Err("battery provider is not supported on this platform".into())
+    }
+}
+
+/// Battery of host machine, `BAT none` on desktops
+pub struct BatteryProvider {
+    config: BatteryConfig,
+    backend: Backend,
+}
+
+impl BatteryProvider {
+    pub fn new(config: BatteryConfig) -> Self {
+        BatteryProvider {
+            config,
+            backend: Backend::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for BatteryProvider {
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        match self.backend.status().await? {
+            Some(status) => Ok(vec![to_line(&self.config, status)]),
+            None => Ok(vec![Line::new(format!("{} none", self.config.label))]),
+        }
+    }
+}
+
+#[test]
+fn testing_battery_line() {
+    let config = BatteryConfig::default();
+    let status = |percent, state, minutes_left| Status {
+        percent,
+        state,
+        minutes_left,
+    };
+
+    let line = to_line(&config, status(54.0, State::Discharging, Some(161)));
+    assert_eq!(line.text, "BAT 54% 2h41m");
+    assert!(!line.highlight);
+    let line = to_line(&config, status(12.0, State::Discharging, None));
+    assert_eq!(line.text, "BAT 12% ▼");
+    assert!(line.highlight);
+    assert_eq!(line.metric.unwrap().value, 12.0);
+
+    let line = to_line(&config, status(12.0, State::Charging, Some(30)));
+    assert_eq!(line.text, "BAT 12% ▲");
+    assert!(!line.highlight);
+    assert_eq!(
+        to_line(&config, status(100.0, State::Full, None)).text,
+        "BAT 100%"
+    );
+}
@@ -0,0 +1,42 @@
+//! Windows power status, same one taskbar battery icon shows
+
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+use super::{State, Status};
+use crate::BoxError;
+
+/// `BatteryFlag` of machine without battery
+const NO_BATTERY: u8 = 128;
+/// `BatteryFlag` bit set while charging
+const CHARGING: u8 = 8;
+/// `BatteryLifePercent` and `BatteryLifeTime` when unknown
+const UNKNOWN_PERCENT: u8 = 255;
+const UNKNOWN_TIME: u32 = u32::MAX;
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    pub async fn status(&self) -> Result<Option<Status>, BoxError> {
+        let mut power = SYSTEM_POWER_STATUS::default();
+        unsafe { GetSystemPowerStatus(&mut power)? };
+        if power.BatteryFlag == NO_BATTERY || power.BatteryLifePercent == UNKNOWN_PERCENT {
+            return Ok(None);
+        }
+        let state = match (power.ACLineStatus, power.BatteryFlag & CHARGING != 0) {
+            (_, true) => State::Charging,
+            (0, _) => State::Discharging,
+            _ => State::Full,
+        };
+        let minutes_left = (state == State::Discharging && power.BatteryLifeTime != UNKNOWN_TIME)
+            .then_some(power.BatteryLifeTime / 60);
+        Ok(Some(Status {
+            percent: power.BatteryLifePercent.into(),
+            state,
+            minutes_left,
+        }))
+    }
+}
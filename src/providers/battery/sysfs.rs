@@ -0,0 +1,92 @@
+//! Linux power supply class in sysfs, `/sys/class/power_supply`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{State, Status};
+use crate::BoxError;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    /// first system battery, ex. `BAT0`. Batteries of mice and headsets
+    /// have `Device` scope and are skipped
+    pub async fn status(&self) -> Result<Option<Status>, BoxError> {
+        let mut supplies = fs::read_dir(POWER_SUPPLY)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                read(path, "type").as_deref() == Some("Battery")
+                    && read(path, "scope").as_deref() != Some("Device")
+            })
+            .collect::<Vec<PathBuf>>();
+        supplies.sort();
+        let Some(battery) = supplies.first() else {
+            return Ok(None);
+        };
+        let number = |name| read(battery, name).and_then(|value| value.parse::<f64>().ok());
+        Ok(Some(parse(
+            number("capacity"),
+            read(battery, "status").as_deref().unwrap_or_default(),
+            // µWh and µW, or µAh and µA on batteries reporting charge
+            number("energy_now").or_else(|| number("charge_now")),
+            number("power_now").or_else(|| number("current_now")),
+        )?))
+    }
+}
+
+fn read(supply: &Path, attribute: &str) -> Option<String> {
+    fs::read_to_string(supply.join(attribute))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Status from sysfs attributes, time left is remaining charge over draw
+fn parse(
+    capacity: Option<f64>,
+    status: &str,
+    now: Option<f64>,
+    draw: Option<f64>,
+) -> Result<Status, BoxError> {
+    let percent = capacity.ok_or("battery doesn't report capacity")?;
+    let state = match status {
+        "Charging" => State::Charging,
+        "Discharging" => State::Discharging,
+        // `Not charging` is plugged in and held at charge limit
+        _ => State::Full,
+    };
+    let minutes_left = match (state, now, draw) {
+        (State::Discharging, Some(now), Some(draw)) if draw > 0.0 => {
+            Some((now / draw * 60.0) as u32)
+        }
+        _ => None,
+    };
+    Ok(Status {
+        percent,
+        state,
+        minutes_left,
+    })
+}
+
+#[test]
+fn testing_sysfs_status() {
+    let status = parse(
+        Some(54.0),
+        "Discharging",
+        Some(30_150_000.0),
+        Some(11_230_000.0),
+    )
+    .unwrap();
+    assert_eq!(status.state, State::Discharging);
+    assert_eq!(status.minutes_left, Some(161));
+    let status = parse(Some(80.0), "Not charging", Some(40_000_000.0), Some(0.0)).unwrap();
+    assert_eq!((status.state, status.minutes_left), (State::Full, None));
+    assert!(parse(None, "Unknown", None, None).is_err());
+}
@@ -0,0 +1,74 @@
+//! BlueZ devices over D-Bus system bus, `org.bluez.Battery1` is present on
+//! connected devices which report their battery
+
+use tokio::sync::Mutex;
+use zbus::{fdo::ObjectManagerProxy, Connection};
+
+use super::Device;
+use crate::BoxError;
+
+const BLUEZ: &str = "org.bluez";
+const DEVICE: &str = "org.bluez.Device1";
+const BATTERY: &str = "org.bluez.Battery1";
+
+pub struct Backend {
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend {
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// system bus connection, opened on first use and reused after
+    async fn connection(&self) -> Result<Connection, BoxError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            return Ok(connection.clone());
+        }
+        let opened = Connection::system().await?;
+        *connection = Some(opened.clone());
+        Ok(opened)
+    }
+
+    pub async fn devices(&self) -> Result<Vec<Device>, BoxError> {
+        let connection = self.connection().await?;
+        let objects = ObjectManagerProxy::builder(&connection)
+            .destination(BLUEZ)?
+            .path("/")?
+            .build()
+            .await?
+            .get_managed_objects()
+            .await?;
+
+        let mut devices = Vec::new();
+        for interfaces in objects.values() {
+            let (Some(device), Some(battery)) = (
+                interfaces.iter().find(|(name, _)| name.as_str() == DEVICE),
+                interfaces.iter().find(|(name, _)| name.as_str() == BATTERY),
+            ) else {
+                continue;
+            };
+            // alias is name user gave device, or its own name without one
+            let name = device
+                .1
+                .get("Alias")
+                .or_else(|| device.1.get("Name"))
+                .and_then(|name| <&str>::try_from(name).ok())
+                .map(String::from);
+            let percent = battery
+                .1
+                .get("Percentage")
+                .and_then(|percent| u8::try_from(percent).ok());
+            if let (Some(name), Some(percent)) = (name, percent) {
+                devices.push(Device {
+                    name,
+                    percent: percent.into(),
+                });
+            }
+        }
+        Ok(devices)
+    }
+}
@@ -0,0 +1,67 @@
+//! Connected devices from `system_profiler SPBluetoothDataType -json`
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::Device;
+use crate::BoxError;
+
+/// battery level keys, earbuds report left and right bud separately
+const LEVELS: [&str; 3] = [
+    "device_batteryLevelMain",
+    "device_batteryLevelLeft",
+    "device_batteryLevelRight",
+];
+
+#[derive(Debug, Deserialize)]
+struct Profile {
+    #[serde(rename = "SPBluetoothDataType")]
+    controllers: Vec<Controller>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Controller {
+    /// list of single entry maps of device name to its properties
+    #[serde(default)]
+    device_connected: Vec<HashMap<String, HashMap<String, serde_json::Value>>>,
+}
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    pub async fn devices(&self) -> Result<Vec<Device>, BoxError> {
+        let output = Command::new("system_profiler")
+            .args(["SPBluetoothDataType", "-json"])
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr)
+                .trim()
+                .to_string()
+                .into());
+        }
+        let profile: Profile = serde_json::from_slice(&output.stdout)?;
+        let connected = profile
+            .controllers
+            .into_iter()
+            .flat_map(|controller| controller.device_connected)
+            .flatten();
+        Ok(connected
+            .filter_map(|(name, properties)| {
+                // lower of both buds, as one running out ends the call
+                let percent = LEVELS
+                    .iter()
+                    .filter_map(|key| properties.get(*key)?.as_str())
+                    .filter_map(|level| level.trim_end_matches('%').parse::<f64>().ok())
+                    .reduce(f64::min)?;
+                Some(Device { name, percent })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,188 @@
+//! Battery levels of paired Bluetooth devices, ex. `MOUSE 83%` and
+//! `HEADSET 40%`, meant for own peripherals page
+//!
+//! Backends: BlueZ over D-Bus system bus on linux and `system_profiler` on
+//! macos, as CoreBluetooth doesn't expose levels of connected devices.
+//! Devices report battery only while connected. Line of device below
+//! `warn_percent` is highlighted, percent carries metric under label.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+#[cfg(target_os = "linux")]
+mod bluez;
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "linux")]
+use bluez::Backend;
+#[cfg(target_os = "macos")]
+use macos::Backend;
+
+/// `[bluetooth]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BluetoothConfig {
+    /// `[LABEL=]device name`, ex. `MOUSE=MX Master 3`, all devices
+    /// reporting battery when empty
+    pub devices: Vec<String>,
+    /// device below this percent is highlighted
+    pub warn_percent: f64,
+}
+
+impl Default for BluetoothConfig {
+    fn default() -> Self {
+        BluetoothConfig {
+            devices: Vec::new(),
+            warn_percent: 20.0,
+        }
+    }
+}
+
+impl BluetoothConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !(0.0..=100.0).contains(&self.warn_percent) {
+            return Err(EloraError::ConfigInvalid(
+                "bluetooth.warn_percent must be between 0 and 100".into(),
+            ));
+        }
+        if let Some(device) = self.devices.iter().find(|device| device.ends_with('=')) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "bluetooth device {:?} is not in [LABEL=]device name form",
+                device
+            )));
+        }
+        Ok(())
+    }
+
+    /// label of device, `None` when it isn't configured to be shown
+    fn label(&self, name: &str) -> Option<String> {
+        if self.devices.is_empty() {
+            return Some(name.to_string());
+        }
+        self.devices.iter().find_map(|device| {
+            let (label, device) = device.split_once('=').unwrap_or((device, device));
+            device.eq_ignore_ascii_case(name).then(|| label.to_string())
+        })
+    }
+}
+
+/// Connected device which reports its battery
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub name: String,
+    pub percent: f64,
+}
+
+/// Formats devices into lines sorted by label, ex. `MOUSE 83%`
+fn to_lines(config: &BluetoothConfig, devices: &[Device]) -> Vec<Line> {
+    let mut devices = devices
+        .iter()
+        .filter_map(|device| Some((config.label(&device.name)?, device)))
+        .collect::<Vec<_>>();
+    devices.sort_by(|a, b| a.0.cmp(&b.0));
+    devices
+        .into_iter()
+        .map(|(label, device)| {
+            Line::new(format!("{} {:.0}%", label, device.percent))
+                .with_field("label", label.as_str())
+                .with_field("name", device.name.as_str())
+                .with_field("percent", device.percent)
+                .with_highlight(device.percent < config.warn_percent)
+                .with_metric(label, device.percent)
+        })
+        .collect()
+}
+
+/// Fallback for platforms without bluetooth battery support
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+struct Backend;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+impl Backend {
+    fn new() -> Self {
+        Backend
+    }
+
+    async fn devices(&self) -> Result<Vec<Device>, BoxError> {
+        Err("bluetooth provider is not supported on this platform".into())
+    }
+}
+
+/// Batteries of connected Bluetooth devices
+pub struct BluetoothProvider {
+    config: BluetoothConfig,
+    backend: Backend,
+}
+
+impl BluetoothProvider {
+    pub fn new(config: BluetoothConfig) -> Self {
+        BluetoothProvider {
+            config,
+            backend: Backend::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for BluetoothProvider {
+    fn name(&self) -> &str {
+        "bluetooth"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        // levels drop slowly and system_profiler takes a second to run
+        Some(Duration::from_secs(300))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let lines = to_lines(&self.config, &self.backend.devices().await?);
+        if lines.is_empty() {
+            return Ok(vec![Line::new("BT none")]);
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_bluetooth_lines() {
+    let devices = [
+        Device {
+            name: "MX Master 3".into(),
+            percent: 83.0,
+        },
+        Device {
+            name: "WH-1000XM4".into(),
+            percent: 10.0,
+        },
+        Device {
+            name: "Magic Keyboard".into(),
+            percent: 60.0,
+        },
+    ];
+    let lines = to_lines(&BluetoothConfig::default(), &devices);
+    let texts = lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        texts,
+        ["MX Master 3 83%", "Magic Keyboard 60%", "WH-1000XM4 10%"]
+    );
+    assert!(lines[2].highlight);
+
+    let config = BluetoothConfig {
+        devices: vec!["MOUSE=mx master 3".into(), "WH-1000XM4".into()],
+        ..BluetoothConfig::default()
+    };
+    config.validate().unwrap();
+    let lines = to_lines(&config, &devices);
+    assert_eq!(lines[0].text, "MOUSE 83%");
+    assert_eq!(lines[0].metric.as_ref().unwrap().symbol, "MOUSE");
+    assert_eq!(lines.len(), 2);
+}
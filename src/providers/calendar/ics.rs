@@ -0,0 +1,498 @@
+//! Events of iCalendar (RFC 5545) file with their recurrences
+//!
+//! Only what is needed to find upcoming meetings: timed `VEVENT`s with
+//! `RRULE` of daily, weekly, monthly or yearly frequency (`BYDAY` for daily
+//! and weekly ones), `EXDATE`, moved occurrences (`RECURRENCE-ID`) and
+//! cancelled events. All-day events are skipped.
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// recurrences are expanded at most this many steps from start
+const MAX_STEPS: usize = 100_000;
+
+/// Timezone wall clock time of event is in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Utc,
+    Tz(Tz),
+    /// floating time, same wall clock in every timezone
+    Local,
+}
+
+impl Zone {
+    fn to_utc(self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Utc => Some(time.and_utc()),
+            Zone::Tz(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+            Zone::Local => Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+    /// weekdays of daily and weekly rules, empty for every day of start
+    weekdays: Vec<Weekday>,
+}
+
+/// Single event, or recurring series of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    start: NaiveDateTime,
+    zone: Zone,
+    recurrence: Option<Recurrence>,
+    excluded: Vec<DateTime<Utc>>,
+    /// start of occurrence of series this event replaces
+    replaces: Option<DateTime<Utc>>,
+}
+
+/// property line split into name, parameters and value
+struct Property<'a> {
+    name: &'a str,
+    params: Vec<(&'a str, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Property<'a>> {
+        // value starts after first colon which isn't inside quoted parameter
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let mut head = line[..colon].split(';');
+        let name = head.next()?;
+        let params = head
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key, value.trim_matches('"')))
+            .collect();
+        Some(Property {
+            name,
+            params,
+            value: &line[colon + 1..],
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&'a str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| *v)
+    }
+
+    /// wall clock time and its zone, `None` for dates of all-day events
+    fn time(&self) -> Option<(NaiveDateTime, Zone)> {
+        if self.param("VALUE") == Some("DATE") {
+            return None;
+        }
+        parse_time(self.value.split(',').next()?, self.param("TZID"))
+    }
+
+    fn times_utc(&self) -> Vec<DateTime<Utc>> {
+        let tzid = self.param("TZID");
+        self.value
+            .split(',')
+            .filter_map(|value| parse_time(value, tzid))
+            .filter_map(|(time, zone)| zone.to_utc(time))
+            .collect()
+    }
+}
+
+fn parse_time(value: &str, tzid: Option<&str>) -> Option<(NaiveDateTime, Zone)> {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = match (utc, tzid) {
+        (true, _) => Zone::Utc,
+        (false, Some(tzid)) => match tzid.parse::<Tz>() {
+            Ok(tz) => Zone::Tz(tz),
+            Err(_) => {
+                log::debug!("Unknown timezone {:?}, using local time", tzid);
+                Zone::Local
+            }
+        },
+        (false, None) => Zone::Local,
+    };
+    Some((time, zone))
+}
+
+fn parse_until(value: &str, zone: Zone) -> Option<DateTime<Utc>> {
+    match parse_time(value, None) {
+        Some((time, Zone::Utc)) => Some(time.and_utc()),
+        Some((time, _)) => zone.to_utc(time),
+        // date only, whole day is included
+        None => zone.to_utc(
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()?
+                .and_hms_opt(23, 59, 59)?,
+        ),
+    }
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    Some(match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Parses `RRULE` value, `None` for rules using parts which aren't supported
+fn parse_recurrence(rule: &str, zone: Zone) -> Option<Recurrence> {
+    let mut recurrence = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        weekdays: Vec::new(),
+    };
+    let mut frequency = None;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key {
+            "FREQ" => {
+                frequency = Some(match value {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => recurrence.interval = value.parse().ok().filter(|&i| i > 0)?,
+            "COUNT" => recurrence.count = Some(value.parse().ok()?),
+            "UNTIL" => recurrence.until = Some(parse_until(value, zone)?),
+            "BYDAY" => {
+                recurrence.weekdays = value.split(',').map(parse_weekday).collect::<Option<_>>()?
+            }
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    recurrence.frequency = frequency?;
+    let by_day = !recurrence.weekdays.is_empty();
+    if by_day && !matches!(recurrence.frequency, Frequency::Daily | Frequency::Weekly) {
+        return None;
+    }
+    Some(recurrence)
+}
+
+/// replaces escaped characters of text value
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Timed events of calendar, cancelled and all-day ones left out
+pub fn parse(ics: &str) -> Vec<Event> {
+    // folded lines continue after line break followed by space or tab
+    let unfolded = ics
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut events = Vec::new();
+    let mut properties: Option<Vec<Property>> = None;
+    for line in unfolded.lines() {
+        match line {
+            "BEGIN:VEVENT" => properties = Some(Vec::new()),
+            "END:VEVENT" => {
+                if let Some(event) = properties.take().and_then(|p| to_event(&p)) {
+                    events.push(event);
+                }
+            }
+            line => {
+                if let Some(properties) = properties.as_mut() {
+                    properties.extend(Property::parse(line));
+                }
+            }
+        }
+    }
+    events
+}
+
+fn to_event(properties: &[Property]) -> Option<Event> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+    if get("STATUS").is_some_and(|status| status.value == "CANCELLED") {
+        return None;
+    }
+    let (start, zone) = get("DTSTART")?.time()?;
+    let recurrence = get("RRULE").and_then(|rule| {
+        let recurrence = parse_recurrence(rule.value, zone);
+        if recurrence.is_none() {
+            log::debug!("Unsupported RRULE {:?}, using first occurrence", rule.value);
+        }
+        recurrence
+    });
+    Some(Event {
+        uid: get("UID")
+            .map(|uid| uid.value.to_string())
+            .unwrap_or_default(),
+        summary: get("SUMMARY")
+            .map(|summary| unescape(summary.value))
+            .unwrap_or_default(),
+        start,
+        zone,
+        recurrence,
+        excluded: properties
+            .iter()
+            .filter(|p| p.name == "EXDATE")
+            .flat_map(|p| p.times_utc())
+            .collect(),
+        replaces: get("RECURRENCE-ID").and_then(|id| id.times_utc().into_iter().next()),
+    })
+}
+
+impl Recurrence {
+    /// Days of `step`th period of series starting on `date`, in order. Empty
+    /// when period has no day of it, ex. 31st in shorter month
+    fn dates(&self, date: NaiveDate, step: i64) -> Vec<NaiveDate> {
+        let interval = self.interval as i64;
+        match self.frequency {
+            Frequency::Daily => {
+                let day = date + Duration::days(step * interval);
+                let wanted = self.weekdays.is_empty() || self.weekdays.contains(&day.weekday());
+                wanted.then_some(day).into_iter().collect()
+            }
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                vec![date + Duration::weeks(step * interval)]
+            }
+            Frequency::Weekly => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(step * interval);
+                let mut days: Vec<NaiveDate> = self
+                    .weekdays
+                    .iter()
+                    .map(|day| monday + Duration::days(day.num_days_from_monday() as i64))
+                    .filter(|day| *day >= date)
+                    .collect();
+                days.sort();
+                days
+            }
+            Frequency::Monthly => {
+                let months = date.month0() as i64 + step * interval;
+                NaiveDate::from_ymd_opt(
+                    date.year() + (months / 12) as i32,
+                    (months % 12) as u32 + 1,
+                    date.day(),
+                )
+                .into_iter()
+                .collect()
+            }
+            Frequency::Yearly => date
+                .with_year(date.year() + (step * interval) as i32)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl Event {
+    /// Wall clock starts of series in order, without exclusions. Expanded
+    /// lazily, so callers stop walking series once they have enough
+    fn starts(&self) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let recurrence = self.recurrence.as_ref();
+        let steps = if recurrence.is_some() { MAX_STEPS } else { 1 };
+        (0..steps as i64)
+            .flat_map(move |step| match recurrence {
+                Some(recurrence) => recurrence.dates(self.start.date(), step),
+                None => vec![self.start.date()],
+            })
+            .map(|day| day.and_time(self.start.time()))
+            .take_while(move |start| {
+                let until = recurrence.and_then(|recurrence| recurrence.until);
+                until
+                    .zip(self.zone.to_utc(*start))
+                    .is_none_or(|(until, start)| start <= until)
+            })
+            .take(
+                recurrence
+                    .and_then(|recurrence| recurrence.count)
+                    .unwrap_or(usize::MAX),
+            )
+    }
+
+    /// Starts of up to `limit` occurrences beginning after `after`
+    fn starts_after(&self, after: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+        let mut found = Vec::new();
+        if self.recurrence.is_none() {
+            found.extend(self.zone.to_utc(self.start).filter(|start| *start > after));
+            return found;
+        }
+        // series is walked from its start, which for old series takes a while,
+        // so it stops once enough future occurrences are found
+        for start in self.starts() {
+            let Some(start) = self.zone.to_utc(start) else {
+                continue;
+            };
+            if start > after && !self.excluded.contains(&start) {
+                found.push(start);
+                if found.len() >= limit {
+                    break;
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Next `count` occurrences starting after `now`, soonest first
+pub fn upcoming(
+    events: &[Event],
+    now: DateTime<Utc>,
+    count: usize,
+) -> Vec<(DateTime<Utc>, &Event)> {
+    // occurrences moved to other time by their own event
+    let moved: Vec<(&str, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.as_str(), event.replaces?)))
+        .collect();
+    let mut upcoming: Vec<(DateTime<Utc>, &Event)> = events
+        .iter()
+        .flat_map(|event| {
+            event
+                .starts_after(now, count + moved.len())
+                .into_iter()
+                .filter(|start| {
+                    event.replaces.is_some() || !moved.contains(&(event.uid.as_str(), *start))
+                })
+                .map(move |start| (start, event))
+        })
+        .collect();
+    upcoming.sort_by_key(|(start, _)| *start);
+    upcoming.truncate(count);
+    upcoming
+}
+
+#[test]
+fn testing_ics_recurrence() {
+    let ics = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+SUMMARY:Daily\r\n  standup\\, team\r\n\
+DTSTART;TZID=Europe/Vilnius:20260601T093000\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n\
+EXDATE;TZID=Europe/Vilnius:20261015T093000\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+SUMMARY:Moved standup\r\n\
+RECURRENCE-ID;TZID=Europe/Vilnius:20261016T093000\r\n\
+DTSTART;TZID=Europe/Vilnius:20261016T110000\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:cancelled\r\n\
+STATUS:CANCELLED\r\n\
+DTSTART:20261014T120000Z\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:holiday\r\n\
+DTSTART;VALUE=DATE:20261014\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+    let events = parse(ics);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].summary, "Daily standup, team");
+
+    // wednesday 14th after standup: thursday is excluded, friday moved
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 8, 0, 0).unwrap();
+    let next = |now, count| -> Vec<(String, String)> {
+        upcoming(&events, now, count)
+            .into_iter()
+            .map(|(start, event)| (start.to_rfc3339(), event.summary.clone()))
+            .collect()
+    };
+    assert_eq!(
+        next(now, 2),
+        vec![
+            ("2026-10-16T08:00:00+00:00".into(), "Moved standup".into()),
+            (
+                "2026-10-19T06:30:00+00:00".into(),
+                "Daily standup, team".into()
+            ),
+        ]
+    );
+    // after dst ends wall clock stays 9:30
+    let now = Utc.with_ymd_and_hms(2026, 10, 24, 8, 0, 0).unwrap();
+    assert_eq!(
+        next(now, 1),
+        vec![(
+            "2026-10-26T07:30:00+00:00".into(),
+            "Daily standup, team".into()
+        )]
+    );
+
+    let rule = parse_recurrence("FREQ=MONTHLY;COUNT=3", Zone::Utc).unwrap();
+    let event = Event {
+        recurrence: Some(rule),
+        ..events[1].clone()
+    };
+    assert_eq!(event.starts().count(), 3);
+
+    // endless daily series started long ago stops at first future ones
+    let rule = parse_recurrence("FREQ=DAILY", Zone::Utc).unwrap();
+    let event = Event {
+        start: NaiveDate::from_ymd_opt(1800, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap(),
+        zone: Zone::Utc,
+        recurrence: Some(rule),
+        ..events[1].clone()
+    };
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+    assert_eq!(
+        event.starts_after(now, 2),
+        vec![
+            Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap(),
+        ]
+    );
+    assert_eq!(parse_recurrence("FREQ=MONTHLY;BYDAY=1MO", Zone::Utc), None);
+}
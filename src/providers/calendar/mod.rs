@@ -0,0 +1,231 @@
+//! Next meetings from iCalendar feed, ex. `Standup 12m`
+//!
+//! Feed is secret ics address of calendar (Google, Outlook, CalDAV servers
+//! export one) or local file, downloaded every `fetch_secs` while countdown
+//! is updated every minute. First line carries minutes until meeting as
+//! metric, so keyboard gets alert `alert_minutes` before it starts.
+
+use std::{
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+mod ics;
+
+/// symbol of minutes until next meeting metric
+pub const METRIC: &str = "calendar";
+
+/// `[calendar]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// ics feed url, `webcal://` works too
+    pub url: Option<String>,
+    /// local ics file instead of url
+    pub path: Option<PathBuf>,
+    /// upcoming meetings shown
+    pub count: usize,
+    /// keyboard is alerted this many minutes before next meeting, 0 disables
+    pub alert_minutes: u64,
+    /// feed is downloaded again after this long
+    pub fetch_secs: u64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            url: None,
+            path: None,
+            count: 1,
+            alert_minutes: 5,
+            fetch_secs: 900,
+        }
+    }
+}
+
+impl CalendarConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.url.is_some() == self.path.is_some() {
+            return Err(EloraError::ConfigInvalid(
+                "calendar needs either url or path".into(),
+            ));
+        }
+        if self.count == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "calendar.count must be greater than 0".into(),
+            ));
+        }
+        if self.fetch_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "calendar.fetch_secs must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rule alerting keyboard before next meeting, none when `alert_minutes`
+    /// is 0
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if self.alert_minutes == 0 {
+            return Vec::new();
+        }
+        vec![Rule {
+            symbol: METRIC.into(),
+            provider: Some("calendar".into()),
+            direction: Direction::Below,
+            threshold: self.alert_minutes as f64,
+            window: None,
+            text: Some(format!("Meeting in {} min", self.alert_minutes)),
+            urgent: false,
+            notify: true,
+        }]
+    }
+}
+
+/// time left until start, ex. `12m`, `2h05m`, or start itself when it's
+/// more than day away, ex. `Tue 14:30`
+fn until(start: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = (start - now).num_minutes();
+    match minutes {
+        0 => "<1m".into(),
+        1..=59 => format!("{}m", minutes),
+        60..=1439 => format!("{}h{:02}m", minutes / 60, minutes % 60),
+        _ => start.with_timezone(&Local).format("%a %H:%M").to_string(),
+    }
+}
+
+fn to_lines(upcoming: &[(DateTime<Utc>, &ics::Event)], now: DateTime<Utc>) -> Vec<Line> {
+    upcoming
+        .iter()
+        .enumerate()
+        .map(|(i, (start, event))| {
+            let minutes = (*start - now).num_seconds() as f64 / 60.0;
+            let until = until(*start, now);
+            let line = Line::new(format!("{} {}", event.summary, until))
+                .with_field("title", event.summary.as_str())
+                .with_field("until", until)
+                .with_field("minutes", minutes.floor())
+                .with_field(
+                    "start",
+                    start.with_timezone(&Local).format("%H:%M").to_string(),
+                );
+            match i {
+                0 => line.with_metric(METRIC, minutes),
+                _ => line,
+            }
+        })
+        .collect()
+}
+
+/// Upcoming meetings of configured calendar
+pub struct CalendarProvider {
+    config: CalendarConfig,
+    client: Client,
+    /// events of last download and when it was done
+    events: Mutex<Option<(Instant, Vec<ics::Event>)>>,
+}
+
+impl CalendarProvider {
+    pub fn new(config: CalendarConfig) -> Self {
+        CalendarProvider {
+            config,
+            client: Client::new(),
+            events: Mutex::new(None),
+        }
+    }
+
+    async fn download(&self) -> Result<String, BoxError> {
+        if let Some(path) = &self.config.path {
+            return Ok(tokio::fs::read_to_string(path).await?);
+        }
+        let url = self.config.url.as_deref().unwrap_or_default();
+        let url = match url.strip_prefix("webcal://") {
+            Some(rest) => format!("https://{}", rest),
+            None => url.to_string(),
+        };
+        log::info!("Fetching calendar from remote");
+        Ok(self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    fn is_fresh(&self) -> bool {
+        let max_age = Duration::from_secs(self.config.fetch_secs);
+        matches!(&*self.events.lock().unwrap(), Some((at, _)) if at.elapsed() < max_age)
+    }
+}
+
+#[async_trait]
+impl DataProvider for CalendarProvider {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    /// countdown is in minutes
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        if !self.is_fresh() {
+            match self.download().await {
+                Ok(ics) => {
+                    let events = ics::parse(&ics);
+                    log::debug!("Calendar has {} events", events.len());
+                    *self.events.lock().unwrap() = Some((Instant::now(), events));
+                }
+                // meetings from last download are still good for countdown
+                Err(e) if self.events.lock().unwrap().is_some() => {
+                    log::warn!("Unable to fetch calendar, using last one: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let events = self.events.lock().unwrap();
+        let events = events.as_ref().map(|(_, events)| events.as_slice());
+        let now = Utc::now();
+        let upcoming = ics::upcoming(events.unwrap_or_default(), now, self.config.count);
+        Ok(to_lines(&upcoming, now))
+    }
+}
+
+#[test]
+fn testing_calendar_lines() {
+    use chrono::TimeZone;
+
+    let events = ics::parse(
+        "BEGIN:VEVENT\nUID:1\nSUMMARY:Standup\nDTSTART:20261014T093000Z\nEND:VEVENT\n\
+         BEGIN:VEVENT\nUID:2\nSUMMARY:Retro\nDTSTART:20261014T120500Z\nEND:VEVENT\n",
+    );
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 9, 17, 30).unwrap();
+    let upcoming = ics::upcoming(&events, now, 2);
+    let lines = to_lines(&upcoming, now);
+    assert_eq!(lines[0].text, "Standup 12m");
+    assert_eq!(lines[1].text, "Retro 2h47m");
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 12.5);
+    assert_eq!(lines[1].metric, None);
+
+    let config = CalendarConfig {
+        path: Some("work.ics".into()),
+        ..CalendarConfig::default()
+    };
+    config.validate().unwrap();
+    assert_eq!(config.alert_rules()[0].threshold, 5.0);
+}
@@ -0,0 +1,267 @@
+//! Latest GitHub Actions run of watched repos
+//!
+//! Each repo is shown as `elora_hid ✔ main`. Repo line carries metric which
+//! is 1 for passed and 0 for failed run, so failure raises alert on keyboard
+//! through same rules as price alerts.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::{header, Client};
+use serde::Deserialize;
+
+use super::{github::TOKEN_ENV, DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// `[ci]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CiConfig {
+    /// token with actions read access, needed for private repos. Falls back
+    /// to `GITHUB_TOKEN` env, public repos work without it
+    pub token: Option<String>,
+    /// `owner/repo`, or `owner/repo:workflow.yml` to watch single workflow
+    pub repos: Vec<String>,
+    /// only runs on this branch, latest run of any branch without it
+    pub branch: Option<String>,
+    /// alert keyboard when run fails
+    pub alert: bool,
+}
+
+impl Default for CiConfig {
+    fn default() -> Self {
+        CiConfig {
+            token: None,
+            repos: Vec::new(),
+            branch: None,
+            alert: true,
+        }
+    }
+}
+
+/// Watched repo parsed from `owner/repo[:workflow]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    pub owner: String,
+    pub name: String,
+    pub workflow: Option<String>,
+}
+
+impl Repo {
+    pub fn parse(repo: &str) -> Result<Repo, EloraError> {
+        let (path, workflow) = match repo.split_once(':') {
+            Some((path, workflow)) => (path, Some(workflow.to_string())),
+            None => (repo, None),
+        };
+        match path.split_once('/') {
+            Some((owner, name))
+                if !owner.is_empty()
+                    && !name.is_empty()
+                    && !name.contains('/')
+                    && workflow.as_ref().is_none_or(|w| !w.is_empty()) =>
+            {
+                Ok(Repo {
+                    owner: owner.to_string(),
+                    name: name.to_string(),
+                    workflow,
+                })
+            }
+            _ => Err(EloraError::ConfigInvalid(format!(
+                "ci repo {:?} is not in owner/repo or owner/repo:workflow.yml form",
+                repo
+            ))),
+        }
+    }
+
+    fn runs_url(&self) -> String {
+        match &self.workflow {
+            Some(workflow) => format!(
+                "https://api.github.com/repos/{}/{}/actions/workflows/{}/runs",
+                self.owner, self.name, workflow
+            ),
+            None => format!(
+                "https://api.github.com/repos/{}/{}/actions/runs",
+                self.owner, self.name
+            ),
+        }
+    }
+}
+
+impl CiConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.repos.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "ci.repos needs at least one repo".into(),
+            ));
+        }
+        self.parse_repos().map(|_| ())
+    }
+
+    pub fn parse_repos(&self) -> Result<Vec<Repo>, EloraError> {
+        self.repos.iter().map(|repo| Repo::parse(repo)).collect()
+    }
+
+    /// Alert rule per repo, firing when its run fails. Empty when `alert`
+    /// is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        self.parse_repos()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|repo| Rule {
+                text: Some(format!("{} CI failed", repo.name)),
+                symbol: repo.name,
+                provider: Some("ci".into()),
+                direction: Direction::Below,
+                threshold: 1.0,
+                window: None,
+                urgent: false,
+                notify: true,
+            })
+            .collect()
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.clone().or_else(|| std::env::var(TOKEN_ENV).ok())
+    }
+}
+
+/// Response of `/actions/runs`, only fields we use
+#[derive(Debug, Deserialize)]
+struct RunsResponse {
+    workflow_runs: Vec<Run>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Run {
+    head_branch: Option<String>,
+    /// queued, in_progress or completed
+    status: String,
+    /// success, failure, cancelled, ... once completed
+    conclusion: Option<String>,
+}
+
+/// Formats run into line, ex. `elora_hid ✔ main`. Completed runs carry 1 or
+/// 0 metric for passed or failed one
+fn to_line(repo: &Repo, run: &Run) -> Line {
+    let (icon, passed) = match (run.status.as_str(), run.conclusion.as_deref()) {
+        ("completed", Some("success")) => ('✔', Some(true)),
+        ("completed", Some("failure" | "timed_out" | "startup_failure")) => ('✘', Some(false)),
+        ("completed", _) => ('-', None),
+        _ => ('…', None),
+    };
+    let branch = run.head_branch.as_deref().unwrap_or_default();
+    let line = Line::new(format!("{} {} {}", repo.name, icon, branch).trim_end())
+        .with_field("repo", repo.name.as_str())
+        .with_field("status", icon.to_string())
+        .with_field("branch", branch);
+    match passed {
+        Some(passed) => line.with_metric(&repo.name, if passed { 1.0 } else { 0.0 }),
+        None => line,
+    }
+}
+
+/// GitHub Actions status of configured repos
+pub struct CiProvider {
+    config: CiConfig,
+    repos: Vec<Repo>,
+    client: Client,
+}
+
+impl CiProvider {
+    pub fn new(config: CiConfig) -> Result<Self, BoxError> {
+        Ok(CiProvider {
+            repos: config.parse_repos()?,
+            config,
+            client: Client::new(),
+        })
+    }
+
+    async fn latest_run(&self, repo: &Repo) -> Result<Option<Run>, BoxError> {
+        let mut query = vec![("per_page", "1".to_string())];
+        if let Some(branch) = &self.config.branch {
+            query.push(("branch", branch.clone()));
+        }
+        let mut request = self
+            .client
+            .get(repo.runs_url())
+            .header(header::USER_AGENT, "elora_hid")
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .query(&query);
+        if let Some(token) = self.config.token() {
+            request = request.bearer_auth(token);
+        }
+
+        let response: RunsResponse = request.send().await?.error_for_status()?.json().await?;
+        Ok(response.workflow_runs.into_iter().next())
+    }
+}
+
+#[async_trait]
+impl DataProvider for CiProvider {
+    fn name(&self) -> &str {
+        "ci"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching ci runs from remote");
+
+        let runs = join_all(self.repos.iter().map(|repo| self.latest_run(repo))).await;
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for (repo, run) in self.repos.iter().zip(runs) {
+            match run {
+                Ok(Some(run)) => lines.push(to_line(repo, &run)),
+                Ok(None) => log::debug!("No runs of {}/{}", repo.owner, repo.name),
+                Err(e) => {
+                    log::error!(
+                        "Unable to fetch runs of {}/{}: {}",
+                        repo.owner,
+                        repo.name,
+                        e
+                    );
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.repos.len() {
+            return Err("no repo runs could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_ci_lines() {
+    let repo = Repo::parse("dzhibas/elora_hid").unwrap();
+    assert_eq!(repo.workflow, None);
+    assert!(Repo::parse("elora_hid").is_err());
+    assert!(Repo::parse("dzhibas/elora_hid:").is_err());
+    assert_eq!(
+        Repo::parse("dzhibas/elora_hid:ci.yml")
+            .unwrap()
+            .workflow
+            .as_deref(),
+        Some("ci.yml")
+    );
+
+    let response: RunsResponse = serde_json::from_str(
+        r#"{"total_count":2,"workflow_runs":[{"name":"CI","head_branch":"main","status":"completed","conclusion":"failure"}]}"#,
+    )
+    .unwrap();
+    let line = to_line(&repo, &response.workflow_runs[0]);
+    assert_eq!(line.text, "elora_hid ✘ main");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+
+    let running = Run {
+        head_branch: Some("main".into()),
+        status: "in_progress".into(),
+        conclusion: None,
+    };
+    assert_eq!(to_line(&repo, &running).text, "elora_hid … main");
+    assert_eq!(to_line(&repo, &running).metric, None);
+}
@@ -0,0 +1,174 @@
+//! Local time and time in other timezones
+//!
+//! Clock is refreshed every second, it runs on its own schedule like every
+//! provider, so slow network fetches don't hold it back.
+
+use std::{fmt::Display, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, TimeZone,
+};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[clock]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+    /// strftime format of local time, ex. `%a %d %H:%M:%S`
+    pub format: String,
+    /// extra timezones as `LABEL=Area/City`, ex. `NYC=America/New_York`,
+    /// drawn on one line in given order
+    pub zones: Vec<String>,
+    /// strftime format of time in extra timezones
+    pub zones_format: String,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        ClockConfig {
+            format: "%H:%M:%S".into(),
+            zones: Vec::new(),
+            zones_format: "%H:%M".into(),
+        }
+    }
+}
+
+/// Extra timezone parsed from `LABEL=Area/City`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    pub label: String,
+    pub timezone: Tz,
+}
+
+impl Zone {
+    pub fn parse(zone: &str) -> Result<Zone, EloraError> {
+        let invalid =
+            |reason: String| EloraError::ConfigInvalid(format!("clock zone {:?} {}", zone, reason));
+        let (label, name) = zone
+            .split_once('=')
+            .filter(|(label, _)| !label.trim().is_empty())
+            .ok_or_else(|| invalid("is not in LABEL=Area/City form".into()))?;
+        Ok(Zone {
+            label: label.trim().to_string(),
+            timezone: name.trim().parse().map_err(invalid)?,
+        })
+    }
+}
+
+fn validate_format(key: &str, format: &str) -> Result<(), EloraError> {
+    if format.is_empty() || StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(EloraError::ConfigInvalid(format!(
+            "clock.{} {:?} is not valid strftime format",
+            key, format
+        )));
+    }
+    Ok(())
+}
+
+impl ClockConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        validate_format("format", &self.format)?;
+        validate_format("zones_format", &self.zones_format)?;
+        self.parse_zones().map(|_| ())
+    }
+
+    pub fn parse_zones(&self) -> Result<Vec<Zone>, EloraError> {
+        self.zones.iter().map(|zone| Zone::parse(zone)).collect()
+    }
+}
+
+/// Local time line, ex. `14:14:05`, and line of extra timezones when there
+/// are any, ex. `NYC 09:14 / TOK 22:14`
+fn to_lines<T>(config: &ClockConfig, zones: &[Zone], now: DateTime<T>) -> Vec<Line>
+where
+    T: TimeZone,
+    T::Offset: Display,
+{
+    let time = now.format(&config.format).to_string();
+    let mut lines = vec![Line::new(time.as_str()).with_field("time", time)];
+    if !zones.is_empty() {
+        let times: Vec<(&str, String)> = zones
+            .iter()
+            .map(|zone| {
+                let time = now.with_timezone(&zone.timezone);
+                (
+                    zone.label.as_str(),
+                    time.format(&config.zones_format).to_string(),
+                )
+            })
+            .collect();
+        let text = times
+            .iter()
+            .map(|(label, time)| format!("{} {}", label, time))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        let mut line = Line::new(text);
+        for (label, time) in times {
+            line = line.with_field(label.to_lowercase(), time);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Time of this machine and configured timezones, no network involved
+pub struct ClockProvider {
+    config: ClockConfig,
+    zones: Vec<Zone>,
+}
+
+impl ClockProvider {
+    pub fn new(config: ClockConfig) -> Result<Self, BoxError> {
+        Ok(ClockProvider {
+            zones: config.parse_zones()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl DataProvider for ClockProvider {
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        Ok(to_lines(&self.config, &self.zones, Local::now()))
+    }
+}
+
+#[test]
+fn testing_clock_lines() {
+    let config = ClockConfig {
+        zones: vec!["NYC=America/New_York".into(), "TOK = Asia/Tokyo".into()],
+        ..ClockConfig::default()
+    };
+    let zones = config.parse_zones().unwrap();
+    let now = chrono::Utc
+        .with_ymd_and_hms(2024, 1, 15, 14, 14, 5)
+        .unwrap();
+
+    let lines = to_lines(&config, &zones, now);
+    assert_eq!(lines[0].text, "14:14:05");
+    assert_eq!(lines[1].text, "NYC 09:14 / TOK 23:14");
+    assert_eq!(to_lines(&ClockConfig::default(), &[], now).len(), 1);
+
+    assert!(Zone::parse("America/New_York").is_err());
+    assert!(Zone::parse("NYC=America/Gotham").is_err());
+    assert!(ClockConfig {
+        format: "%H:%Q".into(),
+        ..ClockConfig::default()
+    }
+    .validate()
+    .is_err());
+}
@@ -0,0 +1,164 @@
+//! Countdowns to configured dates, ex. `Vacation in 12d`
+//!
+//! Computed locally from targets in config, no network involved. Countdowns
+//! whose target passed are dropped.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[countdown]` config section
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CountdownConfig {
+    /// `LABEL=target` in given order, target is rfc3339 timestamp, or local
+    /// `YYYY-MM-DD[ HH:MM[:SS]]`, ex. `Release=2026-10-20 15:00`
+    pub items: Vec<String>,
+}
+
+/// Countdown parsed from `LABEL=target`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Countdown {
+    pub label: String,
+    pub target: DateTime<Utc>,
+}
+
+/// Target in local time unless it carries offset, date alone is its midnight
+fn parse_target(target: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(target) {
+        return Some(time.to_utc());
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(target, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(target, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    })?;
+    Some(Local.from_local_datetime(&naive).earliest()?.to_utc())
+}
+
+impl Countdown {
+    pub fn parse(item: &str) -> Result<Countdown, EloraError> {
+        let invalid =
+            |reason: &str| EloraError::ConfigInvalid(format!("countdown {:?} {}", item, reason));
+        let (label, target) = item
+            .split_once('=')
+            .filter(|(label, _)| !label.trim().is_empty())
+            .ok_or_else(|| invalid("is not in LABEL=target form"))?;
+        Ok(Countdown {
+            label: label.trim().to_string(),
+            target: parse_target(target.trim())
+                .ok_or_else(|| invalid("has no rfc3339 or YYYY-MM-DD HH:MM target"))?,
+        })
+    }
+}
+
+impl CountdownConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.items.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "countdown.items needs at least one item".into(),
+            ));
+        }
+        self.parse_items().map(|_| ())
+    }
+
+    pub fn parse_items(&self) -> Result<Vec<Countdown>, EloraError> {
+        self.items
+            .iter()
+            .map(|item| Countdown::parse(item))
+            .collect()
+    }
+}
+
+/// time left in its largest unit, ex. `12d`, `3h`, `25m`
+fn left(secs: i64) -> String {
+    match secs {
+        86400.. => format!("{}d", secs / 86400),
+        3600.. => format!("{}h", secs / 3600),
+        60.. => format!("{}m", secs / 60),
+        _ => "<1m".into(),
+    }
+}
+
+fn to_lines(countdowns: &[Countdown], now: DateTime<Utc>) -> Vec<Line> {
+    countdowns
+        .iter()
+        .filter(|countdown| countdown.target > now)
+        .map(|countdown| {
+            let secs = (countdown.target - now).num_seconds();
+            let left = left(secs);
+            let days = secs as f64 / 86400.0;
+            Line::new(format!("{} in {}", countdown.label, left))
+                .with_field("label", countdown.label.as_str())
+                .with_field("left", left)
+                .with_field("days", days.floor())
+                .with_metric(&countdown.label, days)
+        })
+        .collect()
+}
+
+/// Time left until configured targets
+pub struct CountdownProvider {
+    countdowns: Vec<Countdown>,
+}
+
+impl CountdownProvider {
+    pub fn new(config: CountdownConfig) -> Result<Self, BoxError> {
+        Ok(CountdownProvider {
+            countdowns: config.parse_items()?,
+        })
+    }
+}
+
+#[async_trait]
+impl DataProvider for CountdownProvider {
+    fn name(&self) -> &str {
+        "countdown"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        Ok(to_lines(&self.countdowns, Utc::now()))
+    }
+}
+
+#[test]
+fn testing_countdown_lines() {
+    let config = CountdownConfig {
+        items: vec![
+            "Vacation=2026-10-26T18:00:00+02:00".into(),
+            "Release = 2026-10-14T19:30:00Z".into(),
+            "Launch=2026-10-14T15:10:00Z".into(),
+            "Demo=2026-10-01T10:00:00Z".into(),
+        ],
+    };
+    config.validate().unwrap();
+    let countdowns = config.parse_items().unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 10, 14, 15, 0, 0).unwrap();
+
+    let lines = to_lines(&countdowns, now);
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["Vacation in 12d", "Release in 4h", "Launch in 10m"]);
+    assert_eq!(lines[0].metric.as_ref().unwrap().symbol, "Vacation");
+
+    assert!(Countdown::parse("Vacation=2026-12-20").is_ok());
+    assert!(Countdown::parse("Release=2026-10-20 15:00").is_ok());
+    assert!(Countdown::parse("Vacation=next week").is_err());
+    assert!(Countdown::parse("2026-12-20").is_err());
+}
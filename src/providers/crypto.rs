@@ -0,0 +1,149 @@
+//! Cryptocurrency prices from CoinGecko public api
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{currency_sign, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[crypto]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CryptoConfig {
+    /// CoinGecko coin ids, ex. `["bitcoin", "ethereum", "solana"]`
+    pub coins: Vec<String>,
+    /// currency prices are shown in, ex. `usd` or `eur`
+    pub vs_currency: String,
+}
+
+impl Default for CryptoConfig {
+    fn default() -> Self {
+        CryptoConfig {
+            coins: vec!["bitcoin".into(), "ethereum".into()],
+            vs_currency: "usd".into(),
+        }
+    }
+}
+
+impl CryptoConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.coins.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "crypto.coins needs at least one coin".into(),
+            ));
+        }
+        if self.vs_currency.trim().is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "crypto.vs_currency can't be empty".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// coin id -> price in vs currency
+pub type SimplePriceResponse = HashMap<String, HashMap<String, f64>>;
+
+/// Short ticker-like label for coin id so it fits display same as stocks
+pub fn coin_symbol(id: &str) -> String {
+    match id {
+        "bitcoin" => "BTC".into(),
+        "ethereum" => "ETH".into(),
+        "solana" => "SOL".into(),
+        "cardano" => "ADA".into(),
+        "ripple" => "XRP".into(),
+        "dogecoin" => "DOGE".into(),
+        "polkadot" => "DOT".into(),
+        "litecoin" => "LTC".into(),
+        _ => id.chars().take(4).collect::<String>().to_uppercase(),
+    }
+}
+
+/// Converts CoinGecko response into lines in configured coin order
+fn to_lines(config: &CryptoConfig, prices: &SimplePriceResponse) -> Vec<Line> {
+    let vs_currency = config.vs_currency.to_lowercase();
+    let sign = currency_sign(&vs_currency);
+    config
+        .coins
+        .iter()
+        .map(|id| {
+            let symbol = coin_symbol(id);
+            match prices.get(id).and_then(|p| p.get(&vs_currency)) {
+                Some(&price) => Line::new(format!("{:.4}: {:.0}{}", symbol, price, sign))
+                    .with_metric(&symbol, price)
+                    .with_field("symbol", symbol)
+                    .with_field("price", price)
+                    .with_field("currency", sign.as_str()),
+                None => Line::new(format!("{:.4}: {:.0}{}", symbol, 0.0, sign)),
+            }
+        })
+        .collect()
+}
+
+/// Fetches prices of CoinGecko coin ids in `vs_currency`
+pub async fn fetch_prices(
+    client: &Client,
+    coins: &[String],
+    vs_currency: &str,
+) -> Result<SimplePriceResponse, BoxError> {
+    let url = "https://api.coingecko.com/api/v3/simple/price";
+    Ok(client
+        .get(url)
+        .query(&[
+            ("ids", coins.join(",")),
+            ("vs_currencies", vs_currency.to_lowercase()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// CoinGecko coin prices
+pub struct CryptoProvider {
+    config: CryptoConfig,
+    client: Client,
+}
+
+impl CryptoProvider {
+    pub fn new(config: CryptoConfig) -> Self {
+        CryptoProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for CryptoProvider {
+    fn name(&self) -> &str {
+        "crypto"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching crypto prices from remote");
+
+        let prices =
+            fetch_prices(&self.client, &self.config.coins, &self.config.vs_currency).await?;
+        Ok(to_lines(&self.config, &prices))
+    }
+}
+
+#[test]
+fn testing_crypto_lines() {
+    let prices: SimplePriceResponse =
+        serde_json::from_str(r#"{"bitcoin":{"usd":43012.7},"ethereum":{"usd":2291.2}}"#).unwrap();
+    let config = CryptoConfig {
+        coins: vec!["ethereum".into(), "bitcoin".into(), "avalanche-2".into()],
+        ..CryptoConfig::default()
+    };
+    let lines: Vec<String> = to_lines(&config, &prices)
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["ETH: 2291$", "BTC: 43013$", "AVAL: 0$"]);
+}
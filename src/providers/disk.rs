@@ -0,0 +1,198 @@
+//! Free space of mounted disks from `sysinfo`, ex. `/ 42G 18%`
+//!
+//! Line of disk with less than `warn_percent` free is highlighted on
+//! display. Free percent carries metric under label.
+
+use std::{path::Path, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use sysinfo::{Disk, Disks};
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[disk]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    /// `[LABEL=]mount point`, ex. `/` or `DATA=/mnt/data`, all disks when
+    /// empty
+    pub mounts: Vec<String>,
+    /// disk with less free space than this percent is highlighted
+    pub warn_percent: f64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            mounts: Vec::new(),
+            warn_percent: 10.0,
+        }
+    }
+}
+
+impl DiskConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !(0.0..=100.0).contains(&self.warn_percent) {
+            return Err(EloraError::ConfigInvalid(
+                "disk.warn_percent must be between 0 and 100".into(),
+            ));
+        }
+        if let Some(mount) = self.mounts.iter().find(|mount| mount.ends_with('=')) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "disk mount {:?} is not in [LABEL=]mount point form",
+                mount
+            )));
+        }
+        Ok(())
+    }
+
+    /// configured mounts as label and mount point
+    fn mounts(&self) -> Vec<(&str, &str)> {
+        self.mounts
+            .iter()
+            .map(|mount| mount.split_once('=').unwrap_or((mount, mount)))
+            .collect()
+    }
+}
+
+/// Space of single disk in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Space {
+    available: u64,
+    total: u64,
+}
+
+impl Space {
+    fn of(disk: &Disk) -> Self {
+        Space {
+            available: disk.available_space(),
+            total: disk.total_space(),
+        }
+    }
+
+    fn free_percent(self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.available as f64 / self.total as f64 * 100.0
+    }
+}
+
+/// size in largest unit fitting display, ex. `512M`, `4.2G`, `42G` or `1.8T`
+fn size(bytes: u64) -> String {
+    let gigabytes = bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    match gigabytes {
+        g if g < 1.0 => format!("{:.0}M", g * 1024.0),
+        g if g < 10.0 => format!("{:.1}G", g),
+        g if g < 1024.0 => format!("{:.0}G", g),
+        g => format!("{:.1}T", g / 1024.0),
+    }
+}
+
+/// Formats disk space into line, ex. `/ 42G 18%`
+fn to_line(config: &DiskConfig, label: &str, mount: &str, space: Space) -> Line {
+    let percent = space.free_percent();
+    Line::new(format!(
+        "{} {} {:.0}%",
+        label,
+        size(space.available),
+        percent
+    ))
+    .with_field("label", label)
+    .with_field("mount", mount)
+    .with_field("free", size(space.available))
+    .with_field("total", size(space.total))
+    .with_field("free_percent", percent)
+    .with_highlight(percent < config.warn_percent)
+    .with_metric(label, percent)
+}
+
+/// Free space of local disks, no network involved
+pub struct DiskProvider {
+    config: DiskConfig,
+    disks: Mutex<Disks>,
+}
+
+impl DiskProvider {
+    pub fn new(config: DiskConfig) -> Self {
+        DiskProvider {
+            config,
+            disks: Mutex::new(Disks::new_with_refreshed_list()),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for DiskProvider {
+    fn name(&self) -> &str {
+        "disk"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let mut disks = self.disks.lock().unwrap();
+        // list is refreshed too, so disks mounted since start are found
+        disks.refresh_list();
+        let mut lines = Vec::new();
+        if self.config.mounts.is_empty() {
+            let mut seen = Vec::new();
+            for disk in disks.list() {
+                let mount = disk.mount_point().to_string_lossy();
+                // same mount point can be listed twice, ex. overlay over it
+                if !seen.contains(&mount) {
+                    lines.push(to_line(&self.config, &mount, &mount, Space::of(disk)));
+                    seen.push(mount);
+                }
+            }
+        } else {
+            for (label, mount) in self.config.mounts() {
+                let disk = disks
+                    .list()
+                    .iter()
+                    .find(|disk| disk.mount_point() == Path::new(mount));
+                match disk {
+                    Some(disk) => lines.push(to_line(&self.config, label, mount, Space::of(disk))),
+                    None => log::error!("Disk mounted at {} not found", mount),
+                }
+            }
+        }
+        if lines.is_empty() {
+            return Err("no disk found".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_disk_line() {
+    let config = DiskConfig {
+        mounts: vec!["/".into(), "DATA=/mnt/data".into()],
+        ..DiskConfig::default()
+    };
+    config.validate().unwrap();
+    assert_eq!(config.mounts(), [("/", "/"), ("DATA", "/mnt/data")]);
+
+    let gigabyte = 1024 * 1024 * 1024;
+    let root = Space {
+        available: 42 * gigabyte,
+        total: 233 * gigabyte,
+    };
+    let line = to_line(&config, "/", "/", root);
+    assert_eq!(line.text, "/ 42G 18%");
+    assert!(!line.highlight);
+
+    let data = Space {
+        available: gigabyte * 18 / 10,
+        total: 2048 * gigabyte,
+    };
+    let line = to_line(&config, "DATA", "/mnt/data", data);
+    assert_eq!(line.text, "DATA 1.8G 0%");
+    assert!(line.highlight);
+    assert_eq!(size(2048 * gigabyte), "2.0T");
+    assert_eq!(size(gigabyte / 2), "512M");
+}
@@ -0,0 +1,344 @@
+//! Dividend yield and next ex-dividend date of stock tickers from Yahoo
+//! chart dividend events, ex. `VWRL ex 18 Dec 1.9%`
+//!
+//! Yahoo lists only past payments, so yield is trailing year of payments
+//! against price and next ex-dividend date is projected from last one by
+//! usual gap between payments. Tickers without payment in last year have no
+//! dividend. Same data is added to stock lines with `[quotes] dividends`.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    stocks::{self, AssetClass},
+    DataProvider, Line,
+};
+use crate::{BoxError, EloraError};
+
+/// dividends change few times a year, so they're refetched this rarely
+const MAX_AGE: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// `[dividends]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DividendsConfig {
+    /// tickers to watch, stock tickers of config when empty
+    pub tickers: Vec<String>,
+    /// how many days ahead ex-dividend dates are drawn
+    pub days: u32,
+}
+
+impl Default for DividendsConfig {
+    fn default() -> Self {
+        DividendsConfig {
+            tickers: Vec::new(),
+            days: 30,
+        }
+    }
+}
+
+impl DividendsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.days == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "dividends.days must be greater than 0".into(),
+            ));
+        }
+        if let Some(ticker) = self.tickers.iter().find(|t| t.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "dividends ticker {:?} can't be empty",
+                ticker
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Response of chart api with `events=div`, only fields we use
+#[derive(Debug, Deserialize)]
+struct ChartResponse {
+    chart: Chart,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chart {
+    result: Option<Vec<ChartResult>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartResult {
+    meta: ChartMeta,
+    events: Option<Events>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMeta {
+    regular_market_price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Events {
+    /// keyed by unix seconds of ex-dividend date
+    #[serde(default)]
+    dividends: HashMap<String, Payment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Payment {
+    amount: f64,
+    /// unix seconds of ex-dividend date
+    date: i64,
+}
+
+/// Dividend of single ticker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dividend {
+    /// percent of price paid over last year
+    pub yield_percent: f64,
+    /// projected next ex-dividend date
+    pub ex_date: NaiveDate,
+    /// amount of last payment, in quote currency
+    pub amount: f64,
+}
+
+/// Extracts dividend from chart api json body, `None` when ticker paid
+/// nothing in year before `now`
+fn parse_dividend(body: &str, now: DateTime<Utc>) -> Result<Option<Dividend>, BoxError> {
+    let response: ChartResponse = serde_json::from_str(body)?;
+    let result = response
+        .chart
+        .result
+        .and_then(|results| results.into_iter().next())
+        .ok_or("no result in chart response")?;
+    let price = result
+        .meta
+        .regular_market_price
+        .ok_or("no regularMarketPrice in chart response")?;
+    let mut payments = result
+        .events
+        .map(|events| events.dividends.into_values().collect::<Vec<_>>())
+        .unwrap_or_default();
+    payments.sort_by_key(|payment| payment.date);
+
+    let year_ago = (now - chrono::Duration::days(365)).timestamp();
+    let paid: f64 = payments
+        .iter()
+        .filter(|payment| payment.date > year_ago)
+        .map(|payment| payment.amount)
+        .sum();
+    let Some(last) = payments.last().filter(|_| paid > 0.0) else {
+        return Ok(None);
+    };
+    let last_date = DateTime::from_timestamp(last.date, 0)
+        .ok_or("invalid dividend date")?
+        .date_naive();
+
+    // usual gap is median of gaps, single payment is taken as yearly
+    let mut gaps = payments
+        .windows(2)
+        .map(|pair| (pair[1].date - pair[0].date) / 86_400)
+        .filter(|days| *days > 0)
+        .collect::<Vec<_>>();
+    gaps.sort_unstable();
+    let gap = chrono::Duration::days(gaps.get(gaps.len() / 2).copied().unwrap_or(365));
+    let today = now.date_naive();
+    let mut ex_date = last_date + gap;
+    while ex_date < today {
+        ex_date += gap;
+    }
+
+    Ok(Some(Dividend {
+        yield_percent: paid / price * 100.0,
+        ex_date,
+        amount: last.amount,
+    }))
+}
+
+/// Fetches two years of dividend events of ticker from Yahoo
+pub async fn fetch_dividend(client: &Client, ticker: &str) -> Result<Option<Dividend>, BoxError> {
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}",
+        stocks::escape(ticker)
+    );
+    let body = client
+        .get(url)
+        .query(&[("range", "2y"), ("interval", "1mo"), ("events", "div")])
+        .send()
+        .await?
+        .text()
+        .await?;
+    parse_dividend(&body, Utc::now())
+}
+
+/// Dividends of tickers kept for [`MAX_AGE`], so quote refreshes don't
+/// refetch them
+#[derive(Default)]
+pub struct Dividends {
+    cache: Mutex<HashMap<String, (Instant, Option<Dividend>)>>,
+}
+
+impl Dividends {
+    pub fn new() -> Self {
+        Dividends::default()
+    }
+
+    /// Dividends of stocks among `tickers`, fetching ones not cached or
+    /// older than [`MAX_AGE`]. Ticker which fails to fetch is logged and
+    /// left without dividend until next fetch
+    pub async fn get(&self, client: &Client, tickers: &[String]) -> HashMap<String, Dividend> {
+        let stale = {
+            let cache = self.cache.lock().unwrap();
+            tickers
+                .iter()
+                .filter(|ticker| AssetClass::of(ticker) == AssetClass::Stock)
+                .filter(|ticker| {
+                    cache
+                        .get(*ticker)
+                        .is_none_or(|(at, _)| at.elapsed() > MAX_AGE)
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        if !stale.is_empty() {
+            log::info!("Fetching dividends of {} tickers", stale.len());
+            let fetched = join_all(stale.iter().map(|ticker| fetch_dividend(client, ticker))).await;
+            let mut cache = self.cache.lock().unwrap();
+            for (ticker, dividend) in stale.into_iter().zip(fetched) {
+                match dividend {
+                    Ok(dividend) => {
+                        cache.insert(ticker, (Instant::now(), dividend));
+                    }
+                    Err(e) => log::error!("Unable to fetch dividends of {}: {}", ticker, e),
+                }
+            }
+        }
+        let cache = self.cache.lock().unwrap();
+        tickers
+            .iter()
+            .filter_map(|ticker| {
+                let (_, dividend) = cache.get(ticker)?;
+                Some((ticker.clone(), (*dividend)?))
+            })
+            .collect()
+    }
+}
+
+/// Adds dividend to ticker's line as `dividend_yield` and `ex_dividend`
+/// fields, ex. `18 Dec`
+pub fn with_dividend(line: Line, dividend: &Dividend) -> Line {
+    line.with_field("dividend_yield", dividend.yield_percent)
+        .with_field("ex_dividend", dividend.ex_date.format("%-d %b").to_string())
+}
+
+/// Formats upcoming dividend into line, ex. `VWRL ex 18 Dec 1.9%`
+fn to_line(ticker: &str, dividend: &Dividend, today: NaiveDate) -> Line {
+    let symbol = AssetClass::Stock.symbol(ticker);
+    let ex_date = dividend.ex_date.format("%-d %b").to_string();
+    let days = (dividend.ex_date - today).num_days();
+    Line::new(format!(
+        "{:.4} ex {} {:.1}%",
+        symbol, ex_date, dividend.yield_percent
+    ))
+    .with_field("symbol", symbol)
+    .with_field("ex_date", ex_date)
+    .with_field("days", days as f64)
+    .with_field("yield", dividend.yield_percent)
+    .with_field("amount", dividend.amount)
+    // last day to buy for dividend
+    .with_highlight(days <= 1)
+}
+
+/// Upcoming ex-dividend dates of watched tickers, soonest first
+pub struct DividendsProvider {
+    config: DividendsConfig,
+    tickers: Vec<String>,
+    dividends: Dividends,
+    client: Client,
+}
+
+impl DividendsProvider {
+    /// Watches `tickers` of config unless section lists own
+    pub fn new(config: DividendsConfig, tickers: &[String]) -> Result<Self, BoxError> {
+        Ok(DividendsProvider {
+            tickers: if config.tickers.is_empty() {
+                tickers.to_vec()
+            } else {
+                config.tickers.clone()
+            },
+            config,
+            dividends: Dividends::new(),
+            client: stocks::client()?,
+        })
+    }
+}
+
+#[async_trait]
+impl DataProvider for DividendsProvider {
+    fn name(&self) -> &str {
+        "dividends"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(6 * 60 * 60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let dividends = self.dividends.get(&self.client, &self.tickers).await;
+        let today = Local::now().date_naive();
+        let to = today + chrono::Duration::days(self.config.days.into());
+        let mut upcoming = dividends
+            .iter()
+            .filter(|(_, dividend)| dividend.ex_date <= to)
+            .collect::<Vec<_>>();
+        upcoming.sort_by(|a, b| a.1.ex_date.cmp(&b.1.ex_date).then_with(|| a.0.cmp(b.0)));
+        Ok(upcoming
+            .into_iter()
+            .map(|(ticker, dividend)| to_line(ticker, dividend, today))
+            .collect())
+    }
+}
+
+#[test]
+fn testing_dividend_parsing() {
+    DividendsConfig::default().validate().unwrap();
+
+    let body = r#"{"chart":{"result":[{"meta":{"currency":"EUR","symbol":"VWRL.AS",
+        "regularMarketPrice":120.0},"timestamp":[1697407200],
+        "events":{"dividends":{
+            "1695198600":{"amount":0.5,"date":1695198600},
+            "1703150100":{"amount":0.3,"date":1703150100},
+            "1711014300":{"amount":0.4,"date":1711014300},
+            "1718784000":{"amount":0.9,"date":1718784000}}},
+        "indicators":{"quote":[{"close":[119.2]}]}}],"error":null}}"#;
+    let now = DateTime::parse_from_rfc3339("2024-10-01T12:00:00Z")
+        .unwrap()
+        .to_utc();
+    let dividend = parse_dividend(body, now).unwrap().unwrap();
+    // payments of 21 Dec, 21 Mar and 19 Jun, quarterly after last one with
+    // missed 18 Sep skipped
+    assert_eq!(dividend.yield_percent, 1.6 / 120.0 * 100.0);
+    assert_eq!(dividend.amount, 0.9);
+    assert_eq!(
+        dividend.ex_date,
+        NaiveDate::from_ymd_opt(2024, 12, 18).unwrap()
+    );
+
+    let line = to_line("VWRL.AS", &dividend, now.date_naive());
+    assert_eq!(line.text, "VWRL ex 18 Dec 1.3%");
+    assert!(!line.highlight);
+    let line = with_dividend(Line::new("VWRL 120"), &dividend);
+    assert!(line.field("ex_dividend").is_some());
+
+    let none = r#"{"chart":{"result":[{"meta":{"regularMarketPrice":241.0}}],"error":null}}"#;
+    assert_eq!(parse_dividend(none, now).unwrap(), None);
+}
@@ -0,0 +1,252 @@
+//! Containers of local Docker engine, ex. `DOCKER 5 up 2 exited`
+//!
+//! Engine api is read over its socket, `unix:///var/run/docker.sock` or
+//! `npipe:////./pipe/docker_engine` on windows, or plain `tcp://` when
+//! `DOCKER_HOST` points there. Named containers carry metric 1 while
+//! running, so `[alerts]` rule like `db < 1` wakes keyboard when one stops.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hyper::{Body, Request};
+use serde::{de::DeserializeOwned, Deserialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var host is read from when config has none, same as docker cli
+pub const HOST_ENV: &str = "DOCKER_HOST";
+
+#[cfg(unix)]
+const DEFAULT_HOST: &str = "unix:///var/run/docker.sock";
+#[cfg(windows)]
+const DEFAULT_HOST: &str = "npipe:////./pipe/docker_engine";
+
+/// `[docker]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DockerConfig {
+    /// engine socket, `DOCKER_HOST` env or platform default without it
+    pub host: Option<String>,
+    /// containers shown by name with their state
+    pub containers: Vec<String>,
+    /// label of counts line
+    pub label: String,
+}
+
+impl Default for DockerConfig {
+    fn default() -> Self {
+        DockerConfig {
+            host: None,
+            containers: Vec::new(),
+            label: "DOCKER".into(),
+        }
+    }
+}
+
+impl DockerConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if let Some(host) = &self.host {
+            let scheme = host.split_once("://").map(|(scheme, _)| scheme);
+            let supported = match scheme {
+                Some("tcp") => true,
+                Some("unix") => cfg!(unix),
+                Some("npipe") => cfg!(windows),
+                _ => false,
+            };
+            if !supported {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "docker.host {:?} is not tcp://, unix:// or npipe:// socket of this platform",
+                    host
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn host(&self) -> String {
+        self.host
+            .clone()
+            .or_else(|| std::env::var(HOST_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_HOST.into())
+    }
+}
+
+/// Element of `/containers/json` response, only fields we use
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Container {
+    /// names with leading `/`
+    names: Vec<String>,
+    /// ex. `running`, `exited` or `restarting`
+    state: String,
+    /// ex. `Up 2 hours (healthy)`
+    status: String,
+}
+
+impl Container {
+    fn is_named(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n.trim_start_matches('/') == name)
+    }
+
+    /// health check result from status, empty without health check
+    fn health(&self) -> &str {
+        match self.status.rsplit_once('(') {
+            Some((_, health)) if self.status.ends_with(')') => {
+                health.trim_end_matches(')').trim_start_matches("health: ")
+            }
+            _ => "",
+        }
+    }
+}
+
+/// Counts line, ex. `DOCKER 5 up 2 exited`, then line per named container,
+/// ex. `db running unhealthy`
+fn to_lines(config: &DockerConfig, containers: &[Container]) -> Vec<Line> {
+    let count = |state: &str| containers.iter().filter(|c| c.state == state).count();
+    let (running, exited) = (count("running"), count("exited"));
+    let mut lines = vec![
+        Line::new(format!("{} {} up {} exited", config.label, running, exited))
+            .with_field("label", config.label.as_str())
+            .with_field("running", running as f64)
+            .with_field("exited", exited as f64)
+            .with_field("total", containers.len() as f64),
+    ];
+    for name in &config.containers {
+        let container = containers.iter().find(|c| c.is_named(name));
+        let state = container.map_or("missing", |c| c.state.as_str());
+        let health = container.map_or("", Container::health);
+        let mut text = format!("{} {}", name, state);
+        if health == "unhealthy" {
+            text.push_str(" unhealthy");
+        }
+        let up = state == "running" && health != "unhealthy";
+        lines.push(
+            Line::new(text)
+                .with_field("name", name.as_str())
+                .with_field("state", state)
+                .with_field("health", health)
+                .with_metric(name, if up { 1.0 } else { 0.0 }),
+        );
+    }
+    lines
+}
+
+/// GET request of engine api over already connected stream
+async fn get<S, T>(stream: S, path: &str) -> Result<T, BoxError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T: DeserializeOwned,
+{
+    let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            log::debug!("Docker connection closed: {}", e);
+        }
+    });
+    let request = Request::get(path)
+        .header("Host", "docker")
+        .body(Body::empty())?;
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    if !status.is_success() {
+        return Err(format!(
+            "docker answered {}: {}",
+            status,
+            String::from_utf8_lossy(&body).trim()
+        )
+        .into());
+    }
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Container counts and states of named containers
+pub struct DockerProvider {
+    config: DockerConfig,
+}
+
+impl DockerProvider {
+    pub fn new(config: DockerConfig) -> Self {
+        DockerProvider { config }
+    }
+
+    async fn containers(&self) -> Result<Vec<Container>, BoxError> {
+        let host = self.config.host();
+        let path = "/containers/json?all=true";
+        match host.split_once("://") {
+            Some(("tcp", address)) => {
+                get(tokio::net::TcpStream::connect(address).await?, path).await
+            }
+            #[cfg(unix)]
+            Some(("unix", socket)) => {
+                get(tokio::net::UnixStream::connect(socket).await?, path).await
+            }
+            #[cfg(windows)]
+            Some(("npipe", pipe)) => {
+                let pipe = tokio::net::windows::named_pipe::ClientOptions::new()
+                    .open(pipe.replace('/', "\\"))?;
+                get(pipe, path).await
+            }
+            _ => Err(format!("unsupported docker host {}", host).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for DockerProvider {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::debug!("Fetching containers from docker");
+
+        let containers = self.containers().await?;
+        Ok(to_lines(&self.config, &containers))
+    }
+}
+
+#[test]
+fn testing_docker_lines() {
+    let containers: Vec<Container> = serde_json::from_str(
+        r#"[
+            {"Id":"1","Names":["/web"],"State":"running","Status":"Up 2 hours (healthy)"},
+            {"Id":"2","Names":["/db"],"State":"running","Status":"Up 5 minutes (unhealthy)"},
+            {"Id":"3","Names":["/migrate"],"State":"exited","Status":"Exited (0) 3 hours ago"},
+            {"Id":"4","Names":["/cache"],"State":"restarting","Status":"Restarting (1) 2 seconds ago"}
+        ]"#,
+    )
+    .unwrap();
+    let config = DockerConfig {
+        containers: vec!["web".into(), "db".into(), "queue".into()],
+        ..DockerConfig::default()
+    };
+    config.validate().unwrap();
+    assert!(DockerConfig {
+        host: Some("ssh://server".into()),
+        ..DockerConfig::default()
+    }
+    .validate()
+    .is_err());
+
+    let lines = to_lines(&config, &containers);
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        [
+            "DOCKER 2 up 1 exited",
+            "web running",
+            "db running unhealthy",
+            "queue missing"
+        ]
+    );
+    assert_eq!(containers[2].health(), "");
+    assert_eq!(lines[1].metric.as_ref().unwrap().value, 1.0);
+    assert_eq!(lines[2].metric.as_ref().unwrap().value, 0.0);
+}
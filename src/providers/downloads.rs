@@ -0,0 +1,239 @@
+//! Daily downloads of packages from crates.io and npm, ex. `serde 312k`
+//!
+//! Count is of yesterday, last day registries have complete numbers of.
+//! Line carries metric under package name, so `[alerts]` rule like
+//! `serde > 500000` works.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use futures::future::join_all;
+use reqwest::{header, Client};
+use serde::Deserialize;
+
+use super::{compact_count, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// crates.io rejects requests without user agent naming client
+const USER_AGENT: &str = concat!("elora_hid/", env!("CARGO_PKG_VERSION"));
+
+/// `[downloads]` config section
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DownloadsConfig {
+    /// crates.io crate names
+    pub crates: Vec<String>,
+    /// npm package names, scoped ones like `@acme/ui` too
+    pub npm: Vec<String>,
+}
+
+impl DownloadsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.crates.is_empty() && self.npm.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "downloads needs at least one of crates or npm packages".into(),
+            ));
+        }
+        if let Some(name) = self.crates.iter().chain(&self.npm).find(|n| n.is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "downloads package name {:?} is empty",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    fn packages(&self) -> Vec<Package> {
+        let crates = self.crates.iter().map(|name| Package {
+            registry: Registry::Crates,
+            name: name.clone(),
+        });
+        let npm = self.npm.iter().map(|name| Package {
+            registry: Registry::Npm,
+            name: name.clone(),
+        });
+        crates.chain(npm).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Registry {
+    Crates,
+    Npm,
+}
+
+impl Registry {
+    fn name(self) -> &'static str {
+        match self {
+            Registry::Crates => "crates",
+            Registry::Npm => "npm",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Package {
+    registry: Registry,
+    name: String,
+}
+
+/// Response of crates.io `/crates/:name/downloads`, last 90 days of top
+/// versions with rest of versions summed as extra
+#[derive(Debug, Deserialize)]
+struct CrateDownloads {
+    version_downloads: Vec<DayDownloads>,
+    meta: CrateDownloadsMeta,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateDownloadsMeta {
+    extra_downloads: Vec<DayDownloads>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DayDownloads {
+    date: NaiveDate,
+    downloads: u64,
+}
+
+/// Response of npm `/downloads/point/last-day/:name`
+#[derive(Debug, Deserialize)]
+struct NpmDownloads {
+    downloads: u64,
+}
+
+/// downloads of crate on given day summed over versions
+fn crate_day(downloads: &CrateDownloads, day: NaiveDate) -> u64 {
+    downloads
+        .version_downloads
+        .iter()
+        .chain(&downloads.meta.extra_downloads)
+        .filter(|d| d.date == day)
+        .map(|d| d.downloads)
+        .sum()
+}
+
+fn to_line(package: &Package, downloads: u64) -> Line {
+    Line::new(format!("{} {}", package.name, compact_count(downloads)))
+        .with_field("package", package.name.as_str())
+        .with_field("registry", package.registry.name())
+        .with_field("downloads", downloads as f64)
+        .with_metric(&package.name, downloads as f64)
+}
+
+/// Yesterday's downloads of configured packages
+pub struct DownloadsProvider {
+    packages: Vec<Package>,
+    client: Client,
+}
+
+impl DownloadsProvider {
+    pub fn new(config: DownloadsConfig) -> Self {
+        DownloadsProvider {
+            packages: config.packages(),
+            client: Client::new(),
+        }
+    }
+
+    async fn downloads(&self, package: &Package) -> Result<u64, BoxError> {
+        match package.registry {
+            Registry::Crates => {
+                let url = format!("https://crates.io/api/v1/crates/{}/downloads", package.name);
+                let downloads: CrateDownloads = self
+                    .client
+                    .get(url)
+                    .header(header::USER_AGENT, USER_AGENT)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                let yesterday = Utc::now().date_naive().pred_opt().ok_or("no yesterday")?;
+                Ok(crate_day(&downloads, yesterday))
+            }
+            Registry::Npm => {
+                let url = format!(
+                    "https://api.npmjs.org/downloads/point/last-day/{}",
+                    package.name
+                );
+                let downloads: NpmDownloads = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(downloads.downloads)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for DownloadsProvider {
+    fn name(&self) -> &str {
+        "downloads"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(3600))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching downloads of {} packages", self.packages.len());
+
+        let counts = join_all(self.packages.iter().map(|p| self.downloads(p))).await;
+        let mut lines = Vec::new();
+        for (package, count) in self.packages.iter().zip(counts) {
+            match count {
+                Ok(count) => lines.push(to_line(package, count)),
+                Err(e) => log::error!(
+                    "Unable to fetch {} downloads of {}: {}",
+                    package.registry.name(),
+                    package.name,
+                    e
+                ),
+            }
+        }
+        if lines.is_empty() {
+            return Err("no package downloads could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_downloads_lines() {
+    let config = DownloadsConfig {
+        crates: vec!["serde".into()],
+        npm: vec!["@acme/ui".into()],
+    };
+    config.validate().unwrap();
+    assert!(DownloadsConfig::default().validate().is_err());
+    let packages = config.packages();
+
+    let downloads: CrateDownloads = serde_json::from_str(
+        r#"{"version_downloads":[
+            {"version":1,"downloads":300000,"date":"2026-10-13"},
+            {"version":2,"downloads":9000,"date":"2026-10-13"},
+            {"version":1,"downloads":280000,"date":"2026-10-12"}
+        ],"meta":{"extra_downloads":[{"date":"2026-10-13","downloads":3456}]}}"#,
+    )
+    .unwrap();
+    let day = NaiveDate::from_ymd_opt(2026, 10, 13).unwrap();
+    let count = crate_day(&downloads, day);
+    assert_eq!(count, 312_456);
+    let line = to_line(&packages[0], count);
+    assert_eq!(line.text, "serde 312k");
+    assert_eq!(line.metric.unwrap().value, 312_456.0);
+
+    let npm: NpmDownloads = serde_json::from_str(
+        r#"{"downloads":12345,"start":"2026-10-13","end":"2026-10-13","package":"@acme/ui"}"#,
+    )
+    .unwrap();
+    assert_eq!(to_line(&packages[1], npm.downloads).text, "@acme/ui 12.3k");
+    assert_eq!(compact_count(987), "987");
+    assert_eq!(compact_count(1_234_567), "1.2M");
+}
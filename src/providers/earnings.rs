@@ -0,0 +1,200 @@
+//! Upcoming earnings reports of watched tickers from Finnhub earnings
+//! calendar, ex. `TSLA ER in 2d`
+//!
+//! Needs same free api key as `[finnhub]`, given as `token` or
+//! `FINNHUB_TOKEN` env. Only reports within `days` are drawn, so provider
+//! has no lines most of the time and warns when report comes close.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate};
+use futures::future::try_join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{finnhub, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[earnings]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EarningsConfig {
+    pub token: Option<String>,
+    /// US tickers to watch, stock tickers of config when empty
+    pub tickers: Vec<String>,
+    /// how many days ahead reports are drawn
+    pub days: u32,
+}
+
+impl Default for EarningsConfig {
+    fn default() -> Self {
+        EarningsConfig {
+            token: None,
+            tickers: Vec::new(),
+            days: 7,
+        }
+    }
+}
+
+impl EarningsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.days == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "earnings.days must be greater than 0".into(),
+            ));
+        }
+        if let Some(ticker) = self.tickers.iter().find(|t| t.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "earnings ticker {:?} can't be empty",
+                ticker
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Response of `/api/v1/calendar/earnings`, only fields we use
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Calendar {
+    earnings_calendar: Vec<Report>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Report {
+    symbol: String,
+    date: NaiveDate,
+    /// `bmo` before market open, `amc` after market close, empty when
+    /// unknown
+    #[serde(default)]
+    hour: String,
+}
+
+/// Formats report into line, ex. `TSLA ER today`, `TSLA ER tmrw` or
+/// `TSLA ER in 2d`
+fn to_line(report: &Report, today: NaiveDate) -> Line {
+    let days = (report.date - today).num_days();
+    let until = match days {
+        0 => "today".to_string(),
+        1 => "tmrw".to_string(),
+        _ => format!("in {}d", days),
+    };
+    let mut line = Line::new(format!("{} ER {}", report.symbol, until))
+        .with_field("symbol", report.symbol.as_str())
+        .with_field("days", days as f64)
+        .with_field("date", report.date.to_string())
+        // report lands within hours
+        .with_highlight(days == 0);
+    if !report.hour.is_empty() {
+        line = line.with_field("hour", report.hour.as_str());
+    }
+    line
+}
+
+/// Earnings reports of watched tickers coming within configured days
+pub struct EarningsProvider {
+    config: EarningsConfig,
+    tickers: Vec<String>,
+    client: Client,
+}
+
+impl EarningsProvider {
+    /// Watches `tickers` of config unless section lists own
+    pub fn new(config: EarningsConfig, tickers: &[String]) -> Self {
+        EarningsProvider {
+            tickers: if config.tickers.is_empty() {
+                tickers.to_vec()
+            } else {
+                config.tickers.clone()
+            },
+            config,
+            client: Client::new(),
+        }
+    }
+
+    async fn reports(
+        &self,
+        token: &str,
+        ticker: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<Report>, BoxError> {
+        let calendar: Calendar = self
+            .client
+            .get("https://finnhub.io/api/v1/calendar/earnings")
+            .query(&[
+                ("symbol", ticker),
+                ("from", &from.to_string()),
+                ("to", &to.to_string()),
+                ("token", token),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(calendar.earnings_calendar)
+    }
+}
+
+#[async_trait]
+impl DataProvider for EarningsProvider {
+    fn name(&self) -> &str {
+        "earnings"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(6 * 60 * 60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let token = finnhub::token(self.config.token.as_deref())
+            .ok_or_else(|| format!("earnings.token or {} env is required", finnhub::TOKEN_ENV))?;
+        log::info!("Fetching earnings of {} tickers", self.tickers.len());
+
+        let today = Local::now().date_naive();
+        let to = today + chrono::Duration::days(self.config.days.into());
+        let fetched = try_join_all(
+            self.tickers
+                .iter()
+                .map(|ticker| self.reports(&token, ticker, today, to)),
+        )
+        .await?;
+        let mut reports = fetched
+            .into_iter()
+            .flatten()
+            .filter(|report| report.date >= today && report.date <= to)
+            .collect::<Vec<_>>();
+        reports.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.symbol.cmp(&b.symbol)));
+        reports.dedup();
+        Ok(reports
+            .iter()
+            .map(|report| to_line(report, today))
+            .collect())
+    }
+}
+
+#[test]
+fn testing_earnings_lines() {
+    EarningsConfig::default().validate().unwrap();
+
+    let calendar: Calendar = serde_json::from_str(
+        r#"{"earningsCalendar":[{"date":"2024-10-23","epsActual":null,"epsEstimate":0.5799,
+            "hour":"amc","quarter":3,"revenueActual":null,"revenueEstimate":25475601811,
+            "symbol":"TSLA","year":2024}]}"#,
+    )
+    .unwrap();
+    let report = &calendar.earnings_calendar[0];
+    let day = |d: u32| NaiveDate::from_ymd_opt(2024, 10, d).unwrap();
+    let line = to_line(report, day(21));
+    assert_eq!(line.text, "TSLA ER in 2d");
+    assert!(!line.highlight);
+    assert_eq!(to_line(report, day(22)).text, "TSLA ER tmrw");
+    let line = to_line(report, day(23));
+    assert_eq!(line.text, "TSLA ER today");
+    assert!(line.highlight);
+
+    let provider = EarningsProvider::new(EarningsConfig::default(), &["NVDA".to_string()]);
+    assert_eq!(provider.tickers, ["NVDA"]);
+}
@@ -0,0 +1,119 @@
+//! Day-ahead prices from ENTSO-E transparency platform xml
+//!
+//! Only `Publication_MarketDocument` of `A44` (price) documents is read,
+//! refusals come as `Acknowledgement_MarketDocument` with reason text.
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+use super::Slot;
+use crate::{
+    providers::xml::{self, child, child_text, children, descendants},
+    BoxError,
+};
+
+/// ex. `2026-10-13T22:00Z`
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%MZ")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// ex. `PT15M` or `PT60M`
+fn parse_resolution(resolution: &str) -> Option<Duration> {
+    let minutes = resolution
+        .strip_prefix("PT")?
+        .strip_suffix('M')?
+        .parse()
+        .ok()?;
+    (minutes > 0).then(|| Duration::minutes(minutes))
+}
+
+/// Slots of every period in document, prices in EUR/MWh. Points repeating
+/// previous price may be left out (curve type `A03`), so they're filled
+/// from point before them
+pub fn parse(document: &str) -> Result<Vec<Slot>, BoxError> {
+    let doc = xml::parse(document)?;
+    let root = doc.root_element();
+    if root.tag_name().name() == "Acknowledgement_MarketDocument" {
+        let reason = descendants(root, "text")
+            .next()
+            .map(xml::text)
+            .unwrap_or_else(|| "no reason given".into());
+        return Err(format!("entsoe refused request: {}", reason).into());
+    }
+    let mut slots = Vec::new();
+    for period in descendants(root, "Period") {
+        let interval = child(period, "timeInterval").ok_or("entsoe period has no interval")?;
+        let (Some(start), Some(end), Some(resolution)) = (
+            child_text(interval, "start")
+                .as_deref()
+                .and_then(parse_time),
+            child_text(interval, "end").as_deref().and_then(parse_time),
+            child_text(period, "resolution")
+                .as_deref()
+                .and_then(parse_resolution),
+        ) else {
+            return Err("entsoe period has invalid interval or resolution".into());
+        };
+        let mut points: Vec<(i32, f64)> = children(period, "Point")
+            .filter_map(|point| {
+                Some((
+                    child_text(point, "position")?.parse().ok()?,
+                    child_text(point, "price.amount")?.parse().ok()?,
+                ))
+            })
+            .collect();
+        points.sort_by_key(|(position, _)| *position);
+        let count = ((end - start).num_minutes() / resolution.num_minutes()) as i32;
+        let mut points = points.into_iter().peekable();
+        let mut price = None;
+        for position in 1..=count {
+            while let Some((_, amount)) = points.next_if(|(at, _)| *at <= position) {
+                price = Some(amount);
+            }
+            let Some(price) = price else {
+                continue;
+            };
+            let start = start + resolution * (position - 1);
+            slots.push(Slot {
+                start,
+                end: start + resolution,
+                price,
+            });
+        }
+    }
+    Ok(slots)
+}
+
+#[test]
+fn testing_entsoe_document() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<Publication_MarketDocument xmlns="urn:iec62325.351:tc57wg16:451-3:publicationdocument:7:3">
+  <TimeSeries>
+    <curveType>A03</curveType>
+    <Period>
+      <timeInterval>
+        <start>2026-10-13T22:00Z</start>
+        <end>2026-10-13T23:00Z</end>
+      </timeInterval>
+      <resolution>PT15M</resolution>
+      <Point><position>1</position><price.amount>95.12</price.amount></Point>
+      <Point><position>3</position><price.amount>80.5</price.amount></Point>
+    </Period>
+  </TimeSeries>
+</Publication_MarketDocument>"#;
+    let slots = parse(xml).unwrap();
+    let prices: Vec<f64> = slots.iter().map(|slot| slot.price).collect();
+    assert_eq!(prices, [95.12, 95.12, 80.5, 80.5]);
+    assert_eq!(slots[1].start, parse_time("2026-10-13T22:15Z").unwrap());
+    assert_eq!(slots[3].end, parse_time("2026-10-13T23:00Z").unwrap());
+
+    let refused = r#"<Acknowledgement_MarketDocument xmlns="urn:iec62325.351:tc57wg16:451-1:acknowledgementdocument:7:0">
+  <Reason><code>999</code><text>No matching data found</text></Reason>
+</Acknowledgement_MarketDocument>"#;
+    assert!(parse(refused)
+        .unwrap_err()
+        .to_string()
+        .contains("No matching data"));
+    assert!(parse("<Publication_MarketDocument><Period>").is_err());
+}
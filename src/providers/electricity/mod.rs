@@ -0,0 +1,426 @@
+//! Day-ahead electricity spot price, ex. `EL 12.3c ▼9.8`
+//!
+//! Shows price of current slot and average of next hour from Nord Pool,
+//! aWATTar (Germany and Austria) or ENTSO-E. Day-ahead prices are published
+//! once a day, so they're downloaded every `fetch_secs` and current slot is
+//! picked from them every minute. Current price is line metric, so
+//! `[alerts]` rules like `EL > 30` tell when power gets expensive.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+mod entsoe;
+
+/// env variable used when entsoe `token` is not in config
+pub const TOKEN_ENV: &str = "ENTSOE_TOKEN";
+
+/// where prices come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Nord Pool data portal, nordic and baltic areas, no key needed
+    #[default]
+    Nordpool,
+    /// aWATTar api of Germany and Austria, no key needed
+    Awattar,
+    /// ENTSO-E transparency platform, every european area, needs token
+    Entsoe,
+}
+
+/// unit prices are drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    /// cents per kWh, ex. `12.3c`
+    #[default]
+    Kwh,
+    /// whole currency per MWh as markets quote it, ex. `123`
+    Mwh,
+}
+
+/// `[electricity]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ElectricityConfig {
+    pub source: Source,
+    /// bidding zone, ex. `LT` or `SE3` for nordpool, `DE` or `AT` for
+    /// awattar, EIC code like `10YLT-1001A0008Q` for entsoe
+    pub area: String,
+    /// nordpool only, other sources quote EUR
+    pub currency: String,
+    /// entsoe security token, `ENTSOE_TOKEN` env without it
+    pub token: Option<String>,
+    pub unit: Unit,
+    /// grid fee and margin added to spot price, in shown unit
+    pub fee: f64,
+    /// tax added on top of price and fee, ex. `21`
+    pub vat_percent: f64,
+    /// shown in front of price
+    pub label: String,
+    /// prices are downloaded again after this long
+    pub fetch_secs: u64,
+}
+
+impl Default for ElectricityConfig {
+    fn default() -> Self {
+        ElectricityConfig {
+            source: Source::Nordpool,
+            area: String::new(),
+            currency: "EUR".into(),
+            token: None,
+            unit: Unit::Kwh,
+            fee: 0.0,
+            vat_percent: 0.0,
+            label: "EL".into(),
+            fetch_secs: 3600,
+        }
+    }
+}
+
+impl ElectricityConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.area.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "electricity.area is required, ex. LT or DE".into(),
+            ));
+        }
+        if self.source == Source::Awattar && !["DE", "AT"].contains(&self.area.as_str()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "electricity.area {:?} is not DE or AT, which awattar covers",
+                self.area
+            )));
+        }
+        if self.source != Source::Nordpool && self.currency != "EUR" {
+            return Err(EloraError::ConfigInvalid(
+                "electricity.currency can be changed only with nordpool source".into(),
+            ));
+        }
+        if self.fetch_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "electricity.fetch_secs must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("electricity.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+
+    /// Spot price in currency per MWh converted to shown unit, with fee and
+    /// vat
+    fn shown(&self, spot: f64) -> f64 {
+        let price = match self.unit {
+            Unit::Kwh => spot / 10.0,
+            Unit::Mwh => spot,
+        };
+        (price + self.fee) * (1.0 + self.vat_percent / 100.0)
+    }
+
+    fn format(&self, price: f64) -> String {
+        match self.unit {
+            Unit::Kwh => format!("{:.1}", price),
+            Unit::Mwh => format!("{:.0}", price),
+        }
+    }
+}
+
+/// Market time unit with its spot price in currency per MWh
+#[derive(Debug, Clone, PartialEq)]
+struct Slot {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    price: f64,
+}
+
+/// Spot price at `now` and average of next full hour, which is single slot
+/// on hourly markets and four on 15 minute ones
+fn current_and_next(slots: &[Slot], now: DateTime<Utc>) -> Option<(f64, Option<f64>)> {
+    let current = slots
+        .iter()
+        .find(|slot| slot.start <= now && now < slot.end)?;
+    let next_hour = now.duration_trunc(TimeDelta::hours(1)).ok()? + TimeDelta::hours(1);
+    let next: Vec<f64> = slots
+        .iter()
+        .filter(|slot| slot.start >= next_hour && slot.start < next_hour + TimeDelta::hours(1))
+        .map(|slot| slot.price)
+        .collect();
+    let next = (!next.is_empty()).then(|| next.iter().sum::<f64>() / next.len() as f64);
+    Some((current.price, next))
+}
+
+fn to_line(config: &ElectricityConfig, current: f64, next: Option<f64>) -> Line {
+    let current = config.shown(current);
+    let next = next
+        .map(|next| config.shown(next))
+        .map(|next| (next, if next < current { '▼' } else { '▲' }));
+    let suffix = match config.unit {
+        Unit::Kwh => "c",
+        Unit::Mwh => "",
+    };
+    let mut text = format!("{} {}{}", config.label, config.format(current), suffix);
+    if let Some((next, arrow)) = next {
+        text.push_str(&format!(" {}{}", arrow, config.format(next)));
+    }
+    let mut line = Line::new(text)
+        .with_field("label", config.label.as_str())
+        .with_field("price", current)
+        .with_field("currency", config.currency.as_str());
+    if let Some((next, arrow)) = next {
+        line = line
+            .with_field("arrow", arrow.to_string())
+            .with_field("next", next);
+    }
+    line.with_metric(&config.label, current)
+}
+
+/// Response of aWATTar `/v1/marketdata`, only fields we use
+#[derive(Debug, Deserialize)]
+struct AwattarResponse {
+    data: Vec<AwattarPrice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AwattarPrice {
+    start_timestamp: i64,
+    end_timestamp: i64,
+    /// EUR/MWh
+    marketprice: f64,
+}
+
+/// Response of Nord Pool `/api/DayAheadPrices`, only fields we use
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NordpoolResponse {
+    multi_area_entries: Vec<NordpoolEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NordpoolEntry {
+    delivery_start: DateTime<Utc>,
+    delivery_end: DateTime<Utc>,
+    entry_per_area: std::collections::BTreeMap<String, f64>,
+}
+
+/// Spot price of configured area
+pub struct ElectricityProvider {
+    config: ElectricityConfig,
+    client: Client,
+    /// slots of last download and when it was done
+    slots: Mutex<Option<(Instant, Vec<Slot>)>>,
+}
+
+impl ElectricityProvider {
+    pub fn new(config: ElectricityConfig) -> Self {
+        ElectricityProvider {
+            config,
+            client: Client::new(),
+            slots: Mutex::new(None),
+        }
+    }
+
+    async fn awattar(&self, now: DateTime<Utc>) -> Result<Vec<Slot>, BoxError> {
+        let url = format!(
+            "https://api.awattar.{}/v1/marketdata?start={}&end={}",
+            self.config.area.to_lowercase(),
+            (now - TimeDelta::hours(1)).timestamp_millis(),
+            (now + TimeDelta::days(2)).timestamp_millis()
+        );
+        let response: AwattarResponse = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response
+            .data
+            .iter()
+            .filter_map(|price| {
+                Some(Slot {
+                    start: DateTime::from_timestamp_millis(price.start_timestamp)?,
+                    end: DateTime::from_timestamp_millis(price.end_timestamp)?,
+                    price: price.marketprice,
+                })
+            })
+            .collect())
+    }
+
+    /// Prices of today and tomorrow in CET, tomorrow's are missing until
+    /// auction results are out in early afternoon
+    async fn nordpool(&self, now: DateTime<Utc>) -> Result<Vec<Slot>, BoxError> {
+        let today = now.with_timezone(&chrono_tz::Europe::Oslo).date_naive();
+        let mut slots = Vec::new();
+        for date in [Some(today), today.succ_opt()].into_iter().flatten() {
+            let response = self
+                .client
+                .get("https://dataportal-api.nordpoolgroup.com/api/DayAheadPrices")
+                .query(&[
+                    ("date", date.to_string().as_str()),
+                    ("market", "DayAhead"),
+                    ("deliveryArea", &self.config.area),
+                    ("currency", &self.config.currency),
+                ])
+                .send()
+                .await?
+                .error_for_status()?;
+            if response.status() == reqwest::StatusCode::NO_CONTENT {
+                continue;
+            }
+            let response: NordpoolResponse = response.json().await?;
+            slots.extend(response.multi_area_entries.iter().filter_map(|entry| {
+                Some(Slot {
+                    start: entry.delivery_start,
+                    end: entry.delivery_end,
+                    price: *entry.entry_per_area.get(&self.config.area)?,
+                })
+            }));
+        }
+        Ok(slots)
+    }
+
+    async fn entsoe(&self, now: DateTime<Utc>) -> Result<Vec<Slot>, BoxError> {
+        let token = self.config.token()?;
+        let format = "%Y%m%d%H00";
+        let xml = self
+            .client
+            .get("https://web-api.tp.entsoe.eu/api")
+            .query(&[
+                ("securityToken", token.as_str()),
+                ("documentType", "A44"),
+                ("in_Domain", &self.config.area),
+                ("out_Domain", &self.config.area),
+                (
+                    "periodStart",
+                    &(now - TimeDelta::days(1)).format(format).to_string(),
+                ),
+                (
+                    "periodEnd",
+                    &(now + TimeDelta::days(2)).format(format).to_string(),
+                ),
+            ])
+            .send()
+            .await?
+            .text()
+            .await?;
+        entsoe::parse(&xml)
+    }
+
+    async fn download(&self, now: DateTime<Utc>) -> Result<Vec<Slot>, BoxError> {
+        log::info!("Fetching electricity prices of {}", self.config.area);
+        match self.config.source {
+            Source::Nordpool => self.nordpool(now).await,
+            Source::Awattar => self.awattar(now).await,
+            Source::Entsoe => self.entsoe(now).await,
+        }
+    }
+
+    /// Cached slots are fresh when younger than `fetch_secs` and still cover
+    /// next hour
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        let max_age = Duration::from_secs(self.config.fetch_secs);
+        let covered = now + TimeDelta::hours(2);
+        matches!(
+            &*self.slots.lock().unwrap(),
+            Some((at, slots)) if at.elapsed() < max_age && slots.iter().any(|slot| slot.end >= covered)
+        )
+    }
+}
+
+#[async_trait]
+impl DataProvider for ElectricityProvider {
+    fn name(&self) -> &str {
+        "electricity"
+    }
+
+    /// slots can be 15 minutes long
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let now = Utc::now();
+        if !self.is_fresh(now) {
+            match self.download(now).await {
+                Ok(slots) => *self.slots.lock().unwrap() = Some((Instant::now(), slots)),
+                // day-ahead prices from last download still cover today
+                Err(e) if self.slots.lock().unwrap().is_some() => {
+                    log::warn!("Unable to fetch electricity prices, using last ones: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let slots = self.slots.lock().unwrap();
+        let slots = slots.as_ref().map(|(_, slots)| slots.as_slice());
+        let (current, next) = current_and_next(slots.unwrap_or_default(), now)
+            .ok_or("no electricity price for current time")?;
+        Ok(vec![to_line(&self.config, current, next)])
+    }
+}
+
+#[test]
+fn testing_electricity_line() {
+    use chrono::TimeZone;
+
+    let at = |h: u32, m: u32| Utc.with_ymd_and_hms(2026, 10, 14, h, m, 0).unwrap();
+    let slot = |h: u32, m: u32, price: f64| Slot {
+        start: at(h, m),
+        end: at(h, m) + TimeDelta::minutes(15),
+        price,
+    };
+    let slots: Vec<Slot> = [(14, 45, 123.0), (15, 0, 90.0), (15, 15, 100.0)]
+        .iter()
+        .map(|&(h, m, price)| slot(h, m, price))
+        .collect();
+    assert_eq!(
+        current_and_next(&slots, at(14, 50)),
+        Some((123.0, Some(95.0)))
+    );
+    assert_eq!(current_and_next(&slots, at(15, 20)), Some((100.0, None)));
+    assert_eq!(current_and_next(&slots, at(16, 0)), None);
+
+    let config = ElectricityConfig {
+        area: "LT".into(),
+        fee: 2.0,
+        vat_percent: 20.0,
+        ..ElectricityConfig::default()
+    };
+    config.validate().unwrap();
+    let line = to_line(&config, 123.0, Some(95.0));
+    assert_eq!(line.text, "EL 17.2c ▼13.8");
+    assert_eq!(line.metric.unwrap().symbol, "EL");
+    assert_eq!(
+        to_line(&ElectricityConfig::default(), 123.0, None).text,
+        "EL 12.3c"
+    );
+
+    assert!(ElectricityConfig {
+        source: Source::Awattar,
+        area: "LT".into(),
+        ..ElectricityConfig::default()
+    }
+    .validate()
+    .is_err());
+
+    let nordpool: NordpoolResponse = serde_json::from_str(
+        r#"{"multiAreaEntries":[{"deliveryStart":"2026-10-13T22:00:00Z","deliveryEnd":"2026-10-13T22:15:00Z","entryPerArea":{"LT":95.12}}]}"#,
+    )
+    .unwrap();
+    assert_eq!(nordpool.multi_area_entries[0].entry_per_area["LT"], 95.12);
+}
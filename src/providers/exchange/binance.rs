@@ -0,0 +1,111 @@
+//! Binance spot account, requests signed with HMAC-SHA256 of query
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::{Account, Balance, Credentials};
+use crate::BoxError;
+
+const API: &str = "https://api.binance.com";
+/// milliseconds request is valid for after its timestamp
+const RECV_WINDOW: u32 = 10_000;
+
+/// Response of `/api/v3/account`, only fields we use
+#[derive(Debug, Deserialize)]
+struct AccountResponse {
+    balances: Vec<AssetBalance>,
+}
+
+/// amounts are decimal strings
+#[derive(Debug, Deserialize)]
+struct AssetBalance {
+    asset: String,
+    free: String,
+    locked: String,
+}
+
+/// Error body of rejected request, ex. `{"code":-2015,"msg":"Invalid API-key"}`
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    msg: String,
+}
+
+impl TryFrom<AssetBalance> for Balance {
+    type Error = BoxError;
+
+    fn try_from(balance: AssetBalance) -> Result<Self, Self::Error> {
+        Ok(Balance {
+            free: balance.free.parse()?,
+            locked: balance.locked.parse()?,
+            asset: balance.asset,
+        })
+    }
+}
+
+/// `query` with timestamp and its signature
+fn signed(query: &str, secret: &str) -> String {
+    let query = format!(
+        "{}timestamp={}&recvWindow={}",
+        query,
+        Utc::now().timestamp_millis(),
+        RECV_WINDOW
+    );
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes key of any size");
+    mac.update(query.as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+    format!("{}&signature={}", query, signature)
+}
+
+/// Signed GET of `path`, rejected request errors with its message
+async fn get(
+    client: &Client,
+    credentials: &Credentials,
+    path: &str,
+    query: &str,
+) -> Result<String, BoxError> {
+    let response = client
+        .get(format!(
+            "{}{}?{}",
+            API,
+            path,
+            signed(query, &credentials.secret)
+        ))
+        .header("X-MBX-APIKEY", &credentials.key)
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<ApiError>(&body)
+            .map(|error| error.msg)
+            .unwrap_or(body);
+        return Err(format!("binance {}: {}", status, message).into());
+    }
+    Ok(body)
+}
+
+pub async fn account(client: &Client, credentials: &Credentials) -> Result<Account, BoxError> {
+    let account: AccountResponse = serde_json::from_str(
+        &get(
+            client,
+            credentials,
+            "/api/v3/account",
+            "omitZeroBalances=true&",
+        )
+        .await?,
+    )?;
+    let orders: Vec<serde_json::Value> =
+        serde_json::from_str(&get(client, credentials, "/api/v3/openOrders", "").await?)?;
+    Ok(Account {
+        balances: account
+            .balances
+            .into_iter()
+            .map(Balance::try_from)
+            .collect::<Result<_, _>>()?,
+        open_orders: orders.len(),
+    })
+}
@@ -0,0 +1,161 @@
+//! Kraken spot account, requests signed with HMAC-SHA512 of path and
+//! SHA-256 of nonce and body, keyed with base64 decoded secret
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use sha2::{Digest, Sha256, Sha512};
+
+use super::{Account, Balance, Credentials};
+use crate::BoxError;
+
+const API: &str = "https://api.kraken.com";
+
+/// last nonce sent, kraken rejects nonce not greater than previous one
+static NONCE: AtomicI64 = AtomicI64::new(0);
+
+/// Every response is wrapped in errors and result
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    error: Vec<String>,
+    result: Option<T>,
+}
+
+/// Entry of `/0/private/BalanceEx`, amounts are decimal strings
+#[derive(Debug, Deserialize)]
+struct ExtendedBalance {
+    balance: String,
+    /// held in open orders
+    #[serde(default)]
+    hold_trade: Option<String>,
+}
+
+/// Result of `/0/private/OpenOrders`, keyed by transaction id
+#[derive(Debug, Deserialize)]
+struct OpenOrders {
+    open: HashMap<String, serde_json::Value>,
+}
+
+/// Common name of kraken asset, legacy codes are prefixed with X or Z, ex.
+/// `XXBT` is `BTC` and `ZUSD` is `USD`
+fn asset_name(asset: &str) -> String {
+    let asset = match asset {
+        "XXBT" | "XBT" => "BTC",
+        "XXDG" | "XDG" => "DOGE",
+        "XETH" | "XLTC" | "XXRP" | "XXLM" | "XXMR" | "XZEC" | "XETC" | "XMLN" | "XREP" => {
+            &asset[1..]
+        }
+        "ZUSD" | "ZEUR" | "ZGBP" | "ZJPY" | "ZCAD" | "ZAUD" | "ZCHF" => &asset[1..],
+        other => other,
+    };
+    asset.to_string()
+}
+
+/// increasing nonce, milliseconds unless requests come within same one
+fn nonce() -> i64 {
+    let now = Utc::now().timestamp_millis();
+    let previous = NONCE
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap_or_default();
+    now.max(previous + 1)
+}
+
+/// `API-Sign` header of request
+fn signature(path: &str, nonce: i64, body: &str, secret: &[u8]) -> String {
+    let mut mac = Hmac::<Sha512>::new_from_slice(secret).expect("hmac takes key of any size");
+    mac.update(path.as_bytes());
+    mac.update(&Sha256::digest(format!("{}{}", nonce, body)));
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// Signed POST of private `path`
+async fn post<T: DeserializeOwned>(
+    client: &Client,
+    credentials: &Credentials,
+    path: &str,
+) -> Result<T, BoxError> {
+    let secret = STANDARD.decode(credentials.secret.trim())?;
+    let nonce = nonce();
+    let body = format!("nonce={}", nonce);
+    let response: Response<T> = client
+        .post(format!("{}{}", API, path))
+        .header("API-Key", &credentials.key)
+        .header("API-Sign", signature(path, nonce, &body, &secret))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if !response.error.is_empty() {
+        return Err(format!("kraken: {}", response.error.join(", ")).into());
+    }
+    response
+        .result
+        .ok_or_else(|| "no result in kraken response".into())
+}
+
+fn to_balances(balances: HashMap<String, ExtendedBalance>) -> Result<Vec<Balance>, BoxError> {
+    balances
+        .into_iter()
+        .map(|(asset, balance)| {
+            let total: f64 = balance.balance.parse()?;
+            let locked: f64 = match balance.hold_trade {
+                Some(hold) => hold.parse()?,
+                None => 0.0,
+            };
+            Ok(Balance {
+                asset: asset_name(&asset),
+                free: total - locked,
+                locked,
+            })
+        })
+        .collect()
+}
+
+pub async fn account(client: &Client, credentials: &Credentials) -> Result<Account, BoxError> {
+    let balances = post(client, credentials, "/0/private/BalanceEx").await?;
+    let orders: OpenOrders = post(client, credentials, "/0/private/OpenOrders").await?;
+    Ok(Account {
+        balances: to_balances(balances)?,
+        open_orders: orders.open.len(),
+    })
+}
+
+#[test]
+fn testing_kraken_signature() {
+    // example of kraken api docs
+    let secret = STANDARD
+        .decode("kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==")
+        .unwrap();
+    assert_eq!(
+        signature(
+            "/0/private/AddOrder",
+            1616492376594,
+            "nonce=1616492376594&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25",
+            &secret
+        ),
+        "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ=="
+    );
+
+    let balances: Response<HashMap<String, ExtendedBalance>> = serde_json::from_str(
+        r#"{"error":[],"result":{"XXBT":{"balance":"0.5213000000","hold_trade":"0.1000000000"},
+            "ZUSD":{"balance":"1520.0000","hold_trade":"0.0000"},"DOT":{"balance":"12.5"}}}"#,
+    )
+    .unwrap();
+    let mut balances = to_balances(balances.result.unwrap()).unwrap();
+    balances.sort_by(|a, b| a.asset.cmp(&b.asset));
+    let assets: Vec<&str> = balances.iter().map(|b| b.asset.as_str()).collect();
+    assert_eq!(assets, ["BTC", "DOT", "USD"]);
+    assert_eq!(balances[0].locked, 0.1);
+}
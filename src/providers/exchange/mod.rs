@@ -0,0 +1,261 @@
+//! Spot balances and open orders of Binance or Kraken account, ex.
+//! `BNC 2 orders` with `BTC 0.5213` per asset
+//!
+//! Requests are signed with api key and secret read from keyring of host,
+//! entries `<account>.key` and `<account>.secret` of [`keyring::SERVICE`],
+//! so they never are in config. Read-only key is enough, balances and orders
+//! are only queried.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use super::{DataProvider, Line};
+use crate::{keyring, BoxError, EloraError};
+
+mod binance;
+mod kraken;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Exchange {
+    #[default]
+    Binance,
+    Kraken,
+}
+
+impl Exchange {
+    fn name(self) -> &'static str {
+        match self {
+            Exchange::Binance => "binance",
+            Exchange::Kraken => "kraken",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Exchange::Binance => "BNC",
+            Exchange::Kraken => "KRKN",
+        }
+    }
+}
+
+/// `[exchange]` config section
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExchangeConfig {
+    pub exchange: Exchange,
+    /// keyring account prefix of key and secret entries, exchange name
+    /// without it
+    pub account: Option<String>,
+    /// assets drawn in this order, all held assets by name without it
+    pub assets: Vec<String>,
+    /// `BNC` or `KRKN` without it
+    pub label: Option<String>,
+}
+
+impl ExchangeConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.account.as_ref().is_some_and(|a| a.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(
+                "exchange.account can't be empty".into(),
+            ));
+        }
+        if let Some(asset) = self.assets.iter().find(|a| a.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "exchange asset {:?} can't be empty",
+                asset
+            )));
+        }
+        Ok(())
+    }
+
+    fn account(&self) -> &str {
+        self.account
+            .as_deref()
+            .unwrap_or_else(|| self.exchange.name())
+    }
+
+    fn label(&self) -> &str {
+        self.label
+            .as_deref()
+            .unwrap_or_else(|| self.exchange.label())
+    }
+}
+
+/// Api key and secret of account
+#[derive(Debug, Clone)]
+struct Credentials {
+    key: String,
+    secret: String,
+}
+
+/// Spot balance of single asset
+#[derive(Debug, Clone, PartialEq)]
+struct Balance {
+    /// ex. `BTC`
+    asset: String,
+    free: f64,
+    /// held in open orders
+    locked: f64,
+}
+
+impl Balance {
+    fn total(&self) -> f64 {
+        self.free + self.locked
+    }
+}
+
+/// Balances and open order count of account
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Account {
+    balances: Vec<Balance>,
+    open_orders: usize,
+}
+
+/// Balance amount fitted to display, more decimals for small amounts
+fn amount(value: f64) -> String {
+    match value.abs() {
+        v if v >= 1000.0 => format!("{:.0}", value),
+        v if v >= 1.0 => format!("{:.2}", value),
+        _ => format!("{:.4}", value),
+    }
+}
+
+/// Lines of account, open orders first and line per asset, ex. `BTC 0.5213`.
+/// Assets are in `assets` order when listed, all non-zero by name otherwise
+fn to_lines(label: &str, assets: &[String], account: &Account) -> Vec<Line> {
+    let orders = account.open_orders;
+    let mut lines = vec![Line::new(format!(
+        "{} {} order{}",
+        label,
+        orders,
+        if orders == 1 { "" } else { "s" }
+    ))
+    .with_field("label", label)
+    .with_field("orders", orders as f64)
+    .with_metric(label, orders as f64)];
+
+    let mut balances = account
+        .balances
+        .iter()
+        .filter(|balance| balance.total() > 0.0)
+        .collect::<Vec<_>>();
+    if assets.is_empty() {
+        balances.sort_by(|a, b| a.asset.cmp(&b.asset));
+    } else {
+        balances = assets
+            .iter()
+            .filter_map(|asset| {
+                balances
+                    .iter()
+                    .find(|balance| balance.asset.eq_ignore_ascii_case(asset))
+                    .copied()
+            })
+            .collect();
+    }
+    lines.extend(balances.into_iter().map(|balance| {
+        Line::new(format!("{} {}", balance.asset, amount(balance.total())))
+            .with_field("asset", balance.asset.as_str())
+            .with_field("total", balance.total())
+            .with_field("free", balance.free)
+            .with_field("locked", balance.locked)
+    }));
+    lines
+}
+
+/// Account of configured exchange
+pub struct ExchangeProvider {
+    config: ExchangeConfig,
+    /// read from keyring on first fetch
+    credentials: Mutex<Option<Credentials>>,
+    client: Client,
+}
+
+impl ExchangeProvider {
+    pub fn new(config: ExchangeConfig) -> Self {
+        ExchangeProvider {
+            config,
+            credentials: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        let mut credentials = self.credentials.lock().await;
+        if let Some(credentials) = credentials.as_ref() {
+            return Ok(credentials.clone());
+        }
+        let account = self.config.account();
+        let read = Credentials {
+            key: keyring::secret(&format!("{}.key", account)).await?,
+            secret: keyring::secret(&format!("{}.secret", account)).await?,
+        };
+        *credentials = Some(read.clone());
+        Ok(read)
+    }
+}
+
+#[async_trait]
+impl DataProvider for ExchangeProvider {
+    fn name(&self) -> &str {
+        "exchange"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(300))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let credentials = self.credentials().await?;
+        log::info!("Fetching {} account", self.config.exchange.name());
+
+        let account = match self.config.exchange {
+            Exchange::Binance => binance::account(&self.client, &credentials).await?,
+            Exchange::Kraken => kraken::account(&self.client, &credentials).await?,
+        };
+        Ok(to_lines(self.config.label(), &self.config.assets, &account))
+    }
+}
+
+#[test]
+fn testing_exchange_lines() {
+    let config = ExchangeConfig::default();
+    config.validate().unwrap();
+    assert_eq!(config.account(), "binance");
+    assert_eq!(config.label(), "BNC");
+
+    let account = Account {
+        balances: vec![
+            Balance {
+                asset: "USDT".into(),
+                free: 1200.5,
+                locked: 320.0,
+            },
+            Balance {
+                asset: "BTC".into(),
+                free: 0.5213,
+                locked: 0.0,
+            },
+            Balance {
+                asset: "ETH".into(),
+                free: 0.0,
+                locked: 0.0,
+            },
+        ],
+        open_orders: 2,
+    };
+    let lines: Vec<String> = to_lines("BNC", &[], &account)
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["BNC 2 orders", "BTC 0.5213", "USDT 1520"]);
+    let lines: Vec<String> = to_lines("BNC", &["usdt".into()], &account)
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["BNC 2 orders", "USDT 1520"]);
+}
@@ -0,0 +1,136 @@
+//! Lines printed by user's command, so anything scriptable (todo count, vpn
+//! status) can be shown without writing provider
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[exec]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecConfig {
+    /// program and its arguments, run without shell, ex.
+    /// `["sh", "-c", "todo.sh ls | wc -l"]`
+    pub command: Vec<String>,
+    /// command is killed and fetch fails when it runs longer
+    pub timeout_secs: u64,
+    /// stdout lines shown, rest is dropped
+    pub max_lines: usize,
+}
+
+impl Default for ExecConfig {
+    fn default() -> Self {
+        ExecConfig {
+            command: Vec::new(),
+            timeout_secs: 10,
+            max_lines: 4,
+        }
+    }
+}
+
+impl ExecConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self
+            .command
+            .first()
+            .is_none_or(|program| program.is_empty())
+        {
+            return Err(EloraError::ConfigInvalid(
+                "exec.command needs program to run".into(),
+            ));
+        }
+        if self.timeout_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "exec.timeout_secs must be greater than 0".into(),
+            ));
+        }
+        if self.max_lines == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "exec.max_lines must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// non empty lines of command output, trailing whitespace trimmed
+fn to_lines(stdout: &str, max_lines: usize) -> Vec<Line> {
+    stdout
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .take(max_lines)
+        .map(|line| Line::new(line).with_field("line", line))
+        .collect()
+}
+
+pub struct ExecProvider {
+    config: ExecConfig,
+}
+
+impl ExecProvider {
+    pub fn new(config: ExecConfig) -> Self {
+        ExecProvider { config }
+    }
+}
+
+#[async_trait]
+impl DataProvider for ExecProvider {
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let (program, args) = self.config.command.split_first().ok_or("no command")?;
+        let output = Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            // dropped on timeout, which must not leave command running
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), output)
+            .await
+            .map_err(|_| format!("{} timed out after {}s", program, self.config.timeout_secs))?
+            .map_err(|e| format!("can't run {}: {}", program, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "{} failed with {}: {}",
+                program,
+                output.status,
+                stderr.trim()
+            )
+            .into());
+        }
+        Ok(to_lines(
+            &String::from_utf8_lossy(&output.stdout),
+            self.config.max_lines,
+        ))
+    }
+}
+
+#[test]
+fn testing_exec_lines() {
+    let lines: Vec<String> = to_lines("TODO 3\n\nVPN up  \nthird\nfourth\nfifth\n", 4)
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["TODO 3", "VPN up", "third", "fourth"]);
+
+    assert!(ExecConfig::default().validate().is_err());
+    assert!(ExecConfig {
+        command: vec!["echo".into(), "hi".into()],
+        ..ExecConfig::default()
+    }
+    .validate()
+    .is_ok());
+}
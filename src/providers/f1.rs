@@ -0,0 +1,312 @@
+//! Countdown to next Formula 1 session from OpenF1, ex. `F1 Monza Race 2d 5h`,
+//! and leader while race runs, ex. `F1 Monza Race LIVE VER`
+//!
+//! Hours until session carry metric under label, so `[alerts]` rule like
+//! `F1 < 1` reminds of it hour before.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Utc};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// season calendar changes rarely, it's downloaded again after this
+const SESSIONS_TTL: Duration = Duration::from_secs(6 * 3600);
+
+/// `[f1]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct F1Config {
+    /// OpenF1 api base url
+    pub url: String,
+    /// session names counted down to, ex. `Race` or `Qualifying`, all when
+    /// empty
+    pub sessions: Vec<String>,
+    pub label: String,
+}
+
+impl Default for F1Config {
+    fn default() -> Self {
+        F1Config {
+            url: "https://api.openf1.org/v1".into(),
+            sessions: Vec::new(),
+            label: "F1".into(),
+        }
+    }
+}
+
+impl F1Config {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "f1.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        Ok(())
+    }
+
+    fn wanted(&self, session: &Session) -> bool {
+        self.sessions.is_empty()
+            || self
+                .sessions
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&session.session_name))
+    }
+}
+
+/// Element of `/sessions` response, only fields we use
+#[derive(Debug, Clone, Deserialize)]
+struct Session {
+    session_key: u64,
+    /// ex. `Practice 1`, `Sprint`, `Qualifying` or `Race`
+    session_name: String,
+    /// `Race` for sprint too
+    session_type: String,
+    circuit_short_name: String,
+    date_start: DateTime<Utc>,
+    date_end: DateTime<Utc>,
+}
+
+/// Element of `/position` response
+#[derive(Debug, Deserialize)]
+struct Position {
+    date: DateTime<Utc>,
+    driver_number: u32,
+}
+
+/// Element of `/drivers` response
+#[derive(Debug, Deserialize)]
+struct Driver {
+    /// ex. `VER`
+    name_acronym: String,
+}
+
+/// time until session in two largest units, ex. `2d 5h`, `5h 12m` or `12m`
+fn until(secs: i64) -> String {
+    match secs {
+        86400.. => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+        3600.. => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        60.. => format!("{}m", secs / 60),
+        _ => "<1m".into(),
+    }
+}
+
+/// Session running at `now`, or else next one to start
+fn current_or_next<'a>(
+    config: &F1Config,
+    sessions: &'a [Session],
+    now: DateTime<Utc>,
+) -> Option<&'a Session> {
+    let wanted = || sessions.iter().filter(|session| config.wanted(session));
+    wanted()
+        .find(|session| session.date_start <= now && now < session.date_end)
+        .or_else(|| {
+            wanted()
+                .filter(|session| session.date_start > now)
+                .min_by_key(|session| session.date_start)
+        })
+}
+
+/// Formats session into line, ex. `F1 Monza Race 2d 5h` before it starts or
+/// `F1 Monza Race LIVE VER` with leader while it runs
+fn to_line(config: &F1Config, session: &Session, leader: Option<&str>, now: DateTime<Utc>) -> Line {
+    let prefix = format!(
+        "{} {} {}",
+        config.label, session.circuit_short_name, session.session_name
+    );
+    let line = Line::new("")
+        .with_field("label", config.label.as_str())
+        .with_field("circuit", session.circuit_short_name.as_str())
+        .with_field("session", session.session_name.as_str());
+    if session.date_start <= now {
+        let leader = leader.unwrap_or_default();
+        let mut line = line
+            .with_field("live", 1.0)
+            .with_field("leader", leader)
+            .with_metric(&config.label, 0.0);
+        line.text = format!("{} LIVE {}", prefix, leader).trim_end().to_string();
+        return line;
+    }
+    let secs = (session.date_start - now).num_seconds();
+    let left = until(secs);
+    let mut line = line
+        .with_field("live", 0.0)
+        .with_field("left", left.as_str())
+        .with_metric(&config.label, secs as f64 / 3600.0);
+    line.text = format!("{} {}", prefix, left);
+    line
+}
+
+/// Next Formula 1 session of configured ones
+pub struct F1Provider {
+    config: F1Config,
+    /// sessions of this and next season with time they were downloaded
+    sessions: Mutex<Option<(Instant, Vec<Session>)>>,
+    client: Client,
+}
+
+impl F1Provider {
+    pub fn new(config: F1Config) -> Self {
+        F1Provider {
+            config,
+            sessions: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, BoxError> {
+        let url = format!("{}/{}", self.config.url.trim_end_matches('/'), endpoint);
+        Ok(self
+            .client
+            .get(url)
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// season sessions, cached for [`SESSIONS_TTL`]. Next season is added
+    /// once this one has no sessions left
+    async fn sessions(&self, now: DateTime<Utc>) -> Result<Vec<Session>, BoxError> {
+        if let Some((fetched, sessions)) = &*self.sessions.lock().unwrap() {
+            if fetched.elapsed() < SESSIONS_TTL && sessions.iter().any(|s| s.date_end > now) {
+                return Ok(sessions.clone());
+            }
+        }
+        let mut sessions: Vec<Session> = self
+            .get("sessions", &[("year", now.year().to_string())])
+            .await?;
+        if !sessions.iter().any(|session| session.date_end > now) {
+            let next: Vec<Session> = self
+                .get("sessions", &[("year", (now.year() + 1).to_string())])
+                .await?;
+            sessions.extend(next);
+        }
+        *self.sessions.lock().unwrap() = Some((Instant::now(), sessions.clone()));
+        Ok(sessions)
+    }
+
+    /// acronym of driver in first place, latest position change wins
+    async fn leader(&self, session: &Session) -> Result<Option<String>, BoxError> {
+        let key = session.session_key.to_string();
+        let positions: Vec<Position> = self
+            .get(
+                "position",
+                &[("session_key", key.clone()), ("position", "1".into())],
+            )
+            .await?;
+        let Some(leader) = positions.iter().max_by_key(|position| position.date) else {
+            return Ok(None);
+        };
+        let drivers: Vec<Driver> = self
+            .get(
+                "drivers",
+                &[
+                    ("session_key", key),
+                    ("driver_number", leader.driver_number.to_string()),
+                ],
+            )
+            .await?;
+        Ok(Some(match drivers.into_iter().next() {
+            Some(driver) => driver.name_acronym,
+            None => leader.driver_number.to_string(),
+        }))
+    }
+}
+
+#[async_trait]
+impl DataProvider for F1Provider {
+    fn name(&self) -> &str {
+        "f1"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching f1 sessions");
+
+        let now = Utc::now();
+        let sessions = self.sessions(now).await?;
+        let Some(session) = current_or_next(&self.config, &sessions, now) else {
+            return Ok(vec![Line::new(format!(
+                "{} no sessions",
+                self.config.label
+            ))]);
+        };
+        let mut leader = None;
+        if session.date_start <= now && session.session_type == "Race" {
+            leader = self.leader(session).await.unwrap_or_else(|e| {
+                log::error!("Unable to fetch f1 race leader: {}", e);
+                None
+            });
+        }
+        Ok(vec![to_line(&self.config, session, leader.as_deref(), now)])
+    }
+}
+
+#[test]
+fn testing_f1_lines() {
+    let sessions: Vec<Session> = serde_json::from_str(
+        r#"[
+        {"session_key":9930,"session_name":"Qualifying","session_type":"Qualifying",
+         "circuit_short_name":"Monza","country_name":"Italy",
+         "date_start":"2026-09-05T14:00:00+00:00","date_end":"2026-09-05T15:00:00+00:00"},
+        {"session_key":9934,"session_name":"Race","session_type":"Race",
+         "circuit_short_name":"Monza","country_name":"Italy",
+         "date_start":"2026-09-06T13:00:00+00:00","date_end":"2026-09-06T15:00:00+00:00"}
+    ]"#,
+    )
+    .unwrap();
+    let config = F1Config::default();
+    let at = |time: &str| time.parse::<DateTime<Utc>>().unwrap();
+
+    let now = at("2026-09-04T07:48:00Z");
+    let next = current_or_next(&config, &sessions, now).unwrap();
+    let line = to_line(&config, next, None, now);
+    assert_eq!(line.text, "F1 Monza Qualifying 1d 6h");
+
+    let races = F1Config {
+        sessions: vec!["race".into()],
+        ..F1Config::default()
+    };
+    let next = current_or_next(&races, &sessions, now).unwrap();
+    assert_eq!(next.session_key, 9934);
+
+    let now = at("2026-09-06T14:10:00Z");
+    let live = current_or_next(&config, &sessions, now).unwrap();
+    let line = to_line(&config, live, Some("VER"), now);
+    assert_eq!(line.text, "F1 Monza Race LIVE VER");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+    assert!(current_or_next(&config, &sessions, at("2026-09-07T00:00:00Z")).is_none());
+
+    let positions: Vec<Position> = serde_json::from_str(
+        r#"[{"date":"2026-09-06T13:05:00+00:00","driver_number":16,"position":1,"session_key":9934},
+            {"date":"2026-09-06T13:40:00+00:00","driver_number":1,"position":1,"session_key":9934}]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        positions
+            .iter()
+            .max_by_key(|p| p.date)
+            .unwrap()
+            .driver_number,
+        1
+    );
+    assert_eq!(until(5 * 3600 + 12 * 60), "5h 12m");
+}
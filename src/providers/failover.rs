@@ -0,0 +1,135 @@
+//! Provider serving data from first of several sources which works
+//!
+//! Sources are tried in order on every fetch, so primary source is used again
+//! as soon as it recovers. Lines get `source` field with label of source
+//! which served them, usable in `[templates]`.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+pub struct FailoverProvider {
+    name: String,
+    /// label of source and provider fetching from it, in order of preference
+    sources: Vec<(String, Box<dyn DataProvider>)>,
+    /// label of source which served last successful fetch
+    source: Mutex<Option<String>>,
+}
+
+impl FailoverProvider {
+    /// Provider called `name` trying `sources` in given order, fails without
+    /// any
+    pub fn new(
+        name: impl Into<String>,
+        sources: Vec<(String, Box<dyn DataProvider>)>,
+    ) -> Result<Self, EloraError> {
+        let name = name.into();
+        if sources.is_empty() {
+            return Err(EloraError::ConfigInvalid(format!(
+                "{} failover needs at least one source",
+                name
+            )));
+        }
+        Ok(FailoverProvider {
+            name,
+            sources,
+            source: Mutex::new(None),
+        })
+    }
+
+    /// label of source which served last fetch, `None` before first one
+    pub fn source(&self) -> Option<String> {
+        self.source.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl DataProvider for FailoverProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        self.sources[0].1.refresh_interval()
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let mut error = None;
+        for (label, provider) in &self.sources {
+            match provider.fetch().await {
+                Ok(lines) => {
+                    let mut source = self.source.lock().unwrap();
+                    if source.as_ref() != Some(label) {
+                        log::info!("Serving {} from {}", self.name, label);
+                        *source = Some(label.clone());
+                    }
+                    return Ok(lines
+                        .into_iter()
+                        .map(|line| line.with_field("source", label.as_str()))
+                        .collect());
+                }
+                Err(e) => {
+                    log::warn!("{} source {} failed: {}", self.name, label, e);
+                    error = Some(e);
+                }
+            }
+        }
+        Err(error.unwrap_or_else(|| "no sources to fetch from".into()))
+    }
+
+    async fn changed(&self) {
+        self.sources[0].1.changed().await
+    }
+}
+
+#[cfg(test)]
+struct Source(Option<&'static str>);
+
+#[cfg(test)]
+#[async_trait]
+impl DataProvider for Source {
+    fn name(&self) -> &str {
+        "source"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        match self.0 {
+            Some(text) => Ok(vec![Line::new(text)]),
+            None => Err("rate limited".into()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn testing_failover() {
+    let failover = FailoverProvider::new(
+        "stocks",
+        vec![
+            (
+                "yahoo".into(),
+                Box::new(Source(None)) as Box<dyn DataProvider>,
+            ),
+            ("stooq".into(), Box::new(Source(Some("TSLA: 219$")))),
+        ],
+    )
+    .unwrap();
+    assert_eq!(failover.source(), None);
+    let lines = failover.fetch().await.unwrap();
+    assert_eq!(lines[0].text, "TSLA: 219$");
+    assert!(lines[0].field("source").is_some());
+    assert_eq!(failover.source().as_deref(), Some("stooq"));
+
+    let failing = FailoverProvider::new(
+        "stocks",
+        vec![(
+            "yahoo".into(),
+            Box::new(Source(None)) as Box<dyn DataProvider>,
+        )],
+    )
+    .unwrap();
+    assert!(failing.fetch().await.is_err());
+    assert!(FailoverProvider::new("stocks", Vec::new()).is_err());
+}
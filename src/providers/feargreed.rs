@@ -0,0 +1,242 @@
+//! Market sentiment of CNN Fear & Greed index and crypto Fear & Greed index
+//! of alternative.me in one line, ex. `F&G 62 greed ₿54 x-fear`
+//!
+//! Both are 0 to 100, extreme fear to extreme greed. CNN index is from its
+//! dataviz endpoint, which rejects requests without browser user agent.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{stocks, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+const CNN_URL: &str = "https://production.dataviz.cnn.io/index/fearandgreed/graphdata";
+const CRYPTO_URL: &str = "https://api.alternative.me/fng/?limit=1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Index {
+    /// CNN index of US stock market
+    Stocks,
+    /// alternative.me index of bitcoin and crypto market
+    Crypto,
+}
+
+/// `[feargreed]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FearGreedConfig {
+    /// indices in order they are drawn
+    pub indices: Vec<Index>,
+    pub label: String,
+}
+
+impl Default for FearGreedConfig {
+    fn default() -> Self {
+        FearGreedConfig {
+            indices: vec![Index::Stocks, Index::Crypto],
+            label: "F&G".into(),
+        }
+    }
+}
+
+impl FearGreedConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.indices.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "feargreed.indices needs at least one of stocks or crypto".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Score of single index with its rating, ex. `extreme fear`
+#[derive(Debug, Clone, PartialEq)]
+struct Sentiment {
+    index: Index,
+    score: f64,
+    rating: String,
+}
+
+/// Response of CNN `graphdata`, only fields we use
+#[derive(Debug, Deserialize)]
+struct CnnResponse {
+    fear_and_greed: CnnIndex,
+}
+
+#[derive(Debug, Deserialize)]
+struct CnnIndex {
+    score: f64,
+    /// ex. `extreme greed`
+    rating: String,
+}
+
+/// Response of alternative.me `/fng/`
+#[derive(Debug, Deserialize)]
+struct CryptoResponse {
+    data: Vec<CryptoIndex>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CryptoIndex {
+    /// number as string, ex. `"54"`
+    value: String,
+    /// ex. `Extreme Fear`
+    value_classification: String,
+}
+
+impl From<CnnResponse> for Sentiment {
+    fn from(response: CnnResponse) -> Self {
+        Sentiment {
+            index: Index::Stocks,
+            score: response.fear_and_greed.score,
+            rating: response.fear_and_greed.rating.to_lowercase(),
+        }
+    }
+}
+
+impl TryFrom<CryptoResponse> for Sentiment {
+    type Error = BoxError;
+
+    fn try_from(response: CryptoResponse) -> Result<Self, Self::Error> {
+        let latest = response
+            .data
+            .into_iter()
+            .next()
+            .ok_or("no value in crypto fear and greed response")?;
+        Ok(Sentiment {
+            index: Index::Crypto,
+            score: latest.value.parse()?,
+            rating: latest.value_classification.to_lowercase(),
+        })
+    }
+}
+
+/// Short rating for display, `extreme fear` is `x-fear`
+fn short_rating(rating: &str) -> String {
+    match rating.strip_prefix("extreme ") {
+        Some(rest) => format!("x-{}", rest),
+        None => rating.to_string(),
+    }
+}
+
+/// Formats sentiments into line, ex. `F&G 62 greed ₿54 x-fear`. Metric is
+/// score of first index
+fn to_line(label: &str, sentiments: &[Sentiment]) -> Line {
+    let mut text = label.to_string();
+    let mut line = Line::new("").with_field("label", label);
+    for sentiment in sentiments {
+        let (prefix, name) = match sentiment.index {
+            Index::Stocks => ("", "stocks"),
+            Index::Crypto => ("₿", "crypto"),
+        };
+        text.push_str(&format!(
+            " {}{:.0} {}",
+            prefix,
+            sentiment.score,
+            short_rating(&sentiment.rating)
+        ));
+        line = line
+            .with_field(name, sentiment.score)
+            .with_field(format!("{}_rating", name), sentiment.rating.as_str());
+    }
+    line.text = text;
+    match sentiments.first() {
+        Some(first) => line.with_metric(label, first.score),
+        None => line,
+    }
+}
+
+/// Fear & Greed indices in one line
+pub struct FearGreedProvider {
+    config: FearGreedConfig,
+    client: Client,
+}
+
+impl FearGreedProvider {
+    pub fn new(config: FearGreedConfig) -> Result<Self, BoxError> {
+        Ok(FearGreedProvider {
+            config,
+            client: stocks::client()?,
+        })
+    }
+
+    async fn sentiment(&self, index: Index) -> Result<Sentiment, BoxError> {
+        match index {
+            Index::Stocks => {
+                let response: CnnResponse = self
+                    .client
+                    .get(CNN_URL)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(response.into())
+            }
+            Index::Crypto => {
+                let response: CryptoResponse = self
+                    .client
+                    .get(CRYPTO_URL)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                response.try_into()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for FearGreedProvider {
+    fn name(&self) -> &str {
+        "feargreed"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(900))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching fear and greed of {:?}", self.config.indices);
+
+        let sentiments = try_join_all(
+            self.config
+                .indices
+                .iter()
+                .map(|index| self.sentiment(*index)),
+        )
+        .await?;
+        Ok(vec![to_line(&self.config.label, &sentiments)])
+    }
+}
+
+#[test]
+fn testing_fear_greed_line() {
+    FearGreedConfig::default().validate().unwrap();
+
+    let cnn: CnnResponse = serde_json::from_str(
+        r#"{"fear_and_greed":{"score":62.3428,"rating":"greed","timestamp":"2024-10-16T20:59:59+00:00",
+            "previous_close":60.1,"previous_1_week":55.2},"fear_and_greed_historical":{"data":[]}}"#,
+    )
+    .unwrap();
+    let crypto: CryptoResponse = serde_json::from_str(
+        r#"{"name":"Fear and Greed Index","data":[{"value":"21","value_classification":"Extreme Fear",
+            "timestamp":"1729036800","time_until_update":"40166"}],"metadata":{"error":null}}"#,
+    )
+    .unwrap();
+    let sentiments = [cnn.into(), crypto.try_into().unwrap()];
+    let line = to_line("F&G", &sentiments);
+    assert_eq!(line.text, "F&G 62 greed ₿21 x-fear");
+    assert_eq!(line.metric.unwrap().value, 62.3428);
+
+    let empty = CryptoResponse { data: Vec::new() };
+    assert!(Sentiment::try_from(empty).is_err());
+}
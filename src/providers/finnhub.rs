@@ -0,0 +1,238 @@
+//! Stock quotes and company news from finnhub.io
+//!
+//! Needs free api key, given as `token` in `[finnhub]` config (or
+//! `quotes.finnhub_token` for fallback) or `FINNHUB_TOKEN` env. Free plan
+//! covers US exchanges only, with 60 calls a minute: every ticker takes one
+//! call for quote and one for news per fetch.
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    stocks::{self, Quote},
+    DataProvider, Line,
+};
+use crate::{BoxError, EloraError};
+
+/// env variable used when token is not in config
+pub const TOKEN_ENV: &str = "FINNHUB_TOKEN";
+
+/// how far back company news are looked up
+const NEWS_DAYS: i64 = 7;
+
+/// `[finnhub]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FinnhubConfig {
+    pub token: Option<String>,
+    /// US stock symbols, ex. `["AAPL", "MSFT"]`
+    pub tickers: Vec<String>,
+    /// latest headlines shown per ticker, 0 turns news off
+    pub news: usize,
+}
+
+impl Default for FinnhubConfig {
+    fn default() -> Self {
+        FinnhubConfig {
+            token: None,
+            tickers: Vec::new(),
+            news: 1,
+        }
+    }
+}
+
+impl FinnhubConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.tickers.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "finnhub.tickers needs at least one ticker".into(),
+            ));
+        }
+        if let Some(ticker) = self.tickers.iter().find(|t| t.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "finnhub ticker {:?} can't be empty",
+                ticker
+            )));
+        }
+        Ok(())
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        token(self.token.as_deref())
+            .ok_or_else(|| format!("finnhub.token or {} env is required", TOKEN_ENV).into())
+    }
+}
+
+/// token from config, falling back to [`TOKEN_ENV`]
+pub fn token(configured: Option<&str>) -> Option<String> {
+    configured
+        .map(str::to_string)
+        .or_else(|| std::env::var(TOKEN_ENV).ok())
+}
+
+/// Response of `/api/v1/quote`, only fields we use
+#[derive(Debug, Deserialize)]
+struct QuoteResponse {
+    /// current price
+    c: f64,
+    /// previous close
+    pc: Option<f64>,
+}
+
+/// Item of `/api/v1/company-news` response, only fields we use
+#[derive(Debug, Deserialize)]
+struct News {
+    /// unix timestamp
+    datetime: i64,
+    headline: String,
+    source: String,
+}
+
+/// Unknown symbols aren't error, they come back with zero price
+fn parse_quote(body: &str) -> Result<Quote, BoxError> {
+    let response: QuoteResponse = serde_json::from_str(body)?;
+    if response.c == 0.0 {
+        return Err("no price in finnhub response, symbol may be unknown".into());
+    }
+    Ok(Quote {
+        price: response.c,
+        previous_close: response.pc.filter(|close| *close != 0.0),
+        currency: None,
+        extended: None,
+        day_range: None,
+        year_range: None,
+    })
+}
+
+pub async fn fetch_quote(client: &Client, token: &str, ticker: &str) -> Result<Quote, BoxError> {
+    let body = client
+        .get("https://finnhub.io/api/v1/quote")
+        .query(&[("symbol", ticker), ("token", token)])
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_quote(&body)
+}
+
+/// Latest `count` headlines of company, newest first
+async fn fetch_news(
+    client: &Client,
+    token: &str,
+    ticker: &str,
+    count: usize,
+) -> Result<Vec<News>, BoxError> {
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(NEWS_DAYS);
+    let mut news: Vec<News> = client
+        .get("https://finnhub.io/api/v1/company-news")
+        .query(&[
+            ("symbol", ticker),
+            ("from", &from.to_string()),
+            ("to", &to.to_string()),
+            ("token", token),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    news.sort_by_key(|news| std::cmp::Reverse(news.datetime));
+    news.truncate(count);
+    Ok(news)
+}
+
+/// Headline line, ex. `AAPL: Apple unveils new chip`
+fn news_line(ticker: &str, news: &News) -> Line {
+    Line::new(format!("{}: {}", ticker, news.headline))
+        .with_field("symbol", ticker)
+        .with_field("headline", news.headline.as_str())
+        .with_field("source", news.source.as_str())
+}
+
+/// Finnhub quotes with latest news of each ticker
+pub struct FinnhubProvider {
+    config: FinnhubConfig,
+    client: Client,
+}
+
+impl FinnhubProvider {
+    pub fn new(config: FinnhubConfig) -> Self {
+        FinnhubProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// quote line followed by its headlines, news which fail are only logged
+    async fn ticker_lines(&self, token: &str, ticker: &str) -> Result<Vec<Line>, BoxError> {
+        let quote = fetch_quote(&self.client, token, ticker).await?;
+        let mut lines = vec![stocks::quote_line(ticker, &quote)];
+        if self.config.news > 0 {
+            match fetch_news(&self.client, token, ticker, self.config.news).await {
+                Ok(news) => lines.extend(news.iter().map(|news| news_line(ticker, news))),
+                Err(e) => log::warn!("Unable to fetch news of {}: {}", ticker, e),
+            }
+        }
+        Ok(lines)
+    }
+}
+
+#[async_trait]
+impl DataProvider for FinnhubProvider {
+    fn name(&self) -> &str {
+        "finnhub"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching finnhub quotes from remote");
+
+        let token = self.config.token()?;
+        let fetched = join_all(
+            self.config
+                .tickers
+                .iter()
+                .map(|ticker| self.ticker_lines(&token, ticker)),
+        )
+        .await;
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for (ticker, ticker_lines) in self.config.tickers.iter().zip(fetched) {
+            match ticker_lines {
+                Ok(mut ticker_lines) => lines.append(&mut ticker_lines),
+                Err(e) => {
+                    log::error!("Unable to fetch {} from finnhub: {}", ticker, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.config.tickers.len() {
+            return Err("no ticker could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_finnhub_quote() {
+    let body = r#"{"c":218.89,"d":-8.33,"dp":-3.6661,"h":225.34,"l":217.15,"o":220.08,"pc":227.22,"t":1705093200}"#;
+    let quote = parse_quote(body).unwrap();
+    assert_eq!(quote.price, 218.89);
+    assert_eq!(quote.previous_close, Some(227.22));
+
+    let body = r#"{"c":0,"d":null,"dp":null,"h":0,"l":0,"o":0,"pc":0,"t":0}"#;
+    assert!(parse_quote(body).is_err());
+
+    let news: Vec<News> = serde_json::from_str(
+        r#"[{"category":"company","datetime":1705093200,"headline":"Apple unveils new chip","id":1,"image":"","related":"AAPL","source":"Reuters","summary":"","url":""}]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        news_line("AAPL", &news[0]).text,
+        "AAPL: Apple unveils new chip"
+    );
+}
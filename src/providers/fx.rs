@@ -0,0 +1,221 @@
+//! Currency exchange rates from ECB daily reference rates
+//!
+//! Rates are shared with stocks provider, which converts prices into
+//! display currency, and drawn as lines for configured pairs, ex.
+//! `EUR/USD 1.0942`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+const ECB_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+/// ECB publishes rates once a day, no point in asking more often
+const RATES_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// `[fx]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FxConfig {
+    /// currency all stock prices are converted to, ex. `USD`. Without it
+    /// prices stay in currency they are traded in
+    pub display_currency: Option<String>,
+    /// currency per ticker, for tickers yahoo doesn't report currency of
+    /// or reports wrong one, ex. `{ "VWRL.AS" = "EUR" }`
+    pub currencies: BTreeMap<String, String>,
+    /// pairs drawn as lines, ex. `["EUR/USD"]`
+    pub pairs: Vec<String>,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        FxConfig {
+            display_currency: None,
+            currencies: BTreeMap::new(),
+            pairs: vec!["EUR/USD".into()],
+        }
+    }
+}
+
+fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+impl FxConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let codes = self
+            .display_currency
+            .iter()
+            .chain(self.currencies.values())
+            .map(String::as_str);
+        if let Some(code) = codes.clone().find(|code| !is_currency_code(code)) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "fx currency {:?} is not 3 letter code",
+                code
+            )));
+        }
+        if let Some(pair) = self.pairs.iter().find(|pair| parse_pair(pair).is_none()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "fx pair {:?} is not in `EUR/USD` form",
+                pair
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn parse_pair(pair: &str) -> Option<(String, String)> {
+    let (from, to) = pair.split_once('/')?;
+    let (from, to) = (from.trim(), to.trim());
+    if !is_currency_code(from) || !is_currency_code(to) {
+        return None;
+    }
+    Some((from.to_uppercase(), to.to_uppercase()))
+}
+
+/// Rates of currencies against euro, as ECB publishes them
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rates {
+    /// currency code -> units of currency for one euro
+    per_euro: HashMap<String, f64>,
+}
+
+impl Rates {
+    fn per_euro(&self, currency: &str) -> Option<f64> {
+        let currency = currency.to_uppercase();
+        if currency == "EUR" {
+            return Some(1.0);
+        }
+        self.per_euro.get(&currency).copied()
+    }
+
+    /// rate to multiply amount in `from` currency by to get it in `to`
+    pub fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        Some(self.per_euro(to)? / self.per_euro(from)?)
+    }
+
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        Some(amount * self.rate(from, to)?)
+    }
+}
+
+/// Extracts rates from ECB `eurofxref-daily.xml`
+fn parse_rates(xml: &str) -> Result<Rates, BoxError> {
+    let cube = Regex::new(r"currency='([A-Z]{3})'\s+rate='([0-9.]+)'")?;
+    let per_euro: HashMap<String, f64> = cube
+        .captures_iter(xml)
+        .filter_map(|caps| Some((caps[1].to_string(), caps[2].parse().ok()?)))
+        .collect();
+    if per_euro.is_empty() {
+        return Err("no rates in ECB response".into());
+    }
+    Ok(Rates { per_euro })
+}
+
+/// Rates cached for an hour, shared between providers needing them
+pub struct FxRates {
+    client: Client,
+    cached: Mutex<Option<(Instant, Rates)>>,
+}
+
+impl FxRates {
+    pub fn new() -> Self {
+        FxRates {
+            client: Client::new(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// cached rates, refetched when older than an hour
+    pub async fn get(&self) -> Result<Rates, BoxError> {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, rates)) = cached.as_ref() {
+            if fetched_at.elapsed() < RATES_TTL {
+                return Ok(rates.clone());
+            }
+        }
+
+        log::info!("Fetching exchange rates from remote");
+        let xml = self
+            .client
+            .get(ECB_URL)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let rates = parse_rates(&xml)?;
+        *cached = Some((Instant::now(), rates.clone()));
+        Ok(rates)
+    }
+}
+
+impl Default for FxRates {
+    fn default() -> Self {
+        FxRates::new()
+    }
+}
+
+/// Exchange rates of configured pairs
+pub struct FxProvider {
+    pairs: Vec<(String, String)>,
+    rates: Arc<FxRates>,
+}
+
+impl FxProvider {
+    pub fn new(config: &FxConfig, rates: Arc<FxRates>) -> Self {
+        FxProvider {
+            pairs: config.pairs.iter().filter_map(|p| parse_pair(p)).collect(),
+            rates,
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for FxProvider {
+    fn name(&self) -> &str {
+        "fx"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let rates = self.rates.get().await?;
+        self.pairs
+            .iter()
+            .map(|(from, to)| {
+                let rate = rates
+                    .rate(from, to)
+                    .ok_or_else(|| format!("no ECB rate for {}/{}", from, to))?;
+                let pair = format!("{}/{}", from, to);
+                Ok(Line::new(format!("{} {:.4}", pair, rate))
+                    .with_metric(&pair, rate)
+                    .with_field("pair", pair)
+                    .with_field("rate", rate))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn testing_rates_parsing_and_conversion() {
+    let xml = r#"<gesmes:Envelope><Cube><Cube time='2024-01-12'>
+        <Cube currency='USD' rate='1.0942'/>
+        <Cube currency='GBP' rate='0.85950'/>
+    </Cube></Cube></gesmes:Envelope>"#;
+    let rates = parse_rates(xml).unwrap();
+    assert_eq!(rates.rate("EUR", "USD"), Some(1.0942));
+    assert!((rates.convert(100.0, "USD", "EUR").unwrap() - 91.3910).abs() < 0.0001);
+    assert!((rates.rate("GBP", "USD").unwrap() - 1.2731).abs() < 0.0001);
+    assert_eq!(rates.rate("EUR", "XYZ"), None);
+    assert!(parse_rates("<html></html>").is_err());
+}
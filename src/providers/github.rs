@@ -0,0 +1,213 @@
+//! Unread GitHub notifications
+//!
+//! Polls `api.github.com/notifications` with personal access token (classic
+//! with `notifications` scope, or fine-grained one). Requests are conditional
+//! on `Last-Modified`, so polls without new notifications don't count against
+//! rate limit.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header, Client, StatusCode};
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env variable used when `token` is not in config
+pub const TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// notifications on single page, GitHub caps it at 50
+const PER_PAGE: usize = 50;
+
+/// `[github]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    pub token: Option<String>,
+    /// only notifications you're directly participating in or mentioned on
+    pub participating: bool,
+    /// shown in front of counts
+    pub label: String,
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        GitHubConfig {
+            token: None,
+            participating: false,
+            label: "GH".into(),
+        }
+    }
+}
+
+impl GitHubConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.label.trim().is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "github.label can't be empty".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("github.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+}
+
+/// Notification thread of `/notifications` response, only fields we use
+#[derive(Debug, Deserialize)]
+struct Notification {
+    reason: String,
+    subject: Subject,
+    repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+struct Subject {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    name: String,
+}
+
+fn is_mention(reason: &str) -> bool {
+    matches!(reason, "mention" | "team_mention")
+}
+
+/// Counts line, ex. `GH 5 new 2rev 1@`, and latest review request or mention
+/// when there is one, ex. `elora_hid: Fix scroll`. Notifications come newest
+/// first
+fn to_lines(config: &GitHubConfig, notifications: &[Notification]) -> Vec<Line> {
+    let reviews = notifications
+        .iter()
+        .filter(|n| n.reason == "review_requested")
+        .count();
+    let mentions = notifications
+        .iter()
+        .filter(|n| is_mention(&n.reason))
+        .count();
+    let unread = if notifications.len() >= PER_PAGE {
+        format!("{}+", PER_PAGE)
+    } else {
+        notifications.len().to_string()
+    };
+
+    let mut lines = vec![Line::new(format!(
+        "{} {} new {}rev {}@",
+        config.label, unread, reviews, mentions
+    ))
+    .with_metric(&config.label, notifications.len() as f64)
+    .with_field("label", config.label.as_str())
+    .with_field("unread", notifications.len() as f64)
+    .with_field("reviews", reviews as f64)
+    .with_field("mentions", mentions as f64)];
+
+    let latest = notifications
+        .iter()
+        .find(|n| n.reason == "review_requested" || is_mention(&n.reason));
+    if let Some(latest) = latest {
+        lines.push(
+            Line::new(format!(
+                "{}: {}",
+                latest.repository.name, latest.subject.title
+            ))
+            .with_field("repo", latest.repository.name.as_str())
+            .with_field("title", latest.subject.title.as_str())
+            .with_field("reason", latest.reason.as_str()),
+        );
+    }
+    lines
+}
+
+/// GitHub notifications of token owner
+pub struct GitHubProvider {
+    config: GitHubConfig,
+    client: Client,
+    /// `Last-Modified` of previous response with lines made from it
+    last: Mutex<Option<(String, Vec<Line>)>>,
+}
+
+impl GitHubProvider {
+    pub fn new(config: GitHubConfig) -> Self {
+        GitHubProvider {
+            config,
+            client: Client::new(),
+            last: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for GitHubProvider {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    /// GitHub asks clients not to poll notifications more often than minute
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching github notifications from remote");
+
+        let mut request = self
+            .client
+            .get("https://api.github.com/notifications")
+            .bearer_auth(self.config.token()?)
+            .header(header::USER_AGENT, "elora_hid")
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .query(&[
+                ("participating", self.config.participating.to_string()),
+                ("per_page", PER_PAGE.to_string()),
+            ]);
+        let last_modified = self.last.lock().unwrap().as_ref().map(|(m, _)| m.clone());
+        if let Some(last_modified) = last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, lines)) = self.last.lock().unwrap().as_ref() {
+                return Ok(lines.clone());
+            }
+        }
+        let response = response.error_for_status()?;
+        let modified = response
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let notifications: Vec<Notification> = response.json().await?;
+
+        let lines = to_lines(&self.config, &notifications);
+        *self.last.lock().unwrap() = modified.map(|modified| (modified, lines.clone()));
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_github_lines() {
+    let notifications: Vec<Notification> = serde_json::from_str(
+        r#"[
+            {"reason":"subscribed","unread":true,"subject":{"title":"Release 0.2","type":"Release"},"repository":{"name":"qmk_firmware","full_name":"qmk/qmk_firmware"}},
+            {"reason":"review_requested","unread":true,"subject":{"title":"Fix scroll","type":"PullRequest"},"repository":{"name":"elora_hid","full_name":"dzhibas/elora_hid"}},
+            {"reason":"mention","unread":true,"subject":{"title":"Breaks on rev2","type":"Issue"},"repository":{"name":"elora_hid","full_name":"dzhibas/elora_hid"}}
+        ]"#,
+    )
+    .unwrap();
+    let config = GitHubConfig::default();
+
+    let lines = to_lines(&config, &notifications);
+    assert_eq!(lines[0].text, "GH 3 new 1rev 1@");
+    assert_eq!(lines[1].text, "elora_hid: Fix scroll");
+    assert_eq!(to_lines(&config, &notifications[..1]).len(), 1);
+}
@@ -0,0 +1,247 @@
+//! Latest GitLab CI pipeline of watched projects
+//!
+//! Each project is shown like `[ci]` repos, ex. `elora_hid ✔ main`, with
+//! metric which is 1 for passed and 0 for failed pipeline, so failure raises
+//! alert on keyboard.
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// env var token is read from when config has none
+pub const TOKEN_ENV: &str = "GITLAB_TOKEN";
+
+/// `[gitlab]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitLabConfig {
+    /// gitlab.com or own instance
+    pub url: String,
+    /// personal access token with `read_api` scope, needed for private
+    /// projects. Falls back to `GITLAB_TOKEN` env
+    pub token: Option<String>,
+    /// `group/project`, or `group/project:branch` to watch single branch
+    pub projects: Vec<String>,
+    /// alert keyboard when pipeline fails
+    pub alert: bool,
+}
+
+impl Default for GitLabConfig {
+    fn default() -> Self {
+        GitLabConfig {
+            url: "https://gitlab.com".into(),
+            token: None,
+            projects: Vec::new(),
+            alert: true,
+        }
+    }
+}
+
+/// Watched project parsed from `group/project[:branch]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// full path with subgroups, ex. `group/subgroup/project`
+    pub path: String,
+    /// last part of path, shown on display
+    pub name: String,
+    pub branch: Option<String>,
+}
+
+impl Project {
+    pub fn parse(project: &str) -> Result<Project, EloraError> {
+        let (path, branch) = match project.split_once(':') {
+            Some((path, branch)) => (path, Some(branch.to_string())),
+            None => (project, None),
+        };
+        match path.rsplit_once('/') {
+            Some((group, name))
+                if !group.split('/').any(str::is_empty)
+                    && !name.is_empty()
+                    && branch.as_ref().is_none_or(|b| !b.is_empty()) =>
+            {
+                Ok(Project {
+                    path: path.to_string(),
+                    name: name.to_string(),
+                    branch,
+                })
+            }
+            _ => Err(EloraError::ConfigInvalid(format!(
+                "gitlab project {:?} is not in group/project or group/project:branch form",
+                project
+            ))),
+        }
+    }
+}
+
+impl GitLabConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "gitlab.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.projects.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "gitlab.projects needs at least one project".into(),
+            ));
+        }
+        self.parse_projects().map(|_| ())
+    }
+
+    pub fn parse_projects(&self) -> Result<Vec<Project>, EloraError> {
+        self.projects.iter().map(|p| Project::parse(p)).collect()
+    }
+
+    /// Alert rule per project, firing when its pipeline fails. Empty when
+    /// `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        self.parse_projects()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|project| Rule {
+                text: Some(format!("{} CI failed", project.name)),
+                symbol: project.name,
+                provider: Some("gitlab".into()),
+                direction: Direction::Below,
+                threshold: 1.0,
+                window: None,
+                urgent: false,
+                notify: true,
+            })
+            .collect()
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.clone().or_else(|| std::env::var(TOKEN_ENV).ok())
+    }
+}
+
+/// Element of `/projects/:id/pipelines` response, only fields we use
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    #[serde(rename = "ref")]
+    branch: Option<String>,
+    /// created, pending, running, success, failed, canceled, skipped, ...
+    status: String,
+}
+
+/// Formats pipeline into line, ex. `elora_hid ✔ main`. Finished pipelines
+/// carry 1 or 0 metric for passed or failed one
+fn to_line(project: &Project, pipeline: &Pipeline) -> Line {
+    let (icon, passed) = match pipeline.status.as_str() {
+        "success" => ('✔', Some(true)),
+        "failed" => ('✘', Some(false)),
+        "canceled" | "skipped" | "manual" => ('-', None),
+        _ => ('…', None),
+    };
+    let branch = pipeline.branch.as_deref().unwrap_or_default();
+    let line = Line::new(format!("{} {} {}", project.name, icon, branch).trim_end())
+        .with_field("project", project.name.as_str())
+        .with_field("status", icon.to_string())
+        .with_field("branch", branch);
+    match passed {
+        Some(passed) => line.with_metric(&project.name, if passed { 1.0 } else { 0.0 }),
+        None => line,
+    }
+}
+
+/// GitLab CI status of configured projects
+pub struct GitLabProvider {
+    config: GitLabConfig,
+    projects: Vec<Project>,
+    client: Client,
+}
+
+impl GitLabProvider {
+    pub fn new(config: GitLabConfig) -> Result<Self, BoxError> {
+        Ok(GitLabProvider {
+            projects: config.parse_projects()?,
+            config,
+            client: Client::new(),
+        })
+    }
+
+    async fn latest_pipeline(&self, project: &Project) -> Result<Option<Pipeline>, BoxError> {
+        let url = format!(
+            "{}/api/v4/projects/{}/pipelines",
+            self.config.url.trim_end_matches('/'),
+            project.path.replace('/', "%2F")
+        );
+        let mut query = vec![("per_page", "1".to_string())];
+        if let Some(branch) = &project.branch {
+            query.push(("ref", branch.clone()));
+        }
+        let mut request = self.client.get(url).query(&query);
+        if let Some(token) = self.config.token() {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+
+        let pipelines: Vec<Pipeline> = request.send().await?.error_for_status()?.json().await?;
+        Ok(pipelines.into_iter().next())
+    }
+}
+
+#[async_trait]
+impl DataProvider for GitLabProvider {
+    fn name(&self) -> &str {
+        "gitlab"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching gitlab pipelines from {}", self.config.url);
+
+        let pipelines = join_all(self.projects.iter().map(|p| self.latest_pipeline(p))).await;
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for (project, pipeline) in self.projects.iter().zip(pipelines) {
+            match pipeline {
+                Ok(Some(pipeline)) => lines.push(to_line(project, &pipeline)),
+                Ok(None) => log::debug!("No pipelines of {}", project.path),
+                Err(e) => {
+                    log::error!("Unable to fetch pipelines of {}: {}", project.path, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.projects.len() {
+            return Err("no project pipelines could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_gitlab_lines() {
+    let project = Project::parse("dzhibas/tools/elora_hid:main").unwrap();
+    assert_eq!(project.name, "elora_hid");
+    assert_eq!(project.branch.as_deref(), Some("main"));
+    assert!(Project::parse("elora_hid").is_err());
+    assert!(Project::parse("dzhibas//elora_hid").is_err());
+    assert!(Project::parse("dzhibas/elora_hid:").is_err());
+
+    let pipelines: Vec<Pipeline> = serde_json::from_str(
+        r#"[{"id":47,"iid":12,"project_id":1,"status":"failed","source":"push","ref":"main","sha":"a91957a8"}]"#,
+    )
+    .unwrap();
+    let line = to_line(&project, &pipelines[0]);
+    assert_eq!(line.text, "elora_hid ✘ main");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+
+    let running = Pipeline {
+        branch: Some("main".into()),
+        status: "running".into(),
+    };
+    assert_eq!(to_line(&project, &running).text, "elora_hid … main");
+    assert_eq!(to_line(&project, &running).metric, None);
+}
@@ -0,0 +1,268 @@
+//! Utilization, VRAM and temperature of GPUs, ex. `GPU 87% 6.1/10G 71C`
+//!
+//! NVIDIA cards are read through NVML library which ships with driver. ROCm
+//! itself isn't used, AMD cards are read on linux from amdgpu sysfs counters
+//! `rocm-smi` reads too. Line of GPU at or above `warn_temp` is highlighted
+//! and utilization carries metric under label.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Nvml};
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// PCI vendor id of AMD in sysfs
+#[cfg(target_os = "linux")]
+const AMD_VENDOR: &str = "0x1002";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vendor {
+    /// nvidia first, amd when NVML isn't available
+    #[default]
+    Auto,
+    Nvidia,
+    Amd,
+}
+
+/// `[gpu]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpuConfig {
+    pub vendor: Vendor,
+    /// GPU at or above this °C is highlighted
+    pub warn_temp: f64,
+    /// label of single GPU, more are numbered, ex. `GPU0` and `GPU1`
+    pub label: String,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        GpuConfig {
+            vendor: Vendor::default(),
+            warn_temp: 85.0,
+            label: "GPU".into(),
+        }
+    }
+}
+
+impl GpuConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.warn_temp <= 0.0 {
+            return Err(EloraError::ConfigInvalid(
+                "gpu.warn_temp must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Stats of single GPU, VRAM in MiB
+#[derive(Debug, Clone, PartialEq)]
+struct Gpu {
+    name: String,
+    utilization: f64,
+    vram_used: f64,
+    vram_total: f64,
+    temp: Option<f64>,
+}
+
+/// NVIDIA cards through `nvml`, VRAM bytes of it in MiB
+fn nvidia(nvml: &Nvml) -> Result<Vec<Gpu>, BoxError> {
+    let mebibytes = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+    (0..nvml.device_count()?)
+        .map(|index| {
+            let device = nvml.device_by_index(index)?;
+            let memory = device.memory_info()?;
+            Ok(Gpu {
+                name: device.name()?,
+                utilization: device.utilization_rates()?.gpu.into(),
+                vram_used: mebibytes(memory.used),
+                vram_total: mebibytes(memory.total),
+                // not every card has sensor
+                temp: device
+                    .temperature(TemperatureSensor::Gpu)
+                    .ok()
+                    .map(f64::from),
+            })
+        })
+        .collect()
+}
+
+/// AMD cards from `/sys/class/drm/card*/device`, busy percent and VRAM
+/// bytes of amdgpu driver, temperature in m°C of its hwmon
+#[cfg(target_os = "linux")]
+fn amd() -> Result<Vec<Gpu>, BoxError> {
+    use std::{fs, path::Path};
+
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let number = |path: &Path| read(path)?.parse::<f64>().ok();
+    let mut cards = fs::read_dir("/sys/class/drm")?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        // `card0`, not connectors like `card0-DP-1`
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str()?.strip_prefix("card"))
+                .is_some_and(|index| index.chars().all(|ch| ch.is_ascii_digit()))
+        })
+        .map(|card| card.join("device"))
+        .filter(|device| read(&device.join("vendor")).as_deref() == Some(AMD_VENDOR))
+        .collect::<Vec<_>>();
+    cards.sort();
+    let mebibytes = |bytes: f64| bytes / 1024.0 / 1024.0;
+    Ok(cards
+        .into_iter()
+        .filter_map(|device| {
+            let utilization = number(&device.join("gpu_busy_percent"))?;
+            let temp = fs::read_dir(device.join("hwmon"))
+                .ok()?
+                .filter_map(|entry| number(&entry.ok()?.path().join("temp1_input")))
+                .next();
+            Some(Gpu {
+                name: read(&device.join("product_name")).unwrap_or_else(|| "AMD".into()),
+                utilization,
+                vram_used: mebibytes(
+                    number(&device.join("mem_info_vram_used")).unwrap_or_default(),
+                ),
+                vram_total: mebibytes(
+                    number(&device.join("mem_info_vram_total")).unwrap_or_default(),
+                ),
+                temp: temp.map(|millis| millis / 1000.0),
+            })
+        })
+        .collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn amd() -> Result<Vec<Gpu>, BoxError> {
+    Err("amd gpus are supported on linux only".into())
+}
+
+/// Formats GPU into line, ex. `GPU 87% 6.1/10G 71C`
+fn to_line(config: &GpuConfig, label: &str, gpu: &Gpu) -> Line {
+    let gigabytes = |mebibytes: f64| mebibytes / 1024.0;
+    let mut text = format!(
+        "{} {:.0}% {:.1}/{:.0}G",
+        label,
+        gpu.utilization,
+        gigabytes(gpu.vram_used),
+        gigabytes(gpu.vram_total)
+    );
+    let mut line = Line::new("")
+        .with_field("label", label)
+        .with_field("name", gpu.name.as_str())
+        .with_field("utilization", gpu.utilization)
+        .with_field("vram_used", gigabytes(gpu.vram_used))
+        .with_field("vram_total", gigabytes(gpu.vram_total));
+    if let Some(temp) = gpu.temp {
+        text.push_str(&format!(" {:.0}C", temp));
+        line = line
+            .with_field("temp", temp)
+            .with_highlight(temp >= config.warn_temp);
+    }
+    line.text = text;
+    line.with_metric(label, gpu.utilization)
+}
+
+/// Local GPU stats, no network involved
+pub struct GpuProvider {
+    config: GpuConfig,
+    /// loaded on first nvidia fetch, failed loading is retried next fetch
+    nvml: Mutex<Option<Nvml>>,
+}
+
+impl GpuProvider {
+    pub fn new(config: GpuConfig) -> Self {
+        GpuProvider {
+            config,
+            nvml: Mutex::new(None),
+        }
+    }
+
+    fn nvidia(&self) -> Result<Vec<Gpu>, BoxError> {
+        let mut nvml = self.nvml.lock().unwrap();
+        let nvml = match nvml.as_mut() {
+            Some(nvml) => nvml,
+            None => nvml.insert(Nvml::init()?),
+        };
+        nvidia(nvml)
+    }
+}
+
+#[async_trait]
+impl DataProvider for GpuProvider {
+    fn name(&self) -> &str {
+        "gpu"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(2))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let gpus = match self.config.vendor {
+            Vendor::Nvidia => self.nvidia()?,
+            Vendor::Amd => amd()?,
+            Vendor::Auto => match self.nvidia() {
+                Ok(gpus) => gpus,
+                Err(e) => {
+                    log::debug!("No nvidia gpu: {}", e);
+                    amd()?
+                }
+            },
+        };
+        if gpus.is_empty() {
+            return Err("no gpu found".into());
+        }
+        let numbered = gpus.len() > 1;
+        Ok(gpus
+            .iter()
+            .enumerate()
+            .map(|(index, gpu)| {
+                let label = if numbered {
+                    format!("{}{}", self.config.label, index)
+                } else {
+                    self.config.label.clone()
+                };
+                to_line(&self.config, &label, gpu)
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn testing_gpu_lines() {
+    let gpus = [
+        Gpu {
+            name: "NVIDIA GeForce RTX 3080".into(),
+            utilization: 87.0,
+            vram_used: 6246.0,
+            vram_total: 10240.0,
+            temp: Some(71.0),
+        },
+        Gpu {
+            name: "NVIDIA GeForce GT 710".into(),
+            utilization: 3.0,
+            vram_used: 120.0,
+            vram_total: 2048.0,
+            temp: None,
+        },
+    ];
+
+    let config = GpuConfig::default();
+    let line = to_line(&config, "GPU", &gpus[0]);
+    assert_eq!(line.text, "GPU 87% 6.1/10G 71C");
+    assert_eq!(line.metric.unwrap().value, 87.0);
+    assert!(!line.highlight);
+    let line = to_line(&config, "GPU1", &gpus[1]);
+    assert_eq!(line.text, "GPU1 3% 0.1/2G");
+
+    let hot = Gpu {
+        temp: Some(91.0),
+        ..gpus[0].clone()
+    };
+    assert!(to_line(&config, "GPU", &hot).highlight);
+}
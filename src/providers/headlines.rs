@@ -0,0 +1,283 @@
+//! Headlines of RSS or Atom feed, or Hacker News top stories
+//!
+//! Titles are downloaded every `fetch_secs` and cycled through `shown` at a
+//! time every `rotate_secs`, so headlines page lists all of them in turn.
+//! Titles longer than display are best read with `[scroll]` on.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{xml, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+const HN_API: &str = "https://hacker-news.firebaseio.com/v0";
+
+/// `[headlines]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeadlinesConfig {
+    /// RSS or Atom feed, Hacker News top stories without it
+    pub url: Option<String>,
+    /// titles pulled from feed
+    pub count: usize,
+    /// titles on display at once
+    pub shown: usize,
+    /// next titles are shown after this long
+    pub rotate_secs: u64,
+    /// feed is downloaded again after this long
+    pub fetch_secs: u64,
+    /// drawn in front of every title, ex. `HN`
+    pub prefix: Option<String>,
+}
+
+impl Default for HeadlinesConfig {
+    fn default() -> Self {
+        HeadlinesConfig {
+            url: None,
+            count: 10,
+            shown: 1,
+            rotate_secs: 15,
+            fetch_secs: 900,
+            prefix: None,
+        }
+    }
+}
+
+impl HeadlinesConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if let Some(url) = &self.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "headlines.url {:?} is not http(s) url",
+                    url
+                )));
+            }
+        }
+        if self.count == 0 || self.shown == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "headlines.count and headlines.shown must be greater than 0".into(),
+            ));
+        }
+        if self.rotate_secs == 0 || self.fetch_secs == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "headlines.rotate_secs and headlines.fetch_secs must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Single headline with Hacker News score when it has one
+#[derive(Debug, Clone, PartialEq)]
+struct Headline {
+    title: String,
+    score: Option<u64>,
+}
+
+/// Titles of RSS `<item>`s or Atom `<entry>`s
+fn parse_feed(feed: &str) -> Result<Vec<Headline>, BoxError> {
+    let doc = xml::parse(feed)?;
+    let mut items: Vec<_> = xml::descendants(doc.root_element(), "item").collect();
+    if items.is_empty() {
+        items = xml::descendants(doc.root_element(), "entry").collect();
+    }
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let title = xml::child_text(item, "title")?;
+            // titles may span lines in feed
+            let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!title.is_empty()).then_some(Headline { title, score: None })
+        })
+        .collect())
+}
+
+/// Response of Hacker News `/item/<id>.json`, only fields we use
+#[derive(Debug, Deserialize)]
+struct Story {
+    title: Option<String>,
+    score: Option<u64>,
+}
+
+/// `shown` headlines starting at rotation `turn`, wrapping around
+fn to_lines(config: &HeadlinesConfig, headlines: &[Headline], turn: u64) -> Vec<Line> {
+    if headlines.is_empty() {
+        return Vec::new();
+    }
+    let shown = config.shown.min(headlines.len());
+    let start = (turn as usize * shown) % headlines.len();
+    (0..shown)
+        .map(|i| &headlines[(start + i) % headlines.len()])
+        .map(|headline| {
+            let text = match &config.prefix {
+                Some(prefix) => format!("{} {}", prefix, headline.title),
+                None => headline.title.clone(),
+            };
+            let line = Line::new(text).with_field("title", headline.title.as_str());
+            match headline.score {
+                Some(score) => line.with_field("score", score as f64),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+/// Headlines of configured feed
+pub struct HeadlinesProvider {
+    config: HeadlinesConfig,
+    client: Client,
+    /// headlines of last download and when it was done
+    headlines: Mutex<Option<(Instant, Vec<Headline>)>>,
+}
+
+impl HeadlinesProvider {
+    pub fn new(config: HeadlinesConfig) -> Self {
+        HeadlinesProvider {
+            config,
+            client: Client::new(),
+            headlines: Mutex::new(None),
+        }
+    }
+
+    async fn hacker_news(&self) -> Result<Vec<Headline>, BoxError> {
+        let ids: Vec<u64> = self
+            .client
+            .get(format!("{}/topstories.json", HN_API))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let stories = join_all(ids.iter().take(self.config.count).map(|id| async move {
+            let story: Story = self
+                .client
+                .get(format!("{}/item/{}.json", HN_API, id))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok::<_, BoxError>(story)
+        }))
+        .await;
+        let headlines: Vec<Headline> = stories
+            .into_iter()
+            .filter_map(|story| match story {
+                Ok(Story {
+                    title: Some(title),
+                    score,
+                }) => Some(Headline { title, score }),
+                Ok(_) => None,
+                Err(e) => {
+                    log::warn!("Unable to fetch Hacker News story: {}", e);
+                    None
+                }
+            })
+            .collect();
+        if headlines.is_empty() {
+            return Err("no Hacker News story could be fetched".into());
+        }
+        Ok(headlines)
+    }
+
+    async fn download(&self) -> Result<Vec<Headline>, BoxError> {
+        let Some(url) = &self.config.url else {
+            log::info!("Fetching Hacker News top stories");
+            return self.hacker_news().await;
+        };
+        log::info!("Fetching headlines from {}", url);
+        let feed = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let mut headlines = parse_feed(&feed)?;
+        headlines.truncate(self.config.count);
+        Ok(headlines)
+    }
+
+    fn is_fresh(&self) -> bool {
+        let max_age = Duration::from_secs(self.config.fetch_secs);
+        matches!(&*self.headlines.lock().unwrap(), Some((at, _)) if at.elapsed() < max_age)
+    }
+}
+
+#[async_trait]
+impl DataProvider for HeadlinesProvider {
+    fn name(&self) -> &str {
+        "headlines"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.config.rotate_secs))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        if !self.is_fresh() {
+            match self.download().await {
+                Ok(headlines) => {
+                    *self.headlines.lock().unwrap() = Some((Instant::now(), headlines));
+                }
+                Err(e) if self.headlines.lock().unwrap().is_some() => {
+                    log::warn!("Unable to fetch headlines, using last ones: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let turn = chrono::Utc::now().timestamp() as u64 / self.config.rotate_secs;
+        let headlines = self.headlines.lock().unwrap();
+        let headlines = headlines
+            .as_ref()
+            .map(|(_, headlines)| headlines.as_slice());
+        Ok(to_lines(&self.config, headlines.unwrap_or_default(), turn))
+    }
+}
+
+#[test]
+fn testing_headlines_lines() {
+    let rss = r#"<?xml version="1.0"?><rss version="2.0"><channel>
+        <title>Blog</title>
+        <item><title>Rust 2.0 &amp; you</title><link>https://example.com/1</link></item>
+        <item><title><![CDATA[QMK
+            raw hid]]></title></item>
+        <item><title>Third</title></item>
+        </channel></rss>"#;
+    let headlines = parse_feed(rss).unwrap();
+    let titles: Vec<&str> = headlines.iter().map(|h| h.title.as_str()).collect();
+    assert_eq!(titles, ["Rust 2.0 & you", "QMK raw hid", "Third"]);
+
+    let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Feed</title>
+        <entry><title type="text">Atom entry</title><link href="https://example.com"/></entry></feed>"#;
+    assert_eq!(parse_feed(atom).unwrap()[0].title, "Atom entry");
+
+    let config = HeadlinesConfig {
+        shown: 2,
+        prefix: Some("NEWS".into()),
+        ..HeadlinesConfig::default()
+    };
+    config.validate().unwrap();
+    let texts = |turn| -> Vec<String> {
+        to_lines(&config, &headlines, turn)
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    };
+    assert_eq!(texts(0), ["NEWS Rust 2.0 & you", "NEWS QMK raw hid"]);
+    assert_eq!(texts(1), ["NEWS Third", "NEWS Rust 2.0 & you"]);
+
+    let story: Story = serde_json::from_str(
+        r#"{"by":"pg","id":1,"score":57,"title":"Y Combinator","type":"story"}"#,
+    )
+    .unwrap();
+    assert_eq!(story.score, Some(57));
+}
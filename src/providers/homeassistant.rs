@@ -0,0 +1,248 @@
+//! States of Home Assistant entities from its REST api
+//!
+//! Each entity is shown as `LABEL state unit`, ex. `HALL 21.5°C` or
+//! `DOOR locked`. Numeric states carry metric under label, so thresholds in
+//! `[alerts]` work on them same as on prices.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var token is read from when config has none
+pub const TOKEN_ENV: &str = "HASS_TOKEN";
+
+/// `[homeassistant]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HomeAssistantConfig {
+    /// base url of Home Assistant
+    pub url: String,
+    /// long-lived access token from user profile, `HASS_TOKEN` env without it
+    pub token: Option<String>,
+    /// `[LABEL=]entity_id[:attribute]`, ex. `HALL=climate.hall:current_temperature`.
+    /// Label defaults to friendly name of entity
+    pub entities: Vec<String>,
+}
+
+impl Default for HomeAssistantConfig {
+    fn default() -> Self {
+        HomeAssistantConfig {
+            url: "http://homeassistant.local:8123".into(),
+            token: None,
+            entities: Vec::new(),
+        }
+    }
+}
+
+/// Entity shown, parsed from `[LABEL=]entity_id[:attribute]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    pub label: Option<String>,
+    pub id: String,
+    /// attribute shown instead of state
+    pub attribute: Option<String>,
+}
+
+impl Entity {
+    pub fn parse(entity: &str) -> Result<Entity, EloraError> {
+        let (label, rest) = match entity.split_once('=') {
+            Some((label, rest)) => (Some(label.to_string()), rest),
+            None => (None, entity),
+        };
+        let (id, attribute) = match rest.split_once(':') {
+            Some((id, attribute)) => (id, Some(attribute.to_string())),
+            None => (rest, None),
+        };
+        let valid = |part: &str| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        match id.split_once('.') {
+            Some((domain, object))
+                if valid(domain)
+                    && valid(object)
+                    && label.as_ref().is_none_or(|l| !l.is_empty())
+                    && attribute.as_ref().is_none_or(|a| valid(a)) =>
+            {
+                Ok(Entity {
+                    label,
+                    id: id.to_string(),
+                    attribute,
+                })
+            }
+            _ => Err(EloraError::ConfigInvalid(format!(
+                "homeassistant entity {:?} is not in [LABEL=]domain.object[:attribute] form",
+                entity
+            ))),
+        }
+    }
+}
+
+impl HomeAssistantConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "homeassistant.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.entities.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "homeassistant.entities needs at least one entity".into(),
+            ));
+        }
+        self.parse_entities().map(|_| ())
+    }
+
+    pub fn parse_entities(&self) -> Result<Vec<Entity>, EloraError> {
+        self.entities.iter().map(|e| Entity::parse(e)).collect()
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV).map_err(|_| {
+                format!("homeassistant.token or {} env is required", TOKEN_ENV).into()
+            }),
+        }
+    }
+}
+
+/// Response of `/api/states/<entity_id>`, only fields we use
+#[derive(Debug, Deserialize)]
+struct State {
+    state: String,
+    #[serde(default)]
+    attributes: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Formats entity state into line, ex. `HALL 21.5°C`
+fn to_line(entity: &Entity, state: &State) -> Line {
+    let text_attribute = |name: &str| match state.attributes.get(name)? {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    };
+    let label = entity
+        .label
+        .clone()
+        .or_else(|| text_attribute("friendly_name"))
+        .unwrap_or_else(|| entity.id.clone());
+    let (value, unit) = match &entity.attribute {
+        Some(attribute) => (
+            text_attribute(attribute).unwrap_or_else(|| "unknown".into()),
+            String::new(),
+        ),
+        None => (
+            state.state.clone(),
+            text_attribute("unit_of_measurement").unwrap_or_default(),
+        ),
+    };
+    let line = Line::new(format!("{} {}{}", label, value, unit))
+        .with_field("label", label.as_str())
+        .with_field("state", value.as_str())
+        .with_field("unit", unit);
+    match value.parse::<f64>() {
+        Ok(number) => line.with_metric(&label, number),
+        Err(_) => line,
+    }
+}
+
+/// States of configured Home Assistant entities
+pub struct HomeAssistantProvider {
+    config: HomeAssistantConfig,
+    entities: Vec<Entity>,
+    client: Client,
+}
+
+impl HomeAssistantProvider {
+    pub fn new(config: HomeAssistantConfig) -> Result<Self, BoxError> {
+        Ok(HomeAssistantProvider {
+            entities: config.parse_entities()?,
+            config,
+            client: Client::new(),
+        })
+    }
+
+    async fn state(&self, entity: &Entity, token: &str) -> Result<State, BoxError> {
+        let url = format!(
+            "{}/api/states/{}",
+            self.config.url.trim_end_matches('/'),
+            entity.id
+        );
+        let response = self.client.get(url).bearer_auth(token).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("no entity {}", entity.id).into());
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[async_trait]
+impl DataProvider for HomeAssistantProvider {
+    fn name(&self) -> &str {
+        "homeassistant"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching entity states from home assistant");
+
+        let token = self.config.token()?;
+        let states = join_all(self.entities.iter().map(|e| self.state(e, &token))).await;
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for (entity, state) in self.entities.iter().zip(states) {
+            match state {
+                Ok(state) => lines.push(to_line(entity, &state)),
+                Err(e) => {
+                    log::error!("Unable to fetch state of {}: {}", entity.id, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.entities.len() {
+            return Err("no entity state could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_homeassistant_lines() {
+    let hall = Entity::parse("HALL=climate.hall:current_temperature").unwrap();
+    assert_eq!(hall.id, "climate.hall");
+    assert!(Entity::parse("climate").is_err());
+    assert!(Entity::parse("=lock.front_door").is_err());
+    assert!(Entity::parse("lock.front_door:").is_err());
+
+    let climate: State = serde_json::from_str(
+        r#"{"entity_id":"climate.hall","state":"heat","attributes":{"current_temperature":21.5,"friendly_name":"Hall"}}"#,
+    )
+    .unwrap();
+    let line = to_line(&hall, &climate);
+    assert_eq!(line.text, "HALL 21.5");
+    assert_eq!(line.metric.unwrap().value, 21.5);
+
+    let lock: State = serde_json::from_str(
+        r#"{"entity_id":"lock.front_door","state":"locked","attributes":{"friendly_name":"Door"}}"#,
+    )
+    .unwrap();
+    let line = to_line(&Entity::parse("lock.front_door").unwrap(), &lock);
+    assert_eq!(line.text, "Door locked");
+    assert_eq!(line.metric, None);
+
+    let energy: State =
+        serde_json::from_str(r#"{"state":"7.2","attributes":{"unit_of_measurement":"kWh"}}"#)
+            .unwrap();
+    let line = to_line(&Entity::parse("KWH=sensor.energy_today").unwrap(), &energy);
+    assert_eq!(line.text, "KWH 7.2kWh");
+}
@@ -0,0 +1,227 @@
+//! Unread mail counts per IMAP folder
+//!
+//! Every fetch logs in, asks `STATUS <folder> (UNSEEN)` for configured
+//! folders and logs out, so no connection is kept between polls. Gmail
+//! labels are IMAP folders too (`[Gmail]/Important`), log in there with app
+//! password.
+
+use std::{fmt::Debug, time::Duration};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use utf7_imap::encode_utf7_imap;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env variable used when `password` is not in config
+pub const PASSWORD_ENV: &str = "IMAP_PASSWORD";
+
+/// whole session, from connect to logout, must fit in it
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `[imap]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImapConfig {
+    pub host: String,
+    pub port: u16,
+    /// implicit tls, turn off only for local bridges
+    pub tls: bool,
+    pub username: String,
+    pub password: Option<String>,
+    /// `[LABEL=]folder`, ex. `WORK=Work/Alerts`, label defaults to folder
+    pub folders: Vec<String>,
+}
+
+impl Default for ImapConfig {
+    fn default() -> Self {
+        ImapConfig {
+            host: String::new(),
+            port: 993,
+            tls: true,
+            username: String::new(),
+            password: None,
+            folders: vec!["INBOX".into()],
+        }
+    }
+}
+
+impl ImapConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.host.is_empty() || self.username.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "imap needs host and username".into(),
+            ));
+        }
+        if self.folders.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "imap.folders needs at least one folder".into(),
+            ));
+        }
+        if let Some(folder) = self
+            .folders()
+            .find(|(label, folder)| label.is_empty() || folder.is_empty())
+        {
+            return Err(EloraError::ConfigInvalid(format!(
+                "imap folder {:?} is not in [LABEL=]folder form",
+                folder.1
+            )));
+        }
+        Ok(())
+    }
+
+    /// label and name of every configured folder
+    fn folders(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.folders
+            .iter()
+            .map(|folder| folder.split_once('=').unwrap_or((folder, folder)))
+    }
+
+    fn password(&self) -> Result<String, BoxError> {
+        match &self.password {
+            Some(password) => Ok(password.clone()),
+            None => std::env::var(PASSWORD_ENV)
+                .map_err(|_| format!("imap.password or {} env is required", PASSWORD_ENV).into()),
+        }
+    }
+}
+
+/// Logs in over `stream` and counts unseen messages of configured folders
+async fn unread_counts<S>(
+    stream: S,
+    config: &ImapConfig,
+    password: &str,
+) -> Result<Vec<(String, u64)>, BoxError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Debug + Send,
+{
+    let mut client = async_imap::Client::new(stream);
+    client
+        .read_response()
+        .await?
+        .ok_or("imap server closed connection before greeting")?;
+    let mut session = client
+        .login(&config.username, password)
+        .await
+        .map_err(|(e, _)| format!("imap LOGIN failed: {}", e))?;
+    let mut counts = Vec::new();
+    for (label, folder) in config.folders() {
+        // non ascii folder names go over the wire in modified UTF-7
+        let mailbox = session
+            .status(encode_utf7_imap(folder.to_string()), "(UNSEEN)")
+            .await?;
+        let unseen = mailbox
+            .unseen
+            .ok_or_else(|| format!("no unseen count of {} in imap answer", folder))?;
+        counts.push((label.to_string(), unseen as u64));
+    }
+    // counts are in already, failed logout doesn't matter
+    let _ = session.logout().await;
+    Ok(counts)
+}
+
+fn to_line(label: &str, unread: u64) -> Line {
+    Line::new(format!("{} {}", label, unread))
+        .with_field("label", label)
+        .with_field("unread", unread as f64)
+        .with_metric(label, unread as f64)
+}
+
+/// Unread counts of configured folders
+pub struct ImapProvider {
+    config: ImapConfig,
+}
+
+impl ImapProvider {
+    pub fn new(config: ImapConfig) -> Self {
+        ImapProvider { config }
+    }
+
+    async fn counts(&self) -> Result<Vec<(String, u64)>, BoxError> {
+        let password = self.config.password()?;
+        let tcp = TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+        if !self.config.tls {
+            return unread_counts(tcp, &self.config, &password).await;
+        }
+        let connector = tokio_native_tls::TlsConnector::from(
+            tokio_native_tls::native_tls::TlsConnector::new()?,
+        );
+        let tls = connector.connect(&self.config.host, tcp).await?;
+        unread_counts(tls, &self.config, &password).await
+    }
+}
+
+#[async_trait]
+impl DataProvider for ImapProvider {
+    fn name(&self) -> &str {
+        "imap"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(120))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching unread counts from {}", self.config.host);
+
+        let counts = tokio::time::timeout(TIMEOUT, self.counts())
+            .await
+            .map_err(|_| format!("imap server {} timed out", self.config.host))??;
+        Ok(counts
+            .iter()
+            .map(|(label, unread)| to_line(label, *unread))
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn testing_imap_unread_counts() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let config = ImapConfig {
+        host: "imap.example.com".into(),
+        username: "me@example.com".into(),
+        folders: vec!["INBOX".into(), "WORK=Entwürfe".into()],
+        ..ImapConfig::default()
+    };
+    config.validate().unwrap();
+
+    let (client, server) = tokio::io::duplex(4096);
+    let server = tokio::spawn(async move {
+        let mut server = BufReader::new(server);
+        server
+            .get_mut()
+            .write_all(b"* OK IMAP4rev1 ready\r\n")
+            .await
+            .unwrap();
+        let mut requests = Vec::new();
+        let answers: [&[u8]; 4] = [
+            b"A0001 OK LOGIN completed\r\n",
+            b"* STATUS INBOX (UNSEEN 3)\r\nA0002 OK STATUS completed\r\n",
+            b"* STATUS \"Entw&APw-rfe\" (UNSEEN 0)\r\nA0003 OK STATUS completed\r\n",
+            b"* BYE\r\nA0004 OK LOGOUT completed\r\n",
+        ];
+        for answer in answers {
+            let mut request = String::new();
+            server.read_line(&mut request).await.unwrap();
+            requests.push(request);
+            server.get_mut().write_all(answer).await.unwrap();
+        }
+        requests
+    });
+
+    let counts = unread_counts(client, &config, "se\"cret").await.unwrap();
+    assert_eq!(counts, vec![("INBOX".into(), 3), ("WORK".into(), 0)]);
+    let requests = server.await.unwrap();
+    assert_eq!(
+        requests[0],
+        "A0001 LOGIN \"me@example.com\" \"se\\\"cret\"\r\n"
+    );
+    assert_eq!(requests[2], "A0003 STATUS \"Entw&APw-rfe\" (UNSEEN)\r\n");
+    assert_eq!(to_line("INBOX", 3).text, "INBOX 3");
+}
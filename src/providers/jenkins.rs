@@ -0,0 +1,271 @@
+//! Last build of watched Jenkins jobs, ex. `deploy ✔ 4m12s`
+//!
+//! Job line carries metric like `[ci]` repos, 1 for passed and 0 for failed
+//! build, so failure raises alert on keyboard. Running build shows time it
+//! has been running so far.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// env var api token is read from when config has none
+pub const TOKEN_ENV: &str = "JENKINS_TOKEN";
+
+/// `[jenkins]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JenkinsConfig {
+    /// `[LABEL=]url` of job, ex. `https://ci.example.com/job/app/job/deploy/`.
+    /// Label defaults to last job name in url
+    pub jobs: Vec<String>,
+    /// user api token belongs to, anonymous access without it
+    pub username: Option<String>,
+    /// api token from user settings, `JENKINS_TOKEN` env without it
+    pub token: Option<String>,
+    /// alert keyboard when build fails
+    pub alert: bool,
+}
+
+impl Default for JenkinsConfig {
+    fn default() -> Self {
+        JenkinsConfig {
+            jobs: Vec::new(),
+            username: None,
+            token: None,
+            alert: true,
+        }
+    }
+}
+
+/// Watched job parsed from `[LABEL=]url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub label: String,
+    /// job url without trailing slash
+    pub url: String,
+}
+
+impl Job {
+    pub fn parse(job: &str) -> Result<Job, EloraError> {
+        let (label, url) = match job.split_once('=') {
+            Some((label, url)) if !label.contains('/') => (Some(label), url),
+            _ => (None, job),
+        };
+        let url = url.trim_end_matches('/');
+        let label = label.or_else(|| url.rsplit_once("/job/").map(|(_, name)| name));
+        match label {
+            Some(label)
+                if !label.is_empty()
+                    && (url.starts_with("http://") || url.starts_with("https://")) =>
+            {
+                Ok(Job {
+                    label: label.to_string(),
+                    url: url.to_string(),
+                })
+            }
+            _ => Err(EloraError::ConfigInvalid(format!(
+                "jenkins job {:?} is not in [LABEL=]http(s)://host/job/name form",
+                job
+            ))),
+        }
+    }
+}
+
+impl JenkinsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.jobs.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "jenkins.jobs needs at least one job".into(),
+            ));
+        }
+        self.parse_jobs().map(|_| ())
+    }
+
+    pub fn parse_jobs(&self) -> Result<Vec<Job>, EloraError> {
+        self.jobs.iter().map(|job| Job::parse(job)).collect()
+    }
+
+    /// Alert rule per job, firing when its build fails. Empty when `alert`
+    /// is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        self.parse_jobs()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|job| Rule {
+                text: Some(format!("{} build failed", job.label)),
+                symbol: job.label,
+                provider: Some("jenkins".into()),
+                direction: Direction::Below,
+                threshold: 1.0,
+                window: None,
+                urgent: false,
+                notify: true,
+            })
+            .collect()
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.clone().or_else(|| std::env::var(TOKEN_ENV).ok())
+    }
+}
+
+/// Response of `<job>/lastBuild/api/json`, only fields we use
+#[derive(Debug, Deserialize)]
+struct Build {
+    number: u64,
+    building: bool,
+    /// SUCCESS, FAILURE, UNSTABLE, ABORTED, `None` while building
+    result: Option<String>,
+    /// milliseconds, 0 while building
+    duration: u64,
+    /// start as unix milliseconds
+    timestamp: i64,
+}
+
+/// build duration, ex. `42s`, `4m12s` or `1h05m`
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Formats build into line, ex. `deploy ✔ 4m12s`. Finished builds carry 1
+/// or 0 metric for passed or failed one
+fn to_line(job: &Job, build: &Build, now_ms: i64) -> Line {
+    let (icon, passed) = match (build.building, build.result.as_deref()) {
+        (true, _) | (false, None) => ('…', None),
+        (false, Some("SUCCESS")) => ('✔', Some(true)),
+        (false, Some("FAILURE" | "UNSTABLE")) => ('✘', Some(false)),
+        (false, Some(_)) => ('-', None),
+    };
+    let millis = if build.building {
+        (now_ms - build.timestamp).max(0) as u64
+    } else {
+        build.duration
+    };
+    let duration = format_duration(millis / 1000);
+    let line = Line::new(format!("{} {} {}", job.label, icon, duration))
+        .with_field("job", job.label.as_str())
+        .with_field("status", icon.to_string())
+        .with_field("result", build.result.clone().unwrap_or_default())
+        .with_field("duration", duration)
+        .with_field("number", build.number as f64);
+    match passed {
+        Some(passed) => line.with_metric(&job.label, if passed { 1.0 } else { 0.0 }),
+        None => line,
+    }
+}
+
+/// Jenkins build status of configured jobs
+pub struct JenkinsProvider {
+    config: JenkinsConfig,
+    jobs: Vec<Job>,
+    client: Client,
+}
+
+impl JenkinsProvider {
+    pub fn new(config: JenkinsConfig) -> Result<Self, BoxError> {
+        Ok(JenkinsProvider {
+            jobs: config.parse_jobs()?,
+            config,
+            client: Client::new(),
+        })
+    }
+
+    async fn last_build(&self, job: &Job) -> Result<Option<Build>, BoxError> {
+        let mut request = self
+            .client
+            .get(format!("{}/lastBuild/api/json", job.url))
+            .query(&[("tree", "number,building,result,duration,timestamp")]);
+        if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.token());
+        }
+        let response = request.send().await?;
+        // job which never ran has no last build
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+}
+
+#[async_trait]
+impl DataProvider for JenkinsProvider {
+    fn name(&self) -> &str {
+        "jenkins"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching jenkins builds");
+
+        let builds = join_all(self.jobs.iter().map(|job| self.last_build(job))).await;
+        let now_ms = Utc::now().timestamp_millis();
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for (job, build) in self.jobs.iter().zip(builds) {
+            match build {
+                Ok(Some(build)) => lines.push(to_line(job, &build, now_ms)),
+                Ok(None) => log::debug!("No builds of {}", job.url),
+                Err(e) => {
+                    log::error!("Unable to fetch last build of {}: {}", job.url, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.jobs.len() {
+            return Err("no job builds could be fetched".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_jenkins_lines() {
+    let job = Job::parse("https://ci.example.com/job/app/job/deploy/").unwrap();
+    assert_eq!(job.label, "deploy");
+    assert_eq!(job.url, "https://ci.example.com/job/app/job/deploy");
+    let labeled = Job::parse("APP=https://ci.example.com/job/app/job/main").unwrap();
+    assert_eq!(labeled.label, "APP");
+    assert!(Job::parse("deploy").is_err());
+    assert!(Job::parse("=https://ci.example.com/job/deploy").is_err());
+
+    let build: Build = serde_json::from_str(
+        r#"{"_class":"hudson.model.FreeStyleBuild","building":false,"duration":252000,
+            "number":128,"result":"FAILURE","timestamp":1760454000000}"#,
+    )
+    .unwrap();
+    let line = to_line(&job, &build, 0);
+    assert_eq!(line.text, "deploy ✘ 4m12s");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+
+    let running = Build {
+        number: 129,
+        building: true,
+        result: None,
+        duration: 0,
+        timestamp: 1_760_454_000_000,
+    };
+    let line = to_line(&job, &running, 1_760_454_042_000);
+    assert_eq!(line.text, "deploy … 42s");
+    assert_eq!(line.metric, None);
+    assert_eq!(format_duration(3900), "1h05m");
+}
@@ -0,0 +1,199 @@
+//! Issues assigned to me in Jira, ex. `JIRA 7 WEB-412`
+//!
+//! Count is of issues matching `jql` and assigned to current user, key is
+//! of most recently updated one. Count carries metric under
+//! label, so `[alerts]` rule like `JIRA > 10` works.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var api token is read from when config has none
+pub const TOKEN_ENV: &str = "JIRA_API_TOKEN";
+
+/// `[jira]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JiraConfig {
+    /// site url, ex. `https://acme.atlassian.net` or own server
+    pub url: String,
+    /// account email for Jira Cloud api token. Without it token is sent as
+    /// personal access token of Jira Server and Data Center
+    pub email: Option<String>,
+    /// api token, `JIRA_API_TOKEN` env without it
+    pub token: Option<String>,
+    /// filter issues are narrowed to, without `ORDER BY`. Saved filter works
+    /// as `filter = 10042`
+    pub jql: String,
+    pub label: String,
+}
+
+impl Default for JiraConfig {
+    fn default() -> Self {
+        JiraConfig {
+            url: String::new(),
+            email: None,
+            token: None,
+            jql: "resolution = Unresolved".into(),
+            label: "JIRA".into(),
+        }
+    }
+}
+
+impl JiraConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "jira.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.jql.to_lowercase().contains("order by") {
+            return Err(EloraError::ConfigInvalid(
+                "jira.jql can't have ORDER BY, issues are sorted by update".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// filter assigned to me, most recently updated first
+    fn query(&self) -> String {
+        match self.jql.trim() {
+            "" => "assignee = currentUser() ORDER BY updated DESC".into(),
+            jql => format!(
+                "assignee = currentUser() AND ({}) ORDER BY updated DESC",
+                jql
+            ),
+        }
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("jira.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+}
+
+/// Response of `/rest/api/2/search`, only fields we use
+#[derive(Debug, Deserialize)]
+struct Search {
+    total: u64,
+    issues: Vec<Issue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    key: String,
+    fields: IssueFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueFields {
+    #[serde(default)]
+    summary: String,
+}
+
+/// Formats search into line, ex. `JIRA 7 WEB-412`, only count when none
+/// is assigned
+fn to_line(label: &str, search: &Search) -> Line {
+    let latest = search.issues.first();
+    let key = latest.map(|issue| issue.key.as_str()).unwrap_or_default();
+    let summary = latest
+        .map(|issue| issue.fields.summary.as_str())
+        .unwrap_or_default();
+    Line::new(format!("{} {} {}", label, search.total, key).trim_end())
+        .with_field("label", label)
+        .with_field("count", search.total as f64)
+        .with_field("key", key)
+        .with_field("summary", summary)
+        .with_metric(label, search.total as f64)
+}
+
+/// Count of my issues in configured filter
+pub struct JiraProvider {
+    config: JiraConfig,
+    client: Client,
+}
+
+impl JiraProvider {
+    pub fn new(config: JiraConfig) -> Self {
+        JiraProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for JiraProvider {
+    fn name(&self) -> &str {
+        "jira"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(120))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching jira issues from {}", self.config.url);
+
+        let token = self.config.token()?;
+        let url = format!(
+            "{}/rest/api/2/search",
+            self.config.url.trim_end_matches('/')
+        );
+        let request = self.client.get(url).query(&[
+            ("jql", self.config.query().as_str()),
+            ("maxResults", "1"),
+            ("fields", "summary"),
+        ]);
+        let request = match &self.config.email {
+            Some(email) => request.basic_auth(email, Some(token)),
+            None => request.bearer_auth(token),
+        };
+        let search: Search = request.send().await?.error_for_status()?.json().await?;
+        Ok(vec![to_line(&self.config.label, &search)])
+    }
+}
+
+#[test]
+fn testing_jira_line() {
+    let config = JiraConfig {
+        url: "https://acme.atlassian.net".into(),
+        jql: "project = WEB".into(),
+        ..JiraConfig::default()
+    };
+    config.validate().unwrap();
+    assert_eq!(
+        config.query(),
+        "assignee = currentUser() AND (project = WEB) ORDER BY updated DESC"
+    );
+    let ordered = JiraConfig {
+        jql: "project = WEB order by created".into(),
+        ..config.clone()
+    };
+    assert!(ordered.validate().is_err());
+    assert!(JiraConfig::default().validate().is_err());
+
+    let search: Search = serde_json::from_str(
+        r#"{"expand":"schema,names","startAt":0,"maxResults":1,"total":7,
+            "issues":[{"id":"10412","key":"WEB-412","fields":{"summary":"Fix login redirect"}}]}"#,
+    )
+    .unwrap();
+    let line = to_line(&config.label, &search);
+    assert_eq!(line.text, "JIRA 7 WEB-412");
+    assert_eq!(line.metric.unwrap().value, 7.0);
+
+    let none = Search {
+        total: 0,
+        issues: Vec::new(),
+    };
+    assert_eq!(to_line("JIRA", &none).text, "JIRA 0");
+}
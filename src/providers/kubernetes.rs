@@ -0,0 +1,246 @@
+//! Pod health of Kubernetes namespaces, ex. `prod 12/14 pend 1`
+//!
+//! Pods are listed with kube-rs, which reads kubeconfig the same way kubectl
+//! does (current or configured context, client certs, exec auth plugins)
+//! and falls back to service account when elora runs inside cluster.
+//! Not ready count carries metric under namespace, so `[alerts]` rule like
+//! `prod > 0` wakes keyboard when pods go down.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::{Pod, PodStatus};
+use kube::{api::ListParams, config::KubeConfigOptions, Api, Client, Config, ResourceExt};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[kubernetes]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KubernetesConfig {
+    /// kubeconfig context, current one (or in-cluster config) without it
+    pub context: Option<String>,
+    /// namespaces shown, every namespace with pods when empty
+    pub namespaces: Vec<String>,
+    /// only pods matching label selector, ex. `app=web`
+    pub selector: Option<String>,
+}
+
+impl Default for KubernetesConfig {
+    fn default() -> Self {
+        KubernetesConfig {
+            context: None,
+            namespaces: vec!["default".into()],
+            selector: None,
+        }
+    }
+}
+
+impl KubernetesConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self
+            .context
+            .as_ref()
+            .is_some_and(|context| context.is_empty())
+        {
+            return Err(EloraError::ConfigInvalid(
+                "kubernetes.context can't be empty".into(),
+            ));
+        }
+        if self.namespaces.iter().any(|namespace| namespace.is_empty()) {
+            return Err(EloraError::ConfigInvalid(
+                "kubernetes.namespaces can't have empty namespace".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Pod counts of one namespace, completed pods aren't counted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Health {
+    total: usize,
+    ready: usize,
+    pending: usize,
+}
+
+impl Health {
+    fn add(&mut self, status: Option<&PodStatus>) {
+        match status.and_then(|status| status.phase.as_deref()) {
+            Some("Succeeded") => return,
+            Some("Pending") => self.pending += 1,
+            Some("Running") => {
+                let ready = status
+                    .and_then(|status| status.conditions.as_ref())
+                    .is_some_and(|conditions| {
+                        conditions
+                            .iter()
+                            .any(|c| c.type_ == "Ready" && c.status == "True")
+                    });
+                if ready {
+                    self.ready += 1;
+                }
+            }
+            _ => {}
+        }
+        self.total += 1;
+    }
+}
+
+/// Health per namespace of pods, `namespaces` are present even without pods
+fn health(pods: &[Pod], namespaces: &[String]) -> BTreeMap<String, Health> {
+    let mut health: BTreeMap<String, Health> = namespaces
+        .iter()
+        .map(|namespace| (namespace.clone(), Health::default()))
+        .collect();
+    for pod in pods {
+        health
+            .entry(pod.namespace().unwrap_or_default())
+            .or_default()
+            .add(pod.status.as_ref());
+    }
+    health
+}
+
+/// Formats namespace health into line, ex. `prod 12/14 pend 1`
+fn to_line(namespace: &str, health: &Health) -> Line {
+    let mut text = format!("{} {}/{}", namespace, health.ready, health.total);
+    if health.pending > 0 {
+        text.push_str(&format!(" pend {}", health.pending));
+    }
+    let not_ready = health.total - health.ready;
+    Line::new(text)
+        .with_field("namespace", namespace)
+        .with_field("ready", health.ready as f64)
+        .with_field("total", health.total as f64)
+        .with_field("not_ready", not_ready as f64)
+        .with_field("pending", health.pending as f64)
+        .with_metric(namespace, not_ready as f64)
+}
+
+/// Pod health of configured namespaces
+pub struct KubernetesProvider {
+    config: KubernetesConfig,
+    /// made on first fetch, kubeconfig loading is async
+    client: Mutex<Option<Client>>,
+}
+
+impl KubernetesProvider {
+    pub fn new(config: KubernetesConfig) -> Self {
+        KubernetesProvider {
+            config,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Client of configured context, or of current context falling back to
+    /// in-cluster config like `Client::try_default`
+    async fn client(&self) -> Result<Client, BoxError> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let config = match &self.config.context {
+            Some(context) => {
+                let options = KubeConfigOptions {
+                    context: Some(context.clone()),
+                    ..KubeConfigOptions::default()
+                };
+                Config::from_kubeconfig(&options).await?
+            }
+            None => Config::infer().await?,
+        };
+        log::info!("Using kubernetes api server {}", config.cluster_url);
+        let made = Client::try_from(config)?;
+        *client = Some(made.clone());
+        Ok(made)
+    }
+
+    async fn pods(&self, api: Api<Pod>) -> Result<Vec<Pod>, BoxError> {
+        let mut params = ListParams::default();
+        if let Some(selector) = &self.config.selector {
+            params = params.labels(selector);
+        }
+        Ok(api.list(&params).await?.items)
+    }
+}
+
+#[async_trait]
+impl DataProvider for KubernetesProvider {
+    fn name(&self) -> &str {
+        "kubernetes"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching kubernetes pods");
+
+        let client = self.client().await?;
+        let mut pods = Vec::new();
+        let mut fetched = Vec::new();
+        if self.config.namespaces.is_empty() {
+            pods = self.pods(Api::all(client)).await?;
+        } else {
+            let lists = join_all(
+                self.config
+                    .namespaces
+                    .iter()
+                    .map(|namespace| self.pods(Api::namespaced(client.clone(), namespace))),
+            )
+            .await;
+            for (namespace, list) in self.config.namespaces.iter().zip(lists) {
+                match list {
+                    Ok(list) => {
+                        pods.extend(list);
+                        fetched.push(namespace.clone());
+                    }
+                    Err(e) => {
+                        log::error!("Unable to fetch pods of {}: {}", namespace, e);
+                    }
+                }
+            }
+            if fetched.is_empty() {
+                return Err("no namespace could be fetched".into());
+            }
+        }
+        Ok(health(&pods, &fetched)
+            .iter()
+            .map(|(namespace, health)| to_line(namespace, health))
+            .collect())
+    }
+}
+
+#[test]
+fn testing_kubernetes_lines() {
+    let pods: Vec<Pod> = serde_json::from_str(
+        r#"[
+            {"metadata":{"name":"web-1","namespace":"prod"},"status":{"phase":"Running",
+             "conditions":[{"type":"Ready","status":"True"}]}},
+            {"metadata":{"name":"web-2","namespace":"prod"},"status":{"phase":"Running",
+             "conditions":[{"type":"Ready","status":"False"}]}},
+            {"metadata":{"name":"db-0","namespace":"prod"},"status":{"phase":"Pending"}},
+            {"metadata":{"name":"migrate","namespace":"prod"},"status":{"phase":"Succeeded"}},
+            {"metadata":{"name":"dns","namespace":"kube-system"},"status":{"phase":"Running",
+             "conditions":[{"type":"Ready","status":"True"}]}}
+        ]"#,
+    )
+    .unwrap();
+    let config = KubernetesConfig {
+        namespaces: Vec::new(),
+        ..KubernetesConfig::default()
+    };
+    config.validate().unwrap();
+
+    let health = health(&pods, &["staging".into()]);
+    let lines: Vec<Line> = health.iter().map(|(ns, h)| to_line(ns, h)).collect();
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["kube-system 1/1", "prod 1/3 pend 1", "staging 0/0"]);
+    assert_eq!(lines[1].metric.as_ref().unwrap().value, 2.0);
+}
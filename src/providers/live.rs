@@ -0,0 +1,490 @@
+//! Live status of Twitch and YouTube channels, ex. `shroud LIVE 12.3k`
+//!
+//! Line per channel, live ones with viewer count and offline ones as
+//! `shroud off` unless `show_offline` is off. Viewers carry metric under
+//! channel label, 0 while offline.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use super::{compact_count, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var twitch client secret is read from when config has none
+pub const TWITCH_SECRET_ENV: &str = "TWITCH_CLIENT_SECRET";
+/// env var youtube api key is read from when config has none
+pub const YOUTUBE_KEY_ENV: &str = "YOUTUBE_API_KEY";
+
+/// recent uploads checked for running broadcast per youtube channel
+const RECENT_UPLOADS: &str = "5";
+
+/// `[live]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiveConfig {
+    pub twitch: Option<TwitchConfig>,
+    pub youtube: Option<YouTubeConfig>,
+    /// show line for offline channels too
+    pub show_offline: bool,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        LiveConfig {
+            twitch: None,
+            youtube: None,
+            show_offline: true,
+        }
+    }
+}
+
+/// `[live.twitch]` config section, app from dev.twitch.tv console
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TwitchConfig {
+    pub client_id: String,
+    /// `TWITCH_CLIENT_SECRET` env without it
+    pub client_secret: Option<String>,
+    /// login names, ex. `shroud`
+    pub channels: Vec<String>,
+}
+
+/// `[live.youtube]` config section
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct YouTubeConfig {
+    /// Data API v3 key, `YOUTUBE_API_KEY` env without it
+    pub key: Option<String>,
+    /// `LABEL=channel id`, ex. `MKBHD=UCBJycsmduvYEL83R_U4JriQ`
+    pub channels: Vec<String>,
+}
+
+impl LiveConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let invalid = EloraError::ConfigInvalid;
+        if self.twitch.is_none() && self.youtube.is_none() {
+            return Err(invalid(
+                "live needs [live.twitch] or [live.youtube] section".into(),
+            ));
+        }
+        if let Some(twitch) = &self.twitch {
+            if twitch.client_id.is_empty() {
+                return Err(invalid("live.twitch.client_id is required".into()));
+            }
+            if twitch.channels.is_empty() {
+                return Err(invalid(
+                    "live.twitch.channels needs at least one channel".into(),
+                ));
+            }
+        }
+        if let Some(youtube) = &self.youtube {
+            if youtube.channels.is_empty() {
+                return Err(invalid(
+                    "live.youtube.channels needs at least one channel".into(),
+                ));
+            }
+            youtube.parse_channels()?;
+        }
+        Ok(())
+    }
+}
+
+impl TwitchConfig {
+    fn client_secret(&self) -> Result<String, BoxError> {
+        match &self.client_secret {
+            Some(secret) => Ok(secret.clone()),
+            None => std::env::var(TWITCH_SECRET_ENV).map_err(|_| {
+                format!(
+                    "live.twitch.client_secret or {} env is required",
+                    TWITCH_SECRET_ENV
+                )
+                .into()
+            }),
+        }
+    }
+}
+
+impl YouTubeConfig {
+    /// `(label, channel id)` pairs
+    fn parse_channels(&self) -> Result<Vec<(String, String)>, EloraError> {
+        self.channels
+            .iter()
+            .map(|channel| match channel.split_once('=') {
+                Some((label, id)) if !label.is_empty() && id.starts_with("UC") => {
+                    Ok((label.to_string(), id.to_string()))
+                }
+                _ => Err(EloraError::ConfigInvalid(format!(
+                    "live.youtube channel {:?} is not in LABEL=UC... form",
+                    channel
+                ))),
+            })
+            .collect()
+    }
+
+    fn key(&self) -> Result<String, BoxError> {
+        match &self.key {
+            Some(key) => Ok(key.clone()),
+            None => std::env::var(YOUTUBE_KEY_ENV).map_err(|_| {
+                format!("live.youtube.key or {} env is required", YOUTUBE_KEY_ENV).into()
+            }),
+        }
+    }
+}
+
+/// Channel state of any service
+#[derive(Debug, Clone, PartialEq)]
+struct Status {
+    label: String,
+    service: &'static str,
+    /// `None` while offline
+    viewers: Option<u64>,
+    title: String,
+}
+
+/// Formats status into line, ex. `shroud LIVE 12.3k` or `shroud off`
+fn to_line(status: &Status) -> Line {
+    let text = match status.viewers {
+        Some(viewers) => format!("{} LIVE {}", status.label, compact_count(viewers)),
+        None => format!("{} off", status.label),
+    };
+    let viewers = status.viewers.unwrap_or_default() as f64;
+    Line::new(text)
+        .with_field("channel", status.label.as_str())
+        .with_field("service", status.service)
+        .with_field("live", if status.viewers.is_some() { 1.0 } else { 0.0 })
+        .with_field("viewers", viewers)
+        .with_field("title", status.title.as_str())
+        .with_metric(&status.label, viewers)
+}
+
+/// Response of twitch `/oauth2/token` for client credentials
+#[derive(Debug, Deserialize)]
+struct TwitchToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Response of twitch `/helix/streams`, live channels only
+#[derive(Debug, Deserialize)]
+struct TwitchStreams {
+    data: Vec<TwitchStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwitchStream {
+    user_login: String,
+    viewer_count: u64,
+    #[serde(default)]
+    title: String,
+}
+
+/// Statuses of all twitch channels in config order, channels missing from
+/// streams are offline
+fn twitch_statuses(channels: &[String], streams: TwitchStreams) -> Vec<Status> {
+    channels
+        .iter()
+        .map(|channel| {
+            let stream = streams
+                .data
+                .iter()
+                .find(|stream| stream.user_login.eq_ignore_ascii_case(channel));
+            Status {
+                label: channel.clone(),
+                service: "twitch",
+                viewers: stream.map(|stream| stream.viewer_count),
+                title: stream.map(|s| s.title.clone()).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Response of youtube `/playlistItems`
+#[derive(Debug, Deserialize)]
+struct YouTubePlaylist {
+    items: Vec<YouTubePlaylistItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubePlaylistItem {
+    content_details: YouTubeVideoId,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideoId {
+    video_id: String,
+}
+
+/// Response of youtube `/videos`
+#[derive(Debug, Deserialize)]
+struct YouTubeVideos {
+    items: Vec<YouTubeVideo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideo {
+    snippet: YouTubeSnippet,
+    live_streaming_details: Option<YouTubeLiveDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeSnippet {
+    #[serde(default)]
+    title: String,
+    /// `live`, `upcoming` or `none`
+    live_broadcast_content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeLiveDetails {
+    /// number as string, missing when channel hides it
+    concurrent_viewers: Option<String>,
+}
+
+/// Status of youtube channel from its recent uploads, live when one of them
+/// is running broadcast
+fn youtube_status(label: &str, videos: YouTubeVideos) -> Status {
+    let live = videos
+        .items
+        .into_iter()
+        .find(|video| video.snippet.live_broadcast_content == "live");
+    Status {
+        label: label.to_string(),
+        service: "youtube",
+        viewers: live.as_ref().map(|video| {
+            video
+                .live_streaming_details
+                .as_ref()
+                .and_then(|details| details.concurrent_viewers.as_deref()?.parse().ok())
+                .unwrap_or_default()
+        }),
+        title: live.map(|video| video.snippet.title).unwrap_or_default(),
+    }
+}
+
+/// Live status of configured channels
+pub struct LiveProvider {
+    config: LiveConfig,
+    youtube_channels: Vec<(String, String)>,
+    /// twitch app token with time it expires
+    twitch_token: Mutex<Option<(Instant, String)>>,
+    client: Client,
+}
+
+impl LiveProvider {
+    pub fn new(config: LiveConfig) -> Result<Self, BoxError> {
+        let youtube_channels = match &config.youtube {
+            Some(youtube) => youtube.parse_channels()?,
+            None => Vec::new(),
+        };
+        Ok(LiveProvider {
+            config,
+            youtube_channels,
+            twitch_token: Mutex::new(None),
+            client: Client::new(),
+        })
+    }
+
+    /// cached app token, new one requested once it expires
+    async fn twitch_token(&self, twitch: &TwitchConfig) -> Result<String, BoxError> {
+        if let Some((expires, token)) = &*self.twitch_token.lock().unwrap() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let token: TwitchToken = self
+            .client
+            .post("https://id.twitch.tv/oauth2/token")
+            .form(&[
+                ("client_id", twitch.client_id.as_str()),
+                ("client_secret", twitch.client_secret()?.as_str()),
+                ("grant_type", "client_credentials"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // renewed minute early so it doesn't expire mid request
+        let expires = Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+        *self.twitch_token.lock().unwrap() = Some((expires, token.access_token.clone()));
+        Ok(token.access_token)
+    }
+
+    async fn twitch(&self, twitch: &TwitchConfig) -> Result<Vec<Status>, BoxError> {
+        let token = self.twitch_token(twitch).await?;
+        let query: Vec<(&str, &str)> = twitch
+            .channels
+            .iter()
+            .map(|channel| ("user_login", channel.as_str()))
+            .collect();
+        let response = self
+            .client
+            .get("https://api.twitch.tv/helix/streams")
+            .query(&query)
+            .header("Client-Id", &twitch.client_id)
+            .bearer_auth(token)
+            .send()
+            .await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            // revoked token, next fetch asks for new one
+            *self.twitch_token.lock().unwrap() = None;
+        }
+        let streams = response.error_for_status()?.json().await?;
+        Ok(twitch_statuses(&twitch.channels, streams))
+    }
+
+    async fn youtube(&self, key: &str, label: &str, channel: &str) -> Result<Status, BoxError> {
+        const API: &str = "https://www.googleapis.com/youtube/v3";
+        // uploads playlist of channel is `UU...` for `UC...`, cheaper in
+        // quota than search for live broadcasts
+        let uploads = format!("UU{}", &channel[2..]);
+        let playlist: YouTubePlaylist = self
+            .client
+            .get(format!("{}/playlistItems", API))
+            .query(&[
+                ("part", "contentDetails"),
+                ("playlistId", uploads.as_str()),
+                ("maxResults", RECENT_UPLOADS),
+                ("key", key),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let ids: Vec<String> = playlist
+            .items
+            .into_iter()
+            .map(|item| item.content_details.video_id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(youtube_status(label, YouTubeVideos { items: Vec::new() }));
+        }
+        let videos: YouTubeVideos = self
+            .client
+            .get(format!("{}/videos", API))
+            .query(&[
+                ("part", "snippet,liveStreamingDetails"),
+                ("id", ids.join(",").as_str()),
+                ("key", key),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(youtube_status(label, videos))
+    }
+
+    async fn youtube_all(&self, youtube: &YouTubeConfig) -> Vec<Result<Status, BoxError>> {
+        let key = match youtube.key() {
+            Ok(key) => key,
+            Err(e) => return vec![Err(e)],
+        };
+        join_all(
+            self.youtube_channels
+                .iter()
+                .map(|(label, channel)| self.youtube(&key, label, channel)),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl DataProvider for LiveProvider {
+    fn name(&self) -> &str {
+        "live"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching live status of channels");
+
+        let mut results = Vec::new();
+        if let Some(twitch) = &self.config.twitch {
+            match self.twitch(twitch).await {
+                Ok(statuses) => results.extend(statuses.into_iter().map(Ok)),
+                Err(e) => results.push(Err(e)),
+            }
+        }
+        if let Some(youtube) = &self.config.youtube {
+            results.extend(self.youtube_all(youtube).await);
+        }
+
+        let mut statuses = Vec::new();
+        for result in results {
+            match result {
+                Ok(status) => statuses.push(status),
+                Err(e) => log::error!("Unable to fetch live status: {}", e),
+            }
+        }
+        if statuses.is_empty() {
+            return Err("no channel status could be fetched".into());
+        }
+        Ok(statuses
+            .iter()
+            .filter(|status| self.config.show_offline || status.viewers.is_some())
+            .map(to_line)
+            .collect())
+    }
+}
+
+#[test]
+fn testing_live_lines() {
+    let config: LiveConfig = toml::from_str(
+        r#"
+        [twitch]
+        client_id = "abc"
+        channels = ["shroud", "pokimane"]
+        [youtube]
+        channels = ["MKBHD=UCBJycsmduvYEL83R_U4JriQ"]
+        "#,
+    )
+    .unwrap();
+    config.validate().unwrap();
+    assert!(LiveConfig::default().validate().is_err());
+    let bad = YouTubeConfig {
+        key: None,
+        channels: vec!["UCBJycsmduvYEL83R_U4JriQ".into()],
+    };
+    assert!(bad.parse_channels().is_err());
+
+    let streams: TwitchStreams = serde_json::from_str(
+        r#"{"data":[{"id":"1","user_login":"shroud","user_name":"shroud","type":"live",
+            "title":"ranked","viewer_count":12345}],"pagination":{}}"#,
+    )
+    .unwrap();
+    let twitch = config.twitch.as_ref().unwrap();
+    let statuses = twitch_statuses(&twitch.channels, streams);
+    let lines: Vec<Line> = statuses.iter().map(to_line).collect();
+    assert_eq!(lines[0].text, "shroud LIVE 12.3k");
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 12345.0);
+    assert_eq!(lines[1].text, "pokimane off");
+
+    let videos: YouTubeVideos = serde_json::from_str(
+        r#"{"items":[
+            {"id":"a","snippet":{"title":"Old video","liveBroadcastContent":"none"}},
+            {"id":"b","snippet":{"title":"Live Q&A","liveBroadcastContent":"live"},
+             "liveStreamingDetails":{"concurrentViewers":"850"}}
+        ]}"#,
+    )
+    .unwrap();
+    let status = youtube_status("MKBHD", videos);
+    assert_eq!(to_line(&status).text, "MKBHD LIVE 850");
+    assert_eq!(status.title, "Live Q&A");
+}
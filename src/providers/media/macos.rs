@@ -0,0 +1,53 @@
+//! Music and Spotify apps asked through AppleScript
+
+use tokio::process::Command;
+
+use super::Track;
+use crate::BoxError;
+
+/// `is running` check keeps apps from being launched by the script
+const SCRIPT: &str = r#"
+if application "Spotify" is running then
+    tell application "Spotify"
+        if player state is playing then return (artist of current track) & linefeed & (name of current track)
+    end tell
+end if
+if application "Music" is running then
+    tell application "Music"
+        if player state is playing then return (artist of current track) & linefeed & (name of current track)
+    end tell
+end if
+return ""
+"#;
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    pub async fn now_playing(&self) -> Result<Option<Track>, BoxError> {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(SCRIPT)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr)
+                .trim()
+                .to_string()
+                .into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.trim_end().lines();
+        match (lines.next(), lines.next()) {
+            (Some(artist), Some(title)) => Ok(Some(Track {
+                artist: artist.to_string(),
+                title: title.to_string(),
+            })),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Currently playing track from OS media session, scrolled on display
+//!
+//! Backends: MPRIS over D-Bus on linux, System Media Transport Controls on
+//! windows and AppleScript (Music and Spotify apps) on macos, as MediaRemote
+//! framework is private and not available to unsigned apps.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{render::scroll::marquee, BoxError, EloraError};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "linux")]
+mod mpris;
+#[cfg(windows)]
+mod smtc;
+
+#[cfg(target_os = "macos")]
+use macos::Backend;
+#[cfg(target_os = "linux")]
+use mpris::Backend;
+#[cfg(windows)]
+use smtc::Backend;
+
+/// `[media]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MediaConfig {
+    /// visible characters, longer `artist - title` scrolls by one char every fetch
+    pub width: usize,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        MediaConfig { width: 21 }
+    }
+}
+
+impl MediaConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.width == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "media.width must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Track which is playing right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub artist: String,
+    pub title: String,
+}
+
+impl Track {
+    fn text(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artist, self.title)
+        }
+    }
+}
+
+/// Fallback for platforms without media session support
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+struct Backend;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl Backend {
+    fn new() -> Self {
+        Backend
+    }
+
+    async fn now_playing(&self) -> Result<Option<Track>, BoxError> {
+        Err("media provider is not supported on this platform".into())
+    }
+}
+
+/// Now playing track
+pub struct MediaProvider {
+    config: MediaConfig,
+    backend: Backend,
+    /// track text and current scroll offset of it
+    scroll: Mutex<(String, usize)>,
+}
+
+impl MediaProvider {
+    pub fn new(config: MediaConfig) -> Self {
+        MediaProvider {
+            config,
+            backend: Backend::new(),
+            scroll: Mutex::new((String::new(), 0)),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for MediaProvider {
+    fn name(&self) -> &str {
+        "media"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let text = match self.backend.now_playing().await? {
+            Some(track) => track.text(),
+            None => return Ok(vec![Line::new("not playing")]),
+        };
+
+        let mut scroll = self.scroll.lock().unwrap();
+        if scroll.0 != text {
+            *scroll = (text, 0);
+        } else {
+            scroll.1 += 1;
+        }
+        Ok(vec![Line::new(marquee(
+            &scroll.0,
+            self.config.width,
+            scroll.1,
+        ))])
+    }
+}
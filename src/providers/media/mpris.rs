@@ -0,0 +1,69 @@
+//! MPRIS media players over D-Bus session bus
+
+use std::collections::HashMap;
+
+use tokio::sync::Mutex;
+use zbus::{fdo::DBusProxy, zvariant::OwnedValue, Connection, Proxy};
+
+use super::Track;
+use crate::BoxError;
+
+const PLAYER_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+pub struct Backend {
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend {
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// session bus connection, opened on first use and reused after
+    async fn connection(&self) -> Result<Connection, BoxError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            return Ok(connection.clone());
+        }
+        let opened = Connection::session().await?;
+        *connection = Some(opened.clone());
+        Ok(opened)
+    }
+
+    /// first player on bus which is playing right now
+    pub async fn now_playing(&self) -> Result<Option<Track>, BoxError> {
+        let connection = self.connection().await?;
+        let names = DBusProxy::new(&connection).await?.list_names().await?;
+
+        for name in names.iter().filter(|n| n.starts_with(PLAYER_PREFIX)) {
+            let player = Proxy::new(
+                &connection,
+                name.as_str(),
+                "/org/mpris/MediaPlayer2",
+                "org.mpris.MediaPlayer2.Player",
+            )
+            .await?;
+
+            let status: String = player.get_property("PlaybackStatus").await?;
+            if status != "Playing" {
+                continue;
+            }
+
+            let mut metadata: HashMap<String, OwnedValue> = player.get_property("Metadata").await?;
+            let title = metadata
+                .remove("xesam:title")
+                .and_then(|v| String::try_from(v).ok())
+                .unwrap_or_default();
+            let artist = metadata
+                .remove("xesam:artist")
+                .and_then(|v| Vec::<String>::try_from(v).ok())
+                .map(|artists| artists.join(", "))
+                .unwrap_or_default();
+            return Ok(Some(Track { artist, title }));
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,38 @@
+//! Windows System Media Transport Controls, same session media flyout shows
+
+use windows::Media::Control::{
+    GlobalSystemMediaTransportControlsSessionManager as SessionManager,
+    GlobalSystemMediaTransportControlsSessionPlaybackStatus as PlaybackStatus,
+};
+
+use super::Track;
+use crate::BoxError;
+
+pub struct Backend;
+
+impl Backend {
+    pub fn new() -> Self {
+        Backend
+    }
+
+    /// current session if it is playing right now
+    pub async fn now_playing(&self) -> Result<Option<Track>, BoxError> {
+        // winrt async operations are waited on with blocking `get`
+        tokio::task::spawn_blocking(|| -> Result<Option<Track>, BoxError> {
+            let manager = SessionManager::RequestAsync()?.get()?;
+            let session = match manager.GetCurrentSession() {
+                Ok(session) => session,
+                Err(_) => return Ok(None),
+            };
+            if session.GetPlaybackInfo()?.PlaybackStatus()? != PlaybackStatus::Playing {
+                return Ok(None);
+            }
+            let properties = session.TryGetMediaPropertiesAsync()?.get()?;
+            Ok(Some(Track {
+                artist: properties.Artist()?.to_string(),
+                title: properties.Title()?.to_string(),
+            }))
+        })
+        .await?
+    }
+}
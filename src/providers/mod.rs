@@ -0,0 +1,460 @@
+//! Data providers which fetch stuff from remote services
+//!
+//! Every provider implements [`DataProvider`] and returns lines of text which
+//! all go through the same render and hid pipeline.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{config::Config, metrics::METRICS, render::template::Value, BoxError, EloraError};
+
+pub mod air;
+pub mod alphavantage;
+pub mod astro;
+pub mod battery;
+pub mod bluetooth;
+pub mod calendar;
+pub mod ci;
+pub mod clock;
+pub mod countdown;
+pub mod crypto;
+pub mod disk;
+pub mod dividends;
+pub mod docker;
+pub mod downloads;
+pub mod earnings;
+pub mod electricity;
+pub mod exchange;
+pub mod exec;
+pub mod f1;
+pub mod failover;
+pub mod feargreed;
+pub mod finnhub;
+pub mod fx;
+pub mod github;
+pub mod gitlab;
+pub mod gpu;
+pub mod headlines;
+pub mod homeassistant;
+pub mod imap;
+pub mod jenkins;
+pub mod jira;
+pub mod kubernetes;
+pub mod live;
+pub mod media;
+pub mod mqtt;
+pub mod network;
+pub mod oncall;
+pub mod parcels;
+pub mod pihole;
+pub mod ping;
+pub mod plugin;
+pub mod pomodoro;
+pub mod portfolio;
+pub mod printer;
+pub mod prometheus;
+pub mod push;
+pub mod sensors;
+pub mod sentry;
+pub mod speedtest;
+pub mod sports;
+pub mod stocks;
+pub mod stooq;
+pub mod system;
+pub mod transit;
+pub mod wallets;
+pub mod wasm;
+pub mod weather;
+mod xml;
+
+/// single line of text drawn on keyboard display
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    /// last fetch of provider failed and this is previously fetched line
+    pub stale: bool,
+    /// value crossed provider's warning threshold, ex. disk almost full,
+    /// firmware draws line highlighted
+    pub highlight: bool,
+    /// value shown in line, alerts are checked against it
+    pub metric: Option<Metric>,
+    /// values line is made of, `[templates]` config lays them out instead
+    /// of provider's own format
+    pub fields: Vec<(String, Value)>,
+}
+
+/// Numeric value behind line, ex. `TSLA` price
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub symbol: String,
+    pub value: f64,
+}
+
+impl Line {
+    pub fn new(text: impl Into<String>) -> Self {
+        Line {
+            text: text.into(),
+            stale: false,
+            highlight: false,
+            metric: None,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn with_metric(mut self, symbol: impl Into<String>, value: f64) -> Self {
+        self.metric = Some(Metric {
+            symbol: symbol.into(),
+            value,
+        });
+        self
+    }
+}
+
+#[async_trait]
+pub trait DataProvider: Send + Sync {
+    /// short name used in logs, ex. `stocks`
+    fn name(&self) -> &str;
+
+    /// how often provider should be fetched unless `[intervals]` config says
+    /// otherwise, `None` uses global `refresh_secs`
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError>;
+
+    /// resolves when provider has new data before its interval is up, ex.
+    /// lines pushed over http. Never resolves by default
+    async fn changed(&self) {
+        std::future::pending().await
+    }
+}
+
+/// Sign drawn after price in currency `code`, `$` for dollars and code itself
+/// for others, ex. `EUR`
+pub fn currency_sign(code: &str) -> String {
+    match code.to_uppercase().as_str() {
+        "USD" => "$".into(),
+        other => other.into(),
+    }
+}
+
+/// Count shortened to fit display, ex. `987`, `12.3k` or `1.2M`
+pub fn compact_count(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=99_999 => format!("{:.1}k", count as f64 / 1e3),
+        100_000..=999_999 => format!("{}k", count / 1_000),
+        _ => format!("{:.1}M", count as f64 / 1e6),
+    }
+}
+
+/// Fetches provider, wrapping its error into [`EloraError::FetchFailed`]
+pub async fn fetch(provider: &dyn DataProvider) -> Result<Vec<Line>, EloraError> {
+    let fetched = provider.fetch().await;
+    METRICS.fetched(provider.name(), fetched.is_ok());
+    fetched.map_err(|source| EloraError::FetchFailed {
+        provider: provider.name().to_string(),
+        source,
+    })
+}
+
+/// Wraps error of building provider from config, retrying won't fix it
+fn invalid_config(provider: &str) -> impl FnOnce(BoxError) -> EloraError + '_ {
+    move |source| match source.downcast::<EloraError>() {
+        Ok(error) if matches!(*error, EloraError::ConfigInvalid(_)) => *error,
+        Ok(error) => EloraError::ConfigInvalid(format!("{}: {}", provider, error)),
+        Err(source) => EloraError::ConfigInvalid(format!("{}: {}", provider, source)),
+    }
+}
+
+/// Creates all providers enabled in config
+pub fn from_config(config: &Config) -> Result<Vec<Box<dyn DataProvider>>, EloraError> {
+    let mut providers: Vec<Box<dyn DataProvider>> = Vec::new();
+    // stocks, portfolio and fx provider share rates, so they're fetched once
+    let rates = Arc::new(fx::FxRates::new());
+    if !config.tickers.is_empty() {
+        let stocks_from = |source: stocks::QuoteSource| {
+            let mut stocks = stocks::StocksProvider::new(config.tickers.clone())
+                .map_err(invalid_config("stocks"))?
+                .with_source(source);
+            if let Some(fx) = &config.fx {
+                stocks = stocks.with_fx(fx.clone(), rates.clone());
+            }
+            if let Some(market) = &config.market {
+                stocks = stocks.with_market(market.clone());
+            }
+            if let Some(quotes) = &config.quotes {
+                stocks = stocks
+                    .with_extended_hours(quotes.extended_hours)
+                    .with_ranges(quotes.ranges)
+                    .with_dividends(quotes.dividends);
+            }
+            Ok::<_, EloraError>(stocks)
+        };
+        let yahoo = stocks_from(stocks::QuoteSource::Yahoo)?;
+        let fallbacks = match &config.quotes {
+            Some(quotes) => quotes.sources()?,
+            None => Vec::new(),
+        };
+        if fallbacks.is_empty() {
+            providers.push(Box::new(yahoo));
+        } else {
+            let mut sources: Vec<(String, Box<dyn DataProvider>)> =
+                vec![("yahoo".into(), Box::new(yahoo))];
+            for source in fallbacks {
+                sources.push((source.name().into(), Box::new(stocks_from(source)?)));
+            }
+            providers.push(Box::new(failover::FailoverProvider::new(
+                "stocks", sources,
+            )?));
+        }
+    }
+    if let Some(portfolio) = &config.portfolio {
+        let mut portfolio = portfolio::PortfolioProvider::new(portfolio.clone())
+            .map_err(invalid_config("portfolio"))?;
+        if let Some(fx) = &config.fx {
+            portfolio = portfolio.with_fx(fx.clone(), rates.clone());
+        }
+        if let Some(market) = &config.market {
+            portfolio = portfolio.with_market(market.clone());
+        }
+        providers.push(Box::new(portfolio));
+    }
+    if let Some(fx) = &config.fx {
+        providers.push(Box::new(fx::FxProvider::new(fx, rates.clone())));
+    }
+    if let Some(crypto) = &config.crypto {
+        providers.push(Box::new(crypto::CryptoProvider::new(crypto.clone())));
+    }
+    if let Some(feargreed) = &config.feargreed {
+        let feargreed = feargreed::FearGreedProvider::new(feargreed.clone())
+            .map_err(invalid_config("feargreed"))?;
+        providers.push(Box::new(feargreed));
+    }
+    if let Some(wallets) = &config.wallets {
+        providers.push(Box::new(wallets::WalletsProvider::new(wallets.clone())));
+    }
+    if let Some(exchange) = &config.exchange {
+        providers.push(Box::new(exchange::ExchangeProvider::new(exchange.clone())));
+    }
+    if let Some(weather) = &config.weather {
+        providers.push(Box::new(weather::WeatherProvider::new(weather.clone())));
+    }
+    if let Some(air) = &config.air {
+        providers.push(Box::new(air::AirProvider::new(air.clone())));
+    }
+    if let Some(astro) = &config.astro {
+        providers.push(Box::new(astro::AstroProvider::new(astro.clone())));
+    }
+    if let Some(alphavantage) = &config.alphavantage {
+        providers.push(Box::new(alphavantage::AlphaVantageProvider::new(
+            alphavantage.clone(),
+        )));
+    }
+    if let Some(finnhub) = &config.finnhub {
+        providers.push(Box::new(finnhub::FinnhubProvider::new(finnhub.clone())));
+    }
+    if let Some(earnings) = &config.earnings {
+        providers.push(Box::new(earnings::EarningsProvider::new(
+            earnings.clone(),
+            &config.tickers,
+        )));
+    }
+    if let Some(dividends) = &config.dividends {
+        providers.push(Box::new(
+            dividends::DividendsProvider::new(dividends.clone(), &config.tickers)
+                .map_err(invalid_config("dividends"))?,
+        ));
+    }
+    if let Some(github) = &config.github {
+        providers.push(Box::new(github::GitHubProvider::new(github.clone())));
+    }
+    if let Some(ci) = &config.ci {
+        let ci = ci::CiProvider::new(ci.clone()).map_err(invalid_config("ci"))?;
+        providers.push(Box::new(ci));
+    }
+    if let Some(gitlab) = &config.gitlab {
+        let gitlab =
+            gitlab::GitLabProvider::new(gitlab.clone()).map_err(invalid_config("gitlab"))?;
+        providers.push(Box::new(gitlab));
+    }
+    if let Some(jenkins) = &config.jenkins {
+        let jenkins =
+            jenkins::JenkinsProvider::new(jenkins.clone()).map_err(invalid_config("jenkins"))?;
+        providers.push(Box::new(jenkins));
+    }
+    if let Some(jira) = &config.jira {
+        providers.push(Box::new(jira::JiraProvider::new(jira.clone())));
+    }
+    if let Some(calendar) = &config.calendar {
+        providers.push(Box::new(calendar::CalendarProvider::new(calendar.clone())));
+    }
+    if let Some(countdown) = &config.countdown {
+        let countdown = countdown::CountdownProvider::new(countdown.clone())
+            .map_err(invalid_config("countdown"))?;
+        providers.push(Box::new(countdown));
+    }
+    if let Some(electricity) = &config.electricity {
+        providers.push(Box::new(electricity::ElectricityProvider::new(
+            electricity.clone(),
+        )));
+    }
+    if let Some(transit) = &config.transit {
+        providers.push(Box::new(transit::TransitProvider::new(transit.clone())));
+    }
+    if let Some(headlines) = &config.headlines {
+        providers.push(Box::new(headlines::HeadlinesProvider::new(
+            headlines.clone(),
+        )));
+    }
+    if let Some(kubernetes) = &config.kubernetes {
+        providers.push(Box::new(kubernetes::KubernetesProvider::new(
+            kubernetes.clone(),
+        )));
+    }
+    if let Some(docker) = &config.docker {
+        providers.push(Box::new(docker::DockerProvider::new(docker.clone())));
+    }
+    if let Some(prometheus) = &config.prometheus {
+        let prometheus = prometheus::PrometheusProvider::new(prometheus.clone())
+            .map_err(invalid_config("prometheus"))?;
+        providers.push(Box::new(prometheus));
+    }
+    if let Some(oncall) = &config.oncall {
+        providers.push(Box::new(oncall::OnCallProvider::new(oncall.clone())));
+    }
+    if let Some(sentry) = &config.sentry {
+        providers.push(Box::new(sentry::SentryProvider::new(sentry.clone())));
+    }
+    if let Some(downloads) = &config.downloads {
+        providers.push(Box::new(downloads::DownloadsProvider::new(
+            downloads.clone(),
+        )));
+    }
+    if let Some(live) = &config.live {
+        let live = live::LiveProvider::new(live.clone()).map_err(invalid_config("live"))?;
+        providers.push(Box::new(live));
+    }
+    if let Some(sports) = &config.sports {
+        providers.push(Box::new(sports::SportsProvider::new(sports.clone())));
+    }
+    if let Some(f1) = &config.f1 {
+        providers.push(Box::new(f1::F1Provider::new(f1.clone())));
+    }
+    if let Some(imap) = &config.imap {
+        providers.push(Box::new(imap::ImapProvider::new(imap.clone())));
+    }
+    if let Some(homeassistant) = &config.homeassistant {
+        let homeassistant = homeassistant::HomeAssistantProvider::new(homeassistant.clone())
+            .map_err(invalid_config("homeassistant"))?;
+        providers.push(Box::new(homeassistant));
+    }
+    if let Some(printer) = &config.printer {
+        providers.push(Box::new(printer::PrinterProvider::new(printer.clone())));
+    }
+    if let Some(pihole) = &config.pihole {
+        providers.push(Box::new(pihole::PiHoleProvider::new(pihole.clone())));
+    }
+    if let Some(parcels) = &config.parcels {
+        providers.push(Box::new(parcels::ParcelsProvider::new(parcels.clone())));
+    }
+    if let Some(system) = &config.system {
+        providers.push(Box::new(system::SystemProvider::new(system.clone())));
+    }
+    if let Some(gpu) = &config.gpu {
+        providers.push(Box::new(gpu::GpuProvider::new(gpu.clone())));
+    }
+    if let Some(sensors) = &config.sensors {
+        providers.push(Box::new(sensors::SensorsProvider::new(sensors.clone())));
+    }
+    if let Some(disk) = &config.disk {
+        providers.push(Box::new(disk::DiskProvider::new(disk.clone())));
+    }
+    if let Some(battery) = &config.battery {
+        providers.push(Box::new(battery::BatteryProvider::new(battery.clone())));
+    }
+    if let Some(bluetooth) = &config.bluetooth {
+        providers.push(Box::new(bluetooth::BluetoothProvider::new(
+            bluetooth.clone(),
+        )));
+    }
+    if let Some(network) = &config.network {
+        providers.push(Box::new(network::NetworkProvider::new(network.clone())));
+    }
+    if let Some(ping) = &config.ping {
+        let ping = ping::PingProvider::new(ping.clone()).map_err(invalid_config("ping"))?;
+        providers.push(Box::new(ping));
+    }
+    if let Some(speedtest) = &config.speedtest {
+        providers.push(Box::new(speedtest::SpeedtestProvider::new(
+            speedtest.clone(),
+        )));
+    }
+    if let Some(exec) = &config.exec {
+        providers.push(Box::new(exec::ExecProvider::new(exec.clone())));
+    }
+    if let Some(media) = &config.media {
+        providers.push(Box::new(media::MediaProvider::new(media.clone())));
+    }
+    if let Some(mqtt) = &config.mqtt {
+        providers.push(Box::new(mqtt::MqttProvider::new(mqtt.clone())));
+    }
+    if let Some(push) = &config.push {
+        providers.push(Box::new(push::PushProvider::new(push.clone())));
+    }
+    if let Some(pomodoro) = &config.pomodoro {
+        providers.push(Box::new(pomodoro::PomodoroProvider::new(pomodoro.clone())));
+    }
+    if let Some(clock) = &config.clock {
+        let clock = clock::ClockProvider::new(clock.clone()).map_err(invalid_config("clock"))?;
+        providers.push(Box::new(clock));
+    }
+    if let Some(plugins) = &config.plugins {
+        for (name, path) in plugins.discover() {
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "wasm")
+            {
+                providers.push(Box::new(wasm::WasmProvider::new(&name, &path, plugins)?));
+            } else {
+                providers.push(Box::new(plugin::PluginProvider::new(
+                    &name, &path, plugins,
+                )?));
+            }
+        }
+    }
+    Ok(providers)
+}
+
+#[test]
+fn testing_invalid_provider_config() {
+    let config = Config {
+        tickers: Vec::new(),
+        clock: Some(clock::ClockConfig {
+            zones: vec!["NYC=Nowhere/City".into()],
+            ..clock::ClockConfig::default()
+        }),
+        ..Config::default()
+    };
+    // bad config isn't temporary fetch failure, retrying won't fix it
+    assert!(matches!(
+        from_config(&config),
+        Err(EloraError::ConfigInvalid(_))
+    ));
+}
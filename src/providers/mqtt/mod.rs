@@ -0,0 +1,428 @@
+//! Values of MQTT topics, ex. home automation sensors or 3D printer progress
+//!
+//! Client stays subscribed to `[[mqtt.topics]]` and keeps last value of each,
+//! which is picked from json payload by `path` and laid out by `template`.
+//! Connection is kept by rumqttc, lost one is retried with backoff, last
+//! values stay on display meanwhile and retained messages refresh them after
+//! reconnect.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use jsonpath_rust::{
+    parser::{model::JpQuery, parse_json_path},
+    query::js_path_process,
+};
+use rumqttc::{
+    AsyncClient, Event, MqttOptions, Packet, QoS, SubscribeFilter, SubscribeReasonCode,
+    TlsConfiguration, Transport,
+};
+use serde::Deserialize;
+use tokio::{sync::Notify, task::AbortHandle};
+
+use super::{DataProvider, Line};
+use crate::{
+    render::{template::Value, Template},
+    BoxError, EloraError,
+};
+
+/// longest wait between reconnects
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// largest packet sent or received, json of printers and home automation
+/// bridges gets past rumqttc default of 10 KiB
+const MAX_PACKET: usize = 256 * 1024;
+
+/// `[mqtt]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    /// connect over tls, broker port is usually 8883 then
+    pub tls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// `elora_hid-<pid>` without it
+    pub client_id: Option<String>,
+    /// broker is pinged this often and connection is dropped when it stops
+    /// answering
+    pub keep_alive_secs: u16,
+    /// topics shown, one line each in this order
+    pub topics: Vec<TopicConfig>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: "localhost".into(),
+            port: 1883,
+            tls: false,
+            username: None,
+            password: None,
+            client_id: None,
+            keep_alive_secs: 30,
+            topics: Vec::new(),
+        }
+    }
+}
+
+/// `[[mqtt.topics]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopicConfig {
+    /// topic filter, `+` and `#` wildcards match like on broker
+    pub topic: String,
+    /// JSONPath (RFC 9535) of value in payload, ex. `$.progress.completion`
+    /// or `$.sensors[?@.kind == 'door'].state`, first match is shown. Whole
+    /// payload without it
+    pub path: Option<String>,
+    /// line layout with `value` and `topic` fields
+    pub template: String,
+}
+
+impl Default for TopicConfig {
+    fn default() -> Self {
+        TopicConfig {
+            topic: String::new(),
+            path: None,
+            template: "{value}".into(),
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let invalid = EloraError::ConfigInvalid;
+        if self.host.is_empty() {
+            return Err(invalid("mqtt.host can't be empty".into()));
+        }
+        if self.keep_alive_secs == 0 {
+            return Err(invalid(
+                "mqtt.keep_alive_secs must be greater than 0".into(),
+            ));
+        }
+        if self.topics.is_empty() {
+            return Err(invalid("mqtt.topics needs at least one topic".into()));
+        }
+        for topic in &self.topics {
+            if !valid_filter(&topic.topic) {
+                return Err(invalid(format!(
+                    "mqtt topic {:?} is not valid topic filter",
+                    topic.topic
+                )));
+            }
+            if let Some(path) = &topic.path {
+                parse_path(path)
+                    .map_err(|e| invalid(format!("mqtt topic {:?} path: {}", topic.topic, e)))?;
+            }
+            Template::parse(&topic.template)
+                .map_err(|e| invalid(format!("mqtt topic {:?} template: {}", topic.topic, e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// `#` only as whole last level and `+` only as whole level
+fn valid_filter(filter: &str) -> bool {
+    let levels: Vec<&str> = filter.split('/').collect();
+    !filter.is_empty()
+        && levels.iter().enumerate().all(|(i, level)| match *level {
+            "#" => i == levels.len() - 1,
+            "+" => true,
+            level => !level.contains(['#', '+']),
+        })
+}
+
+/// Whether `topic` matches subscription `filter`
+fn matches(filter: &str, topic: &str) -> bool {
+    let mut topic = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(name)) if level == name => {}
+            _ => return false,
+        }
+    }
+    topic.next().is_none()
+}
+
+/// Parses json path of topic, ex. `$.sensors[?@.kind == 'door'].state`
+fn parse_path(path: &str) -> Result<JpQuery, String> {
+    parse_json_path(path).map_err(|e| format!("{:?} is not valid json path: {}", path, e))
+}
+
+/// Value shown for payload: number or text found at `path`, first one when
+/// it matches several, or whole payload
+fn value(payload: &[u8], path: Option<&JpQuery>) -> Result<Value, String> {
+    let text = String::from_utf8_lossy(payload);
+    let text = text.trim();
+    let Some(path) = path else {
+        return Ok(match text.parse::<f64>() {
+            Ok(number) => Value::Number(number),
+            Err(_) => Value::Text(text.to_string()),
+        });
+    };
+    let json: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let found = js_path_process(path, &json).map_err(|e| e.to_string())?;
+    match found.first().map(|found| found.val) {
+        Some(serde_json::Value::Number(number)) => {
+            Ok(Value::Number(number.as_f64().ok_or("number out of range")?))
+        }
+        Some(serde_json::Value::String(text)) => Ok(Value::Text(text.clone())),
+        Some(serde_json::Value::Null) | None => Err("path not found in payload".into()),
+        Some(other) => Ok(Value::Text(other.to_string())),
+    }
+}
+
+/// Topic config ready to match messages against
+struct Topic {
+    filter: String,
+    path: Option<JpQuery>,
+    template: Template,
+}
+
+/// Last values shared between client and provider
+struct MqttState {
+    topics: Vec<Topic>,
+    /// last topic and value received for each of `topics`
+    values: Mutex<Vec<Option<(String, Value)>>>,
+    changed: Notify,
+}
+
+impl MqttState {
+    fn new(config: &MqttConfig) -> Self {
+        let topics: Vec<Topic> = config
+            .topics
+            .iter()
+            .map(|topic| Topic {
+                filter: topic.topic.clone(),
+                path: topic.path.as_deref().and_then(|p| parse_path(p).ok()),
+                template: Template::parse(&topic.template).unwrap_or_else(|_| {
+                    Template::parse("{value}").expect("default template is valid")
+                }),
+            })
+            .collect();
+        MqttState {
+            values: Mutex::new(vec![None; topics.len()]),
+            topics,
+            changed: Notify::new(),
+        }
+    }
+
+    /// stores payload as value of every topic it matches
+    fn received(&self, topic: &str, payload: &[u8]) {
+        let mut values = self.values.lock().unwrap();
+        let mut changed = false;
+        for (i, config) in self.topics.iter().enumerate() {
+            if !matches(&config.filter, topic) {
+                continue;
+            }
+            match value(payload, config.path.as_ref()) {
+                Ok(value) => {
+                    values[i] = Some((topic.to_string(), value));
+                    changed = true;
+                }
+                Err(e) => log::debug!("Ignoring mqtt message on {}: {}", topic, e),
+            }
+        }
+        if changed {
+            self.changed.notify_one();
+        }
+    }
+
+    fn lines(&self) -> Vec<Line> {
+        let values = self.values.lock().unwrap();
+        self.topics
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(config, value)| {
+                let (topic, value) = value.as_ref()?;
+                let topic = Value::Text(topic.clone());
+                let text = config.template.render(|field| match field {
+                    "value" => Some(value),
+                    "topic" => Some(&topic),
+                    _ => None,
+                });
+                Some(
+                    Line::new(text)
+                        .with_field("value", value.clone())
+                        .with_field("topic", topic),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Client options of config, packets up to [`MAX_PACKET`] both ways
+fn options(config: &MqttConfig) -> MqttOptions {
+    let client_id = config
+        .client_id
+        .clone()
+        .unwrap_or_else(|| format!("elora_hid-{}", std::process::id()));
+    let mut options = MqttOptions::new(client_id, &config.host, config.port);
+    options
+        .set_keep_alive(Duration::from_secs(config.keep_alive_secs.into()))
+        .set_max_packet_size(MAX_PACKET, MAX_PACKET);
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+    }
+    if config.tls {
+        options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+    }
+    options
+}
+
+/// Drives client until provider is dropped. Event loop reconnects by itself
+/// on next poll after error, so failed polls only wait out backoff. Session
+/// isn't kept by broker, topics are subscribed again on every connect
+async fn run(config: MqttConfig, state: Arc<MqttState>) {
+    let (client, mut events) = AsyncClient::new(options(&config), 16);
+    let mut filters: Vec<String> = state.topics.iter().map(|t| t.filter.clone()).collect();
+    filters.sort();
+    filters.dedup();
+
+    let mut failures = 0u32;
+    loop {
+        match events.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                failures = 0;
+                let subscribe = filters
+                    .iter()
+                    .map(|filter| SubscribeFilter::new(filter.clone(), QoS::AtLeastOnce));
+                match client.try_subscribe_many(subscribe) {
+                    Ok(()) => log::info!(
+                        "Subscribed to {} on mqtt broker {}:{}",
+                        filters.join(", "),
+                        config.host,
+                        config.port
+                    ),
+                    Err(e) => log::warn!("Unable to subscribe to mqtt topics: {}", e),
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                state.received(&publish.topic, &publish.payload);
+            }
+            Ok(Event::Incoming(Packet::SubAck(ack)))
+                if ack.return_codes.contains(&SubscribeReasonCode::Failure) =>
+            {
+                log::warn!("Mqtt broker refused some of subscriptions");
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let backoff = Duration::from_secs(1 << failures.min(6)).min(MAX_BACKOFF);
+                log::warn!(
+                    "Mqtt broker {}:{}: {}, reconnecting in {:?}",
+                    config.host,
+                    config.port,
+                    e,
+                    backoff
+                );
+                failures += 1;
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+}
+
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Last values of subscribed topics
+pub struct MqttProvider {
+    config: MqttConfig,
+    state: Arc<MqttState>,
+    /// running client task, stopped with provider
+    client: Mutex<Option<AbortOnDrop>>,
+}
+
+impl MqttProvider {
+    pub fn new(config: MqttConfig) -> Self {
+        MqttProvider {
+            state: Arc::new(MqttState::new(&config)),
+            config,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Client is started by first worker waiting for changes, same as push
+    /// server
+    fn ensure_client(&self) {
+        let mut client = self.client.lock().unwrap();
+        if client.is_none() {
+            let task = tokio::spawn(run(self.config.clone(), self.state.clone()));
+            *client = Some(AbortOnDrop(task.abort_handle()));
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for MqttProvider {
+    fn name(&self) -> &str {
+        "mqtt"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        Ok(self.state.lines())
+    }
+
+    async fn changed(&self) {
+        self.ensure_client();
+        self.state.changed.notified().await
+    }
+}
+
+#[test]
+fn testing_mqtt_topics() {
+    assert!(matches("printer/+/progress", "printer/mk4/progress"));
+    assert!(matches("home/#", "home/kitchen/temp"));
+    assert!(!matches("home/+", "home/kitchen/temp"));
+    assert!(!valid_filter("home/#/temp"));
+
+    let config = MqttConfig {
+        topics: vec![
+            TopicConfig {
+                topic: "printer/+/job".into(),
+                path: Some("$.progress.completion".into()),
+                template: "3D {value:.0}%".into(),
+            },
+            TopicConfig {
+                topic: "home/kitchen/temp".into(),
+                ..TopicConfig::default()
+            },
+        ],
+        ..MqttConfig::default()
+    };
+    config.validate().unwrap();
+    let options = options(&config);
+    assert_eq!(options.broker_address(), ("localhost".to_string(), 1883));
+    assert_eq!(options.keep_alive(), Duration::from_secs(30));
+    let state = MqttState::new(&config);
+    state.received("home/kitchen/temp", b"21.5");
+    state.received("printer/mk4/job", br#"{"progress": {"completion": 42.4}}"#);
+    state.received("printer/mk4/job", b"not json");
+    let lines: Vec<String> = state.lines().into_iter().map(|l| l.text).collect();
+    assert_eq!(lines, vec!["3D 42%", "21.5"]);
+
+    let payload = br#"{"sensors": [{"kind": "window"}, {"kind": "door", "state": "open"}]}"#;
+    let path = parse_path("$.sensors[1].kind").unwrap();
+    assert_eq!(value(payload, Some(&path)), Ok(Value::Text("door".into())));
+    let path = parse_path("$.sensors[?@.kind == 'door'].state").unwrap();
+    assert_eq!(value(payload, Some(&path)), Ok(Value::Text("open".into())));
+    let path = parse_path("$..kind").unwrap();
+    assert_eq!(
+        value(payload, Some(&path)),
+        Ok(Value::Text("window".into()))
+    );
+    assert!(value(payload, Some(&parse_path("$.missing").unwrap())).is_err());
+    assert!(parse_path("sensors").is_err());
+}
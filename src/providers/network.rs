@@ -0,0 +1,221 @@
+//! Throughput of local network interfaces from `sysinfo` counters, ex.
+//! `NET ▼12.3 ▲1.2 pk 95.1`
+//!
+//! Rates are in Mbps, from byte counters of two fetches a second apart, and
+//! `pk` is highest download rate seen within `peak_mins`. Download rate
+//! carries metric under label.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use sysinfo::Networks;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// `[network]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// interfaces summed, ex. `eth0` or `en0`, all but loopback when empty
+    pub interfaces: Vec<String>,
+    /// minutes peak rates are tracked over
+    pub peak_mins: u64,
+    pub label: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            interfaces: Vec::new(),
+            peak_mins: 60,
+            label: "NET".into(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.peak_mins == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "network.peak_mins must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn counted(&self, interface: &str) -> bool {
+        if self.interfaces.is_empty() {
+            !interface.starts_with("lo") && !interface.contains("Loopback")
+        } else {
+            self.interfaces.iter().any(|name| name == interface)
+        }
+    }
+}
+
+/// Received and transmitted byte totals at given time
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    at: Instant,
+    received: u64,
+    transmitted: u64,
+}
+
+/// Mbps down and up between two counter readings. Counters going back, ex.
+/// when interface went away, count as no traffic
+fn rates(before: Counters, after: Counters) -> (f64, f64) {
+    let secs = (after.at - before.at).as_secs_f64();
+    if secs <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let mbps = |bytes: u64| bytes as f64 * 8.0 / 1e6 / secs;
+    (
+        mbps(after.received.saturating_sub(before.received)),
+        mbps(after.transmitted.saturating_sub(before.transmitted)),
+    )
+}
+
+/// Rates within sliding window, for highest of them
+struct Peaks {
+    window: Duration,
+    samples: VecDeque<(Instant, f64, f64)>,
+}
+
+impl Peaks {
+    fn new(window: Duration) -> Self {
+        Peaks {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// adds sample and returns highest down and up rates of window
+    fn push(&mut self, at: Instant, down: f64, up: f64) -> (f64, f64) {
+        self.samples.push_back((at, down, up));
+        while let Some(&(oldest, _, _)) = self.samples.front() {
+            if at.duration_since(oldest) <= self.window {
+                break;
+            }
+            self.samples.pop_front();
+        }
+        self.samples
+            .iter()
+            .fold((0.0, 0.0), |(down, up), &(_, d, u)| {
+                (d.max(down), u.max(up))
+            })
+    }
+}
+
+fn to_line(label: &str, (down, up): (f64, f64), (peak_down, peak_up): (f64, f64)) -> Line {
+    Line::new(format!(
+        "{} ▼{:.1} ▲{:.1} pk {:.1}",
+        label, down, up, peak_down
+    ))
+    .with_field("label", label)
+    .with_field("down", down)
+    .with_field("up", up)
+    .with_field("peak_down", peak_down)
+    .with_field("peak_up", peak_up)
+    .with_metric(label, down)
+}
+
+struct State {
+    networks: Networks,
+    last: Counters,
+    peaks: Peaks,
+}
+
+/// Local network throughput, no network requests involved
+pub struct NetworkProvider {
+    config: NetworkConfig,
+    state: Mutex<State>,
+}
+
+impl NetworkProvider {
+    pub fn new(config: NetworkConfig) -> Self {
+        let networks = Networks::new_with_refreshed_list();
+        // first fetch already has reading to compare with
+        let last = Self::counters(&config, &networks);
+        let peaks = Peaks::new(Duration::from_secs(config.peak_mins * 60));
+        NetworkProvider {
+            config,
+            state: Mutex::new(State {
+                networks,
+                last,
+                peaks,
+            }),
+        }
+    }
+
+    fn counters(config: &NetworkConfig, networks: &Networks) -> Counters {
+        let counted = networks
+            .list()
+            .iter()
+            .filter(|(interface, _)| config.counted(interface));
+        let (received, transmitted) = counted.fold((0, 0), |(rx, tx), (_, data)| {
+            (rx + data.total_received(), tx + data.total_transmitted())
+        });
+        Counters {
+            at: Instant::now(),
+            received,
+            transmitted,
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for NetworkProvider {
+    fn name(&self) -> &str {
+        "network"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let mut state = self.state.lock().unwrap();
+        // list is refreshed too, so interfaces which came up are counted
+        state.networks.refresh_list();
+        let now = Self::counters(&self.config, &state.networks);
+        let rates = rates(state.last, now);
+        state.last = now;
+        let peaks = state.peaks.push(now.at, rates.0, rates.1);
+        Ok(vec![to_line(&self.config.label, rates, peaks)])
+    }
+}
+
+#[test]
+fn testing_network_line() {
+    let start = Instant::now();
+    let at = |secs| start + Duration::from_secs(secs);
+    let before = Counters {
+        at: at(0),
+        received: 1_000_000,
+        transmitted: 500_000,
+    };
+    let after = Counters {
+        at: at(2),
+        received: 4_075_000,
+        transmitted: 800_000,
+    };
+    let (down, up) = rates(before, after);
+    assert_eq!((down, up), (12.3, 1.2));
+    assert_eq!(rates(after, before), (0.0, 0.0));
+
+    let mut peaks = Peaks::new(Duration::from_secs(60));
+    assert_eq!(peaks.push(at(0), 95.1, 8.0), (95.1, 8.0));
+    assert_eq!(peaks.push(at(30), down, up), (95.1, 8.0));
+    assert_eq!(peaks.push(at(61), down, up), (down, up));
+
+    let line = to_line("NET", (down, up), (95.1, 8.0));
+    assert_eq!(line.text, "NET ▼12.3 ▲1.2 pk 95.1");
+    assert_eq!(line.metric.unwrap().value, 12.3);
+    assert!(NetworkConfig::default().counted("eth0"));
+    assert!(!NetworkConfig::default().counted("lo"));
+}
@@ -0,0 +1,407 @@
+//! On-call state and open incidents from PagerDuty or Opsgenie, ex.
+//! `ONCALL on 2 open` followed by newest incident, ex. `#412 DB down`
+//!
+//! Incident which wasn't triggered on previous fetch sets metric of
+//! [`NEW_METRIC`] to 1 for one fetch, so [`OnCallConfig::alert_rules`] sends
+//! urgent alert to keyboard once per new incident.
+
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// env var token is read from when config has none
+pub const TOKEN_ENV: &str = "ONCALL_TOKEN";
+/// metric symbol of new incident flag on first line
+pub const NEW_METRIC: &str = "oncall_new";
+
+/// incident service on-call state is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    #[default]
+    Pagerduty,
+    Opsgenie,
+}
+
+/// `[oncall]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OnCallConfig {
+    pub service: Service,
+    /// api key, `ONCALL_TOKEN` env without it
+    pub token: Option<String>,
+    /// api base url, ex. `https://api.eu.opsgenie.com`, service default
+    /// without it
+    pub url: Option<String>,
+    /// pagerduty user id (owner of token without it) or opsgenie user email
+    pub user: Option<String>,
+    /// opsgenie schedules user is on call in, by name
+    pub schedules: Vec<String>,
+    /// only incidents assigned to user, pagerduty only
+    pub mine: bool,
+    /// label of first line
+    pub label: String,
+    /// urgent alert when new incident is triggered
+    pub alert: bool,
+}
+
+impl Default for OnCallConfig {
+    fn default() -> Self {
+        OnCallConfig {
+            service: Service::Pagerduty,
+            token: None,
+            url: None,
+            user: None,
+            schedules: Vec::new(),
+            mine: false,
+            label: "ONCALL".into(),
+            alert: true,
+        }
+    }
+}
+
+impl OnCallConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if let Some(url) = &self.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "oncall.url {:?} is not http(s) url",
+                    url
+                )));
+            }
+        }
+        if self.service == Service::Opsgenie && (self.user.is_none() || self.schedules.is_empty()) {
+            return Err(EloraError::ConfigInvalid(
+                "oncall.user and oncall.schedules are required with opsgenie".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rule sending urgent alert when new incident is triggered. Empty when
+    /// `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        vec![Rule {
+            symbol: NEW_METRIC.into(),
+            provider: Some("oncall".into()),
+            direction: Direction::Above,
+            threshold: 0.0,
+            window: None,
+            text: Some("Incident triggered".into()),
+            urgent: true,
+            notify: true,
+        }]
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("oncall.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+
+    fn url(&self) -> String {
+        let url = match (&self.url, self.service) {
+            (Some(url), _) => url.as_str(),
+            (None, Service::Pagerduty) => "https://api.pagerduty.com",
+            (None, Service::Opsgenie) => "https://api.opsgenie.com",
+        };
+        url.trim_end_matches('/').to_string()
+    }
+}
+
+/// Open incident
+#[derive(Debug, Clone, PartialEq)]
+struct Incident {
+    id: String,
+    /// short number shown, ex. pagerduty `412` or opsgenie tiny id
+    number: String,
+    title: String,
+    /// not acknowledged yet, opsgenie incidents are triggered while open
+    triggered: bool,
+    created: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerDutyUser {
+    user: PagerDutyId,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerDutyId {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerDutyOnCalls {
+    oncalls: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerDutyIncidents {
+    incidents: Vec<PagerDutyIncident>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagerDutyIncident {
+    id: String,
+    incident_number: u64,
+    title: String,
+    /// triggered, acknowledged or resolved
+    status: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<PagerDutyIncident> for Incident {
+    fn from(incident: PagerDutyIncident) -> Self {
+        Incident {
+            id: incident.id,
+            number: incident.incident_number.to_string(),
+            title: incident.title,
+            triggered: incident.status == "triggered",
+            created: incident.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpsgenieData<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpsgenieOnCall {
+    #[serde(default)]
+    on_call_recipients: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpsgenieIncident {
+    id: String,
+    tiny_id: String,
+    message: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Status line, ex. `ONCALL on 2 open`, then newest incident, ex.
+/// `#412 DB down`. `new` is whether incident was triggered since last fetch
+fn to_lines(config: &OnCallConfig, on_call: bool, incidents: &[Incident], new: bool) -> Vec<Line> {
+    let state = if on_call { "on" } else { "off" };
+    let triggered = incidents.iter().filter(|i| i.triggered).count();
+    let mut lines = vec![Line::new(format!(
+        "{} {} {} open",
+        config.label,
+        state,
+        incidents.len()
+    ))
+    .with_field("label", config.label.as_str())
+    .with_field("oncall", state)
+    .with_field("open", incidents.len() as f64)
+    .with_field("triggered", triggered as f64)
+    .with_metric(NEW_METRIC, if new { 1.0 } else { 0.0 })];
+    // unacknowledged ones first, they need attention
+    let newest = incidents
+        .iter()
+        .filter(|i| i.triggered)
+        .max_by_key(|i| i.created)
+        .or_else(|| incidents.iter().max_by_key(|i| i.created));
+    if let Some(incident) = newest {
+        lines.push(
+            Line::new(format!("#{} {}", incident.number, incident.title))
+                .with_field("number", incident.number.as_str())
+                .with_field("title", incident.title.as_str())
+                .with_field("triggered", if incident.triggered { 1.0 } else { 0.0 }),
+        );
+    }
+    lines
+}
+
+/// On-call state and open incidents of configured service
+pub struct OnCallProvider {
+    config: OnCallConfig,
+    client: Client,
+    url: String,
+    /// ids of incidents triggered on last fetch
+    seen: Mutex<Option<HashSet<String>>>,
+}
+
+impl OnCallProvider {
+    pub fn new(config: OnCallConfig) -> Self {
+        OnCallProvider {
+            url: config.url(),
+            config,
+            client: Client::new(),
+            seen: Mutex::new(None),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        token: &str,
+    ) -> Result<T, BoxError> {
+        let request = match self.config.service {
+            Service::Pagerduty => request
+                .header("Authorization", format!("Token token={}", token))
+                .header("Accept", "application/vnd.pagerduty+json;version=2"),
+            Service::Opsgenie => request.header("Authorization", format!("GenieKey {}", token)),
+        };
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn pagerduty(&self, token: &str) -> Result<(bool, Vec<Incident>), BoxError> {
+        let user = match &self.config.user {
+            Some(user) => user.clone(),
+            None => {
+                let me = self.client.get(format!("{}/users/me", self.url));
+                self.get::<PagerDutyUser>(me, token).await?.user.id
+            }
+        };
+        let oncalls = self
+            .client
+            .get(format!("{}/oncalls", self.url))
+            .query(&[("user_ids[]", &user)]);
+        let on_call = !self
+            .get::<PagerDutyOnCalls>(oncalls, token)
+            .await?
+            .oncalls
+            .is_empty();
+
+        let mut incidents = self.client.get(format!("{}/incidents", self.url)).query(&[
+            ("statuses[]", "triggered"),
+            ("statuses[]", "acknowledged"),
+            ("limit", "100"),
+        ]);
+        if self.config.mine {
+            incidents = incidents.query(&[("user_ids[]", &user)]);
+        }
+        let incidents = self
+            .get::<PagerDutyIncidents>(incidents, token)
+            .await?
+            .incidents
+            .into_iter()
+            .map(Incident::from)
+            .collect();
+        Ok((on_call, incidents))
+    }
+
+    async fn opsgenie(&self, token: &str) -> Result<(bool, Vec<Incident>), BoxError> {
+        let user = self.config.user.as_deref().unwrap_or_default();
+        let mut on_call = false;
+        for schedule in &self.config.schedules {
+            let request = self
+                .client
+                .get(format!("{}/v2/schedules/{}/on-calls", self.url, schedule))
+                .query(&[("scheduleIdentifierType", "name"), ("flat", "true")]);
+            let schedule: OpsgenieData<OpsgenieOnCall> = self.get(request, token).await?;
+            on_call |= schedule
+                .data
+                .on_call_recipients
+                .iter()
+                .any(|recipient| recipient.eq_ignore_ascii_case(user));
+        }
+        let request = self
+            .client
+            .get(format!("{}/v1/incidents", self.url))
+            .query(&[("query", "status:open"), ("limit", "100")]);
+        let incidents: OpsgenieData<Vec<OpsgenieIncident>> = self.get(request, token).await?;
+        let incidents = incidents
+            .data
+            .into_iter()
+            .map(|incident| Incident {
+                id: incident.id,
+                number: incident.tiny_id,
+                title: incident.message,
+                triggered: true,
+                created: incident.created_at,
+            })
+            .collect();
+        Ok((on_call, incidents))
+    }
+}
+
+#[async_trait]
+impl DataProvider for OnCallProvider {
+    fn name(&self) -> &str {
+        "oncall"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching on-call state from {}", self.url);
+
+        let token = self.config.token()?;
+        let (on_call, incidents) = match self.config.service {
+            Service::Pagerduty => self.pagerduty(&token).await?,
+            Service::Opsgenie => self.opsgenie(&token).await?,
+        };
+        let triggered: HashSet<String> = incidents
+            .iter()
+            .filter(|i| i.triggered)
+            .map(|i| i.id.clone())
+            .collect();
+        let mut seen = self.seen.lock().unwrap();
+        // incidents already open at start were alerted by service itself
+        let new = seen.as_ref().is_some_and(|seen| !triggered.is_subset(seen));
+        *seen = Some(triggered);
+        Ok(to_lines(&self.config, on_call, &incidents, new))
+    }
+}
+
+#[test]
+fn testing_oncall_lines() {
+    let response: PagerDutyIncidents = serde_json::from_str(
+        r#"{"incidents":[
+            {"id":"P1","incident_number":411,"title":"Disk full","status":"acknowledged",
+             "created_at":"2026-10-14T09:00:00Z","urgency":"high"},
+            {"id":"P2","incident_number":412,"title":"DB down","status":"triggered",
+             "created_at":"2026-10-14T08:00:00Z","urgency":"high"}
+        ],"limit":100,"more":false}"#,
+    )
+    .unwrap();
+    let incidents: Vec<Incident> = response.incidents.into_iter().map(Incident::from).collect();
+    let config = OnCallConfig::default();
+    config.validate().unwrap();
+    assert!(OnCallConfig {
+        service: Service::Opsgenie,
+        ..OnCallConfig::default()
+    }
+    .validate()
+    .is_err());
+
+    let lines = to_lines(&config, true, &incidents, true);
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["ONCALL on 2 open", "#412 DB down"]);
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 1.0);
+
+    let lines = to_lines(&config, false, &[], false);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].text, "ONCALL off 0 open");
+
+    let rule = &config.alert_rules()[0];
+    assert!(rule.urgent);
+
+    let schedule: OpsgenieData<OpsgenieOnCall> = serde_json::from_str(
+        r#"{"data":{"_parent":{"name":"ops"},"onCallRecipients":["me@example.com"]},"took":0.1}"#,
+    )
+    .unwrap();
+    assert_eq!(schedule.data.on_call_recipients, ["me@example.com"]);
+}
@@ -0,0 +1,467 @@
+//! Latest status and ETA of tracked parcels, ex. `LAMP transit 17 Oct`
+//!
+//! Tracked with AfterShip or 17TRACK api key, given as `key` or
+//! `PARCELS_API_KEY` env. Numbers the service doesn't track yet are added to
+//! account on first fetch, carrier is detected by service, so their status
+//! shows up from next fetch.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var api key is read from when config has none
+pub const KEY_ENV: &str = "PARCELS_API_KEY";
+
+const AFTERSHIP: &str = "https://api.aftership.com/tracking/2024-04/trackings";
+const SEVENTEEN_TRACK: &str = "https://api.17track.net/track/v2.2";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Service {
+    #[default]
+    #[serde(rename = "aftership")]
+    AfterShip,
+    #[serde(rename = "17track")]
+    SeventeenTrack,
+}
+
+/// `[parcels]` config section
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParcelsConfig {
+    pub service: Service,
+    /// api key of service, `PARCELS_API_KEY` env without it
+    pub key: Option<String>,
+    /// tracking numbers as `[LABEL=]number`, ex. `LAMP=1Z999AA10123456784`
+    pub parcels: Vec<String>,
+}
+
+impl ParcelsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.parcels.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "parcels.parcels needs at least one tracking number".into(),
+            ));
+        }
+        if let Some(parcel) = self.parcels.iter().find(|p| Parcel::parse(p).is_none()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "parcel {:?} is not [LABEL=]number",
+                parcel
+            )));
+        }
+        Ok(())
+    }
+
+    fn key(&self) -> Option<String> {
+        self.key.clone().or_else(|| std::env::var(KEY_ENV).ok())
+    }
+}
+
+/// Tracked parcel from config
+#[derive(Debug, Clone, PartialEq)]
+struct Parcel {
+    label: String,
+    number: String,
+}
+
+impl Parcel {
+    /// Parses `[LABEL=]number`, number is label without one
+    fn parse(parcel: &str) -> Option<Self> {
+        let (label, number) = match parcel.split_once('=') {
+            Some((label, number)) => (label.trim(), number.trim()),
+            None => (parcel.trim(), parcel.trim()),
+        };
+        (!label.is_empty() && !number.is_empty()).then(|| Parcel {
+            label: label.to_string(),
+            number: number.to_string(),
+        })
+    }
+}
+
+/// Shipment status, normalized across services
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// carrier knows about parcel, it hasn't moved yet
+    Pending,
+    InTransit,
+    OutForDelivery,
+    Pickup,
+    Delivered,
+    /// delivery attempt failed
+    Failed,
+    Exception,
+    /// no updates for long time
+    Expired,
+}
+
+impl Status {
+    /// AfterShip `tag` or 17TRACK `latest_status.status`, they mostly share
+    /// names
+    fn parse(status: &str) -> Self {
+        match status {
+            "InTransit" => Status::InTransit,
+            "OutForDelivery" => Status::OutForDelivery,
+            "AvailableForPickup" => Status::Pickup,
+            "Delivered" => Status::Delivered,
+            "AttemptFail" | "DeliveryFailure" => Status::Failed,
+            "Exception" => Status::Exception,
+            "Expired" => Status::Expired,
+            _ => Status::Pending,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::InTransit => "transit",
+            Status::OutForDelivery => "out",
+            Status::Pickup => "pickup",
+            Status::Delivered => "delivered",
+            Status::Failed => "failed",
+            Status::Exception => "exception",
+            Status::Expired => "expired",
+        }
+    }
+}
+
+/// Tracking state of single number
+#[derive(Debug, Clone, PartialEq)]
+struct Tracking {
+    number: String,
+    status: Status,
+    eta: Option<NaiveDate>,
+    /// latest checkpoint, ex. `Arrived at facility`
+    message: Option<String>,
+}
+
+/// Date part of `2024-10-17` or `2024-10-17T18:00:00+02:00`
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Response of AfterShip `GET /trackings`, only fields we use
+#[derive(Debug, Deserialize)]
+struct AfterShipResponse {
+    data: AfterShipData,
+}
+
+#[derive(Debug, Deserialize)]
+struct AfterShipData {
+    trackings: Vec<AfterShipTracking>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AfterShipTracking {
+    tracking_number: String,
+    tag: String,
+    latest_estimated_delivery: Option<AfterShipEstimate>,
+    /// oldest first
+    #[serde(default)]
+    checkpoints: Vec<AfterShipCheckpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AfterShipEstimate {
+    datetime: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AfterShipCheckpoint {
+    message: Option<String>,
+}
+
+impl From<AfterShipTracking> for Tracking {
+    fn from(tracking: AfterShipTracking) -> Self {
+        Tracking {
+            number: tracking.tracking_number,
+            status: Status::parse(&tracking.tag),
+            eta: tracking
+                .latest_estimated_delivery
+                .and_then(|estimate| parse_date(&estimate.datetime?)),
+            message: tracking
+                .checkpoints
+                .into_iter()
+                .last()
+                .and_then(|checkpoint| checkpoint.message),
+        }
+    }
+}
+
+/// Response of 17TRACK `/gettrackinfo`, only fields we use
+#[derive(Debug, Deserialize)]
+struct TrackInfoResponse {
+    data: TrackInfoData,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrackInfoData {
+    accepted: Vec<Accepted>,
+    /// numbers not registered to account among them
+    #[serde(default)]
+    rejected: Vec<Rejected>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Accepted {
+    number: String,
+    track_info: TrackInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rejected {
+    number: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrackInfo {
+    latest_status: LatestStatus,
+    latest_event: Option<LatestEvent>,
+    time_metrics: Option<TimeMetrics>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestStatus {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatestEvent {
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeMetrics {
+    estimated_delivery_date: Option<EstimatedDelivery>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EstimatedDelivery {
+    /// start of delivery window
+    from: Option<String>,
+}
+
+impl From<Accepted> for Tracking {
+    fn from(accepted: Accepted) -> Self {
+        let info = accepted.track_info;
+        Tracking {
+            number: accepted.number,
+            status: Status::parse(&info.latest_status.status),
+            eta: info
+                .time_metrics
+                .and_then(|metrics| metrics.estimated_delivery_date?.from)
+                .and_then(|from| parse_date(&from)),
+            message: info.latest_event.and_then(|event| event.description),
+        }
+    }
+}
+
+/// Formats parcel into line, ex. `LAMP transit 17 Oct` or `LAMP delivered`.
+/// Parcel waiting to be delivered or picked up is highlighted
+fn to_line(parcel: &Parcel, tracking: Option<&Tracking>) -> Line {
+    let status = tracking.map_or(Status::Pending, |tracking| tracking.status);
+    let mut text = format!("{} {}", parcel.label, status.as_str());
+    let mut line = Line::new("")
+        .with_field("label", parcel.label.as_str())
+        .with_field("number", parcel.number.as_str())
+        .with_field("status", status.as_str())
+        .with_highlight(matches!(status, Status::OutForDelivery | Status::Pickup));
+    let eta = tracking
+        .and_then(|tracking| tracking.eta)
+        .filter(|_| status != Status::Delivered);
+    if let Some(eta) = eta {
+        let eta = eta.format("%-d %b").to_string();
+        text.push_str(&format!(" {}", eta));
+        line = line.with_field("eta", eta);
+    }
+    if let Some(message) = tracking.and_then(|tracking| tracking.message.as_deref()) {
+        line = line.with_field("message", message);
+    }
+    line.text = text;
+    line
+}
+
+/// Status of parcels tracked with AfterShip or 17TRACK
+pub struct ParcelsProvider {
+    config: ParcelsConfig,
+    parcels: Vec<Parcel>,
+    key: Option<String>,
+    client: Client,
+}
+
+impl ParcelsProvider {
+    pub fn new(config: ParcelsConfig) -> Self {
+        ParcelsProvider {
+            parcels: config
+                .parcels
+                .iter()
+                .filter_map(|p| Parcel::parse(p))
+                .collect(),
+            key: config.key(),
+            config,
+            client: Client::new(),
+        }
+    }
+
+    /// Trackings of known numbers, missing ones are created
+    async fn aftership(&self, key: &str) -> Result<Vec<Tracking>, BoxError> {
+        let numbers = self
+            .parcels
+            .iter()
+            .map(|parcel| parcel.number.as_str())
+            .collect::<Vec<_>>();
+        let response: AfterShipResponse = self
+            .client
+            .get(AFTERSHIP)
+            .header("as-api-key", key)
+            .query(&[("tracking_numbers", numbers.join(","))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let trackings = response
+            .data
+            .trackings
+            .into_iter()
+            .map(Tracking::from)
+            .collect::<Vec<_>>();
+        for number in numbers {
+            if trackings.iter().any(|tracking| tracking.number == number) {
+                continue;
+            }
+            log::info!("Adding parcel {} to aftership", number);
+            self.client
+                .post(AFTERSHIP)
+                .header("as-api-key", key)
+                .json(&json!({ "tracking_number": number }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(trackings)
+    }
+
+    /// Trackings of registered numbers, unregistered ones are registered
+    async fn seventeen_track(&self, key: &str) -> Result<Vec<Tracking>, BoxError> {
+        let numbers = self
+            .parcels
+            .iter()
+            .map(|parcel| json!({ "number": parcel.number }))
+            .collect::<Vec<_>>();
+        let response: TrackInfoResponse = self
+            .client
+            .post(format!("{}/gettrackinfo", SEVENTEEN_TRACK))
+            .header("17token", key)
+            .json(&numbers)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.data.rejected.is_empty() {
+            let rejected = response
+                .data
+                .rejected
+                .iter()
+                .map(|rejected| json!({ "number": rejected.number }))
+                .collect::<Vec<_>>();
+            log::info!("Registering {} parcels to 17track", rejected.len());
+            self.client
+                .post(format!("{}/register", SEVENTEEN_TRACK))
+                .header("17token", key)
+                .json(&rejected)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(response
+            .data
+            .accepted
+            .into_iter()
+            .map(Tracking::from)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DataProvider for ParcelsProvider {
+    fn name(&self) -> &str {
+        "parcels"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1800))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let key = self
+            .key
+            .as_deref()
+            .ok_or_else(|| format!("parcels.key or {} env is required", KEY_ENV))?;
+        log::info!("Fetching {} parcels", self.parcels.len());
+
+        let trackings = match self.config.service {
+            Service::AfterShip => self.aftership(key).await?,
+            Service::SeventeenTrack => self.seventeen_track(key).await?,
+        };
+        Ok(self
+            .parcels
+            .iter()
+            .map(|parcel| {
+                let tracking = trackings
+                    .iter()
+                    .find(|tracking| tracking.number == parcel.number);
+                to_line(parcel, tracking)
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn testing_parcel_lines() {
+    let config = ParcelsConfig {
+        parcels: vec!["LAMP=1Z999AA10123456784".into(), "RR123456785DE".into()],
+        ..ParcelsConfig::default()
+    };
+    config.validate().unwrap();
+    let lamp = Parcel::parse(&config.parcels[0]).unwrap();
+    assert_eq!(lamp.label, "LAMP");
+    assert!(Parcel::parse("LAMP=").is_none());
+
+    let response: AfterShipResponse = serde_json::from_str(
+        r#"{"meta":{"code":200},"data":{"trackings":[{"tracking_number":"1Z999AA10123456784",
+            "slug":"ups","tag":"InTransit","subtag":"InTransit_001",
+            "latest_estimated_delivery":{"type":"specific","source":"Carrier",
+            "datetime":"2024-10-17T18:00:00+02:00"},
+            "checkpoints":[{"message":"Shipper created a label"},
+            {"message":"Arrived at facility"}]}]}}"#,
+    )
+    .unwrap();
+    let tracking = Tracking::from(response.data.trackings.into_iter().next().unwrap());
+    let line = to_line(&lamp, Some(&tracking));
+    assert_eq!(line.text, "LAMP transit 17 Oct");
+    assert!(!line.highlight);
+    assert_eq!(tracking.message.as_deref(), Some("Arrived at facility"));
+
+    let response: TrackInfoResponse = serde_json::from_str(
+        r#"{"code":0,"data":{"accepted":[{"number":"RR123456785DE","carrier":7041,
+            "track_info":{"latest_status":{"status":"OutForDelivery","sub_status":"OutForDelivery_Other"},
+            "latest_event":{"time_iso":"2024-10-16T08:12:00+02:00","description":"Out for delivery"},
+            "time_metrics":{"estimated_delivery_date":{"source":"Official","from":"2024-10-16","to":null}}}}],
+            "rejected":[{"number":"LX123","error":{"code":-18019902,"message":"not registered"}}]}}"#,
+    )
+    .unwrap();
+    assert_eq!(response.data.rejected[0].number, "LX123");
+    let tracking = Tracking::from(response.data.accepted.into_iter().next().unwrap());
+    let parcel = Parcel::parse(&config.parcels[1]).unwrap();
+    let line = to_line(&parcel, Some(&tracking));
+    assert_eq!(line.text, "RR123456785DE out 16 Oct");
+    assert!(line.highlight);
+    assert_eq!(to_line(&lamp, None).text, "LAMP pending");
+}
@@ -0,0 +1,269 @@
+//! Queries Pi-hole blocked today, ex. `PIHOLE 12.3k 18.2%`
+//!
+//! Pi-hole v6 logs in with password, or app password, for session reused
+//! until it expires. v5 is asked with api token in query. Blocked percent
+//! carries metric under label.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use super::{compact_count, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var password or v5 api token is read from when config has none
+pub const PASSWORD_ENV: &str = "PIHOLE_PASSWORD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Api {
+    /// `/api`, Pi-hole 6 and later
+    #[default]
+    V6,
+    /// `/admin/api.php`
+    V5,
+}
+
+/// `[pihole]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PiHoleConfig {
+    /// ex. `http://pi.hole`
+    pub url: String,
+    pub api: Api,
+    /// web or app password on v6, api token on v5, `PIHOLE_PASSWORD` env
+    /// without it. Pi-hole without password needs neither
+    pub password: Option<String>,
+    pub label: String,
+}
+
+impl Default for PiHoleConfig {
+    fn default() -> Self {
+        PiHoleConfig {
+            url: "http://pi.hole".into(),
+            api: Api::default(),
+            password: None,
+            label: "PIHOLE".into(),
+        }
+    }
+}
+
+impl PiHoleConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "pihole.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        Ok(())
+    }
+
+    fn password(&self) -> Option<String> {
+        self.password
+            .clone()
+            .or_else(|| std::env::var(PASSWORD_ENV).ok())
+    }
+}
+
+/// Today's query counts of either api
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Summary {
+    queries: u64,
+    blocked: u64,
+    percent: f64,
+}
+
+/// Response of v6 `/api/stats/summary`, only fields we use
+#[derive(Debug, Deserialize)]
+struct StatsSummary {
+    queries: Queries,
+}
+
+#[derive(Debug, Deserialize)]
+struct Queries {
+    total: u64,
+    blocked: u64,
+    percent_blocked: f64,
+}
+
+/// Response of v6 `POST /api/auth`
+#[derive(Debug, Deserialize)]
+struct Auth {
+    session: Session,
+}
+
+#[derive(Debug, Deserialize)]
+struct Session {
+    valid: bool,
+    sid: Option<String>,
+}
+
+/// Response of v5 `/admin/api.php?summaryRaw`
+#[derive(Debug, Deserialize)]
+struct SummaryRaw {
+    dns_queries_today: u64,
+    ads_blocked_today: u64,
+    ads_percentage_today: f64,
+}
+
+impl From<Queries> for Summary {
+    fn from(queries: Queries) -> Self {
+        Summary {
+            queries: queries.total,
+            blocked: queries.blocked,
+            percent: queries.percent_blocked,
+        }
+    }
+}
+
+impl From<SummaryRaw> for Summary {
+    fn from(raw: SummaryRaw) -> Self {
+        Summary {
+            queries: raw.dns_queries_today,
+            blocked: raw.ads_blocked_today,
+            percent: raw.ads_percentage_today,
+        }
+    }
+}
+
+/// Formats summary into line, ex. `PIHOLE 12.3k 18.2%`
+fn to_line(label: &str, summary: Summary) -> Line {
+    Line::new(format!(
+        "{} {} {:.1}%",
+        label,
+        compact_count(summary.blocked),
+        summary.percent
+    ))
+    .with_field("label", label)
+    .with_field("blocked", summary.blocked as f64)
+    .with_field("queries", summary.queries as f64)
+    .with_field("percent", summary.percent)
+    .with_metric(label, summary.percent)
+}
+
+/// Today's blocking of configured Pi-hole
+pub struct PiHoleProvider {
+    config: PiHoleConfig,
+    password: Option<String>,
+    /// v6 session id, logged in again when it expires
+    sid: Mutex<Option<String>>,
+    client: Client,
+}
+
+impl PiHoleProvider {
+    pub fn new(config: PiHoleConfig) -> Self {
+        PiHoleProvider {
+            password: config.password(),
+            config,
+            sid: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.url.trim_end_matches('/'), path)
+    }
+
+    /// v6 session id, `None` when Pi-hole has no password
+    async fn sid(&self) -> Result<Option<String>, BoxError> {
+        if let Some(sid) = self.sid.lock().unwrap().clone() {
+            return Ok(Some(sid));
+        }
+        let Some(password) = &self.password else {
+            return Ok(None);
+        };
+        let auth: Auth = self
+            .client
+            .post(self.url("/api/auth"))
+            .json(&serde_json::json!({ "password": password }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !auth.session.valid {
+            return Err("pihole password was rejected".into());
+        }
+        *self.sid.lock().unwrap() = auth.session.sid.clone();
+        Ok(auth.session.sid)
+    }
+
+    async fn v6(&self) -> Result<Summary, BoxError> {
+        let mut request = self.client.get(self.url("/api/stats/summary"));
+        if let Some(sid) = self.sid().await? {
+            request = request.header("X-FTL-SID", sid);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            // expired session, next fetch logs in again
+            *self.sid.lock().unwrap() = None;
+        }
+        let summary: StatsSummary = response.error_for_status()?.json().await?;
+        Ok(summary.queries.into())
+    }
+
+    async fn v5(&self) -> Result<Summary, BoxError> {
+        let mut query = vec![("summaryRaw", String::new())];
+        if let Some(token) = &self.password {
+            query.push(("auth", token.clone()));
+        }
+        let response = self
+            .client
+            .get(self.url("/admin/api.php"))
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?;
+        // wrong token is answered with empty array
+        let summary: SummaryRaw = serde_json::from_str(&response.text().await?)
+            .map_err(|_| "pihole api token was rejected")?;
+        Ok(summary.into())
+    }
+}
+
+#[async_trait]
+impl DataProvider for PiHoleProvider {
+    fn name(&self) -> &str {
+        "pihole"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching pihole summary from {}", self.config.url);
+
+        let summary = match self.config.api {
+            Api::V6 => self.v6().await?,
+            Api::V5 => self.v5().await?,
+        };
+        Ok(vec![to_line(&self.config.label, summary)])
+    }
+}
+
+#[test]
+fn testing_pihole_line() {
+    PiHoleConfig::default().validate().unwrap();
+
+    let summary: StatsSummary = serde_json::from_str(
+        r#"{"queries":{"total":67210,"blocked":12301,"percent_blocked":18.302,
+            "unique_domains":4120,"forwarded":40122,"cached":14787},
+            "clients":{"active":12,"total":18},"gravity":{"domains_being_blocked":121860}}"#,
+    )
+    .unwrap();
+    let line = to_line("PIHOLE", summary.queries.into());
+    assert_eq!(line.text, "PIHOLE 12.3k 18.3%");
+    assert_eq!(line.metric.unwrap().value, 18.302);
+
+    let raw: SummaryRaw = serde_json::from_str(
+        r#"{"domains_being_blocked":121860,"dns_queries_today":2210,"ads_blocked_today":301,
+            "ads_percentage_today":13.619909,"status":"enabled"}"#,
+    )
+    .unwrap();
+    assert_eq!(to_line("PIHOLE", raw.into()).text, "PIHOLE 301 13.6%");
+    assert!(serde_json::from_str::<SummaryRaw>("[]").is_err());
+}
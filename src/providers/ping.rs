@@ -0,0 +1,299 @@
+//! Latency of watched hosts, ex. `ROUTER 2.1ms` or `VPS DOWN`
+//!
+//! Host without port is pinged with system `ping`, raw ICMP sockets need
+//! privileges this tool shouldn't ask for. Host with port, ex. `vps:22`, is
+//! timed by TCP connect instead, which also gets through firewalls dropping
+//! ICMP. Latency carries metric under label, -1 while host is down, so
+//! [`PingConfig::alert_rules`] alerts keyboard when host stops responding.
+
+use std::{
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::Deserialize;
+use tokio::{net::TcpStream, process::Command};
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// metric value of host which didn't respond
+const DOWN: f64 = -1.0;
+
+/// `[ping]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PingConfig {
+    /// `[LABEL=]host[:port]`, ex. `ROUTER=192.168.1.1` or `VPS=vps.example.com:22`,
+    /// `[::1]:22` for ipv6 with port
+    pub hosts: Vec<String>,
+    /// host not answering within this is down
+    pub timeout_ms: u64,
+    /// alert keyboard when host goes down
+    pub alert: bool,
+}
+
+impl Default for PingConfig {
+    fn default() -> Self {
+        PingConfig {
+            hosts: Vec::new(),
+            timeout_ms: 1000,
+            alert: true,
+        }
+    }
+}
+
+/// Watched host parsed from `[LABEL=]host[:port]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    pub label: String,
+    pub host: String,
+    /// TCP port timed instead of ping
+    pub port: Option<u16>,
+}
+
+impl Host {
+    pub fn parse(entry: &str) -> Result<Host, EloraError> {
+        let invalid = || {
+            EloraError::ConfigInvalid(format!(
+                "ping host {:?} is not in [LABEL=]host[:port] form",
+                entry
+            ))
+        };
+        let (label, target) = match entry.split_once('=') {
+            Some((label, target)) => (Some(label), target),
+            None => (None, entry),
+        };
+        let (host, port) = match target.strip_prefix('[') {
+            // [ipv6]:port or [ipv6]
+            Some(rest) => {
+                let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+                match rest.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None if rest.is_empty() => (host, None),
+                    None => return Err(invalid()),
+                }
+            }
+            // bare ipv6 has more than one colon and no port
+            None => match target.split_once(':') {
+                Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                _ => (target, None),
+            },
+        };
+        let port = port
+            .map(|port| port.parse::<u16>().map_err(|_| invalid()))
+            .transpose()?;
+        let label = label.unwrap_or(host);
+        if host.is_empty() || label.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Host {
+            label: label.to_string(),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl PingConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.hosts.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "ping.hosts needs at least one host".into(),
+            ));
+        }
+        if self.timeout_ms == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "ping.timeout_ms must be greater than 0".into(),
+            ));
+        }
+        self.parse_hosts().map(|_| ())
+    }
+
+    pub fn parse_hosts(&self) -> Result<Vec<Host>, EloraError> {
+        self.hosts.iter().map(|host| Host::parse(host)).collect()
+    }
+
+    /// Alert rule per host, firing when it stops responding. Empty when
+    /// `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        self.parse_hosts()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|host| Rule {
+                text: Some(format!("{} down", host.label)),
+                symbol: host.label,
+                provider: Some("ping".into()),
+                direction: Direction::Below,
+                threshold: 0.0,
+                window: None,
+                urgent: false,
+                notify: true,
+            })
+            .collect()
+    }
+}
+
+/// Round trip in ms from `ping` output, ex. `time=12.3 ms` or `time<1ms`.
+/// Only number before `ms` following `=` or `<` is read, so localized
+/// output like `Zeit=12ms` works too
+fn parse_ping(output: &str) -> Option<f64> {
+    output.match_indices("ms").find_map(|(at, _)| {
+        let before = output[..at].trim_end();
+        let start = before
+            .rfind(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .map_or(0, |i| i + 1);
+        let number = &before[start..];
+        match before[..start].chars().last() {
+            Some('=') | Some('<') if !number.is_empty() => number.parse().ok(),
+            _ => None,
+        }
+    })
+}
+
+/// `ping` arguments for single echo with timeout, per platform
+fn ping_args(host: &str, timeout: Duration) -> Vec<String> {
+    let secs = timeout.as_secs().max(1).to_string();
+    let args = if cfg!(windows) {
+        vec![
+            "-n".into(),
+            "1".into(),
+            "-w".into(),
+            timeout.as_millis().to_string(),
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec!["-c".into(), "1".into(), "-t".into(), secs]
+    } else {
+        vec!["-c".into(), "1".into(), "-W".into(), secs]
+    };
+    args.into_iter().chain([host.to_string()]).collect()
+}
+
+/// Formats latency into line, ex. `ROUTER 2.1ms`, `VPS 48ms` or `VPS DOWN`
+fn to_line(host: &Host, latency: Option<f64>) -> Line {
+    let text = match latency {
+        Some(ms) if ms < 10.0 => format!("{} {:.1}ms", host.label, ms),
+        Some(ms) => format!("{} {:.0}ms", host.label, ms),
+        None => format!("{} DOWN", host.label),
+    };
+    Line::new(text)
+        .with_field("label", host.label.as_str())
+        .with_field("host", host.host.as_str())
+        .with_field("up", if latency.is_some() { 1.0 } else { 0.0 })
+        .with_field("latency", latency.unwrap_or_default())
+        .with_metric(&host.label, latency.unwrap_or(DOWN))
+}
+
+/// Latency of configured hosts
+pub struct PingProvider {
+    hosts: Vec<Host>,
+    timeout: Duration,
+}
+
+impl PingProvider {
+    pub fn new(config: PingConfig) -> Result<Self, BoxError> {
+        Ok(PingProvider {
+            hosts: config.parse_hosts()?,
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+
+    /// latency in ms, `None` when host didn't answer in time. Error is for
+    /// host which couldn't be checked at all, ex. missing `ping`
+    async fn latency(&self, host: &Host) -> Result<Option<f64>, BoxError> {
+        let started = Instant::now();
+        if let Some(port) = host.port {
+            let connect = TcpStream::connect((host.host.as_str(), port));
+            return Ok(match tokio::time::timeout(self.timeout, connect).await {
+                Ok(Ok(_)) => Some(started.elapsed().as_secs_f64() * 1000.0),
+                Ok(Err(e)) => {
+                    log::debug!("Connecting {}:{} failed: {}", host.host, port, e);
+                    None
+                }
+                Err(_) => None,
+            });
+        }
+        let output = Command::new("ping")
+            .args(ping_args(&host.host, self.timeout))
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        // ping's own timeout rounds to seconds, this one is exact
+        let output = match tokio::time::timeout(self.timeout, output).await {
+            Ok(output) => output.map_err(|e| format!("can't run ping: {}", e))?,
+            Err(_) => return Ok(None),
+        };
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(Some(parse_ping(&stdout).unwrap_or_else(|| {
+            started.elapsed().as_secs_f64() * 1000.0
+        })))
+    }
+}
+
+#[async_trait]
+impl DataProvider for PingProvider {
+    fn name(&self) -> &str {
+        "ping"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(10))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let latencies = join_all(self.hosts.iter().map(|host| self.latency(host))).await;
+        let mut lines = Vec::new();
+        for (host, latency) in self.hosts.iter().zip(latencies) {
+            match latency {
+                Ok(latency) => lines.push(to_line(host, latency)),
+                Err(e) => log::error!("Unable to check {}: {}", host.host, e),
+            }
+        }
+        if lines.is_empty() {
+            return Err("no host could be checked".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_ping_lines() {
+    let router = Host::parse("ROUTER=192.168.1.1").unwrap();
+    assert_eq!((router.host.as_str(), router.port), ("192.168.1.1", None));
+    let vps = Host::parse("VPS=vps.example.com:22").unwrap();
+    assert_eq!((vps.host.as_str(), vps.port), ("vps.example.com", Some(22)));
+    assert_eq!(Host::parse("[::1]:22").unwrap().host, "::1");
+    assert_eq!(Host::parse("fe80::1").unwrap().port, None);
+    assert!(Host::parse("vps:ssh").is_err());
+    assert!(Host::parse("VPS=").is_err());
+
+    let linux = "PING 192.168.1.1 (192.168.1.1) 56(84) bytes of data.\n\
+        64 bytes from 192.168.1.1: icmp_seq=1 ttl=64 time=2.14 ms\n\n\
+        --- 192.168.1.1 ping statistics ---\n\
+        1 packets transmitted, 1 received, 0% packet loss, time 0ms\n\
+        rtt min/avg/max/mdev = 2.140/2.140/2.140/0.000 ms";
+    assert_eq!(parse_ping(linux), Some(2.14));
+    let windows = "Reply from 192.168.1.1: bytes=32 time<1ms TTL=64";
+    assert_eq!(parse_ping(windows), Some(1.0));
+    assert_eq!(
+        parse_ping("Antwort von 10.0.0.1: Bytes=32 Zeit=48ms TTL=55"),
+        Some(48.0)
+    );
+
+    assert_eq!(to_line(&router, Some(2.14)).text, "ROUTER 2.1ms");
+    assert_eq!(to_line(&vps, Some(48.2)).text, "VPS 48ms");
+    let down = to_line(&vps, None);
+    assert_eq!(down.text, "VPS DOWN");
+    assert_eq!(down.metric.unwrap().value, DOWN);
+}
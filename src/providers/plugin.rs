@@ -0,0 +1,373 @@
+//! Native plugin providers, shared libraries found in `[plugins]` path.
+//! `.wasm` modules there are sandboxed [`super::wasm`] plugins instead
+//!
+//! Rust trait objects can't cross library boundary, so plugin exports
+//! `elora_plugin_v1` function returning [`PluginV1`] table of C functions,
+//! which is the whole ABI:
+//!
+//! ```c
+//! struct elora_plugin_v1 {
+//!     uint32_t abi_version;   // 1
+//!     uint64_t refresh_secs;  // 0 for global refresh_secs
+//!     void *(*create)(const char *settings_json);
+//!     int32_t (*fetch)(void *state, char **out);
+//!     void (*free_string)(char *text);
+//!     void (*destroy)(void *state);
+//! };
+//! const struct elora_plugin_v1 *elora_plugin_v1(void);
+//! ```
+//!
+//! `create` gets `[plugins.settings.<name>]` table as json and returns state
+//! (null when it fails), `fetch` stores utf-8 text in `out` and returns 0,
+//! or non zero with error message in `out`. Text is lines separated by `\n`
+//! and is handed back to `free_string`, so either side uses own allocator.
+//! Calls come one at a time but not always from same thread. Provider is
+//! named after library file: `libtodo.so`, `libtodo.dylib` and `todo.dll`
+//! are all `todo`. `examples/hello_plugin.rs` is plugin written in rust.
+
+use std::{
+    collections::BTreeMap,
+    ffi::{c_char, c_void, CStr, CString},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use libloading::Library;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// version of [`PluginV1`] layout, plugins reporting other one are refused
+pub const ABI_VERSION: u32 = 1;
+
+/// symbol plugin library exports
+const ENTRY: &str = "elora_plugin_v1";
+
+/// `[plugins]` config section
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// directories searched for plugin libraries
+    pub path: Vec<PathBuf>,
+    /// settings of plugin by its name, handed to it as json
+    pub settings: BTreeMap<String, toml::Value>,
+}
+
+impl PluginsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.path.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "plugins.path needs at least one directory".into(),
+            ));
+        }
+        let found = self.discover();
+        if let Some(name) = self
+            .settings
+            .keys()
+            .find(|name| !found.iter().any(|(plugin, _)| plugin == *name))
+        {
+            return Err(EloraError::ConfigInvalid(format!(
+                "plugins.settings.{} has no plugin library in plugins.path",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// `[plugins.settings.<name>]` as json, empty object without it
+    pub fn settings_json(&self, name: &str) -> Result<String, BoxError> {
+        match self.settings.get(name) {
+            Some(settings) => Ok(serde_json::to_string(settings)?),
+            None => Ok("{}".to_string()),
+        }
+    }
+
+    /// Plugin names and libraries or wasm modules in `path`, sorted by name. Missing
+    /// directories are skipped and first library of same name wins
+    pub fn discover(&self) -> Vec<(String, PathBuf)> {
+        let mut found: Vec<(String, PathBuf)> = Vec::new();
+        for dir in &self.path {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut libraries: Vec<(String, PathBuf)> = entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| Some((plugin_name(&entry.path())?, entry.path())))
+                .collect();
+            libraries.sort();
+            for (name, path) in libraries {
+                if !found.iter().any(|(other, _)| *other == name) {
+                    found.push((name, path));
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+}
+
+/// name of plugin in library or wasm module at `path`, `None` for other
+/// files
+fn plugin_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let name = match path.extension()? {
+        extension if extension == "wasm" => stem,
+        extension if extension == std::env::consts::DLL_EXTENSION => {
+            match std::env::consts::DLL_PREFIX {
+                "" => stem,
+                prefix => stem.strip_prefix(prefix).unwrap_or(stem),
+            }
+        }
+        _ => return None,
+    };
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then(|| name.to_string())
+}
+
+/// Function table plugin returns from `elora_plugin_v1`
+#[repr(C)]
+pub struct PluginV1 {
+    pub abi_version: u32,
+    /// default refresh interval, 0 for global `refresh_secs`
+    pub refresh_secs: u64,
+    pub create: unsafe extern "C" fn(settings: *const c_char) -> *mut c_void,
+    pub fetch: unsafe extern "C" fn(state: *mut c_void, out: *mut *mut c_char) -> i32,
+    pub free_string: unsafe extern "C" fn(text: *mut c_char),
+    pub destroy: unsafe extern "C" fn(state: *mut c_void),
+}
+
+type Entry = unsafe extern "C" fn() -> *const PluginV1;
+
+/// Plugin state created from settings, destroyed on drop
+struct Instance {
+    table: *const PluginV1,
+    state: *mut c_void,
+    /// keeps library table is in loaded until state is destroyed, `None`
+    /// for table of host itself
+    _library: Option<Library>,
+}
+
+// SAFETY: plugins must accept calls from any thread, and `Mutex` around
+// instance keeps them one at a time
+unsafe impl Send for Instance {}
+
+impl Instance {
+    /// Starts plugin of `table` with `settings` json. Table has to stay
+    /// valid for as long as `library` is loaded
+    fn new(
+        library: Option<Library>,
+        table: *const PluginV1,
+        settings: &str,
+    ) -> Result<Self, BoxError> {
+        // SAFETY: table is null or points to function table plugin returned
+        let table =
+            unsafe { table.as_ref() }.ok_or("elora_plugin_v1 returned no function table")?;
+        if table.abi_version != ABI_VERSION {
+            return Err(format!(
+                "plugin abi version {} is not supported, expected {}",
+                table.abi_version, ABI_VERSION
+            )
+            .into());
+        }
+        let settings = CString::new(settings)?;
+        // SAFETY: settings is nul terminated and outlives the call
+        let state = unsafe { (table.create)(settings.as_ptr()) };
+        if state.is_null() {
+            return Err("plugin failed to start with given settings".into());
+        }
+        Ok(Instance {
+            table,
+            state,
+            _library: library,
+        })
+    }
+
+    fn table(&self) -> &PluginV1 {
+        // SAFETY: table was checked in new and library it's in stays loaded
+        unsafe { &*self.table }
+    }
+
+    fn fetch(&mut self) -> Result<String, BoxError> {
+        let mut out: *mut c_char = std::ptr::null_mut();
+        // SAFETY: state came from create of same table and isn't destroyed
+        let code = unsafe { (self.table().fetch)(self.state, &mut out) };
+        let text = if out.is_null() {
+            String::new()
+        } else {
+            // SAFETY: plugin stores nul terminated text it owns in out
+            let text = unsafe { CStr::from_ptr(out) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { (self.table().free_string)(out) };
+            text
+        };
+        match code {
+            0 => Ok(text),
+            _ if text.is_empty() => Err(format!("plugin fetch failed with code {}", code).into()),
+            _ => Err(text.into()),
+        }
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        // SAFETY: state came from create of same table
+        unsafe { (self.table().destroy)(self.state) };
+    }
+}
+
+/// Loads plugin library and starts it with `settings` json
+fn load(path: &Path, settings: &str) -> Result<Instance, BoxError> {
+    // SAFETY: plugins are trusted like binary itself, loading runs their
+    // initializers and they export `elora_plugin_v1` with `Entry` signature
+    let library = unsafe { Library::new(path)? };
+    let table = unsafe { library.get::<Entry>(ENTRY.as_bytes())?() };
+    Instance::new(Some(library), table, settings)
+}
+
+pub struct PluginProvider {
+    name: String,
+    refresh: Option<Duration>,
+    instance: Arc<Mutex<Instance>>,
+}
+
+impl PluginProvider {
+    fn from_instance(name: String, instance: Instance) -> Self {
+        let refresh = Some(instance.table().refresh_secs)
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
+        PluginProvider {
+            name,
+            refresh,
+            instance: Arc::new(Mutex::new(instance)),
+        }
+    }
+
+    /// Loads plugin named `name` from library at `path`
+    pub fn new(name: &str, path: &Path, config: &PluginsConfig) -> Result<Self, EloraError> {
+        let instance = config
+            .settings_json(name)
+            .and_then(|settings| load(path, &settings))
+            .map_err(|source| {
+                EloraError::ConfigInvalid(format!(
+                    "plugin {} can't load {}: {}",
+                    name,
+                    path.display(),
+                    source
+                ))
+            })?;
+        log::info!("Loaded plugin {} from {}", name, path.display());
+        Ok(PluginProvider::from_instance(name.to_string(), instance))
+    }
+}
+
+#[async_trait]
+impl DataProvider for PluginProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        // plugin code blocks, so it doesn't run on runtime threads
+        let instance = self.instance.clone();
+        let text = tokio::task::spawn_blocking(move || instance.lock().unwrap().fetch()).await??;
+        Ok(text
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .map(Line::new)
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn testing_plugin_abi() {
+    unsafe extern "C" fn create(settings: *const c_char) -> *mut c_void {
+        let settings = CStr::from_ptr(settings).to_str().unwrap().to_string();
+        Box::into_raw(Box::new(settings)) as *mut c_void
+    }
+    unsafe extern "C" fn fetch(state: *mut c_void, out: *mut *mut c_char) -> i32 {
+        let settings = &*(state as *const String);
+        let text = format!("SETTINGS {}\nTODO 3\n", settings);
+        *out = CString::new(text).unwrap().into_raw();
+        0
+    }
+    unsafe extern "C" fn free_string(text: *mut c_char) {
+        drop(CString::from_raw(text));
+    }
+    unsafe extern "C" fn destroy(state: *mut c_void) {
+        drop(Box::from_raw(state as *mut String));
+    }
+    static TABLE: PluginV1 = PluginV1 {
+        abi_version: ABI_VERSION,
+        refresh_secs: 30,
+        create,
+        fetch,
+        free_string,
+        destroy,
+    };
+
+    let provider =
+        PluginProvider::from_instance("todo".into(), Instance::new(None, &TABLE, "{}").unwrap());
+    assert_eq!(provider.refresh_interval(), Some(Duration::from_secs(30)));
+    let lines: Vec<String> = provider
+        .fetch()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["SETTINGS {}", "TODO 3"]);
+
+    let library = format!(
+        "{}todo.{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_EXTENSION
+    );
+    assert_eq!(plugin_name(Path::new(&library)).as_deref(), Some("todo"));
+    assert_eq!(plugin_name(Path::new("todo.wasm")).as_deref(), Some("todo"));
+    assert_eq!(plugin_name(Path::new("todo.txt")), None);
+}
+
+#[tokio::test]
+async fn testing_plugin_loading() {
+    // `cargo test` builds examples next to test binary's deps directory
+    let examples = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .with_file_name("examples");
+    assert!(load(&examples.join("missing.so"), "{}").is_err());
+
+    let config = PluginsConfig {
+        path: vec![examples.clone()],
+        settings: BTreeMap::from([(
+            "hello_plugin".to_string(),
+            toml::Value::Table(toml::toml! { greeting = "hi" }),
+        )]),
+    };
+    let Some((name, path)) = config
+        .discover()
+        .into_iter()
+        .find(|(name, _)| name == "hello_plugin")
+    else {
+        // `cargo test --lib` doesn't build examples
+        eprintln!("skipping, hello_plugin example isn't built, see `cargo build --examples`");
+        return;
+    };
+    let provider = PluginProvider::new(&name, &path, &config).unwrap();
+    assert_eq!(provider.refresh_interval(), Some(Duration::from_secs(60)));
+    let lines = provider.fetch().await.unwrap();
+    assert_eq!(lines[0].text, "HELLO {\"greeting\":\"hi\"}");
+}
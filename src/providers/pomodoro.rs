@@ -0,0 +1,395 @@
+//! Pomodoro timer, ex. `WORK 24:59`
+//!
+//! Timer lives in daemon, started and stopped with `elora_hid pomodoro`
+//! (through status socket) or key on keyboard sending [`Command::Pomodoro`].
+//! Remaining time is redrawn every second, and when interval ends its metric
+//! drops to 0 once, which alerts keyboard through rules from
+//! [`PomodoroConfig::alert_rules`].
+//!
+//! [`Command::Pomodoro`]: crate::protocol::Command::Pomodoro
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    protocol::{POMODORO_START, POMODORO_STOP, POMODORO_TOGGLE},
+    BoxError, EloraError,
+};
+
+/// symbol of seconds left in work interval metric
+pub const WORK_METRIC: &str = "pomodoro_work";
+/// symbol of seconds left in break metric, short and long break alike
+pub const BREAK_METRIC: &str = "pomodoro_break";
+
+/// timer shared by provider, status socket and keyboard messages, so it
+/// keeps running when config is reloaded
+static TIMER: Mutex<Timer> = Mutex::new(Timer::new());
+/// notified when timer is started or stopped, so display doesn't wait for
+/// next second
+static CHANGED: Notify = Notify::const_new();
+
+/// `[pomodoro]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PomodoroConfig {
+    pub work_mins: u64,
+    pub break_mins: u64,
+    pub long_break_mins: u64,
+    /// every this many work intervals break is long one
+    pub long_break_every: u32,
+    /// start next interval right away instead of waiting for start
+    pub auto_next: bool,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            work_mins: 25,
+            break_mins: 5,
+            long_break_mins: 15,
+            long_break_every: 4,
+            auto_next: false,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.work_mins == 0 || self.break_mins == 0 || self.long_break_mins == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "pomodoro intervals must be at least a minute".into(),
+            ));
+        }
+        if self.long_break_every == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "pomodoro.long_break_every must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rules alerting keyboard when work interval or break ends
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        let rule = |symbol: &str, text: &str| Rule {
+            symbol: symbol.into(),
+            provider: Some("pomodoro".into()),
+            direction: Direction::Below,
+            threshold: 1.0,
+            window: None,
+            text: Some(text.into()),
+            urgent: false,
+            notify: true,
+        };
+        vec![
+            rule(WORK_METRIC, "Pomodoro done, take a break"),
+            rule(BREAK_METRIC, "Break over, back to work"),
+        ]
+    }
+
+    fn duration(&self, phase: Phase) -> Duration {
+        let mins = match phase {
+            Phase::Work => self.work_mins,
+            Phase::Break => self.break_mins,
+            Phase::LongBreak => self.long_break_mins,
+        };
+        Duration::from_secs(mins * 60)
+    }
+}
+
+/// What start, stop or toggle asks of timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    /// start when stopped, stop when running, ex. for single key
+    Toggle,
+}
+
+impl Action {
+    /// action named in status socket request, ex. `start`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "start" => Some(Action::Start),
+            "stop" => Some(Action::Stop),
+            "toggle" => Some(Action::Toggle),
+            _ => None,
+        }
+    }
+
+    /// action of [`Command::Pomodoro`](crate::protocol::Command::Pomodoro)
+    /// payload, `None` when it's unknown
+    pub fn from_payload(payload: &[u8]) -> Option<Self> {
+        match *payload.first()? {
+            POMODORO_START => Some(Action::Start),
+            POMODORO_STOP => Some(Action::Stop),
+            POMODORO_TOGGLE => Some(Action::Toggle),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Stop => "stop",
+            Action::Toggle => "toggle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+impl Phase {
+    /// as drawn on display, ex. `WORK`
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Work => "WORK",
+            Phase::Break => "BREAK",
+            Phase::LongBreak => "LONG",
+        }
+    }
+}
+
+/// What timer is at, reported to `elora_hid pomodoro`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PomodoroStatus {
+    pub phase: Phase,
+    pub running: bool,
+    /// left of running interval, or whole next one when stopped
+    pub left_secs: u64,
+    /// work intervals finished so far
+    pub done: u32,
+}
+
+struct Timer {
+    /// config of current provider, `None` while pomodoro isn't enabled
+    config: Option<Arc<PomodoroConfig>>,
+    phase: Phase,
+    /// end of running interval
+    deadline: Option<Instant>,
+    done: u32,
+    /// phase which ended and wasn't reported with 0 metric yet
+    ended: Option<Phase>,
+}
+
+impl Timer {
+    const fn new() -> Self {
+        Timer {
+            config: None,
+            phase: Phase::Work,
+            deadline: None,
+            done: 0,
+            ended: None,
+        }
+    }
+
+    fn apply(&mut self, action: Action, now: Instant) -> Result<(), EloraError> {
+        let Some(config) = self.config.clone() else {
+            return Err(EloraError::ConfigInvalid(
+                "pomodoro is not enabled, add [pomodoro] section".into(),
+            ));
+        };
+        match (action, self.deadline) {
+            (Action::Start | Action::Toggle, None) => {
+                self.deadline = Some(now + config.duration(self.phase));
+            }
+            // stopping skips rest of break too, next start is work again
+            (Action::Stop | Action::Toggle, Some(_)) => {
+                self.deadline = None;
+                self.phase = Phase::Work;
+            }
+            (Action::Start, Some(_)) | (Action::Stop, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Moves to next phase when running interval is over
+    fn tick(&mut self, now: Instant) {
+        let (Some(config), Some(deadline)) = (self.config.clone(), self.deadline) else {
+            return;
+        };
+        if deadline > now {
+            return;
+        }
+        self.ended = Some(self.phase);
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.done += 1;
+                match self.done % config.long_break_every {
+                    0 => Phase::LongBreak,
+                    _ => Phase::Break,
+                }
+            }
+            Phase::Break | Phase::LongBreak => Phase::Work,
+        };
+        self.deadline = config.auto_next.then(|| now + config.duration(self.phase));
+    }
+
+    fn status(&self, now: Instant) -> PomodoroStatus {
+        let left = match (self.deadline, &self.config) {
+            (Some(deadline), _) => deadline.saturating_duration_since(now),
+            (None, Some(config)) => config.duration(self.phase),
+            (None, None) => Duration::ZERO,
+        };
+        PomodoroStatus {
+            phase: self.phase,
+            running: self.deadline.is_some(),
+            // rounded up, so timer shows 00:01 in its last second
+            left_secs: left.as_millis().div_ceil(1000) as u64,
+            done: self.done,
+        }
+    }
+
+    /// Line of current state. Metric is seconds left while running, and 0
+    /// once right after interval ended
+    fn line(&mut self, now: Instant) -> Line {
+        self.tick(now);
+        let status = self.status(now);
+        let left = format!("{:02}:{:02}", status.left_secs / 60, status.left_secs % 60);
+        let text = match status.running {
+            true => format!("{} {}", status.phase.label(), left),
+            false => format!("{} {} ready", status.phase.label(), left),
+        };
+        let line = Line::new(text)
+            .with_field("phase", status.phase.label())
+            .with_field("left", left)
+            .with_field("state", if status.running { "running" } else { "ready" })
+            .with_field("done", status.done as f64);
+        let metric = |phase: Phase| match phase {
+            Phase::Work => WORK_METRIC,
+            Phase::Break | Phase::LongBreak => BREAK_METRIC,
+        };
+        match (self.ended.take(), status.running) {
+            (Some(ended), _) => line.with_metric(metric(ended), 0.0),
+            (None, true) => line.with_metric(metric(status.phase), status.left_secs as f64),
+            (None, false) => line,
+        }
+    }
+}
+
+/// Starts or stops timer, returning where it's at afterwards. Fails when
+/// pomodoro isn't enabled in config
+pub fn control(action: Action) -> Result<PomodoroStatus, EloraError> {
+    let now = Instant::now();
+    let mut timer = TIMER.lock().unwrap();
+    timer.tick(now);
+    timer.apply(action, now)?;
+    log::info!("Pomodoro {}", action.name());
+    CHANGED.notify_waiters();
+    Ok(timer.status(now))
+}
+
+/// Where timer is at, `None` while pomodoro isn't enabled
+pub fn status() -> Option<PomodoroStatus> {
+    let now = Instant::now();
+    let mut timer = TIMER.lock().unwrap();
+    timer.config.as_ref()?;
+    timer.tick(now);
+    Some(timer.status(now))
+}
+
+/// Remaining time of shared pomodoro timer
+pub struct PomodoroProvider {
+    config: Arc<PomodoroConfig>,
+}
+
+impl PomodoroProvider {
+    pub fn new(config: PomodoroConfig) -> Self {
+        let config = Arc::new(config);
+        TIMER.lock().unwrap().config = Some(config.clone());
+        PomodoroProvider { config }
+    }
+}
+
+impl Drop for PomodoroProvider {
+    fn drop(&mut self) {
+        let mut timer = TIMER.lock().unwrap();
+        // provider of reloaded config may have taken over already
+        if timer
+            .config
+            .as_ref()
+            .is_some_and(|config| Arc::ptr_eq(config, &self.config))
+        {
+            timer.config = None;
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for PomodoroProvider {
+    fn name(&self) -> &str {
+        "pomodoro"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        Ok(vec![TIMER.lock().unwrap().line(Instant::now())])
+    }
+
+    async fn changed(&self) {
+        CHANGED.notified().await
+    }
+}
+
+#[test]
+fn testing_pomodoro_timer() {
+    let config = PomodoroConfig {
+        long_break_every: 2,
+        ..PomodoroConfig::default()
+    };
+    config.validate().unwrap();
+    let mut timer = Timer::new();
+    let start = Instant::now();
+    assert!(timer.apply(Action::Start, start).is_err());
+    timer.config = Some(Arc::new(config));
+
+    let line = timer.line(start);
+    assert_eq!(line.text, "WORK 25:00 ready");
+    assert_eq!(line.metric, None);
+
+    timer.apply(Action::Toggle, start).unwrap();
+    let line = timer.line(start + Duration::from_millis(1500));
+    assert_eq!(line.text, "WORK 24:59");
+    assert_eq!(line.metric.unwrap().symbol, WORK_METRIC);
+
+    // end of interval drops metric to 0 once, which alerts keyboard
+    let end = start + Duration::from_secs(25 * 60);
+    let line = timer.line(end);
+    assert_eq!(line.text, "BREAK 05:00 ready");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+    assert_eq!(timer.line(end).metric, None);
+
+    timer.apply(Action::Start, end).unwrap();
+    timer.tick(end + Duration::from_secs(5 * 60));
+    timer
+        .apply(Action::Start, end + Duration::from_secs(5 * 60))
+        .unwrap();
+    timer.tick(end + Duration::from_secs(30 * 60));
+    assert_eq!(timer.phase, Phase::LongBreak);
+    assert_eq!(timer.done, 2);
+
+    timer.apply(Action::Start, end).unwrap();
+    timer.apply(Action::Stop, end).unwrap();
+    assert_eq!(timer.status(end).phase, Phase::Work);
+    assert!(!timer.status(end).running);
+    assert_eq!(
+        Action::from_payload(&[POMODORO_TOGGLE]),
+        Some(Action::Toggle)
+    );
+}
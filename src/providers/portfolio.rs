@@ -0,0 +1,282 @@
+//! Value and profit of stock holdings
+//!
+//! Quotes are fetched same way as by stocks provider and converted with
+//! `[fx]` rates when display currency is set, so holdings in different
+//! currencies add up.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    currency_sign,
+    fx::{FxConfig, FxRates},
+    stocks::{self, Quotes},
+    DataProvider, Line,
+};
+use crate::{
+    market::{self, MarketConfig},
+    BoxError, EloraError,
+};
+
+/// `[portfolio]` config section, ticker -> holding
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PortfolioConfig {
+    /// ex. `TSLA = { shares = 10, cost_basis = 180 }`
+    pub holdings: BTreeMap<String, Holding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Holding {
+    pub shares: f64,
+    /// price paid per share
+    pub cost_basis: f64,
+}
+
+impl PortfolioConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.holdings.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "portfolio.holdings needs at least one ticker".into(),
+            ));
+        }
+        if let Some((ticker, _)) = self
+            .holdings
+            .iter()
+            .find(|(_, h)| h.shares <= 0.0 || h.cost_basis < 0.0)
+        {
+            return Err(EloraError::ConfigInvalid(format!(
+                "portfolio holding {} needs positive shares and cost_basis",
+                ticker
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Totals of all holdings
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub value: f64,
+    pub cost: f64,
+    /// change since previous close, of holdings it is known for
+    pub day_change: f64,
+    /// value at previous close, day change percent is computed against it
+    pub previous_value: f64,
+}
+
+impl Summary {
+    pub fn total_pl(&self) -> f64 {
+        self.value - self.cost
+    }
+
+    pub fn total_pl_percent(&self) -> Option<f64> {
+        percent(self.total_pl(), self.cost)
+    }
+
+    pub fn day_change_percent(&self) -> Option<f64> {
+        percent(self.day_change, self.previous_value)
+    }
+}
+
+fn percent(change: f64, base: f64) -> Option<f64> {
+    if base == 0.0 {
+        None
+    } else {
+        Some(change / base * 100.0)
+    }
+}
+
+/// Adds up holdings at fetched quotes. Holdings without price (fetch failed)
+/// are left out, so they don't show up as total loss
+pub fn summarize(holdings: &BTreeMap<String, Holding>, quotes: &Quotes) -> Summary {
+    let mut summary = Summary::default();
+    for (ticker, holding) in holdings {
+        let Some(quote) = quotes.get(ticker).filter(|q| q.price != 0.0) else {
+            continue;
+        };
+        summary.value += holding.shares * quote.price;
+        summary.cost += holding.shares * holding.cost_basis;
+        if let Some(previous) = quote.previous_close {
+            summary.day_change += holding.shares * (quote.price - previous);
+            summary.previous_value += holding.shares * previous;
+        }
+    }
+    summary
+}
+
+/// ex. `▲123$ 1.0%`
+fn change_text(change: f64, percent: Option<f64>, sign: &str) -> String {
+    let arrow = if change < 0.0 { '▼' } else { '▲' };
+    match percent {
+        Some(percent) => format!("{}{:.0}{} {:.1}%", arrow, change.abs(), sign, percent.abs()),
+        None => format!("{}{:.0}{}", arrow, change.abs(), sign),
+    }
+}
+
+/// Lines of portfolio page: value, day change and total profit or loss
+pub fn to_lines(summary: &Summary, sign: &str) -> Vec<Line> {
+    vec![
+        Line::new(format!("PORT {:.0}{}", summary.value, sign)).with_metric("PORT", summary.value),
+        Line::new(format!(
+            "DAY {}",
+            change_text(summary.day_change, summary.day_change_percent(), sign)
+        )),
+        Line::new(format!(
+            "P&L {}",
+            change_text(summary.total_pl(), summary.total_pl_percent(), sign)
+        )),
+    ]
+}
+
+/// Portfolio value and profit
+pub struct PortfolioProvider {
+    config: PortfolioConfig,
+    tickers: Vec<String>,
+    client: Client,
+    fx: Option<(FxConfig, Arc<FxRates>)>,
+    market: Option<MarketConfig>,
+    /// lines of last fetch, shown while markets of all holdings are closed
+    last_lines: Mutex<Option<Vec<Line>>>,
+}
+
+impl PortfolioProvider {
+    pub fn new(config: PortfolioConfig) -> Result<Self, BoxError> {
+        Ok(PortfolioProvider {
+            tickers: config.holdings.keys().cloned().collect(),
+            config,
+            client: stocks::client()?,
+            fx: None,
+            market: None,
+            last_lines: Mutex::new(None),
+        })
+    }
+
+    /// converts prices into `[fx]` display currency before adding them up
+    pub fn with_fx(mut self, config: FxConfig, rates: Arc<FxRates>) -> Self {
+        self.fx = Some((config, rates));
+        self
+    }
+
+    /// skips refetching while exchanges of all holdings are closed
+    pub fn with_market(mut self, config: MarketConfig) -> Self {
+        self.market = Some(config);
+        self
+    }
+
+    async fn fetch_lines(&self) -> Result<Vec<Line>, BoxError> {
+        let mut quotes = stocks::fetch_quotes(&self.client, &self.tickers).await?;
+        if quotes.values().all(|quote| quote.price == 0.0) {
+            return Err("no holding could be fetched".into());
+        }
+        if let Some((config, rates)) = &self.fx {
+            stocks::apply_fx(&mut quotes, config, &rates.get().await?);
+        }
+
+        let mut currencies: Vec<&str> = quotes
+            .values()
+            .filter_map(|q| q.currency.as_deref())
+            .collect();
+        currencies.sort();
+        currencies.dedup();
+        let sign = match currencies.as_slice() {
+            [] => currency_sign("USD"),
+            [currency] => currency_sign(currency),
+            _ => {
+                log::warn!("Portfolio mixes currencies, set fx.display_currency to convert them");
+                String::new()
+            }
+        };
+
+        Ok(to_lines(&summarize(&self.config.holdings, &quotes), &sign))
+    }
+}
+
+#[async_trait]
+impl DataProvider for PortfolioProvider {
+    fn name(&self) -> &str {
+        "portfolio"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let closed = self
+            .market
+            .as_ref()
+            .is_some_and(|market| market.all_closed(self.tickers.iter(), &Utc::now()));
+        let cached = if closed {
+            self.last_lines.lock().unwrap().clone()
+        } else {
+            None
+        };
+        let mut lines = match cached {
+            Some(lines) => {
+                log::debug!("Markets of all holdings closed, skipping fetch");
+                lines
+            }
+            None => {
+                let lines = self.fetch_lines().await?;
+                *self.last_lines.lock().unwrap() = Some(lines.clone());
+                lines
+            }
+        };
+        if closed && !lines.is_empty() {
+            let first = lines.remove(0);
+            lines.insert(0, market::mark_closed(first));
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_portfolio_summary() {
+    use stocks::Quote;
+
+    let holdings = BTreeMap::from([
+        (
+            "TSLA".to_string(),
+            Holding {
+                shares: 10.0,
+                cost_basis: 180.0,
+            },
+        ),
+        (
+            "NVDA".to_string(),
+            Holding {
+                shares: 2.0,
+                cost_basis: 500.0,
+            },
+        ),
+    ]);
+    let quotes = Quotes::from([
+        (
+            "TSLA".to_string(),
+            Quote {
+                price: 240.0,
+                previous_close: Some(250.0),
+                currency: Some("USD".into()),
+                extended: None,
+                day_range: None,
+                year_range: None,
+            },
+        ),
+        ("NVDA".to_string(), Quote::default()),
+    ]);
+
+    let summary = summarize(&holdings, &quotes);
+    assert_eq!(summary.value, 2400.0);
+    assert_eq!(summary.total_pl(), 600.0);
+    assert_eq!(summary.day_change, -100.0);
+
+    let lines = to_lines(&summary, "$");
+    assert_eq!(lines[0].text, "PORT 2400$");
+    assert_eq!(lines[1].text, "DAY ▼100$ 4.0%");
+    assert_eq!(lines[2].text, "P&L ▲600$ 33.3%");
+}
@@ -0,0 +1,420 @@
+//! 3D print progress from OctoPrint or Moonraker (Klipper), ex.
+//! `3D 42% 1h12m 215C` while printing or `3D idle 24C`
+//!
+//! Print finishing sets [`DONE_METRIC`] to 1 for one fetch, so
+//! [`PrinterConfig::alert_rules`] alerts keyboard once per finished print.
+//! First fetch only remembers state, print done before start isn't alerted.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// env var api key is read from when config has none
+pub const KEY_ENV: &str = "OCTOPRINT_API_KEY";
+/// metric symbol which is 1 on fetch print finished in
+pub const DONE_METRIC: &str = "printer_done";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Server {
+    #[default]
+    OctoPrint,
+    Moonraker,
+}
+
+/// `[printer]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrinterConfig {
+    pub server: Server,
+    /// ex. `http://octopi.local` or `http://klipper.local:7125`
+    pub url: String,
+    /// api key from OctoPrint settings, `OCTOPRINT_API_KEY` env without it.
+    /// Moonraker needs it only when its authorization is on
+    pub key: Option<String>,
+    /// alert keyboard when print is done
+    pub alert: bool,
+    pub label: String,
+}
+
+impl Default for PrinterConfig {
+    fn default() -> Self {
+        PrinterConfig {
+            server: Server::default(),
+            url: String::new(),
+            key: None,
+            alert: true,
+            label: "3D".into(),
+        }
+    }
+}
+
+impl PrinterConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "printer.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        Ok(())
+    }
+
+    /// Alert rule firing once per finished print. Empty when `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        vec![Rule {
+            symbol: DONE_METRIC.into(),
+            provider: Some("printer".into()),
+            direction: Direction::Above,
+            threshold: 0.0,
+            window: None,
+            text: Some("Print done".into()),
+            urgent: false,
+            notify: true,
+        }]
+    }
+
+    fn key(&self) -> Option<String> {
+        self.key.clone().or_else(|| std::env::var(KEY_ENV).ok())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Printing,
+    Paused,
+    /// last print finished and printer is idle since
+    Done,
+    Idle,
+    /// server runs, printer isn't connected to it
+    Offline,
+}
+
+/// Printer state of either server
+#[derive(Debug, Clone, PartialEq)]
+struct Status {
+    state: State,
+    /// percent of print done
+    progress: Option<f64>,
+    /// seconds until print is done
+    left: Option<u64>,
+    /// current hotend °C
+    hotend: Option<f64>,
+    file: Option<String>,
+}
+
+/// Response of OctoPrint `/api/job`
+#[derive(Debug, Deserialize)]
+struct OctoJob {
+    job: OctoJobFile,
+    progress: OctoProgress,
+    /// ex. `Printing`, `Paused`, `Operational` or `Offline`
+    state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OctoJobFile {
+    file: OctoFile,
+}
+
+#[derive(Debug, Deserialize)]
+struct OctoFile {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OctoProgress {
+    completion: Option<f64>,
+    #[serde(rename = "printTimeLeft")]
+    print_time_left: Option<u64>,
+}
+
+/// Response of OctoPrint `/api/printer?exclude=sd,state`
+#[derive(Debug, Deserialize)]
+struct OctoPrinter {
+    temperature: OctoTemperature,
+}
+
+#[derive(Debug, Deserialize)]
+struct OctoTemperature {
+    tool0: Option<OctoTool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OctoTool {
+    actual: Option<f64>,
+}
+
+fn octoprint_status(job: OctoJob, hotend: Option<f64>) -> Status {
+    let state = match job.state.as_str() {
+        "Printing" | "Starting" | "Finishing" => State::Printing,
+        "Paused" | "Pausing" | "Resuming" => State::Paused,
+        state if state.starts_with("Offline") || state == "Closed" => State::Offline,
+        // job stays loaded after print, fully completed one is done
+        _ if job.progress.completion.is_some_and(|done| done >= 100.0) => State::Done,
+        _ => State::Idle,
+    };
+    Status {
+        state,
+        progress: job.progress.completion,
+        left: job.progress.print_time_left,
+        hotend,
+        file: job.job.file.name,
+    }
+}
+
+/// Response of Moonraker `/printer/objects/query`
+#[derive(Debug, Deserialize)]
+struct MoonrakerQuery {
+    result: MoonrakerResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoonrakerResult {
+    status: MoonrakerStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoonrakerStatus {
+    print_stats: PrintStats,
+    /// 0 to 1 of file read
+    virtual_sdcard: VirtualSdcard,
+    extruder: Option<Extruder>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrintStats {
+    /// `standby`, `printing`, `paused`, `complete`, `cancelled` or `error`
+    state: String,
+    filename: String,
+    /// seconds spent printing, without pauses
+    print_duration: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct VirtualSdcard {
+    progress: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Extruder {
+    temperature: f64,
+}
+
+fn moonraker_status(status: MoonrakerStatus) -> Status {
+    let stats = status.print_stats;
+    let state = match stats.state.as_str() {
+        "printing" => State::Printing,
+        "paused" => State::Paused,
+        "complete" => State::Done,
+        _ => State::Idle,
+    };
+    let progress = status.virtual_sdcard.progress;
+    // Klipper doesn't estimate, time so far is scaled by progress
+    let left = (state == State::Printing && progress > 0.0)
+        .then(|| (stats.print_duration / progress - stats.print_duration) as u64);
+    Status {
+        state,
+        progress: Some(progress * 100.0),
+        left,
+        hotend: status.extruder.map(|extruder| extruder.temperature),
+        file: Some(stats.filename).filter(|name| !name.is_empty()),
+    }
+}
+
+/// time left, ex. `1h12m` or `12m`
+fn format_left(secs: u64) -> String {
+    match secs {
+        0..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Formats status into line, ex. `3D 42% 1h12m 215C`, `3D paused 42%` or
+/// `3D idle 24C`
+fn to_line(config: &PrinterConfig, status: &Status, done: bool) -> Line {
+    let mut text = config.label.clone();
+    let mut line = Line::new("").with_field("label", config.label.as_str());
+    let state = match status.state {
+        State::Printing => "printing",
+        State::Paused => "paused",
+        State::Done => "done",
+        State::Idle => "idle",
+        State::Offline => "offline",
+    };
+    line = line.with_field("state", state);
+    let progress = status
+        .progress
+        .filter(|_| matches!(status.state, State::Printing | State::Paused));
+    if status.state != State::Printing {
+        text.push_str(&format!(" {}", state));
+    }
+    if let Some(progress) = progress {
+        text.push_str(&format!(" {:.0}%", progress));
+        line = line.with_field("progress", progress);
+    }
+    if let Some(left) = status.left.filter(|_| status.state == State::Printing) {
+        text.push_str(&format!(" {}", format_left(left)));
+        line = line.with_field("left", format_left(left));
+    }
+    if let Some(hotend) = status.hotend.filter(|_| status.state != State::Paused) {
+        text.push_str(&format!(" {:.0}C", hotend));
+        line = line.with_field("hotend", hotend);
+    }
+    if let Some(file) = &status.file {
+        line = line.with_field("file", file.as_str());
+    }
+    line.text = text;
+    line.with_metric(DONE_METRIC, if done { 1.0 } else { 0.0 })
+}
+
+/// Print of configured OctoPrint or Moonraker server
+pub struct PrinterProvider {
+    config: PrinterConfig,
+    key: Option<String>,
+    /// state of previous fetch, `None` before first one
+    last: Mutex<Option<State>>,
+    client: Client,
+}
+
+impl PrinterProvider {
+    pub fn new(config: PrinterConfig) -> Self {
+        PrinterProvider {
+            key: config.key(),
+            config,
+            last: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let url = format!("{}{}", self.config.url.trim_end_matches('/'), path);
+        let request = self.client.get(url);
+        match &self.key {
+            Some(key) => request.header("X-Api-Key", key),
+            None => request,
+        }
+    }
+
+    async fn octoprint(&self) -> Result<Status, BoxError> {
+        let job: OctoJob = self
+            .get("/api/job")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let printer = self.get("/api/printer?exclude=sd,state").send().await?;
+        // 409 while printer isn't connected to OctoPrint
+        let hotend = match printer.status() {
+            StatusCode::CONFLICT => None,
+            _ => {
+                let printer: OctoPrinter = printer.error_for_status()?.json().await?;
+                printer.temperature.tool0.and_then(|tool| tool.actual)
+            }
+        };
+        Ok(octoprint_status(job, hotend))
+    }
+
+    async fn moonraker(&self) -> Result<Status, BoxError> {
+        let request = self
+            .get("/printer/objects/query?print_stats&virtual_sdcard&extruder")
+            .send()
+            .await?;
+        // klippy isn't connected to printer
+        if request.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(Status {
+                state: State::Offline,
+                progress: None,
+                left: None,
+                hotend: None,
+                file: None,
+            });
+        }
+        let query: MoonrakerQuery = request.error_for_status()?.json().await?;
+        Ok(moonraker_status(query.result.status))
+    }
+}
+
+#[async_trait]
+impl DataProvider for PrinterProvider {
+    fn name(&self) -> &str {
+        "printer"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(15))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching printer status from {}", self.config.url);
+
+        let status = match self.config.server {
+            Server::OctoPrint => self.octoprint().await?,
+            Server::Moonraker => self.moonraker().await?,
+        };
+        let previous = self.last.lock().unwrap().replace(status.state);
+        let done = status.state == State::Done
+            && matches!(previous, Some(State::Printing | State::Paused));
+        Ok(vec![to_line(&self.config, &status, done)])
+    }
+}
+
+#[test]
+fn testing_printer_lines() {
+    let config = PrinterConfig {
+        url: "http://octopi.local".into(),
+        ..PrinterConfig::default()
+    };
+    config.validate().unwrap();
+
+    let job: OctoJob = serde_json::from_str(
+        r#"{"job":{"file":{"name":"benchy.gcode","origin":"local"},"estimatedPrintTime":7200},
+            "progress":{"completion":42.3,"filepos":120000,"printTime":3100,"printTimeLeft":4320},
+            "state":"Printing"}"#,
+    )
+    .unwrap();
+    let printer: OctoPrinter = serde_json::from_str(
+        r#"{"temperature":{"tool0":{"actual":214.8,"target":215.0,"offset":0},
+            "bed":{"actual":60.1,"target":60.0,"offset":0}}}"#,
+    )
+    .unwrap();
+    let status = octoprint_status(job, printer.temperature.tool0.unwrap().actual);
+    let line = to_line(&config, &status, false);
+    assert_eq!(line.text, "3D 42% 1h12m 215C");
+    assert_eq!(line.metric.unwrap().value, 0.0);
+
+    let query: MoonrakerQuery = serde_json::from_str(
+        r#"{"result":{"eventtime":1234.5,"status":{
+            "print_stats":{"filename":"benchy.gcode","print_duration":600.0,"state":"complete"},
+            "virtual_sdcard":{"progress":1.0,"is_active":false},
+            "extruder":{"temperature":41.6,"target":0.0}}}}"#,
+    )
+    .unwrap();
+    let status = moonraker_status(query.result.status);
+    assert_eq!(status.state, State::Done);
+    let line = to_line(&config, &status, true);
+    assert_eq!(line.text, "3D done 42C");
+    assert_eq!(line.metric.unwrap().value, 1.0);
+
+    let paused = Status {
+        state: State::Paused,
+        progress: Some(42.0),
+        left: Some(600),
+        hotend: Some(180.0),
+        file: None,
+    };
+    assert_eq!(to_line(&config, &paused, false).text, "3D paused 42%");
+    assert_eq!(format_left(12 * 60 + 5), "12m");
+}
@@ -0,0 +1,306 @@
+//! Results of PromQL instant queries, ex. `5xx 0.4%` or `DISK 71%`
+//!
+//! Every `[[prometheus.queries]]` entry is run against `/api/v1/query` and
+//! laid out by its own template. Vector result gives line per series with
+//! series labels as fields too, so `{instance} {value:.0}` works. Result of
+//! single value carries metric under query label for `[alerts]`.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    render::{template::Value, Template},
+    BoxError, EloraError,
+};
+
+/// `[prometheus]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrometheusConfig {
+    /// base url of prometheus or compatible api, ex. Thanos or Mimir
+    pub url: String,
+    /// extra request headers, ex. `Authorization` or `X-Scope-OrgID`
+    pub headers: BTreeMap<String, String>,
+    /// queries shown, lines in this order
+    pub queries: Vec<QueryConfig>,
+}
+
+impl Default for PrometheusConfig {
+    fn default() -> Self {
+        PrometheusConfig {
+            url: "http://localhost:9090".into(),
+            headers: BTreeMap::new(),
+            queries: Vec::new(),
+        }
+    }
+}
+
+/// `[[prometheus.queries]]` entry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryConfig {
+    pub label: String,
+    /// PromQL expression, ex. `sum(rate(http_requests_total[5m]))`
+    pub query: String,
+    /// line layout with `label`, `value` and series label fields
+    pub template: String,
+    /// at most this many series of vector result are shown
+    pub max_series: usize,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        QueryConfig {
+            label: String::new(),
+            query: String::new(),
+            template: "{label} {value:.2}".into(),
+            max_series: 4,
+        }
+    }
+}
+
+impl PrometheusConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        let invalid = EloraError::ConfigInvalid;
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(invalid(format!(
+                "prometheus.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.queries.is_empty() {
+            return Err(invalid(
+                "prometheus.queries needs at least one query".into(),
+            ));
+        }
+        for query in &self.queries {
+            if query.query.trim().is_empty() {
+                return Err(invalid(format!(
+                    "prometheus query {:?} has no expression",
+                    query.label
+                )));
+            }
+            if query.max_series == 0 {
+                return Err(invalid(format!(
+                    "prometheus query {:?} max_series must be greater than 0",
+                    query.label
+                )));
+            }
+            Template::parse(&query.template).map_err(|e| {
+                invalid(format!(
+                    "prometheus query {:?} template: {}",
+                    query.label, e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Response of `/api/v1/query`
+#[derive(Debug, Deserialize)]
+struct Response {
+    status: String,
+    data: Option<Data>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "resultType", content = "result", rename_all = "lowercase")]
+enum Data {
+    Vector(Vec<Series>),
+    /// range vector, not shown
+    Matrix(serde::de::IgnoredAny),
+    /// `[unix time, "value"]`
+    Scalar((f64, String)),
+    String((f64, String)),
+}
+
+#[derive(Debug, Deserialize)]
+struct Series {
+    #[serde(default)]
+    metric: BTreeMap<String, String>,
+    value: (f64, String),
+}
+
+/// Sample value, numbers like `0.25`, `NaN` or `+Inf` become numbers
+fn sample(value: &str) -> Value {
+    match value.parse::<f64>() {
+        Ok(number) => Value::Number(number),
+        Err(_) => Value::Text(value.to_string()),
+    }
+}
+
+/// Lines of query result, `-` as value when vector is empty
+fn to_lines(query: &QueryConfig, template: &Template, data: Data) -> Result<Vec<Line>, BoxError> {
+    let samples: Vec<(BTreeMap<String, String>, Value)> = match data {
+        Data::Vector(series) => series
+            .into_iter()
+            .take(query.max_series)
+            .map(|series| (series.metric, sample(&series.value.1)))
+            .collect(),
+        Data::Scalar((_, value)) | Data::String((_, value)) => {
+            vec![(BTreeMap::new(), sample(&value))]
+        }
+        Data::Matrix(_) => return Err("range vector result can't be shown".into()),
+    };
+    if samples.is_empty() {
+        return Ok(vec![Line::new(format!("{} -", query.label))
+            .with_field("label", query.label.as_str())
+            .with_field("value", "-")]);
+    }
+    let single = samples.len() == 1;
+    Ok(samples
+        .into_iter()
+        .map(|(labels, value)| {
+            let metric = match value {
+                Value::Number(number) if single => Some(number),
+                _ => None,
+            };
+            let mut line = Line::new("")
+                .with_field("label", query.label.as_str())
+                .with_field("value", value);
+            for (name, label) in labels {
+                line = line.with_field(name, label);
+            }
+            line.text = template.render(|name| line.field(name));
+            match metric {
+                Some(number) => line.with_metric(&query.label, number),
+                None => line,
+            }
+        })
+        .collect())
+}
+
+/// Results of configured PromQL queries
+pub struct PrometheusProvider {
+    config: PrometheusConfig,
+    templates: Vec<Template>,
+    client: Client,
+}
+
+impl PrometheusProvider {
+    pub fn new(config: PrometheusConfig) -> Result<Self, BoxError> {
+        let templates = config
+            .queries
+            .iter()
+            .map(|query| Template::parse(&query.template))
+            .collect::<Result<_, _>>()?;
+        Ok(PrometheusProvider {
+            config,
+            templates,
+            client: Client::new(),
+        })
+    }
+
+    async fn query(&self, query: &QueryConfig) -> Result<Data, BoxError> {
+        let url = format!("{}/api/v1/query", self.config.url.trim_end_matches('/'));
+        let mut request = self.client.get(url).query(&[("query", &query.query)]);
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        // bad queries answer 400 with error in body
+        let response: Response = request.send().await?.json().await?;
+        match response {
+            Response {
+                data: Some(data), ..
+            } if response.status == "success" => Ok(data),
+            Response { error, .. } => Err(error
+                .unwrap_or_else(|| "prometheus query failed".into())
+                .into()),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for PrometheusProvider {
+    fn name(&self) -> &str {
+        "prometheus"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Running prometheus queries against {}", self.config.url);
+
+        let results = join_all(self.config.queries.iter().map(|q| self.query(q))).await;
+        let mut lines = Vec::new();
+        let mut failed = 0;
+        for ((query, template), result) in
+            self.config.queries.iter().zip(&self.templates).zip(results)
+        {
+            match result.and_then(|data| to_lines(query, template, data)) {
+                Ok(query_lines) => lines.extend(query_lines),
+                Err(e) => {
+                    log::error!("Unable to run prometheus query {:?}: {}", query.label, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed == self.config.queries.len() {
+            return Err("no prometheus query succeeded".into());
+        }
+        Ok(lines)
+    }
+}
+
+#[test]
+fn testing_prometheus_lines() {
+    let vector: Response = serde_json::from_str(
+        r#"{"status":"success","data":{"resultType":"vector","result":[
+            {"metric":{"instance":"web-1:9100"},"value":[1760454000.1,"71.234"]},
+            {"metric":{"instance":"web-2:9100"},"value":[1760454000.1,"NaN"]}
+        ]}}"#,
+    )
+    .unwrap();
+    let disk = QueryConfig {
+        label: "DISK".into(),
+        query: "100 - node_filesystem_free_bytes / node_filesystem_size_bytes * 100".into(),
+        template: "{instance:.5} {value:.0}%".into(),
+        ..QueryConfig::default()
+    };
+    let config = PrometheusConfig {
+        queries: vec![disk.clone()],
+        ..PrometheusConfig::default()
+    };
+    config.validate().unwrap();
+    let template = Template::parse(&disk.template).unwrap();
+    let lines = to_lines(&disk, &template, vector.data.unwrap()).unwrap();
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["web-1 71%", "web-2 NaN%"]);
+    assert_eq!(lines[0].metric, None);
+
+    let errors = QueryConfig {
+        label: "5xx".into(),
+        query: "scalar(sum(rate(http_errors_total[5m])))".into(),
+        ..QueryConfig::default()
+    };
+    let template = Template::parse(&errors.template).unwrap();
+    let scalar: Response = serde_json::from_str(
+        r#"{"status":"success","data":{"resultType":"scalar","result":[1760454000,"0.4"]}}"#,
+    )
+    .unwrap();
+    let lines = to_lines(&errors, &template, scalar.data.unwrap()).unwrap();
+    assert_eq!(lines[0].text, "5xx 0.40");
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 0.4);
+
+    let empty: Response =
+        serde_json::from_str(r#"{"status":"success","data":{"resultType":"vector","result":[]}}"#)
+            .unwrap();
+    assert_eq!(
+        to_lines(&errors, &template, empty.data.unwrap()).unwrap()[0].text,
+        "5xx -"
+    );
+    let error: Response = serde_json::from_str(
+        r#"{"status":"error","errorType":"bad_data","error":"parse error at char 5"}"#,
+    )
+    .unwrap();
+    assert_eq!(error.error.as_deref(), Some("parse error at char 5"));
+}
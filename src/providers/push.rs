@@ -0,0 +1,294 @@
+//! Lines pushed by other apps over http
+//!
+//! Embedded server accepts `POST /display` with `{"lines": ["..."]}` body,
+//! so scripts can draw on keyboard through this daemon instead of fighting
+//! over hid device. Optional `ttl_secs` in body removes lines after a while,
+//! `DELETE /display` removes them right away.
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use hyper::{
+    body::HttpBody,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Deserialize;
+use tokio::{sync::Notify, task::AbortHandle};
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// bigger bodies are rejected, display doesn't fit that much anyway
+const MAX_BODY: u64 = 16 * 1024;
+
+/// `[push]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    /// address server listens on, keep it on localhost
+    pub listen: String,
+    /// at most this many pushed lines are kept
+    pub max_lines: usize,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        PushConfig {
+            listen: "127.0.0.1:7878".into(),
+            max_lines: 8,
+        }
+    }
+}
+
+impl PushConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.listen.parse::<SocketAddr>().is_err() {
+            return Err(EloraError::ConfigInvalid(format!(
+                "push.listen {:?} is not ip:port address",
+                self.listen
+            )));
+        }
+        if self.max_lines == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "push.max_lines must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Body of `POST /display`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PushRequest {
+    lines: Vec<String>,
+    ttl_secs: Option<u64>,
+}
+
+/// Pushed lines shared between server and provider
+#[derive(Default)]
+struct PushState {
+    /// lines and when they expire
+    lines: Mutex<(Vec<Line>, Option<Instant>)>,
+    changed: Notify,
+}
+
+impl PushState {
+    fn set(&self, lines: Vec<Line>, ttl: Option<Duration>) {
+        *self.lines.lock().unwrap() = (lines, ttl.map(|ttl| Instant::now() + ttl));
+        self.changed.notify_one();
+    }
+
+    fn current(&self) -> Vec<Line> {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.1.is_some_and(|expires| expires <= Instant::now()) {
+            *lines = (Vec::new(), None);
+        }
+        lines.0.clone()
+    }
+}
+
+fn response(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Body of request, `None` once it grows past [`MAX_BODY`]. Chunked bodies
+/// don't tell their size upfront, so it's checked while reading
+async fn read_body(mut body: Body) -> Result<Option<Vec<u8>>, hyper::Error> {
+    if body.size_hint().lower() > MAX_BODY {
+        return Ok(None);
+    }
+    let mut read = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if (read.len() + chunk.len()) as u64 > MAX_BODY {
+            return Ok(None);
+        }
+        read.extend_from_slice(&chunk);
+    }
+    Ok(Some(read))
+}
+
+async fn handle(
+    req: Request<Body>,
+    state: Arc<PushState>,
+    max_lines: usize,
+) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() != "/display" {
+        return Ok(response(StatusCode::NOT_FOUND, "not found\n"));
+    }
+    match *req.method() {
+        Method::POST => {
+            let body = match read_body(req.into_body()).await {
+                Ok(Some(body)) => body,
+                Ok(None) => return Ok(response(StatusCode::PAYLOAD_TOO_LARGE, "body too large\n")),
+                Err(e) => return Ok(response(StatusCode::BAD_REQUEST, &format!("{}\n", e))),
+            };
+            let push: PushRequest = match serde_json::from_slice(&body) {
+                Ok(push) => push,
+                Err(e) => return Ok(response(StatusCode::BAD_REQUEST, &format!("{}\n", e))),
+            };
+            log::debug!("Pushed {} lines", push.lines.len());
+            let lines = push.lines.into_iter().take(max_lines).map(Line::new);
+            state.set(lines.collect(), push.ttl_secs.map(Duration::from_secs));
+            Ok(response(StatusCode::NO_CONTENT, ""))
+        }
+        Method::DELETE => {
+            state.set(Vec::new(), None);
+            Ok(response(StatusCode::NO_CONTENT, ""))
+        }
+        _ => Ok(response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "use POST or DELETE\n",
+        )),
+    }
+}
+
+async fn serve(addr: SocketAddr, state: Arc<PushState>, max_lines: usize) -> Result<(), BoxError> {
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, state.clone(), max_lines))) }
+    });
+    // server of provider dropped on config reload may still hold the port
+    let mut attempts = 0;
+    let builder = loop {
+        match Server::try_bind(&addr) {
+            Ok(builder) => break builder,
+            Err(_) if attempts < 10 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let server = builder.serve(make_service);
+    log::info!("Accepting pushed lines on http://{}/display", addr);
+    server.await?;
+    Ok(())
+}
+
+/// Lines pushed over http
+pub struct PushProvider {
+    config: PushConfig,
+    state: Arc<PushState>,
+    /// running server task, stopped with provider
+    server: Mutex<Option<AbortHandle>>,
+}
+
+impl PushProvider {
+    pub fn new(config: PushConfig) -> Self {
+        PushProvider {
+            config,
+            state: Arc::new(PushState::default()),
+            server: Mutex::new(None),
+        }
+    }
+
+    /// Server is started by first worker waiting for changes, so one-shot
+    /// fetches (ex. `test-fetch`) don't take the port
+    fn ensure_server(&self) {
+        let mut server = self.server.lock().unwrap();
+        if server.is_some() {
+            return;
+        }
+        let Ok(addr) = self.config.listen.parse() else {
+            log::error!("Invalid push.listen {:?}", self.config.listen);
+            return;
+        };
+        let state = self.state.clone();
+        let max_lines = self.config.max_lines;
+        let task = tokio::spawn(async move {
+            if let Err(e) = serve(addr, state, max_lines).await {
+                log::error!("Push server on {} stopped: {}", addr, e);
+            }
+        });
+        *server = Some(task.abort_handle());
+    }
+}
+
+impl Drop for PushProvider {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.lock().unwrap().take() {
+            server.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for PushProvider {
+    fn name(&self) -> &str {
+        "push"
+    }
+
+    /// often enough to notice expired lines
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        Ok(self.state.current())
+    }
+
+    async fn changed(&self) {
+        self.ensure_server();
+        self.state.changed.notified().await
+    }
+}
+
+#[tokio::test]
+async fn testing_push_request() {
+    let state = Arc::new(PushState::default());
+    let push = |body: &'static str| {
+        let req = Request::post("/display").body(Body::from(body)).unwrap();
+        handle(req, state.clone(), 2)
+    };
+
+    let res = push(r#"{"lines": ["build ok", "tests ok", "deploy"]}"#).await;
+    assert_eq!(res.unwrap().status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        state.current(),
+        vec![Line::new("build ok"), Line::new("tests ok")]
+    );
+
+    let res = push(r#"{"text": "hello"}"#).await;
+    assert_eq!(res.unwrap().status(), StatusCode::BAD_REQUEST);
+
+    let res = push(r#"{"lines": ["gone"], "ttl_secs": 0}"#).await;
+    assert_eq!(res.unwrap().status(), StatusCode::NO_CONTENT);
+    assert!(state.current().is_empty());
+}
+
+#[tokio::test]
+async fn testing_chunked_body_limit() {
+    let chunked = |chunks: Vec<Vec<u8>>| {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for chunk in chunks {
+                sender.send_data(chunk.into()).await.unwrap();
+            }
+        });
+        body
+    };
+    let body = read_body(chunked(vec![
+        b"{\"lines\":".to_vec(),
+        b"[\"hi\"]}".to_vec(),
+    ]))
+    .await
+    .unwrap();
+    assert_eq!(body.as_deref(), Some(&b"{\"lines\":[\"hi\"]}"[..]));
+
+    let big = vec![vec![b' '; 10 * 1024], vec![b' '; 10 * 1024]];
+    assert_eq!(read_body(chunked(big)).await.unwrap(), None);
+    assert_eq!(
+        read_body(Body::from(vec![b' '; 20 * 1024])).await.unwrap(),
+        None
+    );
+}
@@ -0,0 +1,52 @@
+//! `sysinfo` components, SMC sensors on macos and WMI thermal zones on
+//! windows. Fans aren't reported and WMI needs elevated process
+
+use sysinfo::Components;
+
+use super::{Reading, Sensors};
+use crate::BoxError;
+
+/// labels of CPU sensors across platforms, ex. `CPU Die` or `PECI CPU`
+const CPU_LABELS: [&str; 3] = ["CPU", "Package", "Tdie"];
+
+pub struct SysinfoComponents {
+    components: Components,
+}
+
+impl SysinfoComponents {
+    pub fn new() -> Self {
+        SysinfoComponents {
+            components: Components::new_with_refreshed_list(),
+        }
+    }
+}
+
+impl Sensors for SysinfoComponents {
+    fn read(&mut self) -> Result<Reading, BoxError> {
+        self.components.refresh();
+        let temps = || {
+            self.components
+                .list()
+                .iter()
+                .filter(|component| component.temperature() > 0.0)
+        };
+        let cpu = temps()
+            .filter(|component| {
+                CPU_LABELS
+                    .iter()
+                    .any(|label| component.label().contains(label))
+            })
+            .map(|component| component.temperature())
+            .reduce(f32::max);
+        // thermal zone of windows isn't labelled as CPU
+        let cpu_temp = cpu.or_else(|| {
+            temps()
+                .map(|component| component.temperature())
+                .reduce(f32::max)
+        });
+        Ok(Reading {
+            cpu_temp: cpu_temp.map(f64::from),
+            fans: Vec::new(),
+        })
+    }
+}
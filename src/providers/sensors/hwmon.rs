@@ -0,0 +1,112 @@
+//! Linux hwmon sysfs, `/sys/class/hwmon`, which lm-sensors reads too
+
+use std::{fs, path::Path};
+
+use super::{Reading, Sensors};
+use crate::BoxError;
+
+const HWMON: &str = "/sys/class/hwmon";
+/// hwmon drivers of CPU sensors, intel, amd and arm boards like raspberry pi
+const CPU_CHIPS: [&str; 4] = ["coretemp", "k10temp", "zenpower", "cpu_thermal"];
+/// labels of whole package temperature, cores are used without one
+const PACKAGE_LABELS: [&str; 3] = ["Package id 0", "Tctl", "Tdie"];
+
+/// Sensors of single hwmon chip, temperatures in °C with their labels
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Chip {
+    name: String,
+    temps: Vec<(String, f64)>,
+    fans: Vec<f64>,
+}
+
+pub struct Hwmon;
+
+impl Sensors for Hwmon {
+    fn read(&mut self) -> Result<Reading, BoxError> {
+        let chips = fs::read_dir(HWMON)?
+            .filter_map(|entry| Some(read_chip(&entry.ok()?.path())))
+            .collect::<Vec<_>>();
+        Ok(reading(&chips))
+    }
+}
+
+fn read_chip(path: &Path) -> Chip {
+    let read = |name: &str| {
+        fs::read_to_string(path.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+    let mut chip = Chip {
+        name: read("name").unwrap_or_default(),
+        ..Chip::default()
+    };
+    let mut files = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    files.sort();
+    for file in files {
+        let value = || read(&file)?.parse::<f64>().ok();
+        if let Some(sensor) = file.strip_suffix("_input") {
+            if sensor.starts_with("temp") {
+                let label = read(&format!("{}_label", sensor)).unwrap_or_default();
+                // millidegrees
+                if let Some(millis) = value() {
+                    chip.temps.push((label, millis / 1000.0));
+                }
+            } else if sensor.starts_with("fan") {
+                // stopped fans read 0
+                if let Some(rpm) = value().filter(|rpm| *rpm > 0.0) {
+                    chip.fans.push(rpm);
+                }
+            }
+        }
+    }
+    chip
+}
+
+/// CPU package temperature, or hottest core, and fans of all chips
+fn reading(chips: &[Chip]) -> Reading {
+    let cpu = CPU_CHIPS
+        .iter()
+        .find_map(|name| chips.iter().find(|chip| chip.name == *name));
+    let cpu_temp = cpu.and_then(|chip| {
+        let package = chip
+            .temps
+            .iter()
+            .find(|(label, _)| PACKAGE_LABELS.contains(&label.as_str()));
+        package
+            .map(|(_, temp)| *temp)
+            .or_else(|| chip.temps.iter().map(|(_, temp)| *temp).reduce(f64::max))
+    });
+    Reading {
+        cpu_temp,
+        fans: chips.iter().flat_map(|chip| chip.fans.clone()).collect(),
+    }
+}
+
+#[test]
+fn testing_hwmon_reading() {
+    let chip = |name: &str, temps: &[(&str, f64)], fans: &[f64]| Chip {
+        name: name.into(),
+        temps: temps.iter().map(|(l, t)| (l.to_string(), *t)).collect(),
+        fans: fans.to_vec(),
+    };
+    let chips = [
+        chip("nvme", &[("Composite", 41.9)], &[]),
+        chip(
+            "coretemp",
+            &[("Core 0", 66.0), ("Package id 0", 64.0), ("Core 1", 71.0)],
+            &[],
+        ),
+        chip("thinkpad", &[], &[1850.0]),
+    ];
+    let package = reading(&chips);
+    assert_eq!(package.cpu_temp, Some(64.0));
+    assert_eq!(package.fans, [1850.0]);
+
+    let cores = [chip("cpu_thermal", &[("", 52.6), ("", 55.1)], &[])];
+    assert_eq!(reading(&cores).cpu_temp, Some(55.1));
+    assert_eq!(reading(&chips[..1]).cpu_temp, None);
+}
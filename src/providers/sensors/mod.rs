@@ -0,0 +1,170 @@
+//! CPU temperature and fan speed of host machine, ex. `CPU 64C FAN 1850`
+//!
+//! Readings come from one of [`Sensors`] backends: hwmon sysfs on linux,
+//! same one lm-sensors reads, and `sysinfo` components elsewhere, which read
+//! SMC on macos and WMI on windows. Only hwmon has fans. Temperature
+//! carries metric under label, so [`SensorsConfig::alert_rules`] alerts
+//! keyboard when CPU gets hot enough to throttle.
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+#[cfg(not(target_os = "linux"))]
+mod components;
+#[cfg(target_os = "linux")]
+mod hwmon;
+
+/// `[sensors]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorsConfig {
+    /// CPU above this °C is throttling, line is highlighted and keyboard
+    /// alerted then
+    pub throttle_temp: f64,
+    pub alert: bool,
+    pub label: String,
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        SensorsConfig {
+            throttle_temp: 95.0,
+            alert: true,
+            label: "CPU".into(),
+        }
+    }
+}
+
+impl SensorsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.throttle_temp <= 0.0 {
+            return Err(EloraError::ConfigInvalid(
+                "sensors.throttle_temp must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Alert rule firing when CPU gets above `throttle_temp`. Empty when
+    /// `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        vec![Rule {
+            symbol: self.label.clone(),
+            provider: Some("sensors".into()),
+            direction: Direction::Above,
+            threshold: self.throttle_temp,
+            window: None,
+            text: Some(format!("{} throttling", self.label)),
+            urgent: false,
+            notify: true,
+        }]
+    }
+}
+
+/// Temperature and fans read at once
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reading {
+    /// °C of CPU package, hottest core when package isn't reported
+    pub cpu_temp: Option<f64>,
+    /// RPM of spinning fans
+    pub fans: Vec<f64>,
+}
+
+/// Platform source of temperature and fan readings
+pub trait Sensors: Send {
+    fn read(&mut self) -> Result<Reading, BoxError>;
+}
+
+#[cfg(target_os = "linux")]
+fn backend() -> Box<dyn Sensors> {
+    Box::new(hwmon::Hwmon)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn backend() -> Box<dyn Sensors> {
+    Box::new(components::SysinfoComponents::new())
+}
+
+/// Formats reading into line, ex. `CPU 64C FAN 1850`, with fastest fan
+fn to_line(config: &SensorsConfig, reading: &Reading) -> Result<Line, BoxError> {
+    let temp = reading.cpu_temp.ok_or("no cpu temperature sensor found")?;
+    let mut text = format!("{} {:.0}C", config.label, temp);
+    let mut line = Line::new("")
+        .with_field("label", config.label.as_str())
+        .with_field("temp", temp);
+    if let Some(fan) = reading.fans.iter().copied().reduce(f64::max) {
+        text.push_str(&format!(" FAN {:.0}", fan));
+        line = line.with_field("fan", fan);
+    }
+    line.text = text;
+    Ok(line
+        .with_highlight(temp > config.throttle_temp)
+        .with_metric(&config.label, temp))
+}
+
+/// Local temperature sensors, no network involved
+pub struct SensorsProvider {
+    config: SensorsConfig,
+    sensors: Mutex<Box<dyn Sensors>>,
+}
+
+impl SensorsProvider {
+    pub fn new(config: SensorsConfig) -> Self {
+        SensorsProvider {
+            config,
+            sensors: Mutex::new(backend()),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for SensorsProvider {
+    fn name(&self) -> &str {
+        "sensors"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let reading = self.sensors.lock().unwrap().read()?;
+        Ok(vec![to_line(&self.config, &reading)?])
+    }
+}
+
+#[test]
+fn testing_sensors_line() {
+    let config = SensorsConfig::default();
+    let reading = Reading {
+        cpu_temp: Some(64.2),
+        fans: vec![1850.0, 940.0],
+    };
+    let line = to_line(&config, &reading).unwrap();
+    assert_eq!(line.text, "CPU 64C FAN 1850");
+    assert!(!line.highlight);
+
+    let hot = Reading {
+        cpu_temp: Some(97.0),
+        fans: Vec::new(),
+    };
+    let line = to_line(&config, &hot).unwrap();
+    assert_eq!(line.text, "CPU 97C");
+    assert!(line.highlight);
+    assert!(to_line(&config, &Reading::default()).is_err());
+
+    let rule = &config.alert_rules()[0];
+    assert!(line.metric.unwrap().value > rule.threshold);
+    assert_eq!(rule.text.as_deref(), Some("CPU throttling"));
+}
@@ -0,0 +1,249 @@
+//! Unresolved issues and error rate of Sentry project, ex.
+//! `WEB 12 iss 3.4/m ▲`
+//!
+//! Rate is events received per minute over last `window_mins`, arrow shows
+//! whether it rose or fell against window before it, so spikes stand out.
+//! Rate carries metric under label, so `[alerts]` rule like `WEB > 10` works.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env var token is read from when config has none, same as sentry-cli
+pub const TOKEN_ENV: &str = "SENTRY_AUTH_TOKEN";
+
+/// `[sentry]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SentryConfig {
+    /// sentry.io or own instance, ex. `https://de.sentry.io`
+    pub url: String,
+    /// auth token with `project:read`, `SENTRY_AUTH_TOKEN` env without it
+    pub token: Option<String>,
+    /// organization slug
+    pub organization: String,
+    /// project slug
+    pub project: String,
+    /// issues counted, sentry search syntax
+    pub query: String,
+    /// minutes error rate is averaged over
+    pub window_mins: i64,
+    /// uppercase project slug without it
+    pub label: Option<String>,
+}
+
+impl Default for SentryConfig {
+    fn default() -> Self {
+        SentryConfig {
+            url: "https://sentry.io".into(),
+            token: None,
+            organization: String::new(),
+            project: String::new(),
+            query: "is:unresolved".into(),
+            window_mins: 10,
+            label: None,
+        }
+    }
+}
+
+impl SentryConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "sentry.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.organization.is_empty() || self.project.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "sentry.organization and sentry.project are required".into(),
+            ));
+        }
+        if self.window_mins <= 0 {
+            return Err(EloraError::ConfigInvalid(
+                "sentry.window_mins must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("sentry.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+
+    fn label(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| self.project.to_uppercase())
+    }
+}
+
+/// Events per minute of last window and window before it, from
+/// `[unix time, count]` buckets of project stats
+fn rates(buckets: &[(i64, f64)], now: i64, window_mins: i64) -> (f64, f64) {
+    let window = window_mins * 60;
+    let sum = |from: i64, to: i64| -> f64 {
+        buckets
+            .iter()
+            .filter(|(at, _)| (from..to).contains(at))
+            .map(|(_, count)| count)
+            .sum()
+    };
+    let current = sum(now - window, now + 1) / window_mins as f64;
+    let previous = sum(now - 2 * window, now - window) / window_mins as f64;
+    (current, previous)
+}
+
+/// Formats counts into line, ex. `WEB 12 iss 3.4/m ▲`
+fn to_line(label: &str, issues: u64, current: f64, previous: f64) -> Line {
+    let arrow = if current > previous {
+        "▲"
+    } else if current < previous {
+        "▼"
+    } else {
+        ""
+    };
+    let mut text = format!("{} {} iss {:.1}/m", label, issues, current);
+    if !arrow.is_empty() {
+        text.push(' ');
+        text.push_str(arrow);
+    }
+    Line::new(text)
+        .with_field("label", label)
+        .with_field("issues", issues as f64)
+        .with_field("rate", current)
+        .with_field("previous", previous)
+        .with_field("arrow", arrow)
+        .with_metric(label, current)
+}
+
+/// Issues and error rate of configured project
+pub struct SentryProvider {
+    config: SentryConfig,
+    client: Client,
+}
+
+impl SentryProvider {
+    pub fn new(config: SentryConfig) -> Self {
+        SentryProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    fn project_url(&self, endpoint: &str) -> String {
+        format!(
+            "{}/api/0/projects/{}/{}/{}/",
+            self.config.url.trim_end_matches('/'),
+            self.config.organization,
+            self.config.project,
+            endpoint
+        )
+    }
+
+    /// count of matching issues, `X-Hits` header when sentry sends it or
+    /// issues on first page
+    async fn issues(&self, token: &str) -> Result<u64, BoxError> {
+        let response = self
+            .client
+            .get(self.project_url("issues"))
+            .query(&[("query", self.config.query.as_str()), ("limit", "100")])
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?;
+        let hits = response
+            .headers()
+            .get("X-Hits")
+            .and_then(|hits| hits.to_str().ok()?.parse().ok());
+        let issues: Vec<serde_json::Value> = response.json().await?;
+        Ok(hits.unwrap_or(issues.len() as u64))
+    }
+
+    async fn buckets(&self, token: &str, now: i64) -> Result<Vec<(i64, f64)>, BoxError> {
+        let since = now - 2 * self.config.window_mins * 60;
+        Ok(self
+            .client
+            .get(self.project_url("stats"))
+            .query(&[
+                ("stat", "received".to_string()),
+                ("resolution", "10s".to_string()),
+                ("since", since.to_string()),
+                ("until", now.to_string()),
+            ])
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl DataProvider for SentryProvider {
+    fn name(&self) -> &str {
+        "sentry"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!(
+            "Fetching sentry issues of {}/{}",
+            self.config.organization,
+            self.config.project
+        );
+
+        let token = self.config.token()?;
+        let now = Utc::now().timestamp();
+        let (issues, buckets) = tokio::try_join!(self.issues(&token), self.buckets(&token, now))?;
+        let (current, previous) = rates(&buckets, now, self.config.window_mins);
+        Ok(vec![to_line(
+            &self.config.label(),
+            issues,
+            current,
+            previous,
+        )])
+    }
+}
+
+#[test]
+fn testing_sentry_line() {
+    let config = SentryConfig {
+        organization: "acme".into(),
+        project: "web".into(),
+        ..SentryConfig::default()
+    };
+    config.validate().unwrap();
+    assert!(SentryConfig::default().validate().is_err());
+
+    let now = 1_760_454_000;
+    let buckets: Vec<(i64, f64)> = serde_json::from_str(&format!(
+        "[[{}, 5.0], [{}, 10.0], [{}, 24.0], [{}, 10.0]]",
+        now - 1100,
+        now - 700,
+        now - 300,
+        now
+    ))
+    .unwrap();
+    let (current, previous) = rates(&buckets, now, config.window_mins);
+    assert_eq!((current, previous), (3.4, 1.5));
+
+    let line = to_line(&config.label(), 12, current, previous);
+    assert_eq!(line.text, "WEB 12 iss 3.4/m ▲");
+    assert_eq!(line.metric.unwrap().value, 3.4);
+    assert_eq!(to_line("WEB", 0, 0.0, 0.0).text, "WEB 0 iss 0.0/m");
+}
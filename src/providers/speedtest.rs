@@ -0,0 +1,207 @@
+//! Internet speed measured against Cloudflare speed test, ex.
+//! `SPD ▼312 ▲41 12ms`
+//!
+//! Test runs every `interval_mins`, downloading and uploading few megabytes,
+//! so it's meant for hourly cadence rather than seconds. Rates are in Mbps
+//! and download rate carries metric under label.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// latency is lowest of this many empty downloads
+const PINGS: usize = 5;
+/// keyboard refresh within this after test shows its result instead of
+/// running another one
+const MIN_GAP: Duration = Duration::from_secs(300);
+
+/// `[speedtest]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpeedtestConfig {
+    /// server with Cloudflare's `__down` and `__up` endpoints
+    pub url: String,
+    pub interval_mins: u64,
+    /// megabytes downloaded and uploaded per test, more is more accurate on
+    /// fast lines
+    pub down_mb: u64,
+    pub up_mb: u64,
+    pub label: String,
+}
+
+impl Default for SpeedtestConfig {
+    fn default() -> Self {
+        SpeedtestConfig {
+            url: "https://speed.cloudflare.com".into(),
+            interval_mins: 60,
+            down_mb: 25,
+            up_mb: 10,
+            label: "SPD".into(),
+        }
+    }
+}
+
+impl SpeedtestConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "speedtest.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.interval_mins == 0 || self.down_mb == 0 || self.up_mb == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "speedtest.interval_mins, down_mb and up_mb must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Result of single test, rates in Mbps
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Speed {
+    down: f64,
+    up: f64,
+    ping_ms: f64,
+}
+
+fn mbps(bytes: u64, took: Duration) -> f64 {
+    bytes as f64 * 8.0 / 1e6 / took.as_secs_f64().max(f64::EPSILON)
+}
+
+/// Formats speed into line, ex. `SPD ▼312 ▲41 12ms`
+fn to_line(label: &str, speed: Speed) -> Line {
+    Line::new(format!(
+        "{} ▼{:.0} ▲{:.0} {:.0}ms",
+        label, speed.down, speed.up, speed.ping_ms
+    ))
+    .with_field("label", label)
+    .with_field("down", speed.down)
+    .with_field("up", speed.up)
+    .with_field("ping", speed.ping_ms)
+    .with_metric(label, speed.down)
+}
+
+/// Periodic internet speed test
+pub struct SpeedtestProvider {
+    config: SpeedtestConfig,
+    /// last test with time it finished
+    last: Mutex<Option<(Instant, Speed)>>,
+    client: Client,
+}
+
+impl SpeedtestProvider {
+    pub fn new(config: SpeedtestConfig) -> Self {
+        SpeedtestProvider {
+            config,
+            last: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.config.url.trim_end_matches('/'), endpoint)
+    }
+
+    /// lowest round trip of empty downloads, first one also opens connection
+    async fn ping(&self) -> Result<f64, BoxError> {
+        let mut best = Duration::MAX;
+        for _ in 0..PINGS {
+            let started = Instant::now();
+            self.client
+                .get(self.url("__down?bytes=0"))
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            best = best.min(started.elapsed());
+        }
+        Ok(best.as_secs_f64() * 1000.0)
+    }
+
+    /// Mbps of download, timed from first byte so latency isn't counted
+    async fn download(&self) -> Result<f64, BoxError> {
+        let bytes = self.config.down_mb * 1_000_000;
+        let mut response = self
+            .client
+            .get(self.url(&format!("__down?bytes={}", bytes)))
+            .send()
+            .await?
+            .error_for_status()?;
+        let started = Instant::now();
+        let mut received = 0;
+        while let Some(chunk) = response.chunk().await? {
+            received += chunk.len() as u64;
+        }
+        Ok(mbps(received, started.elapsed()))
+    }
+
+    async fn upload(&self) -> Result<f64, BoxError> {
+        let bytes = self.config.up_mb * 1_000_000;
+        let body = vec![0u8; bytes as usize];
+        let started = Instant::now();
+        self.client
+            .post(self.url("__up"))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(mbps(bytes, started.elapsed()))
+    }
+}
+
+#[async_trait]
+impl DataProvider for SpeedtestProvider {
+    fn name(&self) -> &str {
+        "speedtest"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.config.interval_mins * 60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        if let Some((at, speed)) = *self.last.lock().unwrap() {
+            if at.elapsed() < MIN_GAP {
+                return Ok(vec![to_line(&self.config.label, speed)]);
+            }
+        }
+        log::info!("Running speed test against {}", self.config.url);
+
+        // run one after another, so they don't share bandwidth
+        let ping_ms = self.ping().await?;
+        let down = self.download().await?;
+        let up = self.upload().await?;
+        let speed = Speed { down, up, ping_ms };
+        log::debug!("Speed test measured {:?}", speed);
+        *self.last.lock().unwrap() = Some((Instant::now(), speed));
+        Ok(vec![to_line(&self.config.label, speed)])
+    }
+}
+
+#[test]
+fn testing_speedtest_line() {
+    SpeedtestConfig::default().validate().unwrap();
+    assert_eq!(mbps(25_000_000, Duration::from_millis(640)), 312.5);
+
+    let line = to_line(
+        "SPD",
+        Speed {
+            down: 312.5,
+            up: 41.2,
+            ping_ms: 11.8,
+        },
+    );
+    assert_eq!(line.text, "SPD ▼312 ▲41 12ms");
+    assert_eq!(line.metric.unwrap().value, 312.5);
+}
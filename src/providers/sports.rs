@@ -0,0 +1,325 @@
+//! Today's football matches of selected teams and competitions from
+//! football-data.org, ex. `ARS 2-1 CHE 67'` or `LIV-MCI 18:30`
+//!
+//! Score of live match which rose since previous fetch sets metric of
+//! [`GOAL_METRIC`] to 1 for one fetch, so [`SportsConfig::alert_rules`]
+//! alerts keyboard on goal.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{
+    alerts::{Direction, Rule},
+    BoxError, EloraError,
+};
+
+/// env var api token is read from when config has none
+pub const TOKEN_ENV: &str = "FOOTBALL_DATA_TOKEN";
+/// metric symbol of goal flag on first line
+pub const GOAL_METRIC: &str = "sports_goal";
+
+/// `[sports]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SportsConfig {
+    /// football-data.org api token, `FOOTBALL_DATA_TOKEN` env without it
+    pub token: Option<String>,
+    /// competition codes, ex. `PL` or `CL`, all of token's plan when empty
+    pub competitions: Vec<String>,
+    /// team codes or short names, ex. `ARS` or `Arsenal`, matches of these
+    /// only when not empty
+    pub teams: Vec<String>,
+    /// at most this many matches are shown, live ones first
+    pub max_matches: usize,
+    /// alert keyboard on goal in shown live match
+    pub alert: bool,
+}
+
+impl Default for SportsConfig {
+    fn default() -> Self {
+        SportsConfig {
+            token: None,
+            competitions: Vec::new(),
+            teams: Vec::new(),
+            max_matches: 4,
+            alert: true,
+        }
+    }
+}
+
+impl SportsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.max_matches == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "sports.max_matches must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rule alerting keyboard when goal is scored. Empty when `alert` is off
+    pub fn alert_rules(&self) -> Vec<Rule> {
+        if !self.alert {
+            return Vec::new();
+        }
+        vec![Rule {
+            symbol: GOAL_METRIC.into(),
+            provider: Some("sports".into()),
+            direction: Direction::Above,
+            threshold: 0.0,
+            window: None,
+            text: Some("Goal!".into()),
+            urgent: false,
+            notify: true,
+        }]
+    }
+
+    fn token(&self) -> Result<String, BoxError> {
+        match &self.token {
+            Some(token) => Ok(token.clone()),
+            None => std::env::var(TOKEN_ENV)
+                .map_err(|_| format!("sports.token or {} env is required", TOKEN_ENV).into()),
+        }
+    }
+
+    fn follows(&self, team: &Team) -> bool {
+        self.teams.is_empty()
+            || self.teams.iter().any(|wanted| {
+                [&team.tla, &team.short_name, &team.name]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| name.eq_ignore_ascii_case(wanted))
+            })
+    }
+}
+
+/// Response of `/v4/matches`, only fields we use
+#[derive(Debug, Deserialize)]
+struct Matches {
+    matches: Vec<Match>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Match {
+    id: u64,
+    utc_date: DateTime<Utc>,
+    /// SCHEDULED, TIMED, IN_PLAY, PAUSED, FINISHED, POSTPONED, ...
+    status: String,
+    /// minute of play, not in every plan
+    minute: Option<u32>,
+    home_team: Team,
+    away_team: Team,
+    score: Score,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Team {
+    tla: Option<String>,
+    short_name: Option<String>,
+    name: Option<String>,
+}
+
+impl Team {
+    /// three letter code when team has one, it fits display best
+    fn label(&self) -> &str {
+        [&self.tla, &self.short_name, &self.name]
+            .into_iter()
+            .flatten()
+            .next()
+            .map_or("?", String::as_str)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Score {
+    /// current score while match is played
+    full_time: Goals,
+}
+
+#[derive(Debug, Deserialize)]
+struct Goals {
+    home: Option<u32>,
+    away: Option<u32>,
+}
+
+impl Match {
+    fn live(&self) -> bool {
+        matches!(self.status.as_str(), "IN_PLAY" | "PAUSED")
+    }
+
+    fn goals(&self) -> u32 {
+        self.score.full_time.home.unwrap_or_default()
+            + self.score.full_time.away.unwrap_or_default()
+    }
+}
+
+/// Formats match into line, ex. `ARS 2-1 CHE 67'`, `ARS 2-1 CHE FT` or
+/// `LIV-MCI 18:30` with local kickoff time before it starts
+fn to_line(game: &Match) -> Line {
+    let (home, away) = (game.home_team.label(), game.away_team.label());
+    let state = match game.status.as_str() {
+        "IN_PLAY" => game
+            .minute
+            .map_or("LIVE".to_string(), |minute| format!("{}'", minute)),
+        "PAUSED" => "HT".into(),
+        "FINISHED" => "FT".into(),
+        "POSTPONED" => "PP".into(),
+        "SCHEDULED" | "TIMED" => game
+            .utc_date
+            .with_timezone(&Local)
+            .format("%H:%M")
+            .to_string(),
+        _ => "-".into(),
+    };
+    let text = match (&game.score.full_time.home, &game.score.full_time.away) {
+        (Some(h), Some(a)) => format!("{} {}-{} {} {}", home, h, a, away, state),
+        _ => format!("{}-{} {}", home, away, state),
+    };
+    let mut line = Line::new(text)
+        .with_field("home", home)
+        .with_field("away", away)
+        .with_field("state", state)
+        .with_field("status", game.status.as_str());
+    if let (Some(h), Some(a)) = (game.score.full_time.home, game.score.full_time.away) {
+        line = line
+            .with_field("home_goals", h as f64)
+            .with_field("away_goals", a as f64);
+    }
+    line
+}
+
+/// Whether live match scored since previous goal counts, never on first
+/// fetch
+fn scored(previous: Option<&HashMap<u64, u32>>, games: &[&Match]) -> bool {
+    let Some(previous) = previous else {
+        return false;
+    };
+    games
+        .iter()
+        .filter(|game| game.live())
+        .any(|game| game.goals() > previous.get(&game.id).copied().unwrap_or_default())
+}
+
+/// Today's matches of configured teams
+pub struct SportsProvider {
+    config: SportsConfig,
+    /// goals per match id on previous fetch
+    goals: Mutex<Option<HashMap<u64, u32>>>,
+    client: Client,
+}
+
+impl SportsProvider {
+    pub fn new(config: SportsConfig) -> Self {
+        SportsProvider {
+            config,
+            goals: Mutex::new(None),
+            client: Client::new(),
+        }
+    }
+
+    fn to_lines(&self, matches: &Matches) -> Vec<Line> {
+        let mut games: Vec<&Match> = matches
+            .matches
+            .iter()
+            .filter(|game| {
+                self.config.follows(&game.home_team) || self.config.follows(&game.away_team)
+            })
+            .collect();
+        games.sort_by_key(|game| (!game.live(), game.utc_date));
+        games.truncate(self.config.max_matches);
+
+        let mut goals = self.goals.lock().unwrap();
+        let goal = scored(goals.as_ref(), &games);
+        *goals = Some(games.iter().map(|game| (game.id, game.goals())).collect());
+
+        let mut lines: Vec<Line> = games.into_iter().map(to_line).collect();
+        if lines.is_empty() {
+            lines.push(Line::new("No matches"));
+        }
+        let first = lines
+            .remove(0)
+            .with_metric(GOAL_METRIC, if goal { 1.0 } else { 0.0 });
+        lines.insert(0, first);
+        lines
+    }
+}
+
+#[async_trait]
+impl DataProvider for SportsProvider {
+    fn name(&self) -> &str {
+        "sports"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching football matches");
+
+        let mut request = self
+            .client
+            .get("https://api.football-data.org/v4/matches")
+            .header("X-Auth-Token", self.config.token()?);
+        if !self.config.competitions.is_empty() {
+            request = request.query(&[("competitions", self.config.competitions.join(","))]);
+        }
+        let matches: Matches = request.send().await?.error_for_status()?.json().await?;
+        Ok(self.to_lines(&matches))
+    }
+}
+
+#[test]
+fn testing_sports_lines() {
+    let json = |home_goals: u32| {
+        format!(
+            r#"{{"filters":{{}},"resultSet":{{"count":3}},"matches":[
+            {{"id":1,"utcDate":"2026-10-14T18:30:00Z","status":"TIMED","minute":null,
+              "homeTeam":{{"id":64,"name":"Liverpool FC","shortName":"Liverpool","tla":"LIV"}},
+              "awayTeam":{{"id":65,"name":"Manchester City FC","shortName":"Man City","tla":"MCI"}},
+              "score":{{"winner":null,"fullTime":{{"home":null,"away":null}}}}}},
+            {{"id":2,"utcDate":"2026-10-14T16:00:00Z","status":"IN_PLAY","minute":67,
+              "homeTeam":{{"id":57,"name":"Arsenal FC","shortName":"Arsenal","tla":"ARS"}},
+              "awayTeam":{{"id":61,"name":"Chelsea FC","shortName":"Chelsea","tla":"CHE"}},
+              "score":{{"winner":null,"fullTime":{{"home":{},"away":1}}}}}},
+            {{"id":3,"utcDate":"2026-10-14T16:00:00Z","status":"IN_PLAY",
+              "homeTeam":{{"id":66,"name":"Manchester United FC","shortName":"Man United","tla":"MUN"}},
+              "awayTeam":{{"id":73,"name":"Tottenham Hotspur FC","shortName":"Tottenham","tla":"TOT"}},
+              "score":{{"winner":null,"fullTime":{{"home":0,"away":0}}}}}}
+        ]}}"#,
+            home_goals
+        )
+    };
+    let provider = SportsProvider::new(SportsConfig {
+        teams: vec!["ars".into(), "Man City".into()],
+        ..SportsConfig::default()
+    });
+    let matches: Matches = serde_json::from_str(&json(1)).unwrap();
+    let lines = provider.to_lines(&matches);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "ARS 1-1 CHE 67'");
+    assert!(lines[1].text.starts_with("LIV-MCI "));
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 0.0);
+
+    let matches: Matches = serde_json::from_str(&json(2)).unwrap();
+    let lines = provider.to_lines(&matches);
+    assert_eq!(lines[0].text, "ARS 2-1 CHE 67'");
+    assert_eq!(lines[0].metric.as_ref().unwrap().symbol, GOAL_METRIC);
+    assert_eq!(lines[0].metric.as_ref().unwrap().value, 1.0);
+    assert_eq!(
+        provider.to_lines(&matches)[0]
+            .metric
+            .as_ref()
+            .unwrap()
+            .value,
+        0.0
+    );
+}
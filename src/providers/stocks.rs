@@ -0,0 +1,952 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::{future::join_all, Future};
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{
+    currency_sign,
+    dividends::{self, Dividends},
+    finnhub,
+    fx::{FxConfig, FxRates, Rates},
+    stooq, DataProvider, Line,
+};
+use crate::{
+    market::{self, MarketConfig},
+    BoxError, EloraError,
+};
+
+// type alias for stock tickers
+pub type StockTickerType = BTreeMap<String, f64>;
+// ticker -> quote
+pub type Quotes = BTreeMap<String, Quote>;
+// default interested tickers, used when config doesn't define any
+pub const TICKERS: [&str; 3] = ["TSLA", "VWRL.AS", "NVDA"];
+
+const CHROME_USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.114 Safari/537.36";
+
+/// Response of `query1.finance.yahoo.com/v8/finance/chart/<ticker>`, only
+/// fields we use
+#[derive(Debug, Deserialize)]
+struct ChartResponse {
+    chart: Chart,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chart {
+    result: Option<Vec<ChartResult>>,
+    error: Option<ChartError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartResult {
+    meta: ChartMeta,
+    /// unix seconds of candles, only with `range` and `interval` in query
+    #[serde(default)]
+    timestamp: Vec<i64>,
+    indicators: Option<Indicators>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMeta {
+    currency: Option<String>,
+    regular_market_price: Option<f64>,
+    chart_previous_close: Option<f64>,
+    previous_close: Option<f64>,
+    regular_market_day_low: Option<f64>,
+    regular_market_day_high: Option<f64>,
+    fifty_two_week_low: Option<f64>,
+    fifty_two_week_high: Option<f64>,
+    current_trading_period: Option<TradingPeriods>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TradingPeriods {
+    pre: TradingPeriod,
+    post: TradingPeriod,
+}
+
+/// unix seconds
+#[derive(Debug, Deserialize)]
+struct TradingPeriod {
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Indicators {
+    quote: Vec<Candles>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Candles {
+    /// `null` for minutes without trade
+    close: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartError {
+    code: String,
+    description: String,
+}
+
+/// Kind of yahoo symbol, deciding how it's drawn and whether price is money
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetClass {
+    Stock,
+    /// ex. `^GSPC`, price is in points
+    Index,
+    /// ex. `GC=F`
+    Future,
+    /// ex. `EURUSD=X`, price is exchange rate
+    Fx,
+}
+
+impl AssetClass {
+    pub fn of(ticker: &str) -> Self {
+        if ticker.starts_with('^') {
+            AssetClass::Index
+        } else if ticker.ends_with("=F") {
+            AssetClass::Future
+        } else if ticker.ends_with("=X") {
+            AssetClass::Fx
+        } else {
+            AssetClass::Stock
+        }
+    }
+
+    /// Symbol drawn on display, ex. `GSPC`, `GC` or `EUR/USD`. Yahoo's
+    /// `JPY=X` is dollar rate, `USD/JPY`
+    pub fn symbol(self, ticker: &str) -> String {
+        match self {
+            AssetClass::Stock => ticker.to_string(),
+            AssetClass::Index => ticker.trim_start_matches('^').to_string(),
+            AssetClass::Future => ticker.trim_end_matches("=F").to_string(),
+            AssetClass::Fx => {
+                let pair = ticker.trim_end_matches("=X");
+                match pair.len() {
+                    3 => format!("USD/{}", pair),
+                    6 if pair.is_ascii() => format!("{}/{}", &pair[..3], &pair[3..]),
+                    _ => pair.to_string(),
+                }
+            }
+        }
+    }
+
+    /// decimals price is drawn with, rates and cheap commodities need more
+    /// than whole numbers
+    pub fn decimals(self, price: f64) -> usize {
+        match self {
+            AssetClass::Future if price < 1000.0 => 2,
+            AssetClass::Fx if price < 20.0 => 4,
+            AssetClass::Fx => 2,
+            AssetClass::Stock | AssetClass::Index | AssetClass::Future => 0,
+        }
+    }
+
+    /// index points and exchange rates are not in any currency
+    pub fn is_priced(self) -> bool {
+        matches!(self, AssetClass::Stock | AssetClass::Future)
+    }
+}
+
+/// Percent-encodes ticker for url, `^GSPC` is `%5EGSPC` and `GC=F` is
+/// `GC%3DF`
+pub fn escape(ticker: &str) -> String {
+    ticker
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// fallback sources which can be listed in `quotes.fallback`
+const FALLBACKS: [&str; 2] = ["stooq", "finnhub"];
+
+/// `[quotes]` config section
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotesConfig {
+    /// sources tried in order when Yahoo fails, `stooq` or `finnhub`
+    pub fallback: Vec<String>,
+    /// pre-market and after-hours prices of US tickers from Yahoo, shown
+    /// while regular session is closed
+    pub extended_hours: bool,
+    /// day range and 52-week range of Yahoo quotes as `day_low`, `day_high`,
+    /// `year_low` and `year_high` fields, also sent in binary payload
+    pub ranges: bool,
+    /// trailing dividend yield and projected next ex-dividend date of stocks
+    /// as `dividend_yield` and `ex_dividend` fields, see [`dividends`]
+    pub dividends: bool,
+    /// api key of finnhub, falls back to `FINNHUB_TOKEN` env
+    pub finnhub_token: Option<String>,
+}
+
+impl QuotesConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if let Some(source) = self
+            .fallback
+            .iter()
+            .find(|s| !FALLBACKS.contains(&s.as_str()))
+        {
+            return Err(EloraError::ConfigInvalid(format!(
+                "quotes.fallback {:?} is not one of {}",
+                source,
+                FALLBACKS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fallback sources in configured order
+    pub fn sources(&self) -> Result<Vec<QuoteSource>, EloraError> {
+        self.fallback
+            .iter()
+            .map(|source| match source.as_str() {
+                "stooq" => Ok(QuoteSource::Stooq),
+                _ => finnhub::token(self.finnhub_token.as_deref())
+                    .map(|token| QuoteSource::Finnhub { token })
+                    .ok_or_else(|| {
+                        EloraError::ConfigInvalid(format!(
+                            "finnhub fallback needs quotes.finnhub_token or {} env",
+                            finnhub::TOKEN_ENV
+                        ))
+                    }),
+            })
+            .collect()
+    }
+}
+
+/// Where quotes are fetched from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum QuoteSource {
+    #[default]
+    Yahoo,
+    Stooq,
+    Finnhub {
+        token: String,
+    },
+}
+
+impl QuoteSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuoteSource::Yahoo => "yahoo",
+            QuoteSource::Stooq => "stooq",
+            QuoteSource::Finnhub { .. } => "finnhub",
+        }
+    }
+
+    /// `extended` hours are known only to Yahoo
+    async fn fetch_quote(
+        &self,
+        client: &Client,
+        ticker: &str,
+        extended: bool,
+    ) -> Result<Quote, BoxError> {
+        match self {
+            QuoteSource::Yahoo => fetch_quote(client, ticker, extended).await,
+            QuoteSource::Stooq => stooq::fetch_quote(client, ticker).await,
+            QuoteSource::Finnhub { token } => finnhub::fetch_quote(client, token, ticker).await,
+        }
+    }
+}
+
+/// Price of single ticker
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Quote {
+    pub price: f64,
+    /// price change is computed against it, `None` until known
+    pub previous_close: Option<f64>,
+    /// currency price is in, ex. `EUR`, `None` when unknown
+    pub currency: Option<String>,
+    /// latest price outside regular session of US ticker
+    pub extended: Option<Extended>,
+    /// lowest and highest price of day, `None` when unknown
+    pub day_range: Option<(f64, f64)>,
+    /// lowest and highest price of 52 weeks
+    pub year_range: Option<(f64, f64)>,
+}
+
+/// Trading outside regular session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Session {
+    Pre,
+    Post,
+}
+
+impl Session {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Session::Pre => "pre",
+            Session::Post => "post",
+        }
+    }
+}
+
+/// Pre-market or after-hours price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extended {
+    pub session: Session,
+    pub price: f64,
+}
+
+impl Quote {
+    /// change against previous close in percent
+    pub fn change_percent(&self) -> Option<f64> {
+        match self.previous_close {
+            Some(previous) if previous != 0.0 => Some((self.price - previous) / previous * 100.0),
+            _ => None,
+        }
+    }
+}
+
+/// Last traded minute of chart, when it's in pre-market or after-hours
+/// period
+fn extended_price(result: &ChartResult) -> Option<Extended> {
+    let periods = result.meta.current_trading_period.as_ref()?;
+    let closes = &result.indicators.as_ref()?.quote.first()?.close;
+    let (at, price) = result
+        .timestamp
+        .iter()
+        .zip(closes)
+        .filter_map(|(at, close)| Some((*at, (*close)?)))
+        .next_back()?;
+    let within = |period: &TradingPeriod| period.start <= at && at < period.end;
+    let session = if within(&periods.pre) {
+        Session::Pre
+    } else if within(&periods.post) {
+        Session::Post
+    } else {
+        return None;
+    };
+    Some(Extended { session, price })
+}
+
+/// Extracts regular market price and previous close from chart api json
+/// body, and latest extended hours price when chart has minute candles
+fn parse_chart_quote(body: &str) -> Result<Quote, BoxError> {
+    let response: ChartResponse = serde_json::from_str(body)?;
+    if let Some(error) = response.chart.error {
+        return Err(format!("{}: {}", error.code, error.description).into());
+    }
+    let result = response
+        .chart
+        .result
+        .and_then(|results| results.into_iter().next())
+        .ok_or("no result in chart response")?;
+    let extended = extended_price(&result);
+    let meta = result.meta;
+    Ok(Quote {
+        price: meta
+            .regular_market_price
+            .ok_or("no regularMarketPrice in chart response")?,
+        previous_close: meta.previous_close.or(meta.chart_previous_close),
+        currency: meta.currency,
+        extended,
+        day_range: meta
+            .regular_market_day_low
+            .zip(meta.regular_market_day_high),
+        year_range: meta.fifty_two_week_low.zip(meta.fifty_two_week_high),
+    })
+}
+
+/// Only US stocks trade before and after regular session on Yahoo. Other
+/// listings have exchange suffix, ex. `VWRL.AS`, while US share classes
+/// like `BRK-B` don't
+fn has_extended_hours(ticker: &str) -> bool {
+    AssetClass::of(ticker) == AssetClass::Stock && !ticker.contains('.')
+}
+
+/// Fetches quote from yahoo finance json api, with today's minute candles
+/// including pre-market and after-hours when `extended` is asked
+async fn fetch_chart_quote(
+    client: &Client,
+    ticker: &str,
+    extended: bool,
+) -> Result<Quote, BoxError> {
+    let url = format!(
+        "https://query1.finance.yahoo.com/v8/finance/chart/{}",
+        escape(ticker)
+    );
+    let mut request = client.get(url);
+    if extended && has_extended_hours(ticker) {
+        request = request.query(&[
+            ("range", "1d"),
+            ("interval", "1m"),
+            ("includePrePost", "true"),
+        ]);
+    }
+    let body = request.send().await?.text().await?;
+    parse_chart_quote(&body)
+}
+
+/// Scrapes price from yahoo finance quote html page
+async fn scrape_price(client: &Client, ticker: &str) -> Result<f64, BoxError> {
+    let regex_str = format!(
+        "data-symbol=\"{}.*?regularMarketPrice.*?value=\"(?<price>.*?)\"",
+        regex::escape(ticker)
+    );
+
+    let price = Regex::new(&regex_str)?;
+    let url = format!("https://finance.yahoo.com/quote/{}/", escape(ticker));
+    let req = client.get(url).send().await?;
+    let body = req.text().await?;
+
+    let caps = price
+        .captures(&body)
+        .ok_or("price not found in quote page")?;
+    let b = caps.name("price").map_or("0", |m| m.as_str());
+    Ok(b.parse().unwrap_or(0.0))
+}
+
+/// Fetches quote through json api, falling back to html scraper if api fails.
+/// Scraper knows only price, so previous close is unknown then
+async fn fetch_quote(client: &Client, ticker: &str, extended: bool) -> Result<Quote, BoxError> {
+    match fetch_chart_quote(client, ticker, extended).await {
+        Ok(quote) => Ok(quote),
+        Err(e) => {
+            log::warn!(
+                "Chart api failed for {}: {}, falling back to scraper",
+                ticker,
+                e
+            );
+            let price = scrape_price(client, ticker).await?;
+            Ok(Quote {
+                price,
+                previous_close: None,
+                currency: None,
+                extended: None,
+                day_range: None,
+                year_range: None,
+            })
+        }
+    }
+}
+
+/// Http client with browser user agent, so yahoo doesn't reject requests
+pub fn client() -> Result<Client, BoxError> {
+    Ok(Client::builder().user_agent(CHROME_USER_AGENT).build()?)
+}
+
+/// Fetches quotes of all tickers from Yahoo concurrently on shared client.
+/// Ticker which fails to fetch is logged and left with 0 price
+pub async fn fetch_quotes(client: &Client, tickers: &[String]) -> Result<Quotes, BoxError> {
+    fetch_quotes_from(&QuoteSource::Yahoo, client, tickers, false).await
+}
+
+/// Like [`fetch_quotes`], fetching from `source` with `extended` hours
+/// prices where source knows them
+pub async fn fetch_quotes_from(
+    source: &QuoteSource,
+    client: &Client,
+    tickers: &[String],
+    extended: bool,
+) -> Result<Quotes, BoxError> {
+    log::info!("Fetching stock tickers from {}", source.name());
+
+    let fetched = join_all(
+        tickers
+            .iter()
+            .map(|ticker| source.fetch_quote(client, ticker, extended)),
+    )
+    .await;
+
+    let mut quotes = Quotes::new();
+    for (ticker, quote) in tickers.iter().zip(fetched) {
+        let quote = quote.unwrap_or_else(|e| {
+            log::error!("Unable to fetch {}: {}", ticker, e);
+            Quote::default()
+        });
+        quotes.insert(ticker.clone(), quote);
+    }
+
+    log::debug!("Fetching complete");
+
+    Ok(quotes)
+}
+
+/// Fetches prices of all tickers concurrently on shared client
+pub async fn fetch_stock_tickers(
+    client: &Client,
+    tickers: &[String],
+) -> Result<StockTickerType, BoxError> {
+    let quotes = fetch_quotes(client, tickers).await?;
+    Ok(quotes.into_iter().map(|(t, q)| (t, q.price)).collect())
+}
+
+/// Converts fetched tickers into lines drawn on keyboard
+pub fn to_lines(stocks: StockTickerType) -> Vec<Line> {
+    stocks
+        .into_iter()
+        // we use max 4 chars for ticker so it fits. example:
+        // TSLA: 500$
+        // VWRL: 200$
+        .map(|(ticker, v)| Line::new(format!("{:.4}: {:.0}$", ticker, v)))
+        .collect()
+}
+
+/// Formats quote with direction arrow and change, ex. `TSLA 241 ▲1.2%`. Falls
+/// back to plain `TSLA: 241$` (or `VWRL: 107EUR` in other currency) while
+/// change is unknown. Indices, futures and fx pairs are drawn by their
+/// [`AssetClass`], ex. `GSPC 5815 ▲0.4%`, `CL 71.56 ▼1.2%` or
+/// `EUR/USD 1.0942 ▲0.1%`. Extended hours price is drawn with its change
+/// against regular session close and session marker, ex. `TSLA 243 ▲0.8% pre`.
+/// Template fields are `symbol`, `price`, `currency`, `arrow` with `change`
+/// once change is known, and `session` in extended hours
+pub fn quote_line(ticker: &str, quote: &Quote) -> Line {
+    if let Some(extended) = quote.extended {
+        let session = extended.session.as_str();
+        let mut line = quote_line(
+            ticker,
+            &Quote {
+                price: extended.price,
+                previous_close: Some(quote.price),
+                extended: None,
+                ..quote.clone()
+            },
+        );
+        line.text.push_str(&format!(" {}", session));
+        return line.with_field("session", session);
+    }
+    let class = AssetClass::of(ticker);
+    let symbol = class.symbol(ticker);
+    // pairs are recognizable only whole
+    let shown = match class {
+        AssetClass::Fx => symbol.clone(),
+        _ => format!("{:.4}", symbol),
+    };
+    let decimals = class.decimals(quote.price);
+    let currency = if class.is_priced() {
+        currency_sign(quote.currency.as_deref().unwrap_or("USD"))
+    } else {
+        String::new()
+    };
+    let mut line = match quote.change_percent() {
+        Some(change) => {
+            let arrow = if change < 0.0 { '▼' } else { '▲' };
+            Line::new(format!(
+                "{} {:.*} {}{:.1}%",
+                shown,
+                decimals,
+                quote.price,
+                arrow,
+                change.abs()
+            ))
+            .with_field("arrow", arrow.to_string())
+            .with_field("change", change.abs())
+        }
+        None => Line::new(format!(
+            "{}: {:.*}{}",
+            shown, decimals, quote.price, currency
+        )),
+    };
+    line = line
+        .with_field("symbol", symbol)
+        .with_field("price", quote.price)
+        .with_field("currency", currency);
+    // zero price means fetch failed, it isn't real value to alert on
+    if quote.price == 0.0 {
+        line
+    } else {
+        line.with_metric(ticker, quote.price)
+    }
+}
+
+/// Adds day and 52-week range of quote to its line as `day_low`, `day_high`,
+/// `year_low` and `year_high` fields, where they're known
+pub fn with_ranges(mut line: Line, quote: &Quote) -> Line {
+    if let Some((low, high)) = quote.day_range {
+        line = line.with_field("day_low", low).with_field("day_high", high);
+    }
+    if let Some((low, high)) = quote.year_range {
+        line = line
+            .with_field("year_low", low)
+            .with_field("year_high", high);
+    }
+    line
+}
+
+/// Sets configured currency of tickers and converts prices into display
+/// currency. Quotes with unknown currency or rate are left as they are, and
+/// so are indices and fx pairs, which aren't priced in currency
+pub fn apply_fx(quotes: &mut Quotes, config: &FxConfig, rates: &Rates) {
+    for (ticker, quote) in quotes.iter_mut() {
+        if !AssetClass::of(ticker).is_priced() {
+            continue;
+        }
+        if let Some(currency) = config.currencies.get(ticker) {
+            quote.currency = Some(currency.to_uppercase());
+        }
+        let (Some(from), Some(to)) = (&quote.currency, &config.display_currency) else {
+            continue;
+        };
+        let Some(rate) = rates.rate(from, to) else {
+            log::warn!("No exchange rate {}/{} for {}", from, to, ticker);
+            continue;
+        };
+        quote.price *= rate;
+        quote.previous_close = quote.previous_close.map(|close| close * rate);
+        if let Some(extended) = &mut quote.extended {
+            extended.price *= rate;
+        }
+        let convert = |(low, high): (f64, f64)| (low * rate, high * rate);
+        quote.day_range = quote.day_range.map(convert);
+        quote.year_range = quote.year_range.map(convert);
+        quote.currency = Some(to.to_uppercase());
+    }
+}
+
+/// Stock prices, from Yahoo finance unless other source is set
+pub struct StocksProvider {
+    tickers: Vec<String>,
+    source: QuoteSource,
+    client: Client,
+    /// Quotes of previous fetch. Price is used as previous close when api
+    /// doesn't know it, and whole quote is shown while ticker's market is closed
+    last_quotes: Mutex<Quotes>,
+    fx: Option<(FxConfig, Arc<FxRates>)>,
+    market: Option<MarketConfig>,
+    extended_hours: bool,
+    ranges: bool,
+    /// cached dividends, `None` unless asked
+    dividends: Option<Dividends>,
+}
+
+impl StocksProvider {
+    pub fn new(tickers: Vec<String>) -> Result<Self, BoxError> {
+        Ok(StocksProvider {
+            tickers,
+            source: QuoteSource::Yahoo,
+            client: client()?,
+            last_quotes: Mutex::new(Quotes::new()),
+            fx: None,
+            market: None,
+            extended_hours: false,
+            ranges: false,
+            dividends: None,
+        })
+    }
+
+    /// fetches quotes from `source` instead of Yahoo
+    pub fn with_source(mut self, source: QuoteSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// converts prices with shared exchange rates as `[fx]` config says
+    pub fn with_fx(mut self, config: FxConfig, rates: Arc<FxRates>) -> Self {
+        self.fx = Some((config, rates));
+        self
+    }
+
+    /// skips refetching tickers while their exchange is closed
+    pub fn with_market(mut self, config: MarketConfig) -> Self {
+        self.market = Some(config);
+        self
+    }
+
+    /// fetches pre-market and after-hours prices of US tickers, they aren't
+    /// closed for `[market]` then
+    pub fn with_extended_hours(mut self, extended_hours: bool) -> Self {
+        self.extended_hours = extended_hours;
+        self
+    }
+
+    /// adds day and 52-week ranges to lines, see [`with_ranges`]
+    pub fn with_ranges(mut self, ranges: bool) -> Self {
+        self.ranges = ranges;
+        self
+    }
+
+    /// adds dividend yield and next ex-dividend date to stock lines, see
+    /// [`dividends::with_dividend`]
+    pub fn with_dividends(mut self, dividends: bool) -> Self {
+        self.dividends = dividends.then(Dividends::new);
+        self
+    }
+
+    /// Lines of tickers with quotes of ones to refetch from `fetch_quotes`,
+    /// zero price of quote means its ticker failed
+    async fn fetch_with<F, Fut>(&self, fetch_quotes: F) -> Result<Vec<Line>, BoxError>
+    where
+        F: FnOnce(Vec<String>) -> Fut,
+        Fut: Future<Output = Result<Quotes, BoxError>>,
+    {
+        let now = Utc::now();
+        let is_closed = |ticker: &str| {
+            self.market.as_ref().is_some_and(|market| {
+                let trades = market.is_open(ticker, &now)
+                    || (self.extended_hours && market.is_extended(ticker, &now));
+                !trades
+            })
+        };
+        // closed tickers are refetched only until there is quote to show
+        let cached = self.last_quotes.lock().unwrap().clone();
+        let (closed, to_fetch): (Vec<String>, Vec<String>) = self
+            .tickers
+            .iter()
+            .cloned()
+            .partition(|ticker| is_closed(ticker) && cached.contains_key(ticker));
+
+        let mut quotes = if to_fetch.is_empty() {
+            log::debug!("Markets of all tickers closed, skipping fetch");
+            Quotes::new()
+        } else {
+            fetch_quotes(to_fetch).await?
+        };
+        // every ticker failing is likely rate limit or network, so error out
+        // and let fetch be retried
+        if !quotes.is_empty() && quotes.values().all(|quote| quote.price == 0.0) {
+            return Err("no ticker could be fetched".into());
+        }
+        if let Some((config, rates)) = &self.fx {
+            match rates.get().await {
+                Ok(rates) => apply_fx(&mut quotes, config, &rates),
+                Err(e) => log::warn!(
+                    "Unable to fetch exchange rates, showing native prices: {}",
+                    e
+                ),
+            }
+        }
+
+        let dividends = match &self.dividends {
+            Some(dividends) => dividends.get(&self.client, &self.tickers).await,
+            None => HashMap::new(),
+        };
+
+        let mut last_quotes = self.last_quotes.lock().unwrap();
+        let mut failed = Vec::new();
+        for (ticker, quote) in quotes.iter_mut() {
+            if quote.price == 0.0 {
+                failed.push(ticker.clone());
+                continue;
+            }
+            if quote.previous_close.is_none() {
+                quote.previous_close = last_quotes.get(ticker).map(|last| last.price);
+            }
+            last_quotes.insert(ticker.clone(), quote.clone());
+        }
+        // failed ticker shows its last known quote as stale, nothing without one
+        for ticker in &failed {
+            match last_quotes.get(ticker) {
+                Some(last) => quotes.insert(ticker.clone(), last.clone()),
+                None => quotes.remove(ticker),
+            };
+        }
+        for ticker in closed {
+            quotes.insert(ticker.clone(), cached[&ticker].clone());
+        }
+
+        Ok(quotes
+            .iter()
+            .map(|(ticker, quote)| {
+                let mut line = quote_line(ticker, quote);
+                if self.ranges {
+                    line = with_ranges(line, quote);
+                }
+                if let Some(dividend) = dividends.get(ticker) {
+                    line = dividends::with_dividend(line, dividend);
+                }
+                line.stale = failed.contains(ticker);
+                if is_closed(ticker) {
+                    market::mark_closed(line)
+                } else {
+                    line
+                }
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DataProvider for StocksProvider {
+    fn name(&self) -> &str {
+        "stocks"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        self.fetch_with(|tickers| async move {
+            fetch_quotes_from(&self.source, &self.client, &tickers, self.extended_hours).await
+        })
+        .await
+    }
+}
+
+#[tokio::test]
+async fn testing_fetch_of_stock() -> Result<(), BoxError> {
+    let tickers: Vec<String> = TICKERS.iter().map(|t| t.to_string()).collect();
+    let st = fetch_stock_tickers(&client()?, &tickers).await?;
+
+    // Example output:
+    //
+    // [src/providers/stocks.rs:50] &st = {
+    // "VWRL.AS": 107.2,
+    // "TSLA": 237.03,
+    // "AAPL": 180.51,
+    // }
+
+    assert!(st.contains_key("TSLA"));
+    assert!(st.get("TSLA").unwrap() > &0.0);
+
+    assert!(st.contains_key("VWRL.AS"));
+    assert!(st.get("VWRL.AS").unwrap() > &0.0);
+    Ok(())
+}
+
+#[test]
+fn testing_chart_price_parsing() {
+    let body = r#"{"chart":{"result":[{"meta":{"currency":"USD","symbol":"TSLA","regularMarketPrice":237.03,"chartPreviousClose":234.5,
+        "regularMarketDayLow":233.1,"regularMarketDayHigh":238.8,"fiftyTwoWeekLow":138.8,"fiftyTwoWeekHigh":299.29}}],"error":null}}"#;
+    let quote = parse_chart_quote(body).unwrap();
+    assert_eq!(quote.price, 237.03);
+    assert_eq!(quote.previous_close, Some(234.5));
+    assert_eq!(quote.currency.as_deref(), Some("USD"));
+    assert_eq!(quote.extended, None);
+    assert_eq!(quote.day_range, Some((233.1, 238.8)));
+    let line = with_ranges(quote_line("TSLA", &quote), &quote);
+    assert_eq!(
+        line.field("year_high"),
+        Some(&crate::render::template::Value::Number(299.29))
+    );
+
+    // minute candles of pre-market, last one without trade
+    let body = r#"{"chart":{"result":[{"meta":{"currency":"USD","regularMarketPrice":237.03,
+        "chartPreviousClose":234.5,"currentTradingPeriod":{
+        "pre":{"timezone":"EST","start":1704790800,"end":1704810600,"gmtoffset":-18000},
+        "regular":{"timezone":"EST","start":1704810600,"end":1704834000,"gmtoffset":-18000},
+        "post":{"timezone":"EST","start":1704834000,"end":1704848400,"gmtoffset":-18000}}},
+        "timestamp":[1704794940,1704795000,1704795060],
+        "indicators":{"quote":[{"close":[237.9,238.1,null],"volume":[310,120,0]}]}}],"error":null}}"#;
+    let extended = parse_chart_quote(body).unwrap().extended.unwrap();
+    assert_eq!(extended.session, Session::Pre);
+    assert_eq!(extended.price, 238.1);
+    assert!(has_extended_hours("TSLA"));
+    assert!(!has_extended_hours("VWRL.AS"));
+    assert!(has_extended_hours("BRK-B"));
+    assert!(!has_extended_hours("^GSPC"));
+
+    let body = r#"{"chart":{"result":null,"error":{"code":"Not Found","description":"No data found, symbol may be delisted"}}}"#;
+    assert!(parse_chart_quote(body).is_err());
+}
+
+#[test]
+fn testing_quote_line() {
+    let mut quote = Quote {
+        price: 241.0,
+        previous_close: Some(238.14),
+        currency: Some("USD".into()),
+        extended: None,
+        day_range: None,
+        year_range: None,
+    };
+    assert_eq!(quote_line("TSLA", &quote).text, "TSLA 241 ▲1.2%");
+
+    quote.previous_close = Some(250.0);
+    assert_eq!(quote_line("TSLA", &quote).text, "TSLA 241 ▼3.6%");
+
+    quote.previous_close = None;
+    assert_eq!(quote_line("TSLA", &quote).text, "TSLA: 241$");
+
+    quote.currency = Some("EUR".into());
+    assert_eq!(quote_line("VWRL.AS", &quote).text, "VWRL: 241EUR");
+
+    quote.currency = Some("USD".into());
+    quote.extended = Some(Extended {
+        session: Session::Post,
+        price: 243.0,
+    });
+    let line = quote_line("TSLA", &quote);
+    assert_eq!(line.text, "TSLA 243 ▲0.8% post");
+    assert_eq!(line.metric.unwrap().value, 243.0);
+}
+
+#[test]
+fn testing_asset_classes() {
+    assert_eq!(escape("^GSPC"), "%5EGSPC");
+    assert_eq!(escape("GC=F"), "GC%3DF");
+    assert_eq!(escape("VWRL.AS"), "VWRL.AS");
+
+    let quote = |price: f64, previous_close: f64| Quote {
+        price,
+        previous_close: Some(previous_close),
+        currency: Some("USD".into()),
+        ..Quote::default()
+    };
+    let line = quote_line("^GSPC", &quote(5815.03, 5792.0));
+    assert_eq!(line.text, "GSPC 5815 ▲0.4%");
+    assert_eq!(line.metric.unwrap().symbol, "^GSPC");
+    assert_eq!(
+        quote_line("CL=F", &quote(71.56, 72.43)).text,
+        "CL 71.56 ▼1.2%"
+    );
+    assert_eq!(
+        quote_line("GC=F", &quote(2650.3, 2640.0)).text,
+        "GC 2650 ▲0.4%"
+    );
+    assert_eq!(
+        quote_line("EURUSD=X", &quote(1.09421, 1.0931)).text,
+        "EUR/USD 1.0942 ▲0.1%"
+    );
+    assert_eq!(
+        quote_line("JPY=X", &quote(149.532, 149.2)).text,
+        "USD/JPY 149.53 ▲0.2%"
+    );
+    let unknown = Quote {
+        previous_close: None,
+        ..quote(5815.0, 0.0)
+    };
+    assert_eq!(quote_line("^GSPC", &unknown).text, "GSPC: 5815");
+}
+
+#[tokio::test]
+async fn testing_failed_ticker_shows_last_quote() -> Result<(), BoxError> {
+    // tickers without price fail to fetch, like fetch_quotes_from reports them
+    let prices = |prices: &[(&str, f64)]| {
+        let quotes: Quotes = ["AAPL", "TSLA"]
+            .iter()
+            .map(|ticker| {
+                let price = prices
+                    .iter()
+                    .find(|(other, _)| other == ticker)
+                    .map_or(0.0, |(_, price)| *price);
+                let quote = Quote {
+                    price,
+                    ..Quote::default()
+                };
+                (ticker.to_string(), quote)
+            })
+            .collect();
+        move |_| async move { Ok(quotes) }
+    };
+    let tickers = vec!["AAPL".to_string(), "TSLA".to_string()];
+    let provider = StocksProvider::new(tickers.clone())?;
+    let lines = provider
+        .fetch_with(prices(&[("AAPL", 180.0), ("TSLA", 240.0)]))
+        .await?;
+    assert!(lines.iter().all(|line| !line.stale));
+
+    let lines = provider.fetch_with(prices(&[("TSLA", 250.0)])).await?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "AAPL: 180$");
+    assert!(lines[0].stale);
+    assert!(!lines[1].stale);
+
+    // without cached quote failed ticker isn't shown
+    let provider = StocksProvider::new(tickers)?;
+    let lines = provider.fetch_with(prices(&[("TSLA", 250.0)])).await?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].text, "TSLA: 250$");
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Stock quotes from stooq.com csv api, fallback when Yahoo fails
+//!
+//! Stooq needs no key, but it knows only last price: previous close and
+//! currency stay unknown, so change is computed against previous fetch.
+
+use reqwest::Client;
+
+use super::stocks::Quote;
+use crate::BoxError;
+
+/// yahoo ticker suffix and matching stooq market suffix
+const MARKETS: [(&str, &str); 5] = [
+    (".L", ".uk"),
+    (".DE", ".de"),
+    (".F", ".de"),
+    (".T", ".jp"),
+    (".HK", ".hk"),
+];
+
+/// yahoo index and matching stooq index, they're named differently
+const INDICES: [(&str, &str); 6] = [
+    ("^GSPC", "^spx"),
+    ("^IXIC", "^ndq"),
+    ("^DJI", "^dji"),
+    ("^GDAXI", "^dax"),
+    ("^FTSE", "^ukx"),
+    ("^N225", "^nkx"),
+];
+
+/// Stooq symbol of yahoo ticker, ex. `TSLA` -> `tsla.us`, `BP.L` -> `bp.uk`,
+/// `GC=F` -> `gc.f` and `EURUSD=X` -> `eurusd`. Tickers of other exchanges
+/// and other indices are passed as they are
+pub fn symbol(ticker: &str) -> String {
+    let ticker = ticker.to_uppercase();
+    if let Some((_, index)) = INDICES.iter().find(|(yahoo, _)| *yahoo == ticker) {
+        return index.to_string();
+    }
+    let symbol = match MARKETS.iter().find(|(suffix, _)| ticker.ends_with(suffix)) {
+        Some((suffix, market)) => format!("{}{}", &ticker[..ticker.len() - suffix.len()], market),
+        None if ticker.ends_with("=F") => format!("{}.f", &ticker[..ticker.len() - 2]),
+        None if ticker.ends_with("=X") => ticker[..ticker.len() - 2].to_string(),
+        None if !ticker.contains('.') && !ticker.starts_with('^') => format!("{}.us", ticker),
+        None => ticker,
+    };
+    symbol.to_lowercase()
+}
+
+/// Parses `Symbol,Date,Time,Open,High,Low,Close,Volume` csv with header,
+/// unknown symbol has `N/D` in every column
+fn parse_quote(body: &str) -> Result<Quote, BoxError> {
+    let mut rows = body.lines();
+    let header: Vec<&str> = rows
+        .next()
+        .ok_or("empty stooq response")?
+        .split(',')
+        .collect();
+    let row: Vec<&str> = rows
+        .next()
+        .ok_or("no quote in stooq response")?
+        .split(',')
+        .collect();
+    let close = header
+        .iter()
+        .position(|column| column.trim() == "Close")
+        .and_then(|i| row.get(i))
+        .ok_or("no Close column in stooq response")?;
+    let price: f64 = close
+        .trim()
+        .parse()
+        .map_err(|_| format!("no price in stooq response: {}", close))?;
+    Ok(Quote {
+        price,
+        previous_close: None,
+        currency: None,
+        extended: None,
+        day_range: None,
+        year_range: None,
+    })
+}
+
+pub async fn fetch_quote(client: &Client, ticker: &str) -> Result<Quote, BoxError> {
+    let body = client
+        .get("https://stooq.com/q/l/")
+        .query(&[
+            ("s", symbol(ticker).as_str()),
+            ("f", "sd2t2ohlcv"),
+            ("h", ""),
+            ("e", "csv"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_quote(&body)
+}
+
+#[test]
+fn testing_stooq_quote() {
+    assert_eq!(symbol("TSLA"), "tsla.us");
+    assert_eq!(symbol("BP.L"), "bp.uk");
+    assert_eq!(symbol("VWRL.AS"), "vwrl.as");
+    assert_eq!(symbol("^GSPC"), "^spx");
+    assert_eq!(symbol("GC=F"), "gc.f");
+    assert_eq!(symbol("EURUSD=X"), "eurusd");
+
+    let body = "Symbol,Date,Time,Open,High,Low,Close,Volume\r\nTSLA.US,2024-01-12,22:00:19,220.08,225.34,217.15,218.89,122889000\r\n";
+    assert_eq!(parse_quote(body).unwrap().price, 218.89);
+    let body =
+        "Symbol,Date,Time,Open,High,Low,Close,Volume\r\nNOPE.US,N/D,N/D,N/D,N/D,N/D,N/D,N/D\r\n";
+    assert!(parse_quote(body).is_err());
+}
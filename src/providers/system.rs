@@ -0,0 +1,145 @@
+//! Health of host machine from `sysinfo`: cpu, memory and load average
+
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use sysinfo::System;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+const STATS: [&str; 3] = ["cpu", "mem", "load"];
+
+/// `[system]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SystemConfig {
+    /// stats shown and their order, any of `cpu`, `mem`, `load`
+    pub show: Vec<String>,
+}
+
+impl Default for SystemConfig {
+    fn default() -> Self {
+        SystemConfig {
+            show: STATS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl SystemConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.show.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "system.show needs at least one stat".into(),
+            ));
+        }
+        if let Some(stat) = self.show.iter().find(|s| !STATS.contains(&s.as_str())) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "system.show {:?} is not one of {}",
+                stat,
+                STATS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Snapshot of machine stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stats {
+    cpu_percent: f32,
+    used_memory: u64,
+    total_memory: u64,
+    load_one: f64,
+}
+
+fn gigabytes(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0 / 1024.0
+}
+
+/// Formats stats into lines, ex. `CPU 12%`, `MEM 8.1/16G`, `LOAD 1.20`
+fn to_lines(config: &SystemConfig, stats: &Stats) -> Vec<Line> {
+    config
+        .show
+        .iter()
+        .map(|stat| match stat.as_str() {
+            "cpu" => Line::new(format!("CPU {:.0}%", stats.cpu_percent)),
+            "mem" => Line::new(format!(
+                "MEM {:.1}/{:.0}G",
+                gigabytes(stats.used_memory),
+                gigabytes(stats.total_memory)
+            )),
+            _ => Line::new(format!("LOAD {:.2}", stats.load_one)),
+        })
+        .collect()
+}
+
+/// Local machine stats, no network involved
+pub struct SystemProvider {
+    config: SystemConfig,
+    system: Mutex<System>,
+}
+
+impl SystemProvider {
+    pub fn new(config: SystemConfig) -> Self {
+        let mut system = System::new();
+        // cpu usage is difference between two refreshes, so first fetch
+        // already has something to compare with
+        system.refresh_cpu_usage();
+        SystemProvider {
+            config,
+            system: Mutex::new(system),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for SystemProvider {
+    fn name(&self) -> &str {
+        "system"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        let stats = {
+            let mut system = self.system.lock().unwrap();
+            system.refresh_cpu_usage();
+            system.refresh_memory();
+            Stats {
+                cpu_percent: system.global_cpu_info().cpu_usage(),
+                used_memory: system.used_memory(),
+                total_memory: system.total_memory(),
+                load_one: System::load_average().one,
+            }
+        };
+        Ok(to_lines(&self.config, &stats))
+    }
+}
+
+#[test]
+fn testing_system_lines() {
+    let stats = Stats {
+        cpu_percent: 12.4,
+        used_memory: 8_700_000_000,
+        total_memory: 17_179_869_184,
+        load_one: 1.2,
+    };
+    let config = SystemConfig {
+        show: vec!["load".into(), "cpu".into(), "mem".into()],
+    };
+    let lines: Vec<String> = to_lines(&config, &stats)
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["LOAD 1.20", "CPU 12%", "MEM 8.1/16G"]);
+
+    assert!(SystemConfig {
+        show: vec!["gpu".into()]
+    }
+    .validate()
+    .is_err());
+}
@@ -0,0 +1,170 @@
+//! Departures from GTFS-realtime trip updates feed
+//!
+//! Feed is protobuf `FeedMessage` of `gtfs-realtime.proto`, messages below
+//! declare only fields of trip updates needed for departures from one stop,
+//! prost skips the rest. Realtime feed doesn't carry route names or schedule,
+//! so route id is shown and stops which have only delay without predicted
+//! time are skipped.
+
+use chrono::DateTime;
+use prost::Message;
+
+use super::Departure;
+use crate::BoxError;
+
+#[derive(Clone, PartialEq, Message)]
+struct FeedMessage {
+    #[prost(message, repeated, tag = "2")]
+    entity: Vec<FeedEntity>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FeedEntity {
+    #[prost(string, optional, tag = "1")]
+    id: Option<String>,
+    #[prost(message, optional, tag = "3")]
+    trip_update: Option<TripUpdate>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TripUpdate {
+    #[prost(message, optional, tag = "1")]
+    trip: Option<TripDescriptor>,
+    #[prost(message, repeated, tag = "2")]
+    stop_time_update: Vec<StopTimeUpdate>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TripDescriptor {
+    #[prost(string, optional, tag = "1")]
+    trip_id: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    route_id: Option<String>,
+    #[prost(enumeration = "TripRelationship", optional, tag = "4")]
+    schedule_relationship: Option<i32>,
+}
+
+/// `TripDescriptor.ScheduleRelationship`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum TripRelationship {
+    Scheduled = 0,
+    Added = 1,
+    Unscheduled = 2,
+    Canceled = 3,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct StopTimeUpdate {
+    #[prost(string, optional, tag = "4")]
+    stop_id: Option<String>,
+    #[prost(message, optional, tag = "2")]
+    arrival: Option<StopTimeEvent>,
+    #[prost(message, optional, tag = "3")]
+    departure: Option<StopTimeEvent>,
+    #[prost(enumeration = "StopRelationship", optional, tag = "5")]
+    schedule_relationship: Option<i32>,
+}
+
+/// `StopTimeUpdate.ScheduleRelationship`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+enum StopRelationship {
+    Scheduled = 0,
+    Skipped = 1,
+    NoData = 2,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct StopTimeEvent {
+    #[prost(int32, optional, tag = "1")]
+    delay: Option<i32>,
+    #[prost(int64, optional, tag = "2")]
+    time: Option<i64>,
+}
+
+/// Departures from `stop` in feed, unsorted
+pub fn departures(feed: &[u8], stop: &str) -> Result<Vec<Departure>, BoxError> {
+    let feed = FeedMessage::decode(feed).map_err(|e| format!("malformed gtfs-rt feed: {}", e))?;
+    let mut departures = Vec::new();
+    for update in feed.entity.iter().filter_map(|e| e.trip_update.as_ref()) {
+        let trip = update.trip.clone().unwrap_or_default();
+        if trip.schedule_relationship() == TripRelationship::Canceled {
+            continue;
+        }
+        let route = trip.route_id.unwrap_or_else(|| "?".into());
+        for stop_time in &update.stop_time_update {
+            if stop_time.stop_id.as_deref() != Some(stop)
+                || stop_time.schedule_relationship() == StopRelationship::Skipped
+            {
+                continue;
+            }
+            // last stop of trip has arrival only
+            let Some(event) = stop_time.departure.as_ref().or(stop_time.arrival.as_ref()) else {
+                continue;
+            };
+            let Some(when) = event
+                .time
+                .and_then(|time| DateTime::from_timestamp(time, 0))
+            else {
+                continue;
+            };
+            departures.push(Departure {
+                route: route.clone(),
+                direction: None,
+                when,
+                delay_secs: event.delay.map(i64::from),
+                platform: None,
+            });
+        }
+    }
+    Ok(departures)
+}
+
+#[test]
+fn testing_gtfs_feed() {
+    let departure = StopTimeEvent {
+        delay: Some(-60),
+        time: Some(1_760_454_240),
+    };
+    let stop_time = |stop: &str| StopTimeUpdate {
+        stop_id: Some(stop.into()),
+        departure: Some(departure.clone()),
+        ..StopTimeUpdate::default()
+    };
+    let entity = |trip: TripDescriptor, stops: Vec<StopTimeUpdate>| FeedEntity {
+        id: Some(trip.trip_id.clone().unwrap_or_default()),
+        trip_update: Some(TripUpdate {
+            trip: Some(trip),
+            stop_time_update: stops,
+        }),
+    };
+    let feed = FeedMessage {
+        entity: vec![
+            entity(
+                TripDescriptor {
+                    trip_id: Some("trip-1".into()),
+                    route_id: Some("42".into()),
+                    schedule_relationship: None,
+                },
+                vec![stop_time("other"), stop_time("1234")],
+            ),
+            entity(
+                TripDescriptor {
+                    trip_id: Some("trip-2".into()),
+                    route_id: None,
+                    schedule_relationship: Some(TripRelationship::Canceled as i32),
+                },
+                vec![stop_time("1234")],
+            ),
+        ],
+    }
+    .encode_to_vec();
+
+    let departures = departures(&feed, "1234").unwrap();
+    assert_eq!(departures.len(), 1);
+    assert_eq!(departures[0].route, "42");
+    assert_eq!(departures[0].when.timestamp(), 1_760_454_240);
+    assert_eq!(departures[0].delay_secs, Some(-60));
+    assert!(super::gtfs::departures(&[0x12, 0x05, 0x01], "1234").is_err());
+}
@@ -0,0 +1,262 @@
+//! Next departures from public transit stop, ex. `S3 Spandau 4m +2`
+//!
+//! Departures come either from GTFS-realtime trip updates feed of local
+//! transit agency or from HAFAS based `transport.rest` api which covers
+//! national rail and city transit of Germany and neighbours, ex.
+//! `https://v6.db.transport.rest`.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+mod gtfs;
+
+/// format of departures endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Api {
+    /// `transport.rest` HAFAS api, `<url>/stops/<stop>/departures`
+    #[default]
+    Hafas,
+    /// GTFS-realtime trip updates feed at `url`
+    GtfsRt,
+}
+
+/// `[transit]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransitConfig {
+    pub api: Api,
+    /// base url of hafas api or url of gtfs-rt feed
+    pub url: String,
+    /// stop id as api knows it, ex. `900100003` or gtfs `stop_id`
+    pub stop: String,
+    /// extra request headers, ex. api key of gtfs-rt feed
+    pub headers: BTreeMap<String, String>,
+    /// only these lines (route ids for gtfs-rt), all when empty
+    pub lines: Vec<String>,
+    /// departures shown
+    pub count: usize,
+    /// departures sooner than this are left out, ex. time to walk to stop
+    pub walk_mins: i64,
+}
+
+impl Default for TransitConfig {
+    fn default() -> Self {
+        TransitConfig {
+            api: Api::Hafas,
+            url: "https://v6.db.transport.rest".into(),
+            stop: String::new(),
+            headers: BTreeMap::new(),
+            lines: Vec::new(),
+            count: 3,
+            walk_mins: 0,
+        }
+    }
+}
+
+impl TransitConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            return Err(EloraError::ConfigInvalid(format!(
+                "transit.url {:?} is not http(s) url",
+                self.url
+            )));
+        }
+        if self.stop.is_empty() {
+            return Err(EloraError::ConfigInvalid("transit.stop is required".into()));
+        }
+        if self.count == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "transit.count must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Single departure from stop
+#[derive(Debug, Clone, PartialEq)]
+struct Departure {
+    /// line name, route id on gtfs-rt
+    route: String,
+    direction: Option<String>,
+    /// predicted time, with delay
+    when: DateTime<Utc>,
+    delay_secs: Option<i64>,
+    platform: Option<String>,
+}
+
+/// Response of hafas `/stops/<stop>/departures`, v6 wraps list in object
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HafasResponse {
+    Wrapped { departures: Vec<HafasDeparture> },
+    List(Vec<HafasDeparture>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HafasDeparture {
+    /// `None` when departure is cancelled
+    when: Option<DateTime<Utc>>,
+    delay: Option<i64>,
+    direction: Option<String>,
+    line: Option<HafasLine>,
+    platform: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HafasLine {
+    name: String,
+}
+
+impl HafasResponse {
+    fn departures(self) -> Vec<Departure> {
+        let departures = match self {
+            HafasResponse::Wrapped { departures } | HafasResponse::List(departures) => departures,
+        };
+        departures
+            .into_iter()
+            .filter_map(|departure| {
+                Some(Departure {
+                    route: departure.line.map(|line| line.name).unwrap_or_default(),
+                    direction: departure.direction,
+                    when: departure.when?,
+                    delay_secs: departure.delay,
+                    platform: departure.platform,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Next `count` departures of configured lines, ex. `S3 Spandau 4m +2`,
+/// delay shown when it's at least a minute
+fn to_lines(
+    config: &TransitConfig,
+    mut departures: Vec<Departure>,
+    now: DateTime<Utc>,
+) -> Vec<Line> {
+    departures.retain(|departure| {
+        (departure.when - now).num_minutes() >= config.walk_mins
+            && (config.lines.is_empty() || config.lines.contains(&departure.route))
+    });
+    departures.sort_by_key(|departure| departure.when);
+    departures
+        .iter()
+        .take(config.count)
+        .map(|departure| {
+            let minutes = (departure.when - now).num_minutes().max(0);
+            let mut text = departure.route.clone();
+            if let Some(direction) = &departure.direction {
+                text.push(' ');
+                text.push_str(direction);
+            }
+            text.push_str(&format!(" {}m", minutes));
+            let delay = departure.delay_secs.unwrap_or(0) / 60;
+            if delay > 0 {
+                text.push_str(&format!(" +{}", delay));
+            }
+            Line::new(text)
+                .with_field("route", departure.route.as_str())
+                .with_field("direction", departure.direction.clone().unwrap_or_default())
+                .with_field("minutes", minutes as f64)
+                .with_field("delay", delay as f64)
+                .with_field("platform", departure.platform.clone().unwrap_or_default())
+        })
+        .collect()
+}
+
+/// Departures from configured stop
+pub struct TransitProvider {
+    config: TransitConfig,
+    client: Client,
+}
+
+impl TransitProvider {
+    pub fn new(config: TransitConfig) -> Self {
+        TransitProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    async fn departures(&self) -> Result<Vec<Departure>, BoxError> {
+        let url = match self.config.api {
+            Api::Hafas => format!(
+                "{}/stops/{}/departures?duration=120",
+                self.config.url.trim_end_matches('/'),
+                self.config.stop
+            ),
+            Api::GtfsRt => self.config.url.clone(),
+        };
+        let mut request = self.client.get(url);
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?.error_for_status()?;
+        match self.config.api {
+            Api::Hafas => Ok(response.json::<HafasResponse>().await?.departures()),
+            Api::GtfsRt => gtfs::departures(&response.bytes().await?, &self.config.stop),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for TransitProvider {
+    fn name(&self) -> &str {
+        "transit"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching departures from stop {}", self.config.stop);
+
+        let departures = self.departures().await?;
+        Ok(to_lines(&self.config, departures, Utc::now()))
+    }
+}
+
+#[test]
+fn testing_transit_lines() {
+    let response: HafasResponse = serde_json::from_str(
+        r#"{"departures":[
+            {"when":"2026-10-14T17:04:00+02:00","delay":120,"direction":"Spandau","line":{"name":"S3"},"platform":"2"},
+            {"when":null,"delay":null,"direction":"Ahrensfelde","line":{"name":"S7"},"platform":"3"},
+            {"when":"2026-10-14T17:01:00+02:00","delay":0,"direction":"Erkner","line":{"name":"S3"},"platform":"1"},
+            {"when":"2026-10-14T17:20:00+02:00","delay":null,"direction":"Potsdam","line":{"name":"RE1"},"platform":null}
+        ]}"#,
+    )
+    .unwrap();
+    let config = TransitConfig {
+        stop: "900100003".into(),
+        count: 2,
+        walk_mins: 2,
+        ..TransitConfig::default()
+    };
+    config.validate().unwrap();
+    let now = "2026-10-14T15:00:00Z".parse().unwrap();
+
+    let lines = to_lines(&config, response.departures(), now);
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    assert_eq!(texts, ["S3 Spandau 4m +2", "RE1 Potsdam 20m"]);
+
+    let config = TransitConfig {
+        lines: vec!["RE1".into()],
+        ..config
+    };
+    let list: HafasResponse =
+        serde_json::from_str(r#"[{"when":"2026-10-14T17:20:00+02:00","line":{"name":"RE1"}}]"#)
+            .unwrap();
+    assert_eq!(to_lines(&config, list.departures(), now)[0].text, "RE1 20m");
+}
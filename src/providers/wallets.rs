@@ -0,0 +1,305 @@
+//! Balances of public BTC and ETH addresses valued at CoinGecko prices, ex.
+//! `WLT 24512$` with `BTC 0.5213 22401$` per coin
+//!
+//! BTC balances are from Blockstream explorer and ETH from Blockscout, both
+//! need no key. Only public addresses are configured, balances are read
+//! without any keys of wallet. Total value carries metric under label.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{crypto, currency_sign, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+const BTC_EXPLORER: &str = "https://blockstream.info/api/address";
+const ETH_EXPLORER: &str = "https://eth.blockscout.com/api/v2/addresses";
+
+/// `[wallets]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WalletsConfig {
+    /// bitcoin addresses, ex. `bc1q...`
+    pub btc: Vec<String>,
+    /// ethereum addresses, ex. `0x...`
+    pub eth: Vec<String>,
+    /// currency balances are valued in, ex. `usd` or `eur`
+    pub vs_currency: String,
+    pub label: String,
+}
+
+impl Default for WalletsConfig {
+    fn default() -> Self {
+        WalletsConfig {
+            btc: Vec::new(),
+            eth: Vec::new(),
+            vs_currency: "usd".into(),
+            label: "WLT".into(),
+        }
+    }
+}
+
+impl WalletsConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.btc.is_empty() && self.eth.is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "wallets needs at least one btc or eth address".into(),
+            ));
+        }
+        if let Some(address) = self.btc.iter().find(|a| a.trim().is_empty()) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "wallets btc address {:?} can't be empty",
+                address
+            )));
+        }
+        if let Some(address) = self.eth.iter().find(|a| {
+            a.len() != 42 || !a.starts_with("0x") || !a[2..].chars().all(|c| c.is_ascii_hexdigit())
+        }) {
+            return Err(EloraError::ConfigInvalid(format!(
+                "wallets eth address {:?} is not 0x and 40 hex digits",
+                address
+            )));
+        }
+        if self.vs_currency.trim().is_empty() {
+            return Err(EloraError::ConfigInvalid(
+                "wallets.vs_currency can't be empty".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coin {
+    Btc,
+    Eth,
+}
+
+impl Coin {
+    /// CoinGecko coin id
+    fn id(self) -> &'static str {
+        match self {
+            Coin::Btc => "bitcoin",
+            Coin::Eth => "ethereum",
+        }
+    }
+}
+
+/// Response of Blockstream `/api/address/<address>`, only fields we use
+#[derive(Debug, Deserialize)]
+struct BtcAddress {
+    chain_stats: TxoStats,
+    mempool_stats: TxoStats,
+}
+
+/// sums in satoshis
+#[derive(Debug, Deserialize)]
+struct TxoStats {
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+}
+
+/// Response of Blockscout `/api/v2/addresses/<address>`
+#[derive(Debug, Deserialize)]
+struct EthAddress {
+    /// wei as decimal string, `null` for address never seen
+    coin_balance: Option<String>,
+}
+
+impl BtcAddress {
+    /// confirmed and unconfirmed balance in BTC
+    fn balance(&self) -> f64 {
+        let sats = (self.chain_stats.funded_txo_sum + self.mempool_stats.funded_txo_sum)
+            .saturating_sub(self.chain_stats.spent_txo_sum + self.mempool_stats.spent_txo_sum);
+        sats as f64 / 1e8
+    }
+}
+
+impl EthAddress {
+    /// balance in ETH
+    fn balance(&self) -> Result<f64, BoxError> {
+        let wei: u128 = match &self.coin_balance {
+            Some(wei) => wei.parse()?,
+            None => 0,
+        };
+        Ok(wei as f64 / 1e18)
+    }
+}
+
+/// Balance of all addresses of coin and its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Holding {
+    coin: Coin,
+    balance: f64,
+    /// `None` while price is unknown
+    value: Option<f64>,
+}
+
+/// Lines of wallets page, total value of coins with known price first and
+/// line per coin, ex. `BTC 0.5213 22401$`
+fn to_lines(label: &str, holdings: &[Holding], sign: &str) -> Vec<Line> {
+    let total: f64 = holdings.iter().filter_map(|holding| holding.value).sum();
+    let mut lines = vec![Line::new(format!("{} {:.0}{}", label, total, sign))
+        .with_field("label", label)
+        .with_field("value", total)
+        .with_field("currency", sign)
+        .with_metric(label, total)];
+    for holding in holdings {
+        let symbol = crypto::coin_symbol(holding.coin.id());
+        let text = format!("{} {:.4}", symbol, holding.balance);
+        let mut line = Line::new("")
+            .with_field("symbol", symbol)
+            .with_field("balance", holding.balance);
+        line.text = match holding.value {
+            Some(value) => {
+                line = line.with_field("value", value);
+                format!("{} {:.0}{}", text, value, sign)
+            }
+            None => text,
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+/// Value of configured crypto addresses
+pub struct WalletsProvider {
+    config: WalletsConfig,
+    client: Client,
+}
+
+impl WalletsProvider {
+    pub fn new(config: WalletsConfig) -> Self {
+        WalletsProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+
+    async fn balance(&self, coin: Coin, address: &str) -> Result<f64, BoxError> {
+        let request = match coin {
+            Coin::Btc => self.client.get(format!("{}/{}", BTC_EXPLORER, address)),
+            Coin::Eth => self.client.get(format!("{}/{}", ETH_EXPLORER, address)),
+        };
+        let response = request.send().await?.error_for_status()?;
+        match coin {
+            Coin::Btc => Ok(response.json::<BtcAddress>().await?.balance()),
+            Coin::Eth => response.json::<EthAddress>().await?.balance(),
+        }
+    }
+
+    /// all addresses of coin added up, `None` without any configured
+    async fn holding(&self, coin: Coin) -> Result<Option<f64>, BoxError> {
+        let addresses = match coin {
+            Coin::Btc => &self.config.btc,
+            Coin::Eth => &self.config.eth,
+        };
+        if addresses.is_empty() {
+            return Ok(None);
+        }
+        let balances = try_join_all(addresses.iter().map(|a| self.balance(coin, a))).await?;
+        Ok(Some(balances.into_iter().sum()))
+    }
+}
+
+#[async_trait]
+impl DataProvider for WalletsProvider {
+    fn name(&self) -> &str {
+        "wallets"
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(900))
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!(
+            "Fetching balances of {} wallet addresses",
+            self.config.btc.len() + self.config.eth.len()
+        );
+
+        let mut balances = Vec::new();
+        for coin in [Coin::Btc, Coin::Eth] {
+            if let Some(balance) = self.holding(coin).await? {
+                balances.push((coin, balance));
+            }
+        }
+        let coins = balances
+            .iter()
+            .map(|(coin, _)| coin.id().to_string())
+            .collect::<Vec<_>>();
+        let vs_currency = self.config.vs_currency.to_lowercase();
+        // balances are still worth showing when prices can't be fetched
+        let prices = crypto::fetch_prices(&self.client, &coins, &vs_currency)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("Unable to fetch prices of wallet coins: {}", e);
+                Default::default()
+            });
+        let holdings = balances
+            .into_iter()
+            .map(|(coin, balance)| Holding {
+                coin,
+                balance,
+                value: prices
+                    .get(coin.id())
+                    .and_then(|price| price.get(&vs_currency))
+                    .map(|price| price * balance),
+            })
+            .collect::<Vec<_>>();
+        Ok(to_lines(
+            &self.config.label,
+            &holdings,
+            &currency_sign(&vs_currency),
+        ))
+    }
+}
+
+#[test]
+fn testing_wallet_lines() {
+    let config = WalletsConfig {
+        btc: vec!["bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".into()],
+        eth: vec!["0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAe".into()],
+        ..WalletsConfig::default()
+    };
+    config.validate().unwrap();
+    let invalid = WalletsConfig {
+        eth: vec!["0x123".into()],
+        ..WalletsConfig::default()
+    };
+    assert!(invalid.validate().is_err());
+
+    let btc: BtcAddress = serde_json::from_str(
+        r#"{"address":"bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh",
+            "chain_stats":{"funded_txo_count":3,"funded_txo_sum":80130000,"spent_txo_count":1,
+            "spent_txo_sum":28000000,"tx_count":4},
+            "mempool_stats":{"funded_txo_count":0,"funded_txo_sum":0,"spent_txo_count":0,
+            "spent_txo_sum":0,"tx_count":0}}"#,
+    )
+    .unwrap();
+    let eth: EthAddress = serde_json::from_str(
+        r#"{"hash":"0xde0B295669a9FD93d5F28D9Ec85E40f4cb697BAe","coin_balance":"1250000000000000000",
+            "exchange_rate":"2291.2","is_contract":false}"#,
+    )
+    .unwrap();
+    let holdings = [
+        Holding {
+            coin: Coin::Btc,
+            balance: btc.balance(),
+            value: Some(btc.balance() * 43000.0),
+        },
+        Holding {
+            coin: Coin::Eth,
+            balance: eth.balance().unwrap(),
+            value: None,
+        },
+    ];
+    let lines: Vec<String> = to_lines("WLT", &holdings, "$")
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    assert_eq!(lines, vec!["WLT 22416$", "BTC 0.5213 22416$", "ETH 1.2500"]);
+}
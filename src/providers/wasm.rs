@@ -0,0 +1,387 @@
+//! WebAssembly plugin providers, `.wasm` modules found in `[plugins]` path
+//!
+//! Module runs in wasmtime without WASI, so it can't reach files, network
+//! or clock of host. Everything it needs comes through functions of `elora`
+//! import module, which together with its exports is the whole ABI:
+//!
+//! ```text
+//! ;; imports of module "elora", pointers are into exported memory
+//! input_len() -> i32                  ;; length of input buffer
+//! input_read(ptr: i32)                ;; copies input buffer to ptr
+//! http_get(url: i32, len: i32) -> i32 ;; status, -1 when request failed,
+//!                                     ;; body (or error) becomes input
+//! now() -> i64                        ;; unix time in milliseconds
+//! line(ptr: i32, len: i32)            ;; adds utf-8 line to fetched lines
+//! log(ptr: i32, len: i32)             ;; logs utf-8 message
+//!
+//! ;; exports
+//! memory
+//! elora_abi_version() -> i32          ;; 1
+//! elora_refresh_secs() -> i64         ;; optional, 0 for global refresh_secs
+//! elora_init() -> i32                 ;; optional, settings json is input
+//! elora_fetch() -> i32                ;; 0, or non zero with error in lines
+//! ```
+//!
+//! `elora_init` gets `[plugins.settings.<name>]` table as json and returns 0
+//! when it can run with it. `http_get` only works inside `elora_fetch`,
+//! which runs off runtime threads. Every call gets limited fuel and memory
+//! is capped, so plugin stuck in loop or allocating without end fails its
+//! fetch instead of hanging daemon.
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use wasmtime::{
+    bail, Caller, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
+};
+
+use super::{plugin::PluginsConfig, DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// version of host ABI, modules reporting other one are refused
+pub const ABI_VERSION: i32 = 1;
+
+/// linear memory module can grow to
+const MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+/// fuel of single call, roughly number of wasm instructions
+const FUEL: u64 = 2_000_000_000;
+
+/// body of `http_get` response module can get
+const MAX_RESPONSE: usize = 1024 * 1024;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// engine shared by all modules, compiled code of one works in any store
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).expect("wasmtime config with fuel is valid")
+    })
+}
+
+/// State host functions of one module work with
+struct Host {
+    name: String,
+    /// settings on init, last `http_get` body afterwards
+    input: Vec<u8>,
+    /// lines added by module in current call
+    lines: Vec<String>,
+    /// `http_get` is allowed, only while fetching
+    fetching: bool,
+    client: Client,
+    limits: StoreLimits,
+}
+
+impl Host {
+    /// GET of `url` on runtime of blocking thread fetch runs on, body is
+    /// capped at [`MAX_RESPONSE`]
+    fn get(&self, url: &str) -> Result<(u16, Vec<u8>), BoxError> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("{:?} is not http(s) url", url).into());
+        }
+        let runtime = tokio::runtime::Handle::try_current()?;
+        runtime.block_on(async {
+            let mut response = self.client.get(url).timeout(HTTP_TIMEOUT).send().await?;
+            let status = response.status().as_u16();
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len() + chunk.len() > MAX_RESPONSE {
+                    return Err("response is too large".into());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok((status, body))
+        })
+    }
+}
+
+fn memory(caller: &mut Caller<'_, Host>) -> wasmtime::Result<Memory> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => bail!("plugin exports no memory"),
+    }
+}
+
+/// utf-8 text module passed as pointer and length
+fn read(caller: &mut Caller<'_, Host>, ptr: i32, len: i32) -> wasmtime::Result<String> {
+    let len = len as u32 as usize;
+    if len > MAX_MEMORY {
+        bail!("plugin passed {} bytes long text", len);
+    }
+    let mut text = vec![0; len];
+    memory(caller)?.read(&*caller, ptr as u32 as usize, &mut text)?;
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+fn linker() -> wasmtime::Result<Linker<Host>> {
+    let mut linker = Linker::new(engine());
+    linker.func_wrap("elora", "input_len", |caller: Caller<'_, Host>| {
+        caller.data().input.len() as i32
+    })?;
+    linker.func_wrap(
+        "elora",
+        "input_read",
+        |mut caller: Caller<'_, Host>, ptr: i32| -> wasmtime::Result<()> {
+            let input = caller.data().input.clone();
+            memory(&mut caller)?.write(&mut caller, ptr as u32 as usize, &input)?;
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "elora",
+        "http_get",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> wasmtime::Result<i32> {
+            let url = read(&mut caller, ptr, len)?;
+            let host = caller.data_mut();
+            if !host.fetching {
+                bail!("http_get can only be called from elora_fetch");
+            }
+            match host.get(&url) {
+                Ok((status, body)) => {
+                    host.input = body;
+                    Ok(status as i32)
+                }
+                Err(e) => {
+                    log::debug!("Plugin {} request of {} failed: {}", host.name, url, e);
+                    host.input = e.to_string().into_bytes();
+                    Ok(-1)
+                }
+            }
+        },
+    )?;
+    linker.func_wrap("elora", "now", || chrono::Utc::now().timestamp_millis())?;
+    linker.func_wrap(
+        "elora",
+        "line",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+            let line = read(&mut caller, ptr, len)?;
+            caller.data_mut().lines.push(line);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "elora",
+        "log",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+            let message = read(&mut caller, ptr, len)?;
+            log::info!("Plugin {}: {}", caller.data().name, message);
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+/// Instantiated module with its store
+struct Plugin {
+    store: Store<Host>,
+    fetch: TypedFunc<(), i32>,
+}
+
+impl Plugin {
+    /// Instantiates module, checks its ABI version and runs its init with
+    /// `settings` json. Returns refresh interval it asked for too
+    fn new(
+        name: &str,
+        module: &Module,
+        settings: &str,
+    ) -> wasmtime::Result<(Self, Option<Duration>)> {
+        let host = Host {
+            name: name.to_string(),
+            input: Vec::new(),
+            lines: Vec::new(),
+            fetching: false,
+            client: Client::new(),
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build(),
+        };
+        let mut store = Store::new(engine(), host);
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL)?;
+        let instance: Instance = linker()?.instantiate(&mut store, module)?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "elora_abi_version")?
+            .call(&mut store, ())?;
+        if version != ABI_VERSION {
+            bail!(
+                "plugin abi version {} is not supported, expected {}",
+                version,
+                ABI_VERSION
+            );
+        }
+        let refresh = match instance.get_typed_func::<(), i64>(&mut store, "elora_refresh_secs") {
+            Ok(refresh) => Some(refresh.call(&mut store, ())?)
+                .filter(|&secs| secs > 0)
+                .map(|secs| Duration::from_secs(secs as u64)),
+            Err(_) => None,
+        };
+        let fetch = instance.get_typed_func::<(), i32>(&mut store, "elora_fetch")?;
+
+        let mut plugin = Plugin { store, fetch };
+        if let Ok(init) = instance.get_typed_func::<(), i32>(&mut plugin.store, "elora_init") {
+            plugin.store.data_mut().input = settings.as_bytes().to_vec();
+            let code = plugin.call(init)?;
+            if code != 0 {
+                bail!(
+                    "plugin failed to start with given settings: {}",
+                    plugin.error(code)
+                );
+            }
+        }
+        Ok((plugin, refresh))
+    }
+
+    /// calls `function` with fresh fuel and no lines
+    fn call(&mut self, function: TypedFunc<(), i32>) -> wasmtime::Result<i32> {
+        self.store.set_fuel(FUEL)?;
+        self.store.data_mut().lines.clear();
+        function.call(&mut self.store, ())
+    }
+
+    /// error message module added as lines
+    fn error(&self, code: i32) -> String {
+        match self.store.data().lines.join(" ") {
+            message if message.is_empty() => format!("code {}", code),
+            message => message,
+        }
+    }
+
+    fn fetch(&mut self) -> Result<Vec<String>, BoxError> {
+        self.store.data_mut().fetching = true;
+        let code = self.call(self.fetch.clone());
+        let host = self.store.data_mut();
+        host.fetching = false;
+        // body of last request isn't kept between fetches
+        host.input = Vec::new();
+        match code? {
+            0 => Ok(std::mem::take(&mut host.lines)),
+            code => Err(format!("plugin fetch failed: {}", self.error(code)).into()),
+        }
+    }
+}
+
+pub struct WasmProvider {
+    name: String,
+    refresh: Option<Duration>,
+    plugin: Arc<Mutex<Plugin>>,
+}
+
+impl WasmProvider {
+    fn from_module(name: &str, module: &Module, settings: &str) -> Result<Self, BoxError> {
+        let (plugin, refresh) = Plugin::new(name, module, settings)?;
+        Ok(WasmProvider {
+            name: name.to_string(),
+            refresh,
+            plugin: Arc::new(Mutex::new(plugin)),
+        })
+    }
+
+    /// Compiles and starts module named `name` from `path`
+    pub fn new(name: &str, path: &Path, config: &PluginsConfig) -> Result<Self, EloraError> {
+        let provider = config
+            .settings_json(name)
+            .and_then(|settings| {
+                let module = Module::from_file(engine(), path)?;
+                WasmProvider::from_module(name, &module, &settings)
+            })
+            .map_err(|source| {
+                EloraError::ConfigInvalid(format!(
+                    "plugin {} can't load {}: {}",
+                    name,
+                    path.display(),
+                    source
+                ))
+            })?;
+        log::info!("Loaded wasm plugin {} from {}", name, path.display());
+        Ok(provider)
+    }
+}
+
+#[async_trait]
+impl DataProvider for WasmProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        // module blocks while running and on its requests, so it doesn't run
+        // on runtime threads
+        let plugin = self.plugin.clone();
+        let lines = tokio::task::spawn_blocking(move || plugin.lock().unwrap().fetch()).await??;
+        Ok(lines
+            .iter()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .map(Line::new)
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn testing_wasm_plugin() {
+    let module = |wat: &str| Module::new(engine(), wat::parse_str(wat).unwrap()).unwrap();
+    let todo = module(
+        r#"(module
+            (import "elora" "input_len" (func $input_len (result i32)))
+            (import "elora" "input_read" (func $input_read (param i32)))
+            (import "elora" "http_get" (func $http_get (param i32 i32) (result i32)))
+            (import "elora" "line" (func $line (param i32 i32)))
+            (memory (export "memory") 1)
+            (global $settings (mut i32) (i32.const 0))
+            (data (i32.const 0) "TODO 3")
+            (data (i32.const 16) "file:///etc/passwd")
+            (func (export "elora_abi_version") (result i32) (i32.const 1))
+            (func (export "elora_refresh_secs") (result i64) (i64.const 30))
+            (func (export "elora_init") (result i32)
+                (global.set $settings (call $input_len))
+                (call $input_read (i32.const 64))
+                (i32.const 0))
+            (func (export "elora_fetch") (result i32)
+                (call $line (i32.const 64) (global.get $settings))
+                (call $line (i32.const 0) (i32.const 6))
+                (drop (call $http_get (i32.const 16) (i32.const 18)))
+                (call $input_read (i32.const 64))
+                (call $line (i32.const 64) (i32.const 3))
+                (i32.const 0)))"#,
+    );
+    let provider = WasmProvider::from_module("todo", &todo, "{\"list\":\"home\"}").unwrap();
+    assert_eq!(provider.refresh_interval(), Some(Duration::from_secs(30)));
+    let lines: Vec<String> = provider
+        .fetch()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|l| l.text)
+        .collect();
+    // file url is refused, plugin gets error of it as input
+    assert_eq!(lines, vec!["{\"list\":\"home\"}", "TODO 3", "\"fi"]);
+
+    let spinning = module(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "elora_abi_version") (result i32) (i32.const 1))
+            (func (export "elora_fetch") (result i32) (loop (br 0)) (i32.const 0)))"#,
+    );
+    let provider = WasmProvider::from_module("spin", &spinning, "{}").unwrap();
+    assert_eq!(provider.refresh_interval(), None);
+    assert!(provider.fetch().await.is_err());
+
+    let old = module(
+        r#"(module
+            (func (export "elora_abi_version") (result i32) (i32.const 2))
+            (func (export "elora_fetch") (result i32) (i32.const 0)))"#,
+    );
+    assert!(WasmProvider::from_module("old", &old, "{}").is_err());
+}
@@ -0,0 +1,177 @@
+//! Current weather from OpenWeatherMap
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{DataProvider, Line};
+use crate::{BoxError, EloraError};
+
+/// env variable used when `api_key` is not in config
+pub const API_KEY_ENV: &str = "OPENWEATHERMAP_API_KEY";
+
+/// `[weather]` config section. Location is either `city` or `lat` + `lon`
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherConfig {
+    pub api_key: Option<String>,
+    /// city name as OpenWeatherMap knows it, ex. `Amsterdam,NL`
+    pub city: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// shown in front of temperature, defaults to first 3 letters of city
+    pub label: Option<String>,
+    /// metric, imperial or standard
+    pub units: Option<String>,
+}
+
+impl WeatherConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        match (&self.city, self.lat, self.lon) {
+            (Some(_), None, None) | (None, Some(_), Some(_)) => {}
+            _ => {
+                return Err(EloraError::ConfigInvalid(
+                    "weather needs either city or lat and lon".into(),
+                ))
+            }
+        }
+        if let Some(units) = &self.units {
+            if !["metric", "imperial", "standard"].contains(&units.as_str()) {
+                return Err(EloraError::ConfigInvalid(format!(
+                    "weather.units {:?} is not metric, imperial or standard",
+                    units
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn units(&self) -> &str {
+        self.units.as_deref().unwrap_or("metric")
+    }
+
+    fn api_key(&self) -> Result<String, BoxError> {
+        match &self.api_key {
+            Some(key) => Ok(key.clone()),
+            None => std::env::var(API_KEY_ENV)
+                .map_err(|_| format!("weather.api_key or {} env is required", API_KEY_ENV).into()),
+        }
+    }
+}
+
+/// Response of `api.openweathermap.org/data/2.5/weather`, only fields we use
+#[derive(Debug, Deserialize)]
+struct WeatherResponse {
+    name: String,
+    weather: Vec<Condition>,
+    main: Main,
+}
+
+#[derive(Debug, Deserialize)]
+struct Condition {
+    main: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Main {
+    temp: f64,
+}
+
+/// Formats response into line, ex. `AMS 14°C rain`
+fn to_line(config: &WeatherConfig, response: &WeatherResponse) -> Line {
+    let label = match &config.label {
+        Some(label) => label.clone(),
+        None => response
+            .name
+            .chars()
+            .take(3)
+            .collect::<String>()
+            .to_uppercase(),
+    };
+    let unit = match config.units() {
+        "imperial" => "°F",
+        "standard" => "K",
+        _ => "°C",
+    };
+    let condition = response
+        .weather
+        .first()
+        .map(|c| c.main.to_lowercase())
+        .unwrap_or_default();
+    Line::new(format!("{} {:.0}{} {}", label, response.main.temp, unit, condition).trim_end())
+        .with_field("label", label)
+        .with_field("temp", response.main.temp)
+        .with_field("unit", unit)
+        .with_field("condition", condition)
+}
+
+/// OpenWeatherMap current weather at configured location
+pub struct WeatherProvider {
+    config: WeatherConfig,
+    client: Client,
+}
+
+impl WeatherProvider {
+    pub fn new(config: WeatherConfig) -> Self {
+        WeatherProvider {
+            config,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataProvider for WeatherProvider {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, BoxError> {
+        log::info!("Fetching weather from remote");
+
+        let mut query = vec![
+            ("appid", self.config.api_key()?),
+            ("units", self.config.units().to_string()),
+        ];
+        match (&self.config.city, self.config.lat, self.config.lon) {
+            (Some(city), _, _) => query.push(("q", city.clone())),
+            (None, Some(lat), Some(lon)) => {
+                query.push(("lat", lat.to_string()));
+                query.push(("lon", lon.to_string()));
+            }
+            _ => return Err("weather location is not configured".into()),
+        }
+
+        let response: WeatherResponse = self
+            .client
+            .get("https://api.openweathermap.org/data/2.5/weather")
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(vec![to_line(&self.config, &response)])
+    }
+}
+
+#[test]
+fn testing_weather_line() {
+    let response: WeatherResponse = serde_json::from_str(
+        r#"{"weather":[{"id":500,"main":"Rain","description":"light rain"}],"main":{"temp":14.3,"humidity":81},"name":"Amsterdam"}"#,
+    )
+    .unwrap();
+    let mut config = WeatherConfig {
+        city: Some("Amsterdam".into()),
+        ..WeatherConfig::default()
+    };
+    assert_eq!(to_line(&config, &response).text, "AMS 14°C rain");
+
+    config.label = Some("HOME".into());
+    config.units = Some("imperial".into());
+    assert_eq!(to_line(&config, &response).text, "HOME 14°F rain");
+
+    config.lat = Some(52.37);
+    assert!(config.validate().is_err());
+}
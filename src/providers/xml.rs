@@ -0,0 +1,95 @@
+//! Xml reading for providers whose apis answer with xml
+//!
+//! Documents are parsed with `roxmltree`, which decodes entities and CDATA
+//! and resolves namespaces. Elements are looked up by local name in
+//! namespace of element they're searched from, so default namespace of
+//! document doesn't have to be given and `<media:title>` isn't taken for
+//! `<title>` of rss item.
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::BoxError;
+
+/// Parsed `xml`, DTD is allowed as old rss feeds declare html entities in it
+pub fn parse(xml: &str) -> Result<Document<'_>, BoxError> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    Document::parse_with_options(xml, options).map_err(|e| format!("malformed xml: {}", e).into())
+}
+
+/// whether `node` is `<name>` element in namespace of `scope`
+fn is_named(node: &Node, scope: &Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace() == scope.tag_name().namespace()
+}
+
+/// every `<name>` element below `node` at any depth, in document order
+pub fn descendants<'a, 'i: 'a>(
+    node: Node<'a, 'i>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'i>> + 'a {
+    node.descendants()
+        .skip(1)
+        .filter(move |child| is_named(child, &node, name))
+}
+
+/// `<name>` children of `node`
+pub fn children<'a, 'i: 'a>(
+    node: Node<'a, 'i>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'i>> + 'a {
+    node.children()
+        .filter(move |child| is_named(child, &node, name))
+}
+
+/// first `<name>` child of `node`
+pub fn child<'a, 'i: 'a>(node: Node<'a, 'i>, name: &'a str) -> Option<Node<'a, 'i>> {
+    children(node, name).next()
+}
+
+/// Text inside `node`, with text of nested elements, trimmed. Self closing
+/// element has empty text
+pub fn text(node: Node) -> String {
+    node.descendants()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// text of first `<name>` child of `node`
+pub fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name).map(text)
+}
+
+#[test]
+fn testing_xml_elements() {
+    let xml = r#"<!DOCTYPE rss [<!ENTITY nbsp "&#160;">]>
+        <rss xmlns:media="http://search.yahoo.com/mrss/"><channel><items/>
+        <item id="1"><title>Rust &amp; <![CDATA[x]]></title></item>
+        <item><media:title>Media</media:title><title type="html"><![CDATA[Tom & Jerry]]></title>
+            <link/><nested><item><title>caf&#233;&nbsp;&#x2192; &lt;b&gt;</title></item></nested></item>
+        </channel></rss>"#;
+    let doc = parse(xml).unwrap();
+    let items: Vec<Node> = descendants(doc.root_element(), "item").collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(child_text(items[0], "title").unwrap(), "Rust & x");
+    assert_eq!(child_text(items[1], "link").unwrap(), "");
+    assert_eq!(child_text(items[1], "title").unwrap(), "Tom & Jerry");
+    assert_eq!(children(items[1], "title").count(), 1);
+    assert_eq!(
+        child_text(items[2], "title").unwrap(),
+        "caf\u{e9}\u{a0}\u{2192} <b>"
+    );
+    assert!(parse("<rss><item></rss>").is_err());
+
+    let atom =
+        r#"<feed xmlns="http://www.w3.org/2005/Atom"><entry><title>Atom</title></entry></feed>"#;
+    let doc = parse(atom).unwrap();
+    let entry = descendants(doc.root_element(), "entry").next().unwrap();
+    assert_eq!(child_text(entry, "title").unwrap(), "Atom");
+}
@@ -0,0 +1,74 @@
+//! Spacing requests of apis with strict per minute limits
+//!
+//! Requests wait in line for free slot instead of being sent and rejected, so
+//! provider can fetch any number of tickers and stays inside api's limit.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Mutex;
+
+/// Sliding window limiter allowing `max` requests per `window`
+pub struct RateLimiter {
+    max: usize,
+    window: Duration,
+    /// start times of requests inside current window, oldest first. Tokio
+    /// mutex is fair, so waiting requests are let through in order
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(max: usize, window: Duration) -> Self {
+        RateLimiter {
+            max: max.max(1),
+            window,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// `max` requests per minute
+    pub fn per_minute(max: usize) -> Self {
+        RateLimiter::new(max, Duration::from_secs(60))
+    }
+
+    /// Waits until request can be sent without going over limit
+    pub async fn acquire(&self) {
+        let mut sent = self.sent.lock().await;
+        loop {
+            let now = Instant::now();
+            while sent
+                .front()
+                .is_some_and(|&start| now.duration_since(start) >= self.window)
+            {
+                sent.pop_front();
+            }
+            if sent.len() < self.max {
+                sent.push_back(now);
+                return;
+            }
+            let wait = self.window - now.duration_since(sent[0]);
+            log::debug!("Rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// shortest period in which `requests` can be repeated without waiting
+    pub fn period_of(&self, requests: usize) -> Duration {
+        self.window * requests.div_ceil(self.max) as u32
+    }
+}
+
+#[tokio::test]
+async fn testing_rate_limiter() {
+    let limiter = RateLimiter::new(2, Duration::from_millis(200));
+    let start = Instant::now();
+    for _ in 0..2 {
+        limiter.acquire().await;
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+    limiter.acquire().await;
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(limiter.period_of(5), Duration::from_millis(600));
+}
@@ -0,0 +1,93 @@
+//! Watching config file for changes
+//!
+//! Directory of config is watched instead of file itself, editors often save
+//! by writing new file and renaming it over old one, which would end watch
+//! of original inode.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::EloraError;
+
+/// events arriving within this window after first one are one change, saving
+/// file usually fires several of them
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub struct ConfigWatcher {
+    // events stop once watcher is dropped
+    _watcher: RecommendedWatcher,
+    events: mpsc::UnboundedReceiver<()>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Result<Self, EloraError> {
+        let file: PathBuf = path.file_name().map(PathBuf::from).ok_or_else(|| {
+            EloraError::ConfigInvalid(format!("{} is not a file", path.display()))
+        })?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (tx, events) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) => {
+                    let ours = event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == Some(file.as_os_str()));
+                    if ours && !matches!(event.kind, EventKind::Access(_)) {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => log::warn!("Config watch error: {}", e),
+            })
+            .map_err(watch_error)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        log::debug!("Watching {} for changes", path.display());
+
+        Ok(ConfigWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Waits for next change of file, `false` when watcher stopped
+    pub async fn changed(&mut self) -> bool {
+        if self.events.recv().await.is_none() {
+            return false;
+        }
+        tokio::time::sleep(DEBOUNCE).await;
+        while self.events.try_recv().is_ok() {}
+        true
+    }
+}
+
+fn watch_error(error: notify::Error) -> EloraError {
+    EloraError::Io(io::Error::other(error))
+}
+
+#[tokio::test]
+async fn testing_config_watcher() {
+    let dir = std::env::temp_dir().join(format!("elora_hid_reload_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "refresh_secs = 60").unwrap();
+
+    let mut watcher = ConfigWatcher::new(&path).unwrap();
+    std::fs::write(dir.join("other.toml"), "").unwrap();
+    std::fs::write(&path, "refresh_secs = 30").unwrap();
+    let changed = tokio::time::timeout(Duration::from_secs(5), watcher.changed()).await;
+    assert_eq!(changed, Ok(true));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,89 @@
+//! Sending only rows of page which changed since it was last sent
+//!
+//! Most refreshes change single price, so resending whole page is mostly
+//! wasted hid traffic and makes display flicker while firmware redraws it.
+//! Every `full_every` updates whole page is sent anyway, so keyboard which
+//! missed message (ex. rebooted without being unplugged) catches up.
+
+use crate::protocol::Message;
+
+/// default of `--full-refresh-every`
+pub const FULL_REFRESH_EVERY: u32 = 10;
+
+/// Rows last sent to keyboard
+#[derive(Debug)]
+pub struct Delta {
+    full_every: u32,
+    since_full: u32,
+    last: Option<(u8, Vec<Vec<u8>>)>,
+}
+
+impl Delta {
+    pub fn new(full_every: u32) -> Self {
+        Delta {
+            full_every,
+            since_full: 0,
+            last: None,
+        }
+    }
+
+    /// [`Message::rows`] bringing keyboard from last sent rows to `rows`,
+    /// `None` when nothing changed. Rows of whole page are sent when page
+    /// differs from last one and every `full_every` updates
+    pub fn next(&mut self, page: u8, page_count: u8, rows: &[Vec<u8>]) -> Option<Message> {
+        let rows = &rows[..rows.len().min(u8::MAX as usize)];
+        self.since_full += 1;
+        let last = match &self.last {
+            Some((last_page, last)) if *last_page == page && self.since_full < self.full_every => {
+                Some(last)
+            }
+            _ => None,
+        };
+        let changed: Vec<(u8, &[u8])> = rows
+            .iter()
+            .enumerate()
+            .filter(|&(i, row)| last.is_none_or(|last| last.get(i) != Some(row)))
+            .map(|(i, row)| (i as u8, row.as_slice()))
+            .collect();
+        match last {
+            Some(last) if changed.is_empty() && last.len() == rows.len() => return None,
+            Some(_) => {}
+            None => self.since_full = 0,
+        }
+        let message = Message::rows(page, page_count, rows.len() as u8, &changed);
+        self.last = Some((page, rows.to_vec()));
+        Some(message)
+    }
+
+    /// forgets sent rows, next update sends whole page. Used when keyboard
+    /// was reconnected or sending failed
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[test]
+fn testing_delta_rows() {
+    let rows =
+        |texts: &[&str]| -> Vec<Vec<u8>> { texts.iter().map(|t| t.as_bytes().to_vec()).collect() };
+    let mut delta = Delta::new(3);
+
+    let full = delta.next(0, 2, &rows(&["TSLA 241", "BTC 43000"])).unwrap();
+    assert_eq!(
+        full.payload,
+        b"\x00\x02\x02\x00\x08TSLA 241\x01\x09BTC 43000"
+    );
+    let changed = delta.next(0, 2, &rows(&["TSLA 242", "BTC 43000"])).unwrap();
+    assert_eq!(changed.payload, b"\x00\x02\x02\x00\x08TSLA 242");
+    assert_eq!(delta.next(0, 2, &rows(&["TSLA 242", "BTC 43000"])), None);
+    // every third update is full refresh even though nothing changed
+    let refresh = delta.next(0, 2, &rows(&["TSLA 242", "BTC 43000"])).unwrap();
+    assert_eq!(refresh.payload.len(), full.payload.len());
+
+    let shorter = delta.next(0, 2, &rows(&["TSLA 242"])).unwrap();
+    assert_eq!(shorter.payload, b"\x00\x02\x01");
+    assert_eq!(
+        delta.next(1, 2, &rows(&["TSLA 242"])).unwrap().payload[3..5],
+        [0, 8]
+    );
+}
@@ -0,0 +1,124 @@
+//! Mapping of unicode text onto bytes of keyboard font
+//!
+//! QMK glcdfont is cp437-like in its lower half only, upper half is logos
+//! and icons. Characters without glyph are transliterated to ascii, so `€`
+//! becomes `EUR` instead of random glyph. Boards with custom font can map
+//! more characters with `[encoding]` config.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::protocol::{HIGHLIGHT_MARKER, STALE_MARKER};
+
+/// glyphs present in stock QMK glcdfont
+const GLYPHS: [(char, u8); 4] = [
+    ('▲', 0x1E),
+    ('▼', 0x1F),
+    ('‼', STALE_MARKER),
+    ('►', HIGHLIGHT_MARKER),
+];
+
+/// ascii replacement of common characters which font doesn't have
+fn transliterate(ch: char) -> Option<&'static str> {
+    Some(match ch {
+        // 14°C reads fine as 14C
+        '°' => "",
+        '€' => "EUR",
+        '£' => "GBP",
+        '¥' => "JPY",
+        '₿' => "BTC",
+        '¢' => "c",
+        'µ' => "u",
+        '×' => "x",
+        '–' | '—' | '−' => "-",
+        '‘' | '’' | '′' => "'",
+        '“' | '”' | '″' => "\"",
+        '…' => "...",
+        '✔' | '✓' => "OK",
+        '✘' | '✗' => "X",
+        // moon phases of astro provider
+        '🌑' => "new",
+        '🌒' | '🌔' => "wax",
+        '🌓' => "1q",
+        '🌕' => "full",
+        '🌖' | '🌘' => "wane",
+        '🌗' => "3q",
+        '\u{a0}' => " ",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' => "A",
+        'ç' => "c",
+        'Ç' => "C",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' => "E",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'ñ' => "n",
+        'Ñ' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' => "O",
+        'ù' | 'ú' | 'û' | 'ü' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' => "U",
+        'ý' | 'ÿ' => "y",
+        'ß' => "ss",
+        _ => return None,
+    })
+}
+
+/// Unicode to font byte table, stock glcdfont glyphs plus configured ones
+#[derive(Debug, Clone)]
+pub struct Encoding {
+    table: HashMap<char, u8>,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding {
+            table: GLYPHS.into_iter().collect(),
+        }
+    }
+}
+
+impl Encoding {
+    /// stock glyphs with `custom` mappings on top, ex. `'°' => 0xF8` for
+    /// font which has degree sign
+    pub fn new(custom: &BTreeMap<char, u8>) -> Self {
+        let mut encoding = Encoding::default();
+        encoding.table.extend(custom);
+        encoding
+    }
+
+    /// Appends encoded text to `buf`. Mapped characters win over ascii, then
+    /// ascii is copied as is, then transliteration, and `?` if nothing fits
+    pub fn encode(&self, text: &str, buf: &mut Vec<u8>) {
+        for ch in text.chars() {
+            if let Some(&byte) = self.table.get(&ch) {
+                buf.push(byte);
+            } else if ch.is_ascii() {
+                buf.push(ch as u8);
+            } else if let Some(ascii) = transliterate(ch) {
+                buf.extend_from_slice(ascii.as_bytes());
+            } else {
+                buf.push(b'?');
+            }
+        }
+    }
+}
+
+#[test]
+fn testing_encoding() {
+    let encode = |encoding: &Encoding, text: &str| {
+        let mut buf = Vec::new();
+        encoding.encode(text, &mut buf);
+        buf
+    };
+
+    let stock = Encoding::default();
+    assert_eq!(encode(&stock, "AMS 14°C"), b"AMS 14C");
+    assert_eq!(encode(&stock, "€12 £3 ¥4"), b"EUR12 GBP3 JPY4");
+    assert_eq!(encode(&stock, "TSLA ▲1%"), b"TSLA \x1e1%");
+    assert_eq!(encode(&stock, "Zürich ☂"), b"Zurich ?");
+    assert_eq!(encode(&stock, "elora_hid ✔ main"), b"elora_hid OK main");
+    assert_eq!(encode(&stock, "▼18:45 🌕"), b"\x1f18:45 full");
+
+    let custom = Encoding::new(&BTreeMap::from([('°', 0xF8), ('€', 0xEE)]));
+    assert_eq!(encode(&custom, "14°C €5"), b"14\xf8C \xee5");
+}
@@ -0,0 +1,76 @@
+//! Rendering of fetched data into payload which keyboard draws
+
+use crate::{
+    protocol::{HIGHLIGHT_MARKER, STALE_MARKER},
+    providers::Line,
+};
+
+pub mod delta;
+pub mod encoding;
+pub mod scroll;
+pub mod template;
+
+pub use encoding::Encoding;
+pub use scroll::ScrollConfig;
+pub use template::Template;
+
+/// Lays out lines which have fields with `template`, lines without fields
+/// keep their text
+pub fn apply_template(lines: &mut [Line], template: &Template) {
+    for line in lines.iter_mut().filter(|line| !line.fields.is_empty()) {
+        line.text = template.render(|name| line.field(name));
+    }
+}
+
+/// Converts lines into string which is sent through usb to keyboard, with
+/// stock font encoding
+pub fn convert_to_buffer(lines: &[Line]) -> Vec<u8> {
+    convert_with(lines, &Encoding::default())
+}
+
+/// Converts lines into payload using `encoding`. Stale lines are prefixed
+/// with [`STALE_MARKER`] and highlighted ones with [`HIGHLIGHT_MARKER`]
+pub fn convert_with(lines: &[Line], encoding: &Encoding) -> Vec<u8> {
+    convert_rows(lines, encoding).concat()
+}
+
+/// Like [`convert_with`], keeping every line in its own row
+pub fn convert_rows(lines: &[Line], encoding: &Encoding) -> Vec<Vec<u8>> {
+    lines
+        .iter()
+        .map(|line| {
+            let mut row = Vec::new();
+            if line.stale {
+                row.push(STALE_MARKER);
+            }
+            if line.highlight {
+                row.push(HIGHLIGHT_MARKER);
+            }
+            encoding.encode(&line.text, &mut row);
+            row
+        })
+        .collect()
+}
+
+#[test]
+fn testing_conversion_to_buffer() {
+    use crate::providers::stocks::{self, StockTickerType};
+    use std::collections::BTreeMap;
+
+    let stocks: StockTickerType =
+        BTreeMap::from([("TSLA".into(), 500.0), ("VWRL.AS".into(), 200.1)]);
+    let buf = convert_to_buffer(&stocks::to_lines(stocks));
+    assert_eq!(String::from_utf8(buf).unwrap(), "TSLA: 500$VWRL: 200$");
+}
+
+#[test]
+fn testing_stale_marker() {
+    let mut stale = Line::new("BTC: 43013$");
+    stale.stale = true;
+    let buf = convert_to_buffer(&[Line::new("TSLA: 500$"), stale]);
+    assert_eq!(buf[10], STALE_MARKER);
+    assert_eq!(&buf[11..], b"BTC: 43013$");
+
+    let buf = convert_to_buffer(&[Line::new("/ 2.1G 4%").with_highlight(true)]);
+    assert_eq!(buf[0], HIGHLIGHT_MARKER);
+}
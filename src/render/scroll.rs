@@ -0,0 +1,104 @@
+//! Scrolling of lines which don't fit on display
+//!
+//! Display cuts off everything past its width, so long lines are shown as
+//! window which moves by one character every `rate_ms`, wrapping around
+//! with small gap. Every line scrolls on its own, short ones stay put.
+
+use serde::Deserialize;
+
+use super::Encoding;
+use crate::{providers::Line, EloraError};
+
+/// spaces between end and start of scrolled text
+pub const GAP: usize = 3;
+
+/// `[scroll]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrollConfig {
+    /// characters fitting on one display row, 21 on 128px wide OLED
+    pub width: usize,
+    /// how often lines move by one character in milliseconds
+    pub rate_ms: u64,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        ScrollConfig {
+            width: 21,
+            rate_ms: 400,
+        }
+    }
+}
+
+impl ScrollConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.width == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "scroll.width must be greater than 0".into(),
+            ));
+        }
+        if self.rate_ms < 50 {
+            return Err(EloraError::ConfigInvalid(
+                "scroll.rate_ms must be at least 50".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `width` items starting at `offset`, wrapping around with [`GAP`] of
+/// `blank` between end and start. Items which fit are returned as they are
+pub fn window<T: Clone>(items: &[T], blank: T, width: usize, offset: usize) -> Vec<T> {
+    if items.len() <= width {
+        return items.to_vec();
+    }
+    let cycle = items.len() + GAP;
+    (0..width)
+        .map(|i| match (offset + i) % cycle {
+            at if at < items.len() => items[at].clone(),
+            _ => blank.clone(),
+        })
+        .collect()
+}
+
+/// [`window`] over characters of text
+pub fn marquee(text: &str, width: usize, offset: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    window(&chars, ' ', width, offset).into_iter().collect()
+}
+
+/// Encodes lines like [`convert_with`](super::convert_with), cutting lines
+/// longer than `width` bytes to their window at scroll `step`. Width is
+/// counted after encoding, so transliterated `€` takes 3 places like on display
+pub fn convert_scrolled(lines: &[Line], encoding: &Encoding, width: usize, step: usize) -> Vec<u8> {
+    scrolled_rows(lines, encoding, width, step).concat()
+}
+
+/// Like [`convert_scrolled`], keeping every line in its own row
+pub fn scrolled_rows(
+    lines: &[Line],
+    encoding: &Encoding,
+    width: usize,
+    step: usize,
+) -> Vec<Vec<u8>> {
+    super::convert_rows(lines, encoding)
+        .into_iter()
+        .map(|row| window(&row, b' ', width, step))
+        .collect()
+}
+
+#[test]
+fn testing_marquee() {
+    assert_eq!(marquee("short", 10, 3), "short");
+    assert_eq!(marquee("Daft Punk - One More Time", 10, 0), "Daft Punk ");
+    assert_eq!(marquee("Daft Punk - One More Time", 10, 20), " Time   Da");
+    assert_eq!(marquee("Daft Punk - One More Time", 10, 28), "Daft Punk ");
+
+    let lines = [
+        Line::new("TSLA 241"),
+        Line::new("elora_hid: Fix scroll of € lines"),
+    ];
+    let buf = convert_scrolled(&lines, &Encoding::default(), 10, 2);
+    assert_eq!(buf, b"TSLA 241ora_hid: F");
+}
@@ -0,0 +1,214 @@
+//! Small template syntax for user defined line layout
+//!
+//! `{name}` is replaced by field of line, with optional format spec after
+//! colon same as in rust `format!`: alignment (`<`, `>`, `^`), width and
+//! precision, ex. `{symbol:<5}{price:>6.1}{currency}`. Precision rounds
+//! numbers and truncates text. `{{` and `}}` are literal braces.
+
+/// Value of line field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Number(number)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct Spec {
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field { name: String, spec: Spec },
+}
+
+/// Parsed template, see module docs for syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let mut rest = spec;
+    let align = match rest.chars().next() {
+        Some('<') => Some(Align::Left),
+        Some('>') => Some(Align::Right),
+        Some('^') => Some(Align::Center),
+        _ => None,
+    };
+    if align.is_some() {
+        rest = &rest[1..];
+    }
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (rest, None),
+    };
+    let invalid = || format!("invalid format spec {:?}", spec);
+    Ok(Spec {
+        align,
+        width: if width.is_empty() {
+            0
+        } else {
+            width.parse().map_err(|_| invalid())?
+        },
+        precision: precision
+            .map(|p| p.parse().map_err(|_| invalid()))
+            .transpose()?,
+    })
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("unclosed field {{{}", field));
+                    }
+                    let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+                    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                        return Err(format!("invalid field {{{}}}", field));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field {
+                        name: name.to_string(),
+                        spec: parse_spec(spec)?,
+                    });
+                }
+                '}' => return Err("unmatched `}`, use `}}` for literal brace".into()),
+                ch => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Names of fields template uses
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field { name, .. } => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Renders template with `lookup` giving value of field. Unknown fields
+    /// render as empty text
+    pub fn render<'a>(&self, lookup: impl Fn(&str) -> Option<&'a Value>) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Field { name, spec } => {
+                    let text = match (lookup(name), spec.precision) {
+                        (Some(Value::Number(n)), Some(p)) => format!("{:.*}", p, n),
+                        (Some(Value::Number(n)), None) => n.to_string(),
+                        (Some(Value::Text(t)), Some(p)) => t.chars().take(p).collect(),
+                        (Some(Value::Text(t)), None) => t.clone(),
+                        (None, _) => String::new(),
+                    };
+                    pad(
+                        &mut out,
+                        &text,
+                        spec,
+                        matches!(lookup(name), Some(Value::Number(_))),
+                    );
+                }
+            }
+        }
+        out
+    }
+}
+
+/// pads like `format!`: text to left and numbers to right unless aligned
+fn pad(out: &mut String, text: &str, spec: &Spec, is_number: bool) {
+    let fill = spec.width.saturating_sub(text.chars().count());
+    let align = spec
+        .align
+        .unwrap_or(if is_number { Align::Right } else { Align::Left });
+    let (before, after) = match align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    };
+    out.extend(std::iter::repeat_n(' ', before));
+    out.push_str(text);
+    out.extend(std::iter::repeat_n(' ', after));
+}
+
+#[test]
+fn testing_template_rendering() {
+    let fields = [
+        ("symbol", Value::from("VWRL.AS")),
+        ("price", Value::from(107.26)),
+        ("currency", Value::from("EUR")),
+    ];
+    let lookup = |name: &str| fields.iter().find(|(n, _)| *n == name).map(|(_, v)| v);
+
+    let template = Template::parse("{symbol:<5.4}{price:>6.1}{currency}").unwrap();
+    assert_eq!(template.render(lookup), "VWRL  107.3EUR");
+
+    let template = Template::parse("{{{symbol:^9}}} {missing}{price:.0}").unwrap();
+    assert_eq!(template.render(lookup), "{ VWRL.AS } 107");
+    assert_eq!(
+        template.fields().collect::<Vec<_>>(),
+        ["symbol", "missing", "price"]
+    );
+
+    assert!(Template::parse("{symbol").is_err());
+    assert!(Template::parse("{}").is_err());
+    assert!(Template::parse("{price:x}").is_err());
+    assert!(Template::parse("price}").is_err());
+}
@@ -0,0 +1,135 @@
+//! Retrying failed provider fetches with exponential backoff
+//!
+//! Transient failures (rate limits, dns hiccups) are retried a few times
+//! before update is given up on, so one bad request doesn't drop the cycle.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    providers::{self, DataProvider, Line},
+    EloraError,
+};
+
+/// `[retry]` config section
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// fetch attempts per update including first one, 1 disables retrying
+    pub attempts: u32,
+    /// delay before first retry in milliseconds, doubled on every next one
+    pub base_ms: u64,
+    /// upper bound of delay in milliseconds
+    pub max_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            attempts: 3,
+            base_ms: 500,
+            max_ms: 30_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn validate(&self) -> Result<(), EloraError> {
+        if self.attempts == 0 {
+            return Err(EloraError::ConfigInvalid(
+                "retry.attempts must be greater than 0".into(),
+            ));
+        }
+        if self.base_ms > self.max_ms {
+            return Err(EloraError::ConfigInvalid(
+                "retry.base_ms can't be greater than retry.max_ms".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Delay before retry number `retry` (from 0). `jitter` in `0.0..1.0`
+    /// spreads delay over upper half of backoff, so providers failing at
+    /// same time don't retry in lockstep
+    pub fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let backoff = self
+            .base_ms
+            .saturating_mul(1u64.checked_shl(retry).unwrap_or(u64::MAX))
+            .min(self.max_ms);
+        Duration::from_millis((backoff as f64 * (0.5 + jitter / 2.0)) as u64)
+    }
+}
+
+/// random number in `0.0..1.0`, good enough for jitter without extra deps
+pub fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Retry counters of single provider
+#[derive(Debug, Default)]
+pub struct RetryStats {
+    /// fetches which were retried after failure
+    pub retries: AtomicU64,
+    /// updates given up on after all attempts failed
+    pub failures: AtomicU64,
+}
+
+impl RetryStats {
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+}
+
+/// Fetches provider, retrying failures up to `config.attempts` times. Error
+/// of last attempt is returned
+pub async fn fetch_with_retry(
+    provider: &dyn DataProvider,
+    config: &RetryConfig,
+    stats: &RetryStats,
+) -> Result<Vec<Line>, EloraError> {
+    let mut retry = 0;
+    loop {
+        match providers::fetch(provider).await {
+            Ok(lines) => return Ok(lines),
+            Err(e) if retry + 1 < config.attempts => {
+                let delay = config.delay(retry, jitter());
+                stats.retries.fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "{}, retrying in {:?} ({}/{}, {} retries so far)",
+                    e,
+                    delay,
+                    retry + 1,
+                    config.attempts - 1,
+                    stats.retries()
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            Err(e) => {
+                stats.failures.fetch_add(1, Ordering::Relaxed);
+                return Err(e);
+            }
+        }
+    }
+}
+
+#[test]
+fn testing_backoff_delay() {
+    let config = RetryConfig::default();
+    assert_eq!(config.delay(0, 1.0), Duration::from_millis(500));
+    assert_eq!(config.delay(2, 1.0), Duration::from_millis(2000));
+    assert_eq!(config.delay(2, 0.0), Duration::from_millis(1000));
+    assert_eq!(config.delay(40, 1.0), Duration::from_millis(30_000));
+    assert!((0.0..1.0).contains(&jitter()));
+}
@@ -0,0 +1,1017 @@
+//! Periodic worker which fetches data and pushes it to keyboard
+
+use std::{collections::BTreeSet, path::Path, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use tokio::{
+    sync::{mpsc, watch, Notify},
+    task::JoinSet,
+    time::{sleep_until, Instant},
+};
+
+use crate::{
+    alerts::{Alert, Alerts, Rule, Sinks},
+    config::{Config, Keyboard, Payload},
+    hid::{connection, watcher, ConnectionManager},
+    history::History,
+    ipc::{self, DeviceStatus, KeyboardStatus, PageStatus},
+    metrics::METRICS,
+    protocol::{binary::BinaryEncoder, Command, Message, PageRequest},
+    providers::{self, pomodoro, DataProvider, Line},
+    reload::ConfigWatcher,
+    render::{self, delta::Delta, scroll, Encoding, ScrollConfig, Template},
+    retry::{self, RetryConfig, RetryStats},
+    EloraError,
+};
+
+/// Default for how often to refetch new data from dependency services in seconds
+pub const REFRESH_RATE_SECS: u16 = 60;
+
+/// updates arriving within this window are sent to keyboard in single frame
+const COALESCE_WINDOW: Duration = Duration::from_millis(200);
+
+/// Fetches lines from all providers. Failing provider is logged and skipped
+/// so others still get to keyboard
+pub async fn fetch_all(providers: &[Box<dyn DataProvider>]) -> Vec<Line> {
+    fetch_each(providers)
+        .await
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+}
+
+/// Fetches lines from every provider, `None` for providers which failed
+async fn fetch_each(providers: &[Box<dyn DataProvider>]) -> Vec<Option<Vec<Line>>> {
+    let mut fetched = Vec::with_capacity(providers.len());
+    for provider in providers {
+        match providers::fetch(provider.as_ref()).await {
+            Ok(lines) => fetched.push(Some(lines)),
+            Err(e) => {
+                log::error!("{}", e);
+                fetched.push(None);
+            }
+        }
+    }
+    fetched
+}
+
+/// Fetches from all providers and renders payload for keyboard
+pub async fn fetch_payload(
+    providers: &[Box<dyn DataProvider>],
+    encoding: &Encoding,
+) -> Result<Vec<u8>, EloraError> {
+    let lines = fetch_all(providers).await;
+    if lines.is_empty() {
+        return Err(EloraError::NoData);
+    }
+    Ok(render::convert_with(&lines, encoding))
+}
+
+/// Main worker which fetches stuff and sends it to keyboard
+pub async fn run(config: &Config, providers: &[Box<dyn DataProvider>]) -> Result<(), EloraError> {
+    let buf = fetch_payload(providers, &Encoding::new(&config.encoding)).await?;
+    send(
+        &ConnectionManager::new(config.device.clone()),
+        &Message::text(0, 1, &buf),
+    );
+    Ok(())
+}
+
+fn send(manager: &ConnectionManager, message: &Message) -> bool {
+    match manager.send(message) {
+        Ok(()) => {
+            METRICS.refreshed();
+            true
+        }
+        Err(e) => {
+            log::error!("Error occured while sending data to keyboard: {}", e);
+            false
+        }
+    }
+}
+
+/// Screen drawn on display, made of lines of one or more providers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub name: String,
+    /// indexes into providers list
+    pub providers: Vec<usize>,
+}
+
+/// Alert rules of every provider making them by its name, `None` when it
+/// isn't configured
+fn provider_rules(config: &Config) -> [(&'static str, Option<Vec<Rule>>); 10] {
+    [
+        ("ci", config.ci.as_ref().map(|ci| ci.alert_rules())),
+        (
+            "gitlab",
+            config.gitlab.as_ref().map(|gitlab| gitlab.alert_rules()),
+        ),
+        (
+            "jenkins",
+            config.jenkins.as_ref().map(|jenkins| jenkins.alert_rules()),
+        ),
+        (
+            "calendar",
+            config
+                .calendar
+                .as_ref()
+                .map(|calendar| calendar.alert_rules()),
+        ),
+        (
+            "pomodoro",
+            config
+                .pomodoro
+                .as_ref()
+                .map(|pomodoro| pomodoro.alert_rules()),
+        ),
+        (
+            "oncall",
+            config.oncall.as_ref().map(|oncall| oncall.alert_rules()),
+        ),
+        ("ping", config.ping.as_ref().map(|ping| ping.alert_rules())),
+        (
+            "sports",
+            config.sports.as_ref().map(|sports| sports.alert_rules()),
+        ),
+        (
+            "sensors",
+            config.sensors.as_ref().map(|sensors| sensors.alert_rules()),
+        ),
+        (
+            "printer",
+            config.printer.as_ref().map(|printer| printer.alert_rules()),
+        ),
+    ]
+}
+
+/// Builds pages from config for providers with given names. Without
+/// configured pages every provider is drawn on single page
+pub fn pages(config: &Config, provider_names: &[&str]) -> Result<Vec<Page>, EloraError> {
+    if config.pages.is_empty() {
+        return Ok(vec![Page {
+            name: "default".into(),
+            providers: (0..provider_names.len()).collect(),
+        }]);
+    }
+
+    config
+        .pages
+        .iter()
+        .map(|page| {
+            let indexes = page
+                .providers
+                .iter()
+                .map(|name| {
+                    provider_names
+                        .iter()
+                        .position(|p| p == name)
+                        .ok_or_else(|| {
+                            EloraError::ConfigInvalid(format!(
+                                "page {:?} uses unknown provider {:?}",
+                                page.name, name
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<usize>, EloraError>>()?;
+            Ok(Page {
+                name: page.name.clone(),
+                providers: indexes,
+            })
+        })
+        .collect()
+}
+
+/// Page shown after `current` one. While exchanges of all stock tickers are
+/// closed rotation stops on `market.closed_page` when it is set
+pub fn next_page(config: &Config, pages: &[Page], current: usize, now: &DateTime<Utc>) -> usize {
+    let closed_page = config.market.as_ref().and_then(|market| {
+        let page = market.closed_page.as_ref()?;
+        if market.all_closed(config.stock_tickers(), now) {
+            pages.iter().position(|p| &p.name == page)
+        } else {
+            None
+        }
+    });
+    closed_page.unwrap_or((current + 1) % pages.len())
+}
+
+/// Renders rows of every page from last fetched provider lines, long lines
+/// cut to their window at `step` when scrolling is configured
+fn render_pages(
+    pages: &[Page],
+    fetched: &[Option<Vec<Line>>],
+    encoding: &Encoding,
+    scroll: Option<(&ScrollConfig, usize)>,
+) -> Vec<Option<Vec<Vec<u8>>>> {
+    pages
+        .iter()
+        .map(|page| {
+            let lines = page_lines(page, fetched);
+            match scroll {
+                _ if lines.is_empty() => None,
+                Some((scroll, step)) => {
+                    Some(scroll::scrolled_rows(&lines, encoding, scroll.width, step))
+                }
+                None => Some(render::convert_rows(&lines, encoding)),
+            }
+        })
+        .collect()
+}
+
+/// Last fetched lines of page providers
+fn page_lines(page: &Page, fetched: &[Option<Vec<Line>>]) -> Vec<Line> {
+    page.providers
+        .iter()
+        .filter_map(|&i| fetched[i].clone())
+        .flatten()
+        .collect()
+}
+
+/// Sparklines of metrics drawn on page, `None` when page has no history
+fn page_sparklines(
+    page: &Page,
+    fetched: &[Option<Vec<Line>>],
+    history: &History,
+) -> Option<Message> {
+    let mut sparklines = Vec::new();
+    for line in page
+        .providers
+        .iter()
+        .filter_map(|&i| fetched[i].as_ref())
+        .flatten()
+    {
+        let Some(metric) = &line.metric else {
+            continue;
+        };
+        if sparklines
+            .iter()
+            .any(|(symbol, _)| *symbol == metric.symbol)
+        {
+            continue;
+        }
+        if let Some(levels) = history.sparkline(&metric.symbol) {
+            sparklines.push((metric.symbol.as_str(), levels));
+        }
+    }
+    if sparklines.is_empty() {
+        None
+    } else {
+        Some(Message::sparklines(&sparklines))
+    }
+}
+
+/// How often provider is fetched: its entry in `[intervals]` config, then
+/// provider's own default, then global `refresh_secs`
+pub fn refresh_interval(config: &Config, provider: &dyn DataProvider) -> Duration {
+    match config.intervals.get(provider.name()) {
+        Some(&secs) => Duration::from_secs(secs),
+        None => provider
+            .refresh_interval()
+            .unwrap_or(Duration::from_secs(config.refresh_secs)),
+    }
+}
+
+/// Fetches provider every `every` (or right away when `refresh` is notified
+/// or provider says it changed)
+/// and sends its lines tagged with `index` to main loop. Failed fetches are
+/// retried with backoff, then logged and skipped, so last good lines stay on
+/// display marked as stale
+async fn run_provider(
+    index: usize,
+    provider: Arc<dyn DataProvider>,
+    every: Duration,
+    retry: RetryConfig,
+    template: Option<Template>,
+    refresh: Arc<Notify>,
+    tx: mpsc::Sender<(usize, Option<Vec<Line>>)>,
+) {
+    let stats = RetryStats::default();
+    let mut interval = tokio::time::interval(every);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = refresh.notified() => interval.reset(),
+            _ = provider.changed() => interval.reset(),
+        }
+
+        let lines = match retry::fetch_with_retry(provider.as_ref(), &retry, &stats).await {
+            Ok(mut lines) => {
+                if let Some(template) = &template {
+                    render::apply_template(&mut lines, template);
+                }
+                Some(lines)
+            }
+            Err(e) => {
+                log::error!(
+                    "{}, giving up after {} attempts ({} updates failed so far)",
+                    e,
+                    retry.attempts,
+                    stats.failures()
+                );
+                None
+            }
+        };
+        if tx.send((index, lines)).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Stores update of provider. Failed update (`None`) keeps previous lines,
+/// marked as stale
+fn apply_update(fetched: &mut [Option<Vec<Line>>], index: usize, update: Option<Vec<Line>>) {
+    match (update, &mut fetched[index]) {
+        (Some(lines), slot) => *slot = Some(lines),
+        (None, Some(lines)) => lines.iter_mut().for_each(|line| line.stale = true),
+        (None, None) => {}
+    }
+}
+
+/// Sends alert to connected keyboards and other enabled sinks in
+/// background
+fn raise_alert(
+    alert: &Alert,
+    keyboards: &[(Keyboard, ConnectionManager)],
+    connected: &[watch::Receiver<bool>],
+    sinks: &Sinks,
+) {
+    log::info!("Alert {}", alert.text());
+    if sinks.keyboard {
+        for ((_, manager), connected) in keyboards.iter().zip(connected) {
+            if *connected.borrow() {
+                send(manager, &alert.message());
+            }
+        }
+    }
+    sinks.send(alert);
+}
+
+/// Starts listening to keyboard messages on shared connection
+fn open_reader(manager: &ConnectionManager) -> Option<mpsc::Receiver<Message>> {
+    match manager.connection() {
+        Ok(connection) => Some(connection::spawn_reader(connection)),
+        Err(e) => {
+            log::warn!("Unable to listen to keyboard: {}", e);
+            None
+        }
+    }
+}
+
+/// next tick of scrolling, never resolves while scrolling is off
+async fn next_scroll(ticks: &mut Option<tokio::time::Interval>) {
+    match ticks {
+        Some(ticks) => {
+            ticks.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Next message from keyboard, never resolves while there is no reader
+async fn next_message(reader: &mut Option<mpsc::Receiver<Message>>) -> Option<Message> {
+    match reader {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Handles message from keyboard, returning page switch it asked for
+fn handle_message(message: &Message, refresh: &Notify) -> Option<PageRequest> {
+    match message.command {
+        Command::Ack => log::debug!("Keyboard acknowledged {:?}", message.payload.first()),
+        Command::Version => match message.payload.first() {
+            Some(version) => log::info!("Keyboard speaks protocol version {}", version),
+            None => log::warn!("Keyboard sent empty version"),
+        },
+        Command::Refresh => {
+            log::info!("Keyboard requested refresh");
+            refresh.notify_waiters();
+        }
+        Command::Page => {
+            let request = PageRequest::parse(&message.payload);
+            match request {
+                Some(request) => log::debug!("Keyboard requested page {:?}", request),
+                None => log::warn!("Unknown page request {:?}", message.payload),
+            }
+            return request;
+        }
+        Command::Pomodoro => match pomodoro::Action::from_payload(&message.payload) {
+            Some(action) => {
+                if let Err(e) = pomodoro::control(action) {
+                    log::warn!("Keyboard asked for pomodoro {}: {}", action.name(), e);
+                }
+            }
+            None => log::warn!("Unknown pomodoro request {:?}", message.payload),
+        },
+        other => log::warn!("Unexpected {:?} message from keyboard", other),
+    }
+    None
+}
+
+/// Runs worker with providers enabled in config forever
+pub async fn start(config: &Config) -> Result<(), EloraError> {
+    start_with(config, providers::from_config(config)?).await
+}
+
+/// Runs worker with custom set of providers forever. Every provider is
+/// fetched on its own interval (see [`refresh_interval`]), and updates which
+/// arrive close to each other are coalesced into single hid send. Pages
+/// rotate every `page_secs`, on every configured keyboard independently.
+/// While keyboard is disconnected data is still fetched, and current page is
+/// sent right away once keyboard reappears
+pub async fn start_with(
+    config: &Config,
+    providers: Vec<Box<dyn DataProvider>>,
+) -> Result<(), EloraError> {
+    run_worker(config, providers, &keyboards(config, false)).await
+}
+
+/// Runs worker with providers enabled in config, printing frames which
+/// would be sent to stdout instead of keyboard
+pub async fn start_dry_run(config: &Config) -> Result<(), EloraError> {
+    run_worker(
+        config,
+        providers::from_config(config)?,
+        &keyboards(config, true),
+    )
+    .await
+}
+
+/// Configured keyboards with connection of each
+fn keyboards(config: &Config, dry_run: bool) -> Vec<(Keyboard, ConnectionManager)> {
+    config
+        .keyboards()
+        .into_iter()
+        .map(|keyboard| {
+            let manager = if dry_run {
+                ConnectionManager::dry_run(keyboard.device.clone())
+            } else {
+                ConnectionManager::new(keyboard.device.clone())
+            };
+            (keyboard, manager)
+        })
+        .collect()
+}
+
+/// Runs worker with providers enabled in config forever, restarting it
+/// whenever config file at `path` changes. Keyboard display is cleared and
+/// everything refetched with new config, so removed tickers don't linger.
+/// Config which fails to load is logged and previous one keeps running
+pub async fn start_reloading(
+    path: &Path,
+    mut config: Config,
+    dry_run: bool,
+) -> Result<(), EloraError> {
+    let mut watcher = ConfigWatcher::new(path)?;
+    let mut keyboards = keyboards(&config, dry_run);
+    loop {
+        let reloaded = {
+            let worker = run_worker(&config, providers::from_config(&config)?, &keyboards);
+            tokio::pin!(worker);
+            loop {
+                tokio::select! {
+                    res = &mut worker => return res,
+                    changed = watcher.changed() => {
+                        if !changed {
+                            log::warn!("Config watcher stopped, changes won't be applied");
+                            return worker.await;
+                        }
+                        match Config::load(Some(path)) {
+                            Ok(mut reloaded) => {
+                                // given on command line, file doesn't have it
+                                reloaded.full_refresh_every = config.full_refresh_every;
+                                if reloaded != config {
+                                    break reloaded;
+                                }
+                                log::debug!("Config file changed, settings are same");
+                            }
+                            Err(e) => log::error!("Keeping previous config: {}", e),
+                        }
+                    }
+                }
+            }
+        };
+
+        log::info!("Reloaded config from {}", path.display());
+        for (_, manager) in &keyboards {
+            send(manager, &Message::new(Command::Clear, Vec::new()));
+        }
+        if reloaded.keyboards() != config.keyboards() {
+            keyboards = self::keyboards(&reloaded, dry_run);
+        }
+        if reloaded.log_level != config.log_level {
+            log::warn!("log_level change is applied after restart");
+        }
+        if reloaded.metrics != config.metrics {
+            log::warn!("metrics change is applied after restart");
+        }
+        config = reloaded;
+    }
+}
+
+/// Latest lines of every provider, published by worker to keyboards
+struct Snapshot {
+    fetched: Vec<Option<Vec<Line>>>,
+    history: Option<History>,
+}
+
+/// Which pages every keyboard shows. Page with entry in `[routes]` goes
+/// only to keyboards listed there, other pages to keyboards which list them
+/// in their `pages` or don't list any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingTable {
+    /// indexes into pages per keyboard, in order they rotate
+    routes: Vec<Vec<usize>>,
+}
+
+impl RoutingTable {
+    /// Routes `pages` to `keyboards`, fails when some keyboard would get no
+    /// page at all
+    pub fn new(
+        config: &Config,
+        keyboards: &[Keyboard],
+        pages: &[Page],
+    ) -> Result<Self, EloraError> {
+        let routes = keyboards
+            .iter()
+            .map(|keyboard| {
+                let shown = |page: &Page| match config.routes.get(&page.name) {
+                    Some(names) => names.contains(&keyboard.name),
+                    None => keyboard.pages.is_empty() || keyboard.pages.contains(&page.name),
+                };
+                // pages keyboard lists come first, in its order
+                let mut indexes: Vec<usize> = keyboard
+                    .pages
+                    .iter()
+                    .filter_map(|name| pages.iter().position(|page| &page.name == name))
+                    .filter(|&i| shown(&pages[i]))
+                    .collect();
+                for (i, page) in pages.iter().enumerate() {
+                    if shown(page) && !indexes.contains(&i) {
+                        indexes.push(i);
+                    }
+                }
+                if indexes.is_empty() {
+                    return Err(EloraError::ConfigInvalid(format!(
+                        "keyboard {:?} gets no page to show",
+                        keyboard.name
+                    )));
+                }
+                Ok(indexes)
+            })
+            .collect::<Result<_, EloraError>>()?;
+        Ok(RoutingTable { routes })
+    }
+
+    /// indexes of pages shown on keyboard at `keyboard` index
+    pub fn pages(&self, keyboard: usize) -> &[usize] {
+        &self.routes[keyboard]
+    }
+}
+
+/// Tells whether keyboard is connected, always is in dry run
+fn watch_connected(keyboard: &Keyboard, manager: &ConnectionManager) -> watch::Receiver<bool> {
+    if manager.is_dry_run() {
+        // nothing to watch, "keyboard" is always there
+        watch::channel(true).1
+    } else {
+        watcher::spawn(
+            keyboard.device.clone(),
+            Duration::from_secs(keyboard.device.poll_secs),
+        )
+    }
+}
+
+/// Fetches providers and fans their lines out to every keyboard, which
+/// renders and sends its own pages
+async fn run_worker(
+    config: &Config,
+    providers: Vec<Box<dyn DataProvider>>,
+    keyboards: &[(Keyboard, ConnectionManager)],
+) -> Result<(), EloraError> {
+    if providers.is_empty() {
+        return Err(EloraError::ConfigInvalid("no providers enabled".into()));
+    }
+    let providers: Vec<Arc<dyn DataProvider>> = providers.into_iter().map(Arc::from).collect();
+    let names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+    let pages = pages(config, &names)?;
+    let configured: Vec<Keyboard> = keyboards.iter().map(|(k, _)| k.clone()).collect();
+    let routing = RoutingTable::new(config, &configured, &pages)?;
+
+    let refresh = Arc::new(Notify::new());
+    let (tx, mut updates) = mpsc::channel(providers.len().max(1) * 2);
+    // tasks are aborted when set is dropped, so nothing keeps fetching after
+    // this future is cancelled
+    let mut tasks = JoinSet::new();
+    for (index, provider) in providers.iter().cloned().enumerate() {
+        let every = refresh_interval(config, provider.as_ref());
+        let template = config.template(provider.name());
+        log::debug!("Fetching {} every {:?}", provider.name(), every);
+        tasks.spawn(run_provider(
+            index,
+            provider,
+            every,
+            config.retry.clone(),
+            template,
+            refresh.clone(),
+            tx.clone(),
+        ));
+    }
+    drop(tx);
+
+    let alert_config = config.alerts.clone().unwrap_or_default();
+    let mut rules = alert_config.parse_rules()?;
+    for (provider, provider_rules) in provider_rules(config) {
+        if let Some(provider_rules) = provider_rules {
+            rules.extend(alert_config.provider_rules(provider, provider_rules));
+        }
+    }
+    let mut alerts = Alerts::new(rules).with_hysteresis(alert_config.hysteresis);
+    let sinks = Sinks::new(config.alerts.as_ref(), config.retry.clone());
+    let mut fetched: Vec<Option<Vec<Line>>> = vec![None; names.len()];
+    let mut history = config.history.clone().map(History::new);
+
+    ipc::reset();
+    let (snapshots, snapshot) = watch::channel(Arc::new(Snapshot {
+        fetched: fetched.clone(),
+        history: history.clone(),
+    }));
+    // watchers are shared with keyboards, so alerts go only to connected ones
+    let connected: Vec<watch::Receiver<bool>> = keyboards
+        .iter()
+        .map(|(keyboard, manager)| watch_connected(keyboard, manager))
+        .collect();
+    let displays = join_all(keyboards.iter().zip(&connected).enumerate().map(
+        |(i, ((keyboard, manager), connected))| {
+            run_display(
+                config,
+                keyboard,
+                manager,
+                routing
+                    .pages(i)
+                    .iter()
+                    .map(|&page| pages[page].clone())
+                    .collect(),
+                connected.clone(),
+                snapshot.clone(),
+                refresh.clone(),
+            )
+        },
+    ));
+    tokio::pin!(displays);
+
+    // providers updated since batch started, alerts see only their new values
+    let mut updated = BTreeSet::new();
+    // batch is published at deadline armed by its first update, so updates of
+    // providers fetched at same time go out in single frame
+    let mut deadline: Option<Instant> = None;
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let Some((index, lines)) = update else {
+                    return Err(EloraError::NoData);
+                };
+                if let Some(history) = history.as_mut() {
+                    history.record(lines.iter().flatten());
+                }
+                apply_update(&mut fetched, index, lines);
+                updated.insert(index);
+                deadline.get_or_insert_with(|| Instant::now() + COALESCE_WINDOW);
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                deadline = None;
+                let _ = snapshots.send(Arc::new(Snapshot {
+                    fetched: fetched.clone(),
+                    history: history.clone(),
+                }));
+                let lines = updated.iter().flat_map(|&index| {
+                    let name = names[index];
+                    fetched[index].iter().flatten().map(move |line| (name, line))
+                });
+                for alert in alerts.check(lines) {
+                    raise_alert(&alert, keyboards, &connected, &sinks);
+                }
+                updated.clear();
+            }
+            Some(res) = tasks.join_next() => {
+                if let Err(e) = res {
+                    log::error!("Provider task failed: {}", e);
+                }
+            }
+            _ = &mut displays => return Ok(()),
+        }
+    }
+}
+
+/// Shows `pages` on one keyboard, rendered from snapshots worker publishes.
+/// Returns once worker stops publishing
+async fn run_display(
+    config: &Config,
+    keyboard: &Keyboard,
+    manager: &ConnectionManager,
+    pages: Vec<Page>,
+    mut connected: watch::Receiver<bool>,
+    mut snapshots: watch::Receiver<Arc<Snapshot>>,
+    refresh: Arc<Notify>,
+) {
+    let page_count = pages.len() as u8;
+    let dry_run = manager.is_dry_run();
+    let mut page_interval = tokio::time::interval(Duration::from_secs(config.page_secs));
+    // first tick completes immediately, page 0 is shown after first fetch
+    page_interval.tick().await;
+
+    let encoding = Encoding::new(&config.encoding);
+    let mut snapshot = snapshots.borrow_and_update().clone();
+    let mut payloads: Vec<Option<Vec<Vec<u8>>>> = vec![None; pages.len()];
+    let mut current: usize = 0;
+    let mut scroll_ticks = config
+        .scroll
+        .as_ref()
+        .map(|scroll| tokio::time::interval(Duration::from_millis(scroll.rate_ms)));
+    let mut scroll_step: usize = 0;
+    let scroll = |step| config.scroll.as_ref().map(|scroll| (scroll, step));
+    // sparklines are resent only when they change, not on every scroll step
+    let mut sent_sparklines: Option<Message> = None;
+    let mut binary = (keyboard.device.payload == Payload::Binary).then(BinaryEncoder::new);
+    let mut delta = Delta::new(config.full_refresh_every);
+    let mut is_connected = *connected.borrow();
+    let mut watching = !dry_run;
+    let mut reader = if is_connected && !dry_run {
+        open_reader(manager)
+    } else {
+        None
+    };
+
+    loop {
+        tokio::select! {
+            changed = snapshots.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                snapshot = snapshots.borrow_and_update().clone();
+                payloads = render_pages(&pages, &snapshot.fetched, &encoding, scroll(scroll_step));
+            }
+            _ = page_interval.tick(), if pages.len() > 1 => {
+                current = next_page(config, &pages, current, &Utc::now());
+                log::debug!("Switching {} to page {}", keyboard.name, pages[current].name);
+                if config.scroll.is_some() {
+                    // long lines of new page start from their beginning
+                    scroll_step = 0;
+                    payloads = render_pages(&pages, &snapshot.fetched, &encoding, scroll(scroll_step));
+                }
+            }
+            _ = next_scroll(&mut scroll_ticks) => {
+                scroll_step = scroll_step.wrapping_add(1);
+                let scrolled = render_pages(&pages, &snapshot.fetched, &encoding, scroll(scroll_step));
+                // nothing on current page is long enough to move
+                if scrolled[current] == payloads[current] {
+                    continue;
+                }
+                payloads = scrolled;
+            }
+            changed = connected.changed(), if watching => {
+                if changed.is_err() {
+                    // watcher failed to start, keep sending blindly
+                    log::error!("Device watcher of {} stopped", keyboard.name);
+                    watching = false;
+                    is_connected = true;
+                } else {
+                    is_connected = *connected.borrow_and_update();
+                }
+                // handle of unplugged keyboard is dead even if it comes back
+                manager.reset();
+                sent_sparklines = None;
+                if let Some(binary) = binary.as_mut() {
+                    binary.reset();
+                }
+                delta.reset();
+                reader = if is_connected { open_reader(manager) } else { None };
+            }
+            message = next_message(&mut reader) => {
+                let Some(message) = message else {
+                    reader = None;
+                    continue;
+                };
+                let Some(request) = handle_message(&message, &refresh) else {
+                    continue;
+                };
+                current = request.apply(current, pages.len());
+                log::debug!("Switching {} to page {}", keyboard.name, pages[current].name);
+                // chosen page stays for whole page_secs before rotating on
+                page_interval.reset();
+                if config.scroll.is_some() {
+                    scroll_step = 0;
+                    payloads = render_pages(&pages, &snapshot.fetched, &encoding, scroll(scroll_step));
+                }
+            }
+        }
+
+        ipc::update(KeyboardStatus {
+            name: keyboard.name.clone(),
+            device: DeviceStatus::new(&keyboard.device, is_connected, manager.version()),
+            page: PageStatus {
+                index: current,
+                name: pages[current].name.clone(),
+                count: pages.len(),
+            },
+        });
+        if !is_connected {
+            continue;
+        }
+        if let Some(rows) = &payloads[current] {
+            let speaks = |command: Command| {
+                manager
+                    .version()
+                    .is_some_and(|version| version >= command.since_version())
+            };
+            let speaks_binary = speaks(Command::Binary);
+            let message = match binary.as_mut() {
+                Some(binary) if speaks_binary => {
+                    let lines = page_lines(&pages[current], &snapshot.fetched);
+                    let version = manager.version().unwrap_or_default();
+                    Some(binary.page(current as u8, page_count, &lines, &encoding, version))
+                }
+                _ if speaks(Command::Rows) => delta.next(current as u8, page_count, rows),
+                _ => Some(Message::text(current as u8, page_count, &rows.concat())),
+            };
+            // unchanged rows aren't sent, keyboard already shows them
+            let sent = message
+                .as_ref()
+                .is_some_and(|message| send(manager, message));
+            if message.is_some() && !sent {
+                delta.reset();
+            }
+            if let Some(binary) = binary.as_mut().filter(|_| sent && speaks_binary) {
+                binary.confirm();
+            }
+            // send may have reopened connection, reader of old one stops
+            if sent && reader.is_none() && !dry_run {
+                reader = open_reader(manager);
+            }
+        }
+        if let Some(history) = &snapshot.history {
+            let sparklines = page_sparklines(&pages[current], &snapshot.fetched, history);
+            if sparklines != sent_sparklines {
+                if let Some(message) = &sparklines {
+                    send(manager, message);
+                }
+                sent_sparklines = sparklines;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+struct StaticProvider(&'static str);
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl DataProvider for StaticProvider {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    async fn fetch(&self) -> Result<Vec<Line>, crate::BoxError> {
+        Ok(vec![Line::new(self.0)])
+    }
+}
+
+#[test]
+fn testing_pages_from_config() {
+    use crate::config::PageConfig;
+
+    let providers: Vec<Box<dyn DataProvider>> = vec![
+        Box::new(StaticProvider("stocks")),
+        Box::new(StaticProvider("crypto")),
+    ];
+    let names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+    let mut config = Config::default();
+    assert_eq!(pages(&config, &names).unwrap()[0].providers, vec![0, 1]);
+
+    config.pages = vec![
+        PageConfig {
+            name: "coins".into(),
+            providers: vec!["crypto".into()],
+        },
+        PageConfig {
+            name: "all".into(),
+            providers: vec!["crypto".into(), "stocks".into()],
+        },
+    ];
+    let built = pages(&config, &names).unwrap();
+    let fetched = vec![Some(vec![Line::new("TSLA")]), None];
+    assert_eq!(
+        render_pages(&built, &fetched, &Encoding::default(), None),
+        vec![None, Some(vec![b"TSLA".to_vec()])]
+    );
+
+    config.pages[0].providers = vec!["weather".into()];
+    assert!(pages(&config, &names).is_err());
+}
+
+#[test]
+fn testing_routing_table() {
+    let mut config = Config::from_toml(
+        r#"
+        tickers = []
+        [system]
+        [clock]
+
+        [[pages]]
+        name = "stocks"
+        providers = ["clock"]
+        [[pages]]
+        name = "system"
+        providers = ["system"]
+        [[pages]]
+        name = "clock"
+        providers = ["clock"]
+
+        [[keyboards]]
+        name = "elora"
+        serial = "elora"
+        [[keyboards]]
+        name = "macropad"
+        product_id = 0x1234
+        pages = ["clock"]
+
+        [routes]
+        system = ["macropad"]
+        "#,
+    )
+    .unwrap();
+    let names = ["system", "clock"];
+    let built = pages(&config, &names).unwrap();
+    let routing = RoutingTable::new(&config, &config.keyboards(), &built).unwrap();
+    assert_eq!(routing.pages(0), &[0, 2]);
+    assert_eq!(routing.pages(1), &[2, 1]);
+
+    config
+        .routes
+        .insert("stocks".into(), vec!["macropad".into()]);
+    config
+        .routes
+        .insert("clock".into(), vec!["macropad".into()]);
+    assert!(RoutingTable::new(&config, &config.keyboards(), &built).is_err());
+}
+
+#[test]
+fn testing_refresh_interval() {
+    let mut config = Config::default();
+    config.intervals.insert("crypto".into(), 300);
+    config.refresh_secs = 30;
+
+    let secs = |name| refresh_interval(&config, &StaticProvider(name)).as_secs();
+    assert_eq!(secs("crypto"), 300);
+    assert_eq!(secs("stocks"), 30);
+}
+
+#[test]
+fn testing_stale_update() {
+    let mut fetched = vec![None, None];
+    apply_update(&mut fetched, 0, Some(vec![Line::new("TSLA")]));
+    apply_update(&mut fetched, 0, None);
+    apply_update(&mut fetched, 1, None);
+    assert!(fetched[0].as_ref().unwrap()[0].stale);
+    assert_eq!(fetched[1], None);
+
+    apply_update(&mut fetched, 0, Some(vec![Line::new("TSLA")]));
+    assert!(!fetched[0].as_ref().unwrap()[0].stale);
+}
+
+#[test]
+fn testing_closed_market_page() {
+    use crate::market::MarketConfig;
+
+    let mut config = Config::default();
+    let pages: Vec<Page> = ["stocks", "crypto", "weather"]
+        .iter()
+        .map(|name| Page {
+            name: name.to_string(),
+            providers: vec![0],
+        })
+        .collect();
+    let saturday: DateTime<Utc> = "2024-01-13T15:00:00Z".parse().unwrap();
+    assert_eq!(next_page(&config, &pages, 2, &saturday), 0);
+
+    config.market = Some(MarketConfig {
+        closed_page: Some("crypto".into()),
+        ..MarketConfig::default()
+    });
+    assert_eq!(next_page(&config, &pages, 1, &saturday), 1);
+    let tuesday: DateTime<Utc> = "2024-01-09T15:00:00Z".parse().unwrap();
+    assert_eq!(next_page(&config, &pages, 1, &tuesday), 2);
+}
+
+#[test]
+fn testing_alert_providers() {
+    let mut names: Vec<&str> = provider_rules(&Config::default())
+        .iter()
+        .map(|(name, _)| *name)
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, crate::alerts::PROVIDERS);
+}
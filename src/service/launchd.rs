@@ -0,0 +1,136 @@
+//! macOS LaunchAgent installer
+//!
+//! Agent runs `elora_hid --daemon run` on login and is restarted when it
+//! exits with error, logs go to `~/Library/Logs/elora_hid.log`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::EloraError;
+
+/// label of agent, plist is named after it
+pub const LABEL: &str = "io.github.dzhibas.elora_hid";
+
+/// escapes text for plist `<string>`
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Agent plist running `exe` in daemon mode, writing its output to `log`
+pub fn plist(exe: &Path, config: Option<&Path>, log: &Path) -> String {
+    let mut arguments = vec![exe.display().to_string(), "--daemon".to_string()];
+    if let Some(config) = config {
+        arguments.push("--config".to_string());
+        arguments.push(config.display().to_string());
+    }
+    arguments.push("run".to_string());
+    let arguments: String = arguments
+        .iter()
+        .map(|argument| format!("        <string>{}</string>\n", escape(argument)))
+        .collect();
+    let log = escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ThrottleInterval</key>
+    <integer>5</integer>
+    <key>StandardOutPath</key>
+    <string>{}</string>
+    <key>StandardErrorPath</key>
+    <string>{}</string>
+</dict>
+</plist>
+"#,
+        LABEL, arguments, log, log
+    )
+}
+
+fn home() -> Result<PathBuf, EloraError> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| EloraError::ConfigInvalid("HOME is not set, can't find LaunchAgents".into()))
+}
+
+/// `~/Library/LaunchAgents/<label>.plist`
+pub fn plist_path() -> Result<PathBuf, EloraError> {
+    Ok(home()?
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", LABEL)))
+}
+
+fn launchctl(command: &str, path: &Path) -> Result<(), EloraError> {
+    let output = Command::new("launchctl")
+        .arg(command)
+        .arg("-w")
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        return Err(EloraError::Io(std::io::Error::other(format!(
+            "launchctl {} failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(())
+}
+
+/// Writes agent plist for currently running binary and loads it, returns
+/// its path
+pub fn install(config: Option<&Path>) -> Result<PathBuf, EloraError> {
+    let exe = std::env::current_exe()?;
+    // agent runs from other working directory, so config path must be absolute
+    let config = config.map(fs::canonicalize).transpose()?;
+    let log = home()?.join("Library").join("Logs").join("elora_hid.log");
+    let path = plist_path()?;
+    if path.exists() {
+        // reinstall replaces agent, which has to be unloaded first
+        let _ = launchctl("unload", &path);
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, plist(&exe, config.as_deref(), &log))?;
+    launchctl("load", &path)?;
+    Ok(path)
+}
+
+/// Unloads agent written by [`install`] and removes its plist, returns its
+/// path
+pub fn uninstall() -> Result<PathBuf, EloraError> {
+    let path = plist_path()?;
+    launchctl("unload", &path)?;
+    fs::remove_file(&path)?;
+    Ok(path)
+}
+
+#[test]
+fn testing_plist() {
+    let plist = plist(
+        Path::new("/usr/local/bin/elora_hid"),
+        Some(Path::new("/Users/me/R&D/elora.toml")),
+        Path::new("/Users/me/Library/Logs/elora_hid.log"),
+    );
+    assert!(plist.contains(&format!("<string>{}</string>", LABEL)));
+    assert!(plist.contains(
+        "        <string>/usr/local/bin/elora_hid</string>\n        <string>--daemon</string>\n        <string>--config</string>\n        <string>/Users/me/R&amp;D/elora.toml</string>\n        <string>run</string>\n"
+    ));
+    assert!(plist.contains("<key>RunAtLoad</key>\n    <true/>"));
+}
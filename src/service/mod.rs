@@ -0,0 +1,179 @@
+//! systemd integration
+//!
+//! In `--daemon` mode readiness and watchdog pings are reported with
+//! sd_notify, so unit can use `Type=notify` and `WatchdogSec`. Outside of
+//! systemd (no `NOTIFY_SOCKET`) and on other platforms this does nothing.
+//! macOS LaunchAgents live in [`launchd`], Windows services in [`windows`].
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::EloraError;
+
+pub mod launchd;
+#[cfg(windows)]
+pub mod windows;
+
+/// unit name `service install` writes
+pub const UNIT_NAME: &str = "elora_hid.service";
+
+/// tells systemd startup is done
+pub fn notify_ready() {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Ready]) {
+        log::warn!("Unable to notify systemd about readiness: {}", e);
+    }
+}
+
+/// tells systemd service is shutting down
+pub fn notify_stopping() {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Stopping]) {
+        log::warn!("Unable to notify systemd about stopping: {}", e);
+    }
+}
+
+/// Watchdog interval requested by unit's `WatchdogSec`, `None` when
+/// watchdog isn't enabled
+pub fn watchdog_interval() -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut usec = 0;
+        if sd_notify::watchdog_enabled(false, &mut usec) {
+            return Some(Duration::from_micros(usec));
+        }
+    }
+    None
+}
+
+/// Pings systemd watchdog at half of its interval for as long as runtime
+/// lives. Does nothing when watchdog isn't enabled
+pub fn spawn_watchdog() {
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    log::debug!("Pinging systemd watchdog every {:?}", interval / 2);
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval / 2);
+        loop {
+            ticks.tick().await;
+            #[cfg(target_os = "linux")]
+            if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Watchdog]) {
+                log::warn!("Unable to ping systemd watchdog: {}", e);
+            }
+        }
+    });
+}
+
+/// `ExecStart=` argument in double quotes, so spaces don't split it. `%`
+/// would start specifier and `$` variable otherwise
+fn quote(argument: &str) -> String {
+    let escaped = argument
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// Unit file running `exe` in daemon mode. User units are wanted by
+/// `default.target`, system ones by `multi-user.target`
+pub fn unit_file(exe: &Path, config: Option<&Path>, system: bool) -> String {
+    let mut arguments = vec![exe.display().to_string(), "--daemon".to_string()];
+    if let Some(config) = config {
+        arguments.push("--config".to_string());
+        arguments.push(config.display().to_string());
+    }
+    arguments.push("run".to_string());
+    let exec = arguments
+        .iter()
+        .map(|argument| quote(argument))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let wanted_by = if system {
+        "multi-user.target"
+    } else {
+        "default.target"
+    };
+    format!(
+        "[Unit]
+Description=Elora keyboard display data
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=notify
+ExecStart={}
+WatchdogSec=60
+Restart=on-failure
+RestartSec=5
+# invalid config won't fix itself by restarting
+RestartPreventExitStatus=78
+
+[Install]
+WantedBy={}
+",
+        exec, wanted_by
+    )
+}
+
+/// `~/.config/systemd/user` or `/etc/systemd/system` for system unit
+pub fn unit_dir(system: bool) -> Option<PathBuf> {
+    if system {
+        return Some(PathBuf::from("/etc/systemd/system"));
+    }
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("systemd").join("user"))
+}
+
+/// Writes unit file for currently running binary, returns its path
+pub fn install(config: Option<&Path>, system: bool) -> Result<PathBuf, EloraError> {
+    let exe = std::env::current_exe()?;
+    // unit runs from other working directory, so config path must be absolute
+    let config = config.map(fs::canonicalize).transpose()?;
+    let dir = unit_dir(system)
+        .ok_or_else(|| EloraError::ConfigInvalid("HOME is not set, can't find unit dir".into()))?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(UNIT_NAME);
+    fs::write(&path, unit_file(&exe, config.as_deref(), system))?;
+    Ok(path)
+}
+
+/// Removes unit file written by [`install`], returns its path
+pub fn uninstall(system: bool) -> Result<PathBuf, EloraError> {
+    let dir = unit_dir(system)
+        .ok_or_else(|| EloraError::ConfigInvalid("HOME is not set, can't find unit dir".into()))?;
+    let path = dir.join(UNIT_NAME);
+    fs::remove_file(&path)?;
+    Ok(path)
+}
+
+#[test]
+fn testing_unit_file() {
+    let unit = unit_file(
+        Path::new("/usr/bin/elora_hid"),
+        Some(Path::new("/etc/elora_hid.toml")),
+        false,
+    );
+    assert!(unit.contains("Type=notify\n"));
+    assert!(unit.contains(
+        "ExecStart=\"/usr/bin/elora_hid\" \"--daemon\" \"--config\" \"/etc/elora_hid.toml\" \"run\"\n"
+    ));
+    assert!(unit.contains("WantedBy=default.target\n"));
+
+    let unit = unit_file(
+        Path::new("/opt/elora hid/elora_hid"),
+        Some(Path::new("/home/me/My Configs/100%\"elora\".toml")),
+        true,
+    );
+    assert!(unit.contains(
+        "ExecStart=\"/opt/elora hid/elora_hid\" \"--daemon\" \"--config\" \"/home/me/My Configs/100%%\\\"elora\\\".toml\" \"run\"\n"
+    ));
+    assert!(unit.contains("WantedBy=multi-user.target\n"));
+}
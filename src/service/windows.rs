@@ -0,0 +1,165 @@
+//! Windows service integration
+//!
+//! `service install` registers binary with service control manager to start
+//! at boot without console window, launched as `service run`. Control
+//! manager talks to service on its own thread, so daemon is handed over as
+//! worker which runs until stop or shutdown control arrives.
+
+use std::{ffi::OsString, path::Path, sync::Mutex, time::Duration};
+
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult},
+    service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
+};
+
+use crate::EloraError;
+
+/// name service is registered under
+pub const SERVICE_NAME: &str = "elora_hid";
+
+const DISPLAY_NAME: &str = "Elora keyboard display data";
+
+/// Daemon run by service, gets receiver which resolves once service is asked
+/// to stop
+pub type Worker =
+    Box<dyn FnOnce(tokio::sync::oneshot::Receiver<()>) -> Result<(), EloraError> + Send>;
+
+/// worker handed from [`run`] to service main, which has no arguments of ours
+static WORKER: Mutex<Option<Worker>> = Mutex::new(None);
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_error(e: windows_service::Error) -> EloraError {
+    EloraError::Io(std::io::Error::other(e))
+}
+
+/// Registers service starting at boot as LocalSystem, running this binary
+/// with given config
+pub fn install(config: Option<&Path>) -> Result<(), EloraError> {
+    let exe = std::env::current_exe()?;
+    // service doesn't run as current user, so config has to be given by
+    // absolute path instead of being looked up in user's profile
+    let config = crate::config::resolve_path(config)
+        .map(std::fs::canonicalize)
+        .transpose()?;
+    let mut launch_arguments = Vec::new();
+    if let Some(config) = config {
+        launch_arguments.push(OsString::from("--config"));
+        launch_arguments.push(config.into_os_string());
+    }
+    launch_arguments.push(OsString::from("service"));
+    launch_arguments.push(OsString::from("run"));
+
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .map_err(service_error)?;
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(DISPLAY_NAME),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: exe,
+        launch_arguments,
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .map_err(service_error)?;
+    service
+        .set_description("Pushes data through USB raw hid to Elora split keyboard")
+        .map_err(service_error)
+}
+
+/// Stops service when it runs and removes it
+pub fn uninstall() -> Result<(), EloraError> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(service_error)?;
+    let service = manager
+        .open_service(
+            SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .map_err(service_error)?;
+    if service.query_status().map_err(service_error)?.current_state != ServiceState::Stopped {
+        service.stop().map_err(service_error)?;
+    }
+    service.delete().map_err(service_error)
+}
+
+/// Hands `worker` to service control manager and blocks until service
+/// stops. Fails when binary wasn't started by control manager
+pub fn run(worker: Worker) -> Result<(), EloraError> {
+    *WORKER.lock().unwrap() = Some(worker);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(service_error)
+}
+
+fn status(state: ServiceState, exit_code: ServiceExitCode) -> ServiceStatus {
+    let controls_accepted = match state {
+        ServiceState::Running => ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        _ => ServiceControlAccept::empty(),
+    };
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code,
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    }
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_worker() {
+        log::error!("Service failed: {}", e);
+    }
+}
+
+fn run_worker() -> Result<(), EloraError> {
+    let worker = WORKER
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or(EloraError::ConfigInvalid("service started twice".into()))?;
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+    let stop_tx = Mutex::new(Some(stop_tx));
+    let handle = service_control_handler::register(SERVICE_NAME, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            log::info!("Service asked to stop");
+            if let Some(stop_tx) = stop_tx.lock().unwrap().take() {
+                let _ = stop_tx.send(());
+            }
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })
+    .map_err(service_error)?;
+    handle
+        .set_service_status(status(ServiceState::Running, ServiceExitCode::Win32(0)))
+        .map_err(service_error)?;
+
+    // worker clears display before returning, so service is reported stopped
+    // only once keyboard was left blank
+    let res = worker(stop_rx);
+    let exit_code = match &res {
+        Ok(()) => ServiceExitCode::Win32(0),
+        Err(_) => ServiceExitCode::ServiceSpecific(1),
+    };
+    handle
+        .set_service_status(status(ServiceState::Stopped, exit_code))
+        .map_err(service_error)?;
+    res
+}